@@ -14,7 +14,6 @@ use security_framework::passwords::{
     delete_generic_password, delete_internet_password, get_generic_password, get_internet_password,
     set_generic_password, set_internet_password,
 };
-use security_framework_sys::base::errSecItemNotFound;
 use security_framework_sys::keychain::SecAuthenticationType::Any;
 use security_framework_sys::keychain::SecProtocolType::HTTP;
 
@@ -40,19 +39,19 @@ fn test_missing_generic_password() {
     let result = delete_generic_password(name, name);
     match result {
         Ok(()) => (),
-        Err(err) if err.code() == errSecItemNotFound => (),
+        Err(err) if err.is_not_found() => (),
         Err(err) => panic!("test_missing_generic_password: delete failed with status: {}", err.code()),
     }
     let result = get_generic_password(name, name);
     match result {
         Ok(bytes) => panic!("test_missing_password: get returned {bytes:?}"),
-        Err(err) if err.code() == errSecItemNotFound => (),
+        Err(err) if err.is_not_found() => (),
         Err(err) => panic!("test_missing_generic_password: get failed with status: {}", err.code()),
     }
     let result = delete_generic_password(name, name);
     match result {
         Ok(()) => panic!("test_missing_generic_password: second delete found a password"),
-        Err(err) if err.code() == errSecItemNotFound => (),
+        Err(err) if err.is_not_found() => (),
         Err(err) => panic!("test_missing_generic_password: delete failed with status: {}", err.code()),
     }
     println!("test_missing_generic_password: pass");
@@ -123,19 +122,19 @@ fn test_missing_internet_password() {
     let result = delete_internet_password(name, None, name, "/test", None, HTTP, Any);
     match result {
         Ok(()) => (),
-        Err(err) if err.code() == errSecItemNotFound => (),
+        Err(err) if err.is_not_found() => (),
         Err(err) => panic!("test_missing_internet_password: delete failed with status: {}", err.code()),
     }
     let result = get_internet_password(name, None, name, "/test", None, HTTP, Any);
     match result {
         Ok(bytes) => panic!("test_missing_password: get returned {bytes:?}"),
-        Err(err) if err.code() == errSecItemNotFound => (),
+        Err(err) if err.is_not_found() => (),
         Err(err) => panic!("test_missing_internet_password: get failed with status: {}", err.code()),
     }
     let result = delete_internet_password(name, None, name, "/test", None, HTTP, Any);
     match result {
         Ok(()) => panic!("test_missing_internet_password: second delete found a password"),
-        Err(err) if err.code() == errSecItemNotFound => (),
+        Err(err) if err.is_not_found() => (),
         Err(err) => panic!("test_missing_internet_password: delete failed with status: {}", err.code()),
     }
     println!("test_missing_internet_password: pass");
@@ -38,6 +38,10 @@ pub enum OpaqueSecAccessRef {}
 #[cfg(target_os = "macos")]
 pub type SecAccessRef = *mut OpaqueSecAccessRef;
 
+pub enum OpaqueSecTrustedApplicationRef {}
+#[cfg(target_os = "macos")]
+pub type SecTrustedApplicationRef = *mut OpaqueSecTrustedApplicationRef;
+
 pub enum OpaqueSecAccessControlRef {}
 pub type SecAccessControlRef = *mut OpaqueSecAccessControlRef;
 
@@ -60,13 +64,40 @@ pub const errSecAuthFailed: OSStatus = -25293;
 pub const errSecDuplicateItem: OSStatus = -25299;
 pub const errSecItemNotFound: OSStatus = -25300;
 pub const errSecCreateChainFailed: OSStatus = -25318;
+pub const errSecNotSigner: OSStatus = -26267;
+pub const errSecDecode: OSStatus = -26275;
 pub const errSecConversionError: OSStatus = -67594;
 pub const errSecHostNameMismatch: OSStatus = -67602;
+pub const errSecIncompleteCertRevocationCheck: OSStatus = -67635;
+pub const errSecNoBasicConstraintsCA: OSStatus = -67605;
+pub const errSecInvalidKeyUsageForPolicy: OSStatus = -67608;
 pub const errSecInvalidExtendedKeyUsage: OSStatus = -67609;
+pub const errSecPathLengthConstraintExceeded: OSStatus = -67611;
 pub const errSecTrustSettingDeny: OSStatus = -67654;
+pub const errSecCertificateExpired: OSStatus = -67818;
+pub const errSecCertificateNotValidYet: OSStatus = -67819;
 pub const errSecCertificateRevoked: OSStatus = -67820;
 pub const errSecNotTrusted: OSStatus = -67843;
+pub const errSecCertificateNameNotAllowed: OSStatus = -67900;
 pub const errSecInternalComponent: OSStatus = -2070;
+pub const errSecDiskFull: OSStatus = -34;
+pub const errSecWrPerm: OSStatus = -61;
+pub const errSecUserCanceled: OSStatus = -128;
+pub const errSecPkcs12VerifyFailure: OSStatus = -25264;
+pub const errSecNotAvailable: OSStatus = -25291;
+pub const errSecNoSuchKeychain: OSStatus = -25294;
+pub const errSecDuplicateKeychain: OSStatus = -25296;
+pub const errSecDuplicateCallback: OSStatus = -25297;
+pub const errSecNoSuchAttr: OSStatus = -25303;
+pub const errSecNoSuchClass: OSStatus = -25306;
+pub const errSecNoDefaultKeychain: OSStatus = -25307;
+pub const errSecInteractionNotAllowed: OSStatus = -25308;
+pub const errSecInteractionRequired: OSStatus = -25315;
+pub const errSecDataNotAvailable: OSStatus = -25316;
+pub const errSecInDarkWake: OSStatus = -25320;
+pub const errSecMissingEntitlement: OSStatus = -34018;
+pub const errSecRestrictedAPI: OSStatus = -34020;
+pub const errSecVerifyFailed: OSStatus = -67808;
 
 extern "C" {
     // this is available on iOS 11.3+, MacOS 10.3+
@@ -1,4 +1,5 @@
-use core_foundation_sys::base::{Boolean, CFOptionFlags, CFTypeID};
+use core_foundation_sys::base::{Boolean, CFOptionFlags, CFTypeID, CFTypeRef};
+use core_foundation_sys::dictionary::CFDictionaryRef;
 use core_foundation_sys::string::CFStringRef;
 
 use crate::base::SecPolicyRef;
@@ -17,6 +18,29 @@ mod revocation_flags {
 pub use revocation_flags::*;
 
 extern "C" {
+    pub static kSecPolicyAppleX509Basic: CFStringRef;
+    pub static kSecPolicyAppleSSL: CFStringRef;
+    pub static kSecPolicyAppleSMIME: CFStringRef;
+    pub static kSecPolicyAppleEAP: CFStringRef;
+    pub static kSecPolicyAppleIPsec: CFStringRef;
+    pub static kSecPolicyApplePKINITClient: CFStringRef;
+    pub static kSecPolicyApplePKINITServer: CFStringRef;
+    pub static kSecPolicyAppleCodeSigning: CFStringRef;
+    pub static kSecPolicyAppleIDValidation: CFStringRef;
+    pub static kSecPolicyAppleTimeStamping: CFStringRef;
+    pub static kSecPolicyAppleRevocation: CFStringRef;
+    pub static kSecPolicyApplePassbookSigning: CFStringRef;
+
+    pub static kSecPolicyOid: CFStringRef;
+    pub static kSecPolicyName: CFStringRef;
+    pub static kSecPolicyClient: CFStringRef;
+    pub static kSecPolicyRevocationFlags: CFStringRef;
+    pub static kSecPolicyTeamIdentifier: CFStringRef;
+}
+
+extern "C" {
+    pub fn SecPolicyCreateWithProperties(policyIdentifier: CFTypeRef, properties: CFDictionaryRef) -> SecPolicyRef;
+    pub fn SecPolicyCopyProperties(policyRef: SecPolicyRef) -> CFDictionaryRef;
     pub fn SecPolicyCreateSSL(server: Boolean, hostname: CFStringRef) -> SecPolicyRef;
     pub fn SecPolicyCreateRevocation(revocationFlags: CFOptionFlags) -> SecPolicyRef;
     pub fn SecPolicyGetTypeID() -> CFTypeID;
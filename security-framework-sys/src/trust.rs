@@ -1,8 +1,11 @@
 use crate::base::{SecCertificateRef, SecKeyRef};
 use core_foundation_sys::array::CFArrayRef;
 use core_foundation_sys::base::{Boolean, CFIndex, CFTypeID, CFTypeRef, OSStatus};
+use core_foundation_sys::data::CFDataRef;
 use core_foundation_sys::date::CFDateRef;
+use core_foundation_sys::dictionary::CFDictionaryRef;
 use core_foundation_sys::error::CFErrorRef;
+use core_foundation_sys::string::CFStringRef;
 
 pub type SecTrustResultType = u32;
 
@@ -34,6 +37,16 @@ pub enum __SecTrust {}
 
 pub type SecTrustRef = *mut __SecTrust;
 
+extern "C" {
+    pub static kSecTrustEvaluationDate: CFStringRef;
+    pub static kSecTrustExtendedValidation: CFStringRef;
+    pub static kSecTrustOrganizationName: CFStringRef;
+    pub static kSecTrustResultValue: CFStringRef;
+    pub static kSecTrustRevocationChecked: CFStringRef;
+    pub static kSecTrustRevocationValidUntilDate: CFStringRef;
+    pub static kSecTrustCertificateTransparency: CFStringRef;
+}
+
 extern "C" {
     pub fn SecTrustGetTypeID() -> CFTypeID;
     #[cfg(any(feature = "macos-12", not(target_os = "macos")))]
@@ -49,6 +62,9 @@ extern "C" {
     #[deprecated(note = "deprecated by Apple")]
     pub fn SecTrustEvaluate(trust: SecTrustRef, result: *mut SecTrustResultType) -> OSStatus;
     pub fn SecTrustEvaluateWithError(trust: SecTrustRef, error: *mut CFErrorRef) -> bool;
+    pub fn SecTrustCopyResult(trust: SecTrustRef) -> CFDictionaryRef;
+    pub fn SecTrustCopyExceptions(trust: SecTrustRef) -> CFDataRef;
+    pub fn SecTrustSetExceptions(trust: SecTrustRef, exceptions: CFDataRef) -> bool;
     pub fn SecTrustCreateWithCertificates(
         certificates: CFTypeRef,
         policies: CFTypeRef,
@@ -1,6 +1,8 @@
-use crate::base::SecCertificateRef;
+use crate::base::{SecCertificateRef, SecTrustedApplicationRef};
 use core_foundation_sys::array::CFArrayRef;
 use core_foundation_sys::base::{CFTypeRef, OSStatus};
+use core_foundation_sys::data::CFDataRef;
+use std::os::raw::c_char;
 
 pub type SecTrustSettingsDomain = u32;
 
@@ -16,6 +18,16 @@ pub const kSecTrustSettingsResultTrustAsRoot: SecTrustSettingsResult = 2;
 pub const kSecTrustSettingsResultDeny: SecTrustSettingsResult = 3;
 pub const kSecTrustSettingsResultUnspecified: SecTrustSettingsResult = 4;
 
+pub type SecTrustSettingsKeyUsage = u32;
+
+pub const kSecTrustSettingsKeyUseSignature: SecTrustSettingsKeyUsage = 0x0000_0001;
+pub const kSecTrustSettingsKeyUseEnDecryptData: SecTrustSettingsKeyUsage = 0x0000_0002;
+pub const kSecTrustSettingsKeyUseEnDecryptKey: SecTrustSettingsKeyUsage = 0x0000_0004;
+pub const kSecTrustSettingsKeyUseSignCert: SecTrustSettingsKeyUsage = 0x0000_0008;
+pub const kSecTrustSettingsKeyUseSignRevocation: SecTrustSettingsKeyUsage = 0x0000_0010;
+pub const kSecTrustSettingsKeyUseKeyExchange: SecTrustSettingsKeyUsage = 0x0000_0020;
+pub const kSecTrustSettingsKeyUseAny: SecTrustSettingsKeyUsage = 0xffff_ffff;
+
 extern "C" {
     pub fn SecTrustSettingsCopyCertificates(
         domain: SecTrustSettingsDomain,
@@ -31,4 +43,30 @@ extern "C" {
         domain: SecTrustSettingsDomain,
         trustSettingsDictOrArray: CFTypeRef,
     ) -> OSStatus;
+    pub fn SecTrustSettingsRemoveTrustSettings(
+        certificateRef: SecCertificateRef,
+        domain: SecTrustSettingsDomain,
+    ) -> OSStatus;
+    pub fn SecTrustSettingsCreateExternalRepresentation(
+        domain: SecTrustSettingsDomain,
+        trustSettings: *mut CFDataRef,
+    ) -> OSStatus;
+    pub fn SecTrustSettingsImportExternalRepresentation(
+        domain: SecTrustSettingsDomain,
+        trustSettings: CFDataRef,
+    ) -> OSStatus;
+
+    // the values of `kSecTrustSettingsApplication`
+    pub fn SecTrustedApplicationCreateFromPath(
+        path: *const c_char,
+        app: *mut SecTrustedApplicationRef,
+    ) -> OSStatus;
+    pub fn SecTrustedApplicationCopyData(
+        appRef: SecTrustedApplicationRef,
+        data: *mut CFDataRef,
+    ) -> OSStatus;
+    pub fn SecTrustedApplicationSetData(
+        appRef: SecTrustedApplicationRef,
+        data: CFDataRef,
+    ) -> OSStatus;
 }
@@ -1,9 +1,8 @@
 //! Support types for other modules.
 
-use core_foundation::string::CFString;
 use core_foundation_sys::base::OSStatus;
 use std::num::NonZeroI32;
-use std::{error, fmt, result};
+use std::{error, fmt, io, result};
 
 /// A `Result` type commonly returned by functions.
 pub type Result<T, E = Error> = result::Result<T, E>;
@@ -17,6 +16,9 @@ impl fmt::Debug for Error {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut builder = fmt.debug_struct("Error");
         builder.field("code", &self.0);
+        if let Some(name) = self.name() {
+            builder.field("name", &name);
+        }
         if let Some(message) = self.message() {
             builder.field("message", &message);
         }
@@ -24,6 +26,63 @@ impl fmt::Debug for Error {
     }
 }
 
+/// Subsystem of Security framework that a status code comes from.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorCategory {
+    /// Generic errors shared by all APIs.
+    General,
+    /// Keychain items, import and export.
+    Keychain,
+    /// Certificate, policy and trust evaluation.
+    Trust,
+    /// Secure Transport (`errSSL*`).
+    Tls,
+    /// Authorization Services (`errAuthorization*`).
+    Authorization,
+    /// Code Signing Services (`errSecCS*`).
+    CodeSigning,
+}
+
+/// A broad classification of an [`Error`], for matching without raw status codes.
+///
+/// New variants may be added as more codes get classified.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The item, keychain or code object doesn't exist (`errSecItemNotFound`).
+    NotFound,
+    /// The item already exists (`errSecDuplicateItem`).
+    Duplicate,
+    /// The user canceled the operation (`errSecUserCanceled`).
+    UserCanceled,
+    /// Authentication or authorization failed (`errSecAuthFailed`).
+    AuthFailed,
+    /// The operation needs user interaction, which isn't allowed (`errSecInteractionNotAllowed`).
+    InteractionNotAllowed,
+    /// The keychain or its data is unavailable, typically because the device is locked.
+    Locked,
+    /// The app lacks an entitlement required for the operation (`errSecMissingEntitlement`).
+    MissingEntitlement,
+    /// A parameter was invalid (`errSecParam`).
+    InvalidParameter,
+    /// The operation isn't implemented (`errSecUnimplemented`).
+    Unimplemented,
+    /// An I/O error (`errSecIO`).
+    Io,
+    /// The TLS operation would block (`errSSLWouldBlock`), or the handshake paused for the caller
+    /// to act, e.g. on `errSSLPeerAuthCompleted` or `errSSLClientCertRequested`, and can be resumed.
+    WouldBlock,
+    /// The TLS connection has been closed.
+    ConnectionClosed,
+    /// A TLS protocol error or alert that isn't about certificates.
+    TlsProtocol,
+    /// A certificate was rejected by trust evaluation, locally or by the peer.
+    CertificateRejected,
+    /// Any other error.
+    Other,
+}
+
 impl Error {
     /// Creates a new `Error` from a status code.
     /// The code must not be zero
@@ -41,8 +100,10 @@ impl Error {
     }
 
     #[cold]
+    #[cfg(target_vendor = "apple")]
     fn inner_message(self) -> Option<String> {
         use core_foundation::base::TCFType;
+        use core_foundation::string::CFString;
         use security_framework_sys::base::SecCopyErrorMessageString;
         use std::ptr;
 
@@ -56,6 +117,75 @@ impl Error {
         }
     }
 
+    #[cfg(not(target_vendor = "apple"))]
+    #[allow(clippy::unused_self)]
+    fn inner_message(self) -> Option<String> {
+        None
+    }
+
+    /// Returns the symbolic name of the status code, e.g. `errSecItemNotFound`.
+    ///
+    /// This uses a built-in table, and works on all platforms.
+    #[must_use]
+    pub fn name(self) -> Option<&'static str> {
+        crate::error_codes::lookup(self.code()).map(|e| e.name)
+    }
+
+    /// Returns a description of the status code from the built-in table.
+    ///
+    /// Unlike `message()`, this doesn't need Security.framework.
+    #[must_use]
+    pub fn static_message(self) -> Option<&'static str> {
+        crate::error_codes::lookup(self.code()).map(|e| e.description)
+    }
+
+    /// Returns the subsystem the status code belongs to, if it's a known code.
+    #[must_use]
+    pub fn category(self) -> Option<ErrorCategory> {
+        crate::error_codes::lookup(self.code()).map(|e| e.category)
+    }
+
+    /// Returns the classification of the error.
+    #[must_use]
+    pub fn kind(self) -> ErrorKind {
+        crate::error_codes::kind(self.code())
+    }
+
+    /// Returns `true` if the item could not be found.
+    #[inline]
+    #[must_use]
+    pub fn is_not_found(self) -> bool {
+        self.kind() == ErrorKind::NotFound
+    }
+
+    /// Returns `true` if the item already exists.
+    #[inline]
+    #[must_use]
+    pub fn is_duplicate(self) -> bool {
+        self.kind() == ErrorKind::Duplicate
+    }
+
+    /// Returns `true` if the user canceled the operation.
+    #[inline]
+    #[must_use]
+    pub fn is_user_canceled(self) -> bool {
+        self.kind() == ErrorKind::UserCanceled
+    }
+
+    /// Returns `true` if authentication failed.
+    #[inline]
+    #[must_use]
+    pub fn is_auth_failed(self) -> bool {
+        self.kind() == ErrorKind::AuthFailed
+    }
+
+    /// Returns `true` if the operation required user interaction that wasn't allowed.
+    #[inline]
+    #[must_use]
+    pub fn is_interaction_not_allowed(self) -> bool {
+        self.kind() == ErrorKind::InteractionNotAllowed
+    }
+
     /// Returns the code of the current error.
     #[inline(always)]
     #[must_use]
@@ -76,6 +206,8 @@ impl fmt::Display for Error {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         if let Some(message) = self.message() {
             write!(fmt, "{message}")
+        } else if let Some(entry) = crate::error_codes::lookup(self.code()) {
+            write!(fmt, "{} ({}): {}", entry.name, entry.code, entry.description)
         } else {
             write!(fmt, "error code {}", self.code())
         }
@@ -83,3 +215,278 @@ impl fmt::Display for Error {
 }
 
 impl error::Error for Error {}
+
+impl From<Error> for io::Error {
+    /// Maps to the nearest `io::ErrorKind`, keeping the `Error` as the inner error.
+    fn from(err: Error) -> Self {
+        Self::new(crate::error_codes::io_kind(err.kind()), err)
+    }
+}
+
+/// An error from either an `OSStatus` or a `CFError`.
+///
+/// Both are represented as a domain and a code, like `CFError` does. `OSStatus` codes
+/// use the [`DomainError::OSSTATUS_DOMAIN`] domain, and can be converted back with
+/// [`DomainError::status`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DomainError {
+    domain: String,
+    code: isize,
+    description: Option<String>,
+    user_info: Vec<(String, String)>,
+    underlying: Option<Box<DomainError>>,
+}
+
+impl DomainError {
+    /// The `kCFErrorDomainOSStatus` domain.
+    pub const OSSTATUS_DOMAIN: &'static str = "NSOSStatusErrorDomain";
+
+    /// Creates an error with the given domain and code, and no other details.
+    #[must_use]
+    pub fn new(domain: impl Into<String>, code: isize) -> Self {
+        Self {
+            domain: domain.into(),
+            code,
+            description: None,
+            user_info: Vec::new(),
+            underlying: None,
+        }
+    }
+
+    /// Sets the human-readable description.
+    #[must_use]
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Adds an entry of the `userInfo` dictionary.
+    #[must_use]
+    pub fn with_user_info(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.user_info.push((key.into(), value.into()));
+        self
+    }
+
+    /// Sets the error that caused this one (`kCFErrorUnderlyingErrorKey`).
+    #[must_use]
+    pub fn with_underlying(mut self, underlying: impl Into<Self>) -> Self {
+        self.underlying = Some(Box::new(underlying.into()));
+        self
+    }
+
+    /// Returns the error domain, e.g. `NSOSStatusErrorDomain`.
+    #[inline]
+    #[must_use]
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+
+    /// Returns the error code within the domain.
+    #[inline]
+    #[must_use]
+    pub fn code(&self) -> isize {
+        self.code
+    }
+
+    /// Returns the description, if the error had one.
+    #[inline]
+    #[must_use]
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Returns the entries of the `userInfo` dictionary.
+    ///
+    /// Values that aren't strings are given as their `CFCopyDescription`.
+    #[inline]
+    #[must_use]
+    pub fn user_info(&self) -> &[(String, String)] {
+        &self.user_info
+    }
+
+    /// Returns the value of a `userInfo` entry.
+    #[must_use]
+    pub fn user_info_value(&self, key: &str) -> Option<&str> {
+        self.user_info.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    /// Returns the error that caused this one.
+    #[inline]
+    #[must_use]
+    pub fn underlying(&self) -> Option<&Self> {
+        self.underlying.as_deref()
+    }
+
+    /// Returns the `OSStatus` error, if this error is in the `OSStatus` domain.
+    #[must_use]
+    pub fn status(&self) -> Option<Error> {
+        if self.domain != Self::OSSTATUS_DOMAIN {
+            return None;
+        }
+        let code = OSStatus::try_from(self.code).ok()?;
+        NonZeroI32::new(code).map(Error)
+    }
+}
+
+impl From<Error> for DomainError {
+    #[inline]
+    fn from(err: Error) -> Self {
+        Self::new(Self::OSSTATUS_DOMAIN, err.code() as isize)
+    }
+}
+
+#[cfg(target_vendor = "apple")]
+impl From<core_foundation::error::CFError> for DomainError {
+    /// Copies the domain, code, description, values of `userInfo` (as a description
+    /// if they aren't strings), and the chain of underlying errors.
+    fn from(err: core_foundation::error::CFError) -> Self {
+        Self::from(&err)
+    }
+}
+
+#[cfg(target_vendor = "apple")]
+impl From<&core_foundation::error::CFError> for DomainError {
+    fn from(err: &core_foundation::error::CFError) -> Self {
+        use core_foundation::base::{CFType, TCFType};
+        use core_foundation::dictionary::CFDictionary;
+        use core_foundation::error::CFError;
+        use core_foundation::string::CFString;
+        use core_foundation_sys::error::{kCFErrorUnderlyingErrorKey, CFErrorCopyUserInfo};
+
+        let mut out = Self::new(err.domain().to_string(), err.code())
+            .with_description(err.description().to_string());
+        unsafe {
+            let info = CFErrorCopyUserInfo(err.as_concrete_TypeRef());
+            if info.is_null() {
+                return out;
+            }
+            let info = CFDictionary::<CFString, CFType>::wrap_under_create_rule(info);
+            let (keys, values) = info.get_keys_and_values();
+            for (k, v) in keys.into_iter().zip(values) {
+                let key = CFString::wrap_under_get_rule(k.cast());
+                let value = CFType::wrap_under_get_rule(v);
+                if key == CFString::wrap_under_get_rule(kCFErrorUnderlyingErrorKey) {
+                    if let Some(underlying) = value.downcast::<CFError>() {
+                        out.underlying = Some(Box::new(Self::from(&underlying)));
+                    }
+                } else if let Some(value) = value.downcast::<CFString>() {
+                    out.user_info.push((key.to_string(), value.to_string()));
+                } else {
+                    out.user_info.push((key.to_string(), format!("{value:?}")));
+                }
+            }
+        }
+        out
+    }
+}
+
+impl fmt::Display for DomainError {
+    #[cold]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(description) = &self.description {
+            fmt.write_str(description)
+        } else if let Some(status) = self.status() {
+            fmt::Display::fmt(&status, fmt)
+        } else {
+            write!(fmt, "{} error {}", self.domain, self.code)
+        }
+    }
+}
+
+impl error::Error for DomainError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        self.underlying.as_deref().map(|e| e as _)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn catalogue() {
+        let err = Error::from_code(-25300);
+        assert_eq!(err.name(), Some("errSecItemNotFound"));
+        assert_eq!(err.category(), Some(ErrorCategory::Keychain));
+        assert_eq!(Error::from_code(-9807).category(), Some(ErrorCategory::Tls));
+        assert_eq!(Error::from_code(-60005).name(), Some("errAuthorizationDenied"));
+        assert_eq!(Error::from_code(-67062).category(), Some(ErrorCategory::CodeSigning));
+        assert_eq!(Error::from_code(12345).name(), None);
+    }
+
+    #[test]
+    fn kind() {
+        assert!(Error::from_code(-25300).is_not_found());
+        assert!(Error::from_code(-25299).is_duplicate());
+        assert!(Error::from_code(-128).is_user_canceled());
+        assert!(Error::from_code(-25293).is_auth_failed());
+        assert!(Error::from_code(-25308).is_interaction_not_allowed());
+        assert!(!Error::from_code(-25308).is_not_found());
+
+        let err = io::Error::from(Error::from_code(-25300));
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        let inner = err.into_inner().unwrap().downcast::<Error>().unwrap();
+        assert_eq!(inner.code(), -25300);
+    }
+
+    #[test]
+    fn domain_error_from_status() {
+        let err = DomainError::from(Error::from_code(-25300));
+        assert_eq!(err.domain(), DomainError::OSSTATUS_DOMAIN);
+        assert_eq!(err.code(), -25300);
+        assert_eq!(err.status().unwrap().code(), -25300);
+        assert_eq!(err.to_string(), Error::from_code(-25300).to_string());
+        assert!(error::Error::source(&err).is_none());
+    }
+
+    #[test]
+    fn domain_error_chain() {
+        let err = DomainError::new("NSCocoaErrorDomain", 4)
+            .with_description("The file couldn’t be opened.")
+            .with_user_info("NSFilePath", "/tmp/x")
+            .with_underlying(DomainError::new("NSPOSIXErrorDomain", 2));
+        assert!(err.status().is_none());
+        assert_eq!(err.to_string(), "The file couldn’t be opened.");
+        assert_eq!(err.user_info_value("NSFilePath"), Some("/tmp/x"));
+        let source = error::Error::source(&err).unwrap();
+        assert_eq!(source.to_string(), "NSPOSIXErrorDomain error 2");
+        assert_eq!(err.underlying().unwrap().code(), 2);
+
+        assert!(DomainError::new(DomainError::OSSTATUS_DOMAIN, 0).status().is_none());
+        assert!(DomainError::new(DomainError::OSSTATUS_DOMAIN, isize::MAX).status().is_none());
+    }
+
+    #[test]
+    #[cfg(target_vendor = "apple")]
+    fn domain_error_from_cf_error() {
+        use core_foundation::base::{CFType, TCFType};
+        use core_foundation::dictionary::CFDictionary;
+        use core_foundation::error::CFError;
+        use core_foundation::number::CFNumber;
+        use core_foundation::string::CFString;
+        use core_foundation_sys::error::CFErrorCreate;
+
+        let info = CFDictionary::<CFString, CFType>::from_CFType_pairs(&[
+            (CFString::new("NSFilePath"), CFString::new("/tmp/x").as_CFType()),
+            (CFString::new("Attempts"), CFNumber::from(3).as_CFType()),
+        ]);
+        let domain = CFString::new("NSCocoaErrorDomain");
+        let err = unsafe {
+            CFError::wrap_under_create_rule(CFErrorCreate(std::ptr::null(), domain.as_concrete_TypeRef(), 4, info.as_concrete_TypeRef()))
+        };
+        let err = DomainError::from(&err);
+        assert_eq!(err.code(), 4);
+        assert_eq!(err.user_info_value("NSFilePath"), Some("/tmp/x"));
+        assert!(err.user_info_value("Attempts").unwrap().contains('3'));
+    }
+
+    #[test]
+    #[cfg(not(target_vendor = "apple"))]
+    fn display_fallback() {
+        assert_eq!(
+            Error::from_code(-9807).to_string(),
+            "errSSLXCertChainInvalid (-9807): Invalid certificate chain."
+        );
+        assert_eq!(Error::from_code(12345).to_string(), "error code 12345");
+    }
+}
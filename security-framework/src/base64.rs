@@ -0,0 +1,91 @@
+//! Standard base64 (RFC 4648) with padding.
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub(crate) fn encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |acc, (i, &b)| acc | u32::from(b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(char::from(ALPHABET[(n >> (18 - 6 * i)) as usize & 63]));
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn value(c: u8) -> Option<u32> {
+    Some(u32::from(match c {
+        b'A'..=b'Z' => c - b'A',
+        b'a'..=b'z' => c - b'a' + 26,
+        b'0'..=b'9' => c - b'0' + 52,
+        b'+' => 62,
+        b'/' => 63,
+        _ => return None,
+    }))
+}
+
+/// Decodes canonical padded base64. ASCII whitespace is skipped.
+pub(crate) fn decode(text: &[u8]) -> Option<Vec<u8>> {
+    let chars: Vec<u8> = text.iter().copied().filter(|c| !c.is_ascii_whitespace()).collect();
+    if chars.len() % 4 != 0 {
+        return None;
+    }
+    let mut out = Vec::with_capacity(chars.len() / 4 * 3);
+    let quads = chars.len() / 4;
+    for (q, quad) in chars.chunks_exact(4).enumerate() {
+        let padding = quad.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 || (padding > 0 && q + 1 != quads) {
+            return None;
+        }
+        let mut n = 0;
+        for &c in &quad[..4 - padding] {
+            n = n << 6 | value(c)?;
+        }
+        n <<= 6 * padding;
+        let bytes = [(n >> 16) as u8, (n >> 8) as u8, n as u8];
+        // non-zero bits hidden by the padding are not canonical
+        if bytes[3 - padding..].iter().any(|&b| b != 0) {
+            return None;
+        }
+        out.extend_from_slice(&bytes[..3 - padding]);
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rfc4648_vectors() {
+        let vectors = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (plain, encoded) in vectors {
+            assert_eq!(encode(plain.as_bytes()), encoded);
+            assert_eq!(decode(encoded.as_bytes()).unwrap(), plain.as_bytes());
+        }
+        let all: Vec<u8> = (0..=255).collect();
+        assert_eq!(decode(encode(&all).as_bytes()).unwrap(), all);
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(decode(b"Zm9v\r\nYmFy\n").unwrap(), b"foobar");
+        assert!(decode(b"Zm9").is_none());
+        assert!(decode(b"Zm==Zm9v").is_none());
+        assert!(decode(b"Z===").is_none());
+        assert!(decode(b"Zh==").is_none());
+        assert!(decode(b"Zm9-").is_none());
+    }
+}
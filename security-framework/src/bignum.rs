@@ -0,0 +1,258 @@
+//! Minimal unsigned big integers with Montgomery arithmetic, for the RSA and ECDSA verifiers.
+//!
+//! This is only used with public data or test keys, and is not constant-time.
+
+#![allow(clippy::many_single_char_names)] // variable names follow the usual bignum notation
+
+/// Unsigned big integer, as little-endian 64-bit limbs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Uint(pub(crate) Vec<u64>);
+
+impl Uint {
+    pub fn from_be_bytes(bytes: &[u8]) -> Self {
+        let limbs = bytes.rchunks(8)
+            .map(|chunk| chunk.iter().fold(0u64, |acc, &b| (acc << 8) | u64::from(b)))
+            .collect();
+        let mut n = Self(limbs);
+        n.normalize();
+        n
+    }
+
+    pub fn to_be_bytes(&self, len: usize) -> Vec<u8> {
+        let mut out: Vec<u8> = self.0.iter().rev().flat_map(|limb| limb.to_be_bytes()).collect();
+        let skip = out.len().saturating_sub(len);
+        out.drain(..skip);
+        let mut padded = vec![0; len - out.len()];
+        padded.extend(out);
+        padded
+    }
+
+    pub fn normalize(&mut self) {
+        while self.0.last() == Some(&0) {
+            self.0.pop();
+        }
+    }
+
+    pub fn bits(&self) -> usize {
+        self.0.last().map_or(0, |top| self.0.len() * 64 - top.leading_zeros() as usize)
+    }
+
+    pub fn bit(&self, i: usize) -> bool {
+        self.0.get(i / 64).is_some_and(|limb| limb >> (i % 64) & 1 == 1)
+    }
+
+    pub fn is_odd(&self) -> bool {
+        self.0.first().is_some_and(|limb| limb & 1 == 1)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Whether `self < other`, for normalized values.
+    pub fn less_than(&self, other: &Self) -> bool {
+        self.0.len() < other.0.len() || (self.0.len() == other.0.len() && less_than(&self.0, &other.0))
+    }
+}
+
+/// Compares limb slices of equal length.
+pub(crate) fn less_than(a: &[u64], b: &[u64]) -> bool {
+    for (x, y) in a.iter().rev().zip(b.iter().rev()) {
+        if x != y {
+            return x < y;
+        }
+    }
+    false
+}
+
+/// `a -= b`, for `a >= b` of equal length. Returns the borrow.
+pub(crate) fn sub_in_place(a: &mut [u64], b: &[u64]) -> bool {
+    let mut borrow = false;
+    for (x, &y) in a.iter_mut().zip(b) {
+        let (d, b1) = x.overflowing_sub(y);
+        let (d, b2) = d.overflowing_sub(u64::from(borrow));
+        *x = d;
+        borrow = b1 || b2;
+    }
+    borrow
+}
+
+/// `a += b` for slices of equal length. Returns the carry.
+fn add_in_place(a: &mut [u64], b: &[u64]) -> bool {
+    let mut carry = false;
+    for (x, &y) in a.iter_mut().zip(b) {
+        let (s, c1) = x.overflowing_add(y);
+        let (s, c2) = s.overflowing_add(u64::from(carry));
+        *x = s;
+        carry = c1 || c2;
+    }
+    carry
+}
+
+/// Arithmetic modulo an odd modulus in Montgomery form.
+///
+/// Values are slices of as many limbs as the modulus, and less than it.
+pub(crate) struct Montgomery {
+    modulus: Vec<u64>,
+    /// `-modulus⁻¹ mod 2⁶⁴`
+    m_inv: u64,
+    /// `R² mod modulus`, with `R = 2^(64·limbs)`
+    r_squared: Vec<u64>,
+}
+
+impl Montgomery {
+    pub fn new(modulus: &Uint) -> Self {
+        let n = modulus.0.clone();
+        let mut inv = 1u64;
+        for _ in 0..6 {
+            inv = inv.wrapping_mul(2u64.wrapping_sub(n[0].wrapping_mul(inv)));
+        }
+        // R² mod n by doubling 1 until it's shifted by 2·64·limbs bits
+        let k = n.len();
+        let mut r = vec![0u64; k];
+        r[0] = 1;
+        for _ in 0..128 * k {
+            let carry = r[k - 1] >> 63;
+            for i in (1..k).rev() {
+                r[i] = r[i] << 1 | r[i - 1] >> 63;
+            }
+            r[0] <<= 1;
+            if carry == 1 || !less_than(&r, &n) {
+                sub_in_place(&mut r, &n);
+            }
+        }
+        Self { modulus: n, m_inv: inv.wrapping_neg(), r_squared: r }
+    }
+
+    /// The number of limbs of values.
+    pub fn limbs(&self) -> usize {
+        self.modulus.len()
+    }
+
+    /// `a·b·R⁻¹ mod n`
+    pub fn mul(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let n = &self.modulus;
+        let k = n.len();
+        let mut t = vec![0u64; k + 2];
+        for &ai in a {
+            let mut carry = 0u128;
+            for j in 0..k {
+                let s = u128::from(t[j]) + u128::from(ai) * u128::from(b[j]) + carry;
+                t[j] = s as u64;
+                carry = s >> 64;
+            }
+            let s = u128::from(t[k]) + carry;
+            t[k] = s as u64;
+            t[k + 1] = (s >> 64) as u64;
+
+            let m = t[0].wrapping_mul(self.m_inv);
+            let mut carry = (u128::from(t[0]) + u128::from(m) * u128::from(n[0])) >> 64;
+            for j in 1..k {
+                let s = u128::from(t[j]) + u128::from(m) * u128::from(n[j]) + carry;
+                t[j - 1] = s as u64;
+                carry = s >> 64;
+            }
+            let s = u128::from(t[k]) + carry;
+            t[k - 1] = s as u64;
+            t[k] = t[k + 1] + (s >> 64) as u64;
+            t[k + 1] = 0;
+        }
+        if t[k] != 0 || !less_than(&t[..k], n) {
+            let (low, _) = t.split_at_mut(k);
+            sub_in_place(low, n);
+        }
+        t.truncate(k);
+        t
+    }
+
+    /// `a + b mod n`
+    pub fn add(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let mut sum = a.to_vec();
+        if add_in_place(&mut sum, b) || !less_than(&sum, &self.modulus) {
+            sub_in_place(&mut sum, &self.modulus);
+        }
+        sum
+    }
+
+    /// `a - b mod n`
+    pub fn sub(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let mut difference = a.to_vec();
+        if sub_in_place(&mut difference, b) {
+            add_in_place(&mut difference, &self.modulus);
+        }
+        difference
+    }
+
+    /// Converts `a < n` to Montgomery form, padding it to the limbs of the modulus.
+    pub fn to_montgomery(&self, a: &Uint) -> Vec<u64> {
+        let mut limbs = a.0.clone();
+        limbs.resize(self.limbs(), 0);
+        self.mul(&limbs, &self.r_squared)
+    }
+
+    /// Converts `a` out of Montgomery form.
+    pub fn to_normal(&self, a: &[u64]) -> Uint {
+        let mut one = vec![0u64; self.limbs()];
+        one[0] = 1;
+        let mut result = Uint(self.mul(a, &one));
+        result.normalize();
+        result
+    }
+
+    /// `base^exp mod n`, for `base < n`
+    pub fn pow(&self, base: &Uint, exp: &Uint) -> Uint {
+        let base_m = self.to_montgomery(base);
+        let mut acc = self.to_montgomery(&Uint(vec![1]));
+        for i in (0..exp.bits()).rev() {
+            acc = self.mul(&acc, &acc);
+            if exp.bit(i) {
+                acc = self.mul(&acc, &base_m);
+            }
+        }
+        self.to_normal(&acc)
+    }
+
+    /// `a⁻¹ mod n` for `0 < a < n` and a prime modulus, by Fermat's little theorem.
+    pub fn inverse(&self, a: &Uint) -> Uint {
+        let mut exp = self.modulus.clone();
+        let mut two = vec![0u64; self.limbs()];
+        two[0] = 2;
+        sub_in_place(&mut exp, &two);
+        let mut exp = Uint(exp);
+        exp.normalize();
+        self.pow(a, &exp)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn small_numbers() {
+        let m = Montgomery::new(&Uint(vec![3233]));
+        let x = m.pow(&Uint(vec![65]), &Uint(vec![17]));
+        assert_eq!(x, Uint(vec![2790]));
+        assert_eq!(m.pow(&x, &Uint(vec![413])), Uint(vec![65]));
+
+        let n = Uint::from_be_bytes(&hex::decode("fffffffffffffffffffffffffffffff1").unwrap());
+        let m = Montgomery::new(&n);
+        let two = Uint(vec![2]);
+        // 2^127 mod n = 2^127 (it's smaller than n)
+        assert_eq!(m.pow(&two, &Uint(vec![127])).to_be_bytes(16), hex::decode("80000000000000000000000000000000").unwrap());
+        // 2^128 mod n = 15
+        assert_eq!(m.pow(&two, &Uint(vec![128])), Uint(vec![15]));
+    }
+
+    #[test]
+    fn add_sub_inverse() {
+        // 2^61 - 1 is prime
+        let p = Uint(vec![(1 << 61) - 1]);
+        let m = Montgomery::new(&p);
+        assert_eq!(m.add(&[(1 << 61) - 2], &[5]), [4]);
+        assert_eq!(m.sub(&[3], &[5]), [(1 << 61) - 3]);
+        let inverse = m.inverse(&Uint(vec![12345]));
+        let product = m.mul(&m.to_montgomery(&inverse), &[12345]);
+        assert_eq!(product, [1]);
+    }
+}
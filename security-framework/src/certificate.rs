@@ -1,38 +1,96 @@
 //! Certificate support.
+//!
+//! [`Parsed`] and the other pure-Rust types here are available on all platforms.
+#[cfg(target_vendor = "apple")]
 use core_foundation::array::{CFArray, CFArrayRef};
+#[cfg(target_vendor = "apple")]
 use core_foundation::base::{TCFType, ToVoid};
+#[cfg(target_vendor = "apple")]
 use core_foundation::data::CFData;
+#[cfg(target_vendor = "apple")]
 use core_foundation::dictionary::CFMutableDictionary;
+#[cfg(target_vendor = "apple")]
 use core_foundation::string::CFString;
+#[cfg(target_vendor = "apple")]
 use core_foundation::{declare_TCFType, impl_TCFType};
+#[cfg(target_vendor = "apple")]
 use core_foundation_sys::base::kCFAllocatorDefault;
 #[cfg(any(target_os = "ios", target_os = "tvos", target_os = "watchos", target_os = "visionos"))]
 use security_framework_sys::base::{errSecNotTrusted, errSecSuccess};
+#[cfg(target_vendor = "apple")]
 use security_framework_sys::base::{errSecParam, SecCertificateRef};
+#[cfg(target_vendor = "apple")]
 use security_framework_sys::certificate::*;
+#[cfg(target_vendor = "apple")]
 use security_framework_sys::keychain_item::SecItemDelete;
+#[cfg(target_vendor = "apple")]
 use std::fmt;
+#[cfg(target_vendor = "apple")]
 use std::ptr;
 
+#[cfg(target_vendor = "apple")]
 use crate::base::{Error, Result};
+#[cfg(target_vendor = "apple")]
 use crate::cvt;
+#[cfg(target_vendor = "apple")]
 use crate::key;
 #[cfg(target_os = "macos")]
 use crate::os::macos::keychain::SecKeychain;
+#[cfg(target_vendor = "apple")]
 use core_foundation::base::FromVoid;
+#[cfg(target_vendor = "apple")]
 use core_foundation::error::{CFError, CFErrorRef};
+#[cfg(target_vendor = "apple")]
 use core_foundation::number::CFNumber;
+#[cfg(target_vendor = "apple")]
 use security_framework_sys::item::kSecValueRef;
 
+mod alt_name;
+mod builder;
+mod chain;
+mod csr;
+pub mod expiry;
+mod extensions;
+#[cfg(test)]
+pub(crate) mod fixtures;
+mod key_id;
+mod name;
+mod parsed;
+
+use crate::pem;
+pub use crate::der::Oid;
+pub use alt_name::{dns_name_matches, matches_host, GeneralName};
+pub use builder::{CertificateBuilder, Signer};
+pub use chain::{Chain, ChainBuilder};
+pub use csr::CsrBuilder;
+pub use extensions::{BasicConstraints, KeyUsage};
+pub use crate::digest::{DigestAlgorithm, Fingerprint};
+pub use key_id::AuthorityKeyIdentifier;
+pub use name::{AttributeTypeAndValue, DistinguishedName, RelativeDistinguishedName};
+pub use parsed::{AlgorithmIdentifier, Extension, Parsed, SubjectPublicKeyInfo};
+
+/// Decodes all PEM blocks in a bundle, e.g. a CA file.
+///
+/// Each block has its label (such as [`pem::CERTIFICATE`]) and DER contents.
+/// Text between blocks is ignored.
+pub fn parse_pem_bundle(pem: &[u8]) -> crate::base::Result<Vec<pem::Block>> {
+    pem::parse(pem)
+}
+
+#[cfg(target_vendor = "apple")]
 declare_TCFType! {
     /// A type representing a certificate.
     SecCertificate, SecCertificateRef
 }
+#[cfg(target_vendor = "apple")]
 impl_TCFType!(SecCertificate, SecCertificateRef, SecCertificateGetTypeID);
 
+#[cfg(target_vendor = "apple")]
 unsafe impl Sync for SecCertificate {}
+#[cfg(target_vendor = "apple")]
 unsafe impl Send for SecCertificate {}
 
+#[cfg(target_vendor = "apple")]
 impl fmt::Debug for SecCertificate {
     #[cold]
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -42,6 +100,7 @@ impl fmt::Debug for SecCertificate {
     }
 }
 
+#[cfg(target_vendor = "apple")]
 impl SecCertificate {
     /// Creates a `SecCertificate` from DER encoded certificate data.
     pub fn from_der(der_data: &[u8]) -> Result<Self> {
@@ -65,6 +124,23 @@ impl SecCertificate {
         }
     }
 
+    /// Creates a `SecCertificate` from the first `CERTIFICATE` block of PEM data.
+    pub fn from_pem(pem_data: &[u8]) -> Result<Self> {
+        Self::from_der(&pem::parse_first(pem_data, pem::CERTIFICATE)?)
+    }
+
+    /// Returns the certificate as a PEM `CERTIFICATE` block.
+    #[must_use]
+    pub fn to_pem(&self) -> String {
+        pem::encode(pem::CERTIFICATE, &self.to_der())
+    }
+
+    /// Decodes the certificate's fields without going through Security.framework,
+    /// so that the results are the same on all platforms.
+    pub fn parse(&self) -> Result<Parsed> {
+        Parsed::from_der(&self.to_der())
+    }
+
     /// Adds a certificate to a keychain.
     #[cfg(target_os = "macos")]
     pub fn add_to_keychain(&self, keychain: Option<SecKeychain>) -> Result<()> {
@@ -118,6 +194,57 @@ impl SecCertificate {
         }
     }
 
+    /// Returns the issuer name as it appears in the certificate.
+    ///
+    /// Unlike [`SecCertificate::issuer`], the name is not normalized.
+    pub fn issuer_name(&self) -> Result<DistinguishedName> {
+        Ok(self.parse()?.issuer().clone())
+    }
+
+    /// Returns the subject name as it appears in the certificate.
+    ///
+    /// Unlike [`SecCertificate::subject`], the name is not normalized.
+    pub fn subject_name(&self) -> Result<DistinguishedName> {
+        Ok(self.parse()?.subject().clone())
+    }
+
+    /// Returns a hash of the DER-encoded certificate.
+    ///
+    /// This is computed without Security.framework, so all algorithms are available on all platforms.
+    /// On macOS, `SecCertificateExt::fingerprint` also returns the SHA-256 hash.
+    #[must_use]
+    pub fn fingerprint_with(&self, algorithm: DigestAlgorithm) -> Fingerprint {
+        Fingerprint::new(algorithm, &self.to_der())
+    }
+
+    /// Returns the base64 SHA-256 hash of the certificate's `SubjectPublicKeyInfo`,
+    /// as used for `pin-sha256` certificate pinning.
+    pub fn spki_sha256_pin(&self) -> Result<String> {
+        Ok(self.parse()?.spki_sha256_pin())
+    }
+
+    /// Returns the subject key identifier extension, if present.
+    pub fn subject_key_identifier(&self) -> Result<Option<Vec<u8>>> {
+        self.parse()?.subject_key_identifier()
+    }
+
+    /// Returns the authority key identifier extension, if present.
+    pub fn authority_key_identifier(&self) -> Result<Option<AuthorityKeyIdentifier>> {
+        self.parse()?.authority_key_identifier()
+    }
+
+    /// Returns the entries of the subject alternative name extension, or an empty list if there is none.
+    pub fn subject_alt_names(&self) -> Result<Vec<GeneralName>> {
+        self.parse()?.subject_alt_names()
+    }
+
+    /// Checks whether this certificate is valid for `host` (a DNS name or an IP address)
+    /// according to its subject alternative names, following RFC 6125.
+    #[must_use]
+    pub fn matches_host(&self, host: &str) -> bool {
+        self.parse().is_ok_and(|cert| cert.matches_host(host))
+    }
+
     /// Returns DER encoded serial number of the certificate.
     pub fn serial_number_bytes(&self) -> Result<Vec<u8>, CFError> {
         unsafe {
@@ -144,24 +271,7 @@ impl SecCertificate {
     #[allow(clippy::unused_self)]
     #[allow(clippy::needless_pass_by_value)]
     fn pk_to_der(&self, public_key: key::SecKey) -> Option<Vec<u8>> {
-        use security_framework_sys::item::{kSecAttrKeySizeInBits, kSecAttrKeyType};
-
-        let public_key_attributes = public_key.attributes();
-        let public_key_type = public_key_attributes
-            .find(unsafe { kSecAttrKeyType }.cast::<std::os::raw::c_void>())?;
-        let public_keysize = public_key_attributes
-            .find(unsafe { kSecAttrKeySizeInBits }.cast::<std::os::raw::c_void>())?;
-        let public_keysize = unsafe { CFNumber::from_void(*public_keysize) };
-        let public_keysize_val = public_keysize.to_i64()? as u32;
-        let hdr_bytes = get_asn1_header_bytes(
-            unsafe { CFString::wrap_under_get_rule((*public_key_type).cast()) },
-            public_keysize_val,
-        )?;
-        let public_key_data = public_key.external_representation()?;
-        let mut out = Vec::with_capacity(hdr_bytes.len() + public_key_data.len() as usize);
-        out.extend_from_slice(hdr_bytes);
-        out.extend_from_slice(public_key_data.bytes());
-        Some(out)
+        public_key_info_der(&public_key)
     }
 
     /// Get public key from certificate
@@ -194,6 +304,33 @@ impl SecCertificate {
     }
 }
 
+/// The key type (e.g. `kSecAttrKeyTypeRSA`) and size in bits of a key.
+#[cfg(target_vendor = "apple")]
+pub(crate) fn key_type_and_size(key: &key::SecKey) -> Option<(CFString, u32)> {
+    use security_framework_sys::item::{kSecAttrKeySizeInBits, kSecAttrKeyType};
+
+    let attributes = key.attributes();
+    let key_type = attributes.find(unsafe { kSecAttrKeyType }.cast::<std::os::raw::c_void>())?;
+    let key_size = attributes.find(unsafe { kSecAttrKeySizeInBits }.cast::<std::os::raw::c_void>())?;
+    let key_size = unsafe { CFNumber::from_void(*key_size) }.to_i64()? as u32;
+    Some((unsafe { CFString::wrap_under_get_rule((*key_type).cast()) }, key_size))
+}
+
+/// DER-encoded `SubjectPublicKeyInfo` of a public key.
+///
+/// Supports 2048 and 4096-bit RSA keys and P-256 and P-384 EC keys.
+#[cfg(target_vendor = "apple")]
+pub(crate) fn public_key_info_der(public_key: &key::SecKey) -> Option<Vec<u8>> {
+    let (key_type, key_size) = key_type_and_size(public_key)?;
+    let hdr_bytes = get_asn1_header_bytes(key_type, key_size)?;
+    let public_key_data = public_key.external_representation()?;
+    let mut out = Vec::with_capacity(hdr_bytes.len() + public_key_data.len() as usize);
+    out.extend_from_slice(hdr_bytes);
+    out.extend_from_slice(public_key_data.bytes());
+    Some(out)
+}
+
+#[cfg(target_vendor = "apple")]
 fn get_asn1_header_bytes(pkt: CFString, ksz: u32) -> Option<&'static [u8]> {
     use security_framework_sys::item::{kSecAttrKeyTypeECSECPrimeRandom, kSecAttrKeyTypeRSA};
 
@@ -212,28 +349,33 @@ fn get_asn1_header_bytes(pkt: CFString, ksz: u32) -> Option<&'static [u8]> {
     None
 }
 
+#[cfg(target_vendor = "apple")]
 const RSA_2048_ASN1_HEADER: [u8; 24] = [
     0x30, 0x82, 0x01, 0x22, 0x30, 0x0d, 0x06, 0x09, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01,
     0x01, 0x05, 0x00, 0x03, 0x82, 0x01, 0x0f, 0x00,
 ];
 
+#[cfg(target_vendor = "apple")]
 const RSA_4096_ASN1_HEADER: [u8; 24] = [
     0x30, 0x82, 0x02, 0x22, 0x30, 0x0d, 0x06, 0x09, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01,
     0x01, 0x05, 0x00, 0x03, 0x82, 0x02, 0x0f, 0x00,
 ];
 
+#[cfg(target_vendor = "apple")]
 const EC_DSA_SECP_256_R1_ASN1_HEADER: [u8; 26] = [
     0x30, 0x59, 0x30, 0x13, 0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01, 0x06, 0x08, 0x2a,
     0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07, 0x03, 0x42, 0x00,
 ];
 
+#[cfg(target_vendor = "apple")]
 const EC_DSA_SECP_384_R1_ASN1_HEADER: [u8; 23] = [
     0x30, 0x76, 0x30, 0x10, 0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01, 0x06, 0x05, 0x2b,
     0x81, 0x04, 0x00, 0x22, 0x03, 0x62, 0x00,
 ];
 
-#[cfg(test)]
+#[cfg(all(test, target_vendor = "apple"))]
 mod test {
+    use super::DigestAlgorithm;
     use crate::test::certificate;
     use x509_parser::prelude::*;
 
@@ -261,6 +403,51 @@ mod test {
         );
     }
 
+    #[test]
+    fn parse() {
+        let cert = certificate();
+        let parsed = cert.parse().unwrap();
+        assert_eq!(parsed.serial_number(), cert.serial_number_bytes().unwrap());
+        assert_eq!(parsed.public_key_info().der(), cert.public_key_info_der().unwrap().unwrap());
+    }
+
+    #[test]
+    fn fingerprint() {
+        let cert = certificate();
+        assert_eq!(
+            cert.fingerprint_with(DigestAlgorithm::Sha1).to_hex(),
+            "cac2dd809452a4834f549500559ca3e7f792e545"
+        );
+        assert_eq!(cert.spki_sha256_pin().unwrap(), "bB7vC0XX1gJaYSDgYzK0ua9gRpduQgzYidZVUofVwso=");
+        assert_eq!(cert.subject_key_identifier().unwrap().unwrap().len(), 20);
+        assert!(cert.authority_key_identifier().unwrap().unwrap().key_identifier().is_some());
+    }
+
+    #[test]
+    fn pem() {
+        let cert = certificate();
+        let pem = cert.to_pem();
+        assert_eq!(super::SecCertificate::from_pem(pem.as_bytes()).unwrap(), cert);
+        let blocks = super::parse_pem_bundle(pem.as_bytes()).unwrap();
+        assert_eq!(blocks[0].der(), cert.to_der());
+    }
+
+    #[test]
+    fn subject_alt_names() {
+        let cert = certificate();
+        assert_eq!(cert.subject_alt_names().unwrap().len(), 3);
+        assert!(cert.matches_host("localhost"));
+        assert!(!cert.matches_host("example.com"));
+    }
+
+    #[test]
+    fn subject_name() {
+        let cert = certificate();
+        let name = cert.subject_name().unwrap();
+        assert_eq!(name.common_name(), Some("foobar.com"));
+        assert!(name.matches(&cert.issuer_name().unwrap()));
+    }
+
     #[test]
     fn subject() {
         let cert = certificate();
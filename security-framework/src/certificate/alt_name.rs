@@ -0,0 +1,314 @@
+//! Subject alternative names and hostname matching (RFC 5280 and RFC 6125).
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use super::name::DistinguishedName;
+use crate::base::Result;
+use crate::der::{self, Oid, Reader};
+
+/// An entry of the subject alternative name extension.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum GeneralName {
+    /// A name of a type identified by an OID, e.g. a Microsoft UPN.
+    OtherName {
+        /// The type of the name.
+        type_id: Oid,
+        /// The DER encoding of the value (including its tag).
+        value: Vec<u8>,
+    },
+    /// An email address.
+    Rfc822Name(String),
+    /// A DNS name, possibly with a wildcard.
+    DnsName(String),
+    /// An X.400 address, as DER.
+    X400Address(Vec<u8>),
+    /// A directory name.
+    DirectoryName(DistinguishedName),
+    /// An EDI party name, as DER.
+    EdiPartyName(Vec<u8>),
+    /// A URI, e.g. a SPIFFE ID.
+    Uri(String),
+    /// An IPv4 or IPv6 address.
+    IpAddress(IpAddr),
+    /// A registered OID.
+    RegisteredId(Oid),
+}
+
+impl GeneralName {
+    pub(crate) fn parse(reader: &mut Reader<'_>) -> Result<Self> {
+        let tlv = reader.read_tlv()?;
+        let ia5 = |content: &[u8]| {
+            der::decode_string(der::IA5_STRING, content).ok_or_else(der::decode_error)
+        };
+        Ok(match tlv.tag {
+            t if t == der::context(0) => {
+                let mut other = Reader::new(tlv.content);
+                let type_id = other.read_oid()?;
+                let value = other.read_nested(der::context(0))?.read_tlv()?.raw.to_vec();
+                other.finish()?;
+                Self::OtherName { type_id, value }
+            },
+            t if t == der::context_primitive(1) => Self::Rfc822Name(ia5(tlv.content)?),
+            t if t == der::context_primitive(2) => Self::DnsName(ia5(tlv.content)?),
+            t if t == der::context(3) => Self::X400Address(tlv.content.to_vec()),
+            t if t == der::context(4) => {
+                let mut explicit = Reader::new(tlv.content);
+                let name = explicit.read_tlv()?;
+                explicit.finish()?;
+                Self::DirectoryName(DistinguishedName::from_der(name.raw)?)
+            },
+            t if t == der::context(5) => Self::EdiPartyName(tlv.content.to_vec()),
+            t if t == der::context_primitive(6) => Self::Uri(ia5(tlv.content)?),
+            t if t == der::context_primitive(7) => Self::IpAddress(ip_from_bytes(tlv.content)?),
+            t if t == der::context_primitive(8) => Self::RegisteredId(Oid::from_der_content(tlv.content)?),
+            _ => return Err(der::decode_error()),
+        })
+    }
+
+    /// DER encoding, with the context-specific tag of the name type.
+    pub(crate) fn to_der(&self) -> Vec<u8> {
+        match self {
+            Self::OtherName { type_id, value } => {
+                let content = [der::encode_oid(type_id), der::encode(der::context(0), value)].concat();
+                der::encode(der::context(0), &content)
+            },
+            Self::Rfc822Name(name) => der::encode(der::context_primitive(1), name.as_bytes()),
+            Self::DnsName(name) => der::encode(der::context_primitive(2), name.as_bytes()),
+            Self::X400Address(content) => der::encode(der::context(3), content),
+            Self::DirectoryName(name) => der::encode(der::context(4), &name.to_der()),
+            Self::EdiPartyName(content) => der::encode(der::context(5), content),
+            Self::Uri(uri) => der::encode(der::context_primitive(6), uri.as_bytes()),
+            Self::IpAddress(IpAddr::V4(ip)) => der::encode(der::context_primitive(7), &ip.octets()),
+            Self::IpAddress(IpAddr::V6(ip)) => der::encode(der::context_primitive(7), &ip.octets()),
+            Self::RegisteredId(oid) => der::encode(der::context_primitive(8), oid.as_der_content()),
+        }
+    }
+}
+
+/// Encodes a `GeneralNames` SEQUENCE, e.g. the value of the subject alternative name extension.
+pub(crate) fn encode_general_names(names: &[GeneralName]) -> Vec<u8> {
+    let names: Vec<_> = names.iter().map(GeneralName::to_der).collect();
+    der::encode(der::SEQUENCE, &names.concat())
+}
+
+fn ip_from_bytes(bytes: &[u8]) -> Result<IpAddr> {
+    match bytes.len() {
+        4 => Ok(IpAddr::V4(Ipv4Addr::from(<[u8; 4]>::try_from(bytes).unwrap()))),
+        16 => Ok(IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(bytes).unwrap()))),
+        _ => Err(der::decode_error()),
+    }
+}
+
+/// Decodes the value of the subject alternative name extension.
+pub(crate) fn parse_general_names(value: &[u8]) -> Result<Vec<GeneralName>> {
+    let mut reader = Reader::new(value);
+    let mut seq = reader.read_sequence()?;
+    reader.finish()?;
+    let mut names = Vec::new();
+    while !seq.is_empty() {
+        names.push(GeneralName::parse(&mut seq)?);
+    }
+    if names.is_empty() {
+        return Err(der::decode_error());
+    }
+    Ok(names)
+}
+
+/// Checks whether `hostname` matches a DNS name `pattern` from a certificate, following RFC 6125.
+///
+/// * Comparison is ASCII case-insensitive, and a trailing dot on either side is ignored.
+///   Internationalized names must be in their A-label (`xn--`) form.
+/// * A wildcard is only allowed as the entire left-most label of the pattern (`*.example.com`),
+///   and matches exactly one non-empty label. Partial wildcards such as `f*.example.com` never match.
+/// * Wildcards are not honored directly under a top-level domain (`*.com`).
+/// * IP address literals never match a wildcard.
+#[must_use]
+pub fn dns_name_matches(pattern: &str, hostname: &str) -> bool {
+    let pattern = pattern.strip_suffix('.').unwrap_or(pattern);
+    let hostname = hostname.strip_suffix('.').unwrap_or(hostname);
+    if !is_valid_hostname(hostname) {
+        return false;
+    }
+
+    let Some(suffix) = pattern.strip_prefix("*.") else {
+        return !pattern.contains('*') && pattern.eq_ignore_ascii_case(hostname);
+    };
+    if suffix.contains('*') || suffix.split('.').count() < 2 || suffix.split('.').any(str::is_empty) {
+        return false;
+    }
+    if hostname.parse::<Ipv4Addr>().is_ok() {
+        return false;
+    }
+    match hostname.split_once('.') {
+        Some((_, host_suffix)) => host_suffix.eq_ignore_ascii_case(suffix),
+        None => false,
+    }
+}
+
+fn is_valid_hostname(hostname: &str) -> bool {
+    !hostname.is_empty()
+        && hostname.len() <= 253
+        && hostname.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && label.bytes().all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_')
+        })
+}
+
+/// Checks whether a certificate with these subject alternative names is valid for `host`.
+///
+/// `host` may be a DNS name, or an IPv4 or IPv6 address (optionally in brackets), which is only
+/// compared with [`GeneralName::IpAddress`] entries. The subject's common name is not consulted.
+#[must_use]
+pub fn matches_host(names: &[GeneralName], host: &str) -> bool {
+    let literal = host.strip_prefix('[').and_then(|h| h.strip_suffix(']')).unwrap_or(host);
+    if let Ok(ip) = literal.parse::<IpAddr>() {
+        return names.iter().any(|name| matches!(name, GeneralName::IpAddress(addr) if *addr == ip));
+    }
+    names.iter().any(|name| match name {
+        GeneralName::DnsName(pattern) => dns_name_matches(pattern, host),
+        _ => false,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_server_der() {
+        let cert = crate::certificate::Parsed::from_der(include_bytes!("../../test/server.der")).unwrap();
+        assert_eq!(cert.subject_alt_names().unwrap(), [
+            GeneralName::DnsName("foobar.com".into()),
+            GeneralName::DnsName("localhost".into()),
+            GeneralName::IpAddress(IpAddr::V4(Ipv4Addr::LOCALHOST)),
+        ]);
+        assert!(cert.matches_host("FOOBAR.com."));
+        assert!(cert.matches_host("127.0.0.1"));
+        assert!(!cert.matches_host("www.foobar.com"));
+        assert!(!cert.matches_host("::1"));
+    }
+
+    #[test]
+    fn parse_all_kinds() {
+        // otherName (UPN), rfc822, URI, IPv6, directoryName, registeredID
+        let value = hex::decode(concat!(
+            "308188",
+            "a01b060a2b060104018237140203a00d0c0b7573657240636f72702e78",
+            "8111616c696365406578616d706c652e636f6d",
+            "86227370696666653a2f2f6578616d706c652e6f72672f6e732f64656661756c742f7361",
+            "871000000000000000000000000000000001",
+            "a41b3019311730150603550403130e4578616d706c6520506572736f6e",
+            "8803551d11",
+        )).unwrap();
+        let names = parse_general_names(&value).unwrap();
+        assert_eq!(names.len(), 6);
+        assert_eq!(names[0], GeneralName::OtherName {
+            type_id: "1.3.6.1.4.1.311.20.2.3".parse().unwrap(),
+            value: hex::decode("0c0b7573657240636f72702e78").unwrap(),
+        });
+        assert_eq!(names[1], GeneralName::Rfc822Name("alice@example.com".into()));
+        assert_eq!(names[2], GeneralName::Uri("spiffe://example.org/ns/default/sa".into()));
+        assert_eq!(names[3], GeneralName::IpAddress(IpAddr::V6(Ipv6Addr::LOCALHOST)));
+        match &names[4] {
+            GeneralName::DirectoryName(name) => assert_eq!(name.to_string(), "CN=Example Person"),
+            other => panic!("{other:?}"),
+        }
+        assert_eq!(names[5], GeneralName::RegisteredId(Oid::SUBJECT_ALT_NAME));
+        assert_eq!(encode_general_names(&names), value);
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!(parse_general_names(&[0x30, 0x00]).is_err());
+        // 5-byte IP address
+        assert!(parse_general_names(&[0x30, 0x07, 0x87, 0x05, 1, 2, 3, 4, 5]).is_err());
+        // unknown tag
+        assert!(parse_general_names(&[0x30, 0x02, 0x89, 0x00]).is_err());
+        // trailing data
+        assert!(parse_general_names(&[0x30, 0x02, 0x82, 0x00, 0x00]).is_err());
+    }
+
+    #[test]
+    fn exact_names() {
+        assert!(dns_name_matches("example.com", "example.com"));
+        assert!(dns_name_matches("Example.COM", "eXample.com"));
+        assert!(dns_name_matches("example.com.", "example.com"));
+        assert!(dns_name_matches("example.com", "example.com."));
+        assert!(dns_name_matches("localhost", "localhost"));
+        assert!(dns_name_matches("xn--caf-dma.example", "XN--CAF-DMA.example"));
+        assert!(!dns_name_matches("example.com", "www.example.com"));
+        assert!(!dns_name_matches("www.example.com", "example.com"));
+        assert!(!dns_name_matches("example.com", "example.co"));
+        assert!(!dns_name_matches("example.com", "example.com.."));
+        assert!(!dns_name_matches("", ""));
+        assert!(!dns_name_matches("example.com", ""));
+        assert!(!dns_name_matches("", "example.com"));
+    }
+
+    #[test]
+    fn wildcards() {
+        assert!(dns_name_matches("*.example.com", "www.example.com"));
+        assert!(dns_name_matches("*.example.com", "WWW.EXAMPLE.COM"));
+        assert!(dns_name_matches("*.example.com.", "foo.example.com"));
+        assert!(dns_name_matches("*.a.example.com", "b.a.example.com"));
+        // exactly one label
+        assert!(!dns_name_matches("*.example.com", "example.com"));
+        assert!(!dns_name_matches("*.example.com", "a.b.example.com"));
+        assert!(!dns_name_matches("*.example.com", ".example.com"));
+        // only the whole left-most label
+        assert!(!dns_name_matches("w*.example.com", "www.example.com"));
+        assert!(!dns_name_matches("*w.example.com", "www.example.com"));
+        assert!(!dns_name_matches("xn--*.example.com", "xn--caf-dma.example.com"));
+        assert!(!dns_name_matches("www.*.com", "www.example.com"));
+        assert!(!dns_name_matches("*.*.example.com", "a.b.example.com"));
+        assert!(!dns_name_matches("*", "example"));
+        assert!(!dns_name_matches("*.", "example"));
+        // not directly under a TLD
+        assert!(!dns_name_matches("*.com", "example.com"));
+        assert!(!dns_name_matches("*..com", "a..com"));
+        // the hostname is never a pattern
+        assert!(!dns_name_matches("*.example.com", "*.example.com"));
+        assert!(!dns_name_matches("www.example.com", "*.example.com"));
+        // IP addresses don't match wildcards
+        assert!(!dns_name_matches("*.0.0.1", "127.0.0.1"));
+    }
+
+    #[test]
+    fn invalid_hostnames() {
+        assert!(!dns_name_matches("a b.com", "a b.com"));
+        assert!(!dns_name_matches("*.example.com", "a b.example.com"));
+        assert!(!dns_name_matches("a..com", "a..com"));
+        let long_label = "a".repeat(64);
+        assert!(!dns_name_matches(&long_label, &long_label));
+        assert!(dns_name_matches(&long_label[1..], &long_label[1..]));
+        assert!(dns_name_matches("under_score.example", "under_score.example"));
+    }
+
+    #[test]
+    fn hosts() {
+        let names = [
+            GeneralName::DnsName("*.example.com".into()),
+            GeneralName::DnsName("example.net".into()),
+            GeneralName::IpAddress("10.0.0.1".parse().unwrap()),
+            GeneralName::IpAddress("2001:db8::1".parse().unwrap()),
+            GeneralName::Rfc822Name("example.org".into()),
+            GeneralName::Uri("https://example.org".into()),
+        ];
+        assert!(matches_host(&names, "www.example.com"));
+        assert!(matches_host(&names, "example.net"));
+        assert!(matches_host(&names, "10.0.0.1"));
+        assert!(matches_host(&names, "2001:db8:0::1"));
+        assert!(matches_host(&names, "[2001:db8::1]"));
+        assert!(!matches_host(&names, "example.org"));
+        assert!(!matches_host(&names, "10.0.0.2"));
+        assert!(!matches_host(&names, "::ffff:10.0.0.1"));
+        assert!(!matches_host(&names, "example.com"));
+        assert!(!matches_host(&[], "example.com"));
+
+        // IP addresses don't match DNS entries, even if spelled the same
+        let names = [GeneralName::DnsName("127.0.0.1".into())];
+        assert!(!matches_host(&names, "127.0.0.1"));
+    }
+}
@@ -0,0 +1,452 @@
+//! Creating X.509 certificates.
+
+#[cfg(target_vendor = "apple")]
+use core_foundation::base::TCFType;
+#[cfg(target_vendor = "apple")]
+use core_foundation::string::CFString;
+#[cfg(target_vendor = "apple")]
+use security_framework_sys::base::errSecInternalComponent;
+use security_framework_sys::base::errSecParam;
+#[cfg(target_vendor = "apple")]
+use security_framework_sys::item::{kSecAttrKeyTypeECSECPrimeRandom, kSecAttrKeyTypeRSA};
+use std::time::{Duration, SystemTime};
+
+use super::alt_name::{self, GeneralName};
+use super::extensions::{self, BasicConstraints, KeyUsage};
+use super::name::DistinguishedName;
+use super::parsed::{AlgorithmIdentifier, Extension, Parsed, SubjectPublicKeyInfo};
+use crate::base::{Error, Result};
+use crate::der::{self, Oid, Reader};
+use crate::digest::DigestAlgorithm;
+#[cfg(target_vendor = "apple")]
+use crate::key::{Algorithm, SecKey};
+#[cfg(target_vendor = "apple")]
+use crate::random::SecRandom;
+
+/// Creates signatures for certificates and other signed structures.
+///
+/// `SecKey` implements this for RSA keys (PKCS #1 v1.5 with SHA-256) and
+/// EC keys (ECDSA with SHA-256, SHA-384 or SHA-512 depending on the curve).
+pub trait Signer {
+    /// The signature algorithm, which is included in the signed structure.
+    fn algorithm(&self) -> Result<AlgorithmIdentifier>;
+
+    /// Signs `data`, returning the signature in the form used by X.509
+    /// (e.g. a DER `Ecdsa-Sig-Value` for ECDSA).
+    fn sign(&self, data: &[u8]) -> Result<Vec<u8>>;
+}
+
+#[cfg(target_vendor = "apple")]
+fn key_algorithms(key: &SecKey) -> Result<(AlgorithmIdentifier, Algorithm)> {
+    let (key_type, key_size) = super::key_type_and_size(key).ok_or_else(|| Error::from_code(errSecParam))?;
+    if key_type == unsafe { CFString::wrap_under_get_rule(kSecAttrKeyTypeRSA) } {
+        return Ok((AlgorithmIdentifier::rsa_pkcs1(DigestAlgorithm::Sha256), Algorithm::RSASignatureMessagePKCS1v15SHA256));
+    }
+    if key_type == unsafe { CFString::wrap_under_get_rule(kSecAttrKeyTypeECSECPrimeRandom) } {
+        return Ok(match key_size {
+            384 => (AlgorithmIdentifier::ecdsa(DigestAlgorithm::Sha384)?, Algorithm::ECDSASignatureMessageX962SHA384),
+            521 => (AlgorithmIdentifier::ecdsa(DigestAlgorithm::Sha512)?, Algorithm::ECDSASignatureMessageX962SHA512),
+            _ => (AlgorithmIdentifier::ecdsa(DigestAlgorithm::Sha256)?, Algorithm::ECDSASignatureMessageX962SHA256),
+        });
+    }
+    Err(Error::from_code(errSecParam))
+}
+
+#[cfg(target_vendor = "apple")]
+impl Signer for SecKey {
+    fn algorithm(&self) -> Result<AlgorithmIdentifier> {
+        key_algorithms(self).map(|(id, _)| id)
+    }
+
+    fn sign(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let (_, algorithm) = key_algorithms(self)?;
+        self.create_signature(algorithm, data)
+            .map_err(|e| Error::from_code(e.code() as _))
+    }
+}
+
+/// Builds and signs an X.509 v3 certificate.
+///
+/// The certificate is self-signed unless [`CertificateBuilder::set_issuer`] is used.
+/// A subject key identifier is always added, and an authority key identifier when the issuer has one.
+///
+/// ```rust,ignore
+/// let mut builder = CertificateBuilder::new("CN=localhost".parse()?);
+/// builder
+///     .set_public_key(&key.public_key().unwrap())?
+///     .add_subject_alt_name(GeneralName::DnsName("localhost".into()))
+///     .set_extended_key_usage(&[Oid::SERVER_AUTH]);
+/// let der = builder.sign(&key)?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct CertificateBuilder {
+    subject: DistinguishedName,
+    serial_number: Option<Vec<u8>>,
+    not_before: SystemTime,
+    not_after: SystemTime,
+    public_key_info: Option<Vec<u8>>,
+    issuer: Option<(Vec<u8>, Option<Vec<u8>>)>,
+    extensions: ExtensionSet,
+}
+
+impl CertificateBuilder {
+    /// Starts a certificate for `subject`, valid for a year from now.
+    #[must_use]
+    pub fn new(subject: DistinguishedName) -> Self {
+        let now = SystemTime::now();
+        Self {
+            subject,
+            serial_number: None,
+            not_before: now,
+            not_after: now + Duration::from_secs(365 * 86400),
+            public_key_info: None,
+            issuer: None,
+            extensions: ExtensionSet::default(),
+        }
+    }
+
+    /// Sets the serial number, as big-endian unsigned bytes.
+    ///
+    /// RFC 5280 requires it to be positive and at most 20 octets once DER-encoded, which leaves
+    /// 159 bits; signing fails with `errSecParam` otherwise.
+    ///
+    /// On Apple platforms a random 16-byte serial number is generated by default.
+    /// Elsewhere there is no system random number generator to use, and signing fails
+    /// with `errSecParam` unless a serial number is set.
+    pub fn set_serial_number(&mut self, serial_number: &[u8]) -> &mut Self {
+        self.serial_number = Some(serial_number.to_vec());
+        self
+    }
+
+    /// Sets the validity period. Both ends are inclusive and stored with one-second precision.
+    pub fn set_validity(&mut self, not_before: SystemTime, not_after: SystemTime) -> &mut Self {
+        self.not_before = not_before;
+        self.not_after = not_after;
+        self
+    }
+
+    /// Sets the subject's public key from a DER-encoded `SubjectPublicKeyInfo`.
+    pub fn set_public_key_info(&mut self, public_key_info: &[u8]) -> Result<&mut Self> {
+        let mut reader = Reader::new(public_key_info);
+        SubjectPublicKeyInfo::parse(&mut reader)?;
+        reader.finish()?;
+        self.public_key_info = Some(public_key_info.to_vec());
+        Ok(self)
+    }
+
+    /// Sets the subject's public key.
+    ///
+    /// Supports 2048 and 4096-bit RSA keys and P-256 and P-384 EC keys.
+    #[cfg(target_vendor = "apple")]
+    pub fn set_public_key(&mut self, public_key: &SecKey) -> Result<&mut Self> {
+        let der = super::public_key_info_der(public_key).ok_or_else(|| Error::from_code(errSecParam))?;
+        self.set_public_key_info(&der)
+    }
+
+    /// Makes the certificate issued by the subject of `issuer`, which must then sign it.
+    ///
+    /// The authority key identifier is taken from the issuer's subject key identifier,
+    /// and omitted if the issuer has none.
+    pub fn set_issuer(&mut self, issuer: &Parsed) -> Result<&mut Self> {
+        self.issuer = Some((issuer.subject_der().to_vec(), issuer.subject_key_identifier()?));
+        Ok(self)
+    }
+
+    /// Adds an entry to the subject alternative name extension.
+    pub fn add_subject_alt_name(&mut self, name: GeneralName) -> &mut Self {
+        self.extensions.subject_alt_names.push(name);
+        self
+    }
+
+    /// Sets the (critical) key usage extension.
+    pub fn set_key_usage(&mut self, key_usage: KeyUsage) -> &mut Self {
+        self.extensions.key_usage = Some(key_usage);
+        self
+    }
+
+    /// Sets the extended key usage extension, e.g. [`Oid::SERVER_AUTH`].
+    pub fn set_extended_key_usage(&mut self, usages: &[Oid]) -> &mut Self {
+        self.extensions.extended_key_usage = usages.to_vec();
+        self
+    }
+
+    /// Sets the (critical) basic constraints extension.
+    pub fn set_basic_constraints(&mut self, constraints: BasicConstraints) -> &mut Self {
+        self.extensions.basic_constraints = Some(constraints);
+        self
+    }
+
+    /// Adds another extension. It's added after the ones set with other methods.
+    pub fn add_extension(&mut self, extension: Extension) -> &mut Self {
+        self.extensions.other.push(extension);
+        self
+    }
+
+    /// Encodes the `TBSCertificate`, i.e. the part of the certificate that is signed.
+    pub fn tbs_certificate(&self, signature_algorithm: &AlgorithmIdentifier) -> Result<Vec<u8>> {
+        let public_key_info = self.public_key_info.as_deref().ok_or_else(|| Error::from_code(errSecParam))?;
+        if self.not_after < self.not_before || self.serial_number.as_ref().is_some_and(|s| !valid_serial_number(s)) {
+            return Err(Error::from_code(errSecParam));
+        }
+        let serial_number = match &self.serial_number {
+            Some(serial) => serial.clone(),
+            None => generate_serial_number()?,
+        };
+        let subject = self.subject.to_der();
+        let (issuer, authority_key_id) = match &self.issuer {
+            Some((issuer, key_id)) => (issuer.clone(), key_id.clone()),
+            None => (subject.clone(), None),
+        };
+
+        let mut reader = Reader::new(public_key_info);
+        let spki = SubjectPublicKeyInfo::parse(&mut reader)?;
+        // RFC 5280 method 1: SHA-1 of the subjectPublicKey bits
+        let key_id = DigestAlgorithm::Sha1.digest(spki.public_key());
+        // self-signed certificates identify their own key
+        let authority_key_id = if self.issuer.is_some() { authority_key_id } else { Some(key_id.clone()) };
+
+        let mut key_ids = vec![Extension::new(Oid::SUBJECT_KEY_IDENTIFIER, false, der::encode(der::OCTET_STRING, &key_id))];
+        if let Some(authority_key_id) = authority_key_id {
+            let aki = der::encode_sequence(&[&der::encode(der::context_primitive(0), &authority_key_id)]);
+            key_ids.push(Extension::new(Oid::AUTHORITY_KEY_IDENTIFIER, false, aki));
+        }
+        let extensions = self.extensions.encode(&self.subject, &key_ids);
+
+        Ok(der::encode_sequence(&[
+            &der::encode(der::context(0), &der::encode_uint(2)),
+            &der::encode_unsigned(&serial_number),
+            &signature_algorithm.to_der(),
+            &issuer,
+            &der::encode_sequence(&[&der::encode_time(self.not_before), &der::encode_time(self.not_after)]),
+            &subject,
+            public_key_info,
+            &der::encode(der::context(3), &extensions),
+        ]))
+    }
+
+    /// Signs the certificate and returns its DER encoding.
+    pub fn sign(&self, signer: &dyn Signer) -> Result<Vec<u8>> {
+        let algorithm = signer.algorithm()?;
+        let tbs = self.tbs_certificate(&algorithm)?;
+        let signature = signer.sign(&tbs)?;
+        Ok(der::encode_sequence(&[&tbs, &algorithm.to_der(), &der::encode_bit_string(&signature)]))
+    }
+}
+
+/// The extensions that can be set on both certificates and certificate requests.
+#[derive(Debug, Clone, Default)]
+pub(super) struct ExtensionSet {
+    pub subject_alt_names: Vec<GeneralName>,
+    pub key_usage: Option<KeyUsage>,
+    pub extended_key_usage: Vec<Oid>,
+    pub basic_constraints: Option<BasicConstraints>,
+    pub other: Vec<Extension>,
+}
+
+impl ExtensionSet {
+    /// Encodes the `Extensions` SEQUENCE, with `key_ids` placed before the subject alternative name.
+    pub fn encode(&self, subject: &DistinguishedName, key_ids: &[Extension]) -> Vec<u8> {
+        let mut extensions = Vec::new();
+        if let Some(constraints) = self.basic_constraints {
+            extensions.push(Extension::new(Oid::BASIC_CONSTRAINTS, true, constraints.to_der()));
+        }
+        if let Some(usage) = self.key_usage {
+            extensions.push(Extension::new(Oid::KEY_USAGE, true, usage.to_der()));
+        }
+        if !self.extended_key_usage.is_empty() {
+            extensions.push(Extension::new(Oid::EXTENDED_KEY_USAGE, false, extensions::encode_extended_key_usage(&self.extended_key_usage)));
+        }
+        extensions.extend_from_slice(key_ids);
+        if !self.subject_alt_names.is_empty() {
+            // must be critical if the subject is empty
+            let critical = subject.is_empty();
+            extensions.push(Extension::new(Oid::SUBJECT_ALT_NAME, critical, alt_name::encode_general_names(&self.subject_alt_names)));
+        }
+        extensions.extend(self.other.iter().cloned());
+        let extensions: Vec<_> = extensions.iter().map(Extension::to_der).collect();
+        der::encode(der::SEQUENCE, &extensions.concat())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.subject_alt_names.is_empty() && self.key_usage.is_none() && self.extended_key_usage.is_empty()
+            && self.basic_constraints.is_none() && self.other.is_empty()
+    }
+}
+
+/// Whether the serial number is positive and its INTEGER content fits in 20 octets.
+fn valid_serial_number(serial: &[u8]) -> bool {
+    let start = serial.iter().position(|&b| b != 0).unwrap_or(serial.len());
+    match serial[start..] {
+        [] => false,
+        [first, ..] => serial.len() - start + usize::from(first >= 0x80) <= 20,
+    }
+}
+
+/// A random positive 16-byte serial number, as recommended by the CA/Browser Forum baseline requirements.
+#[cfg(target_vendor = "apple")]
+fn generate_serial_number() -> Result<Vec<u8>> {
+    let mut serial = vec![0; 16];
+    SecRandom::default().copy_bytes(&mut serial).map_err(|_| Error::from_code(errSecInternalComponent))?;
+    // positive, and always 16 bytes long
+    serial[0] = serial[0] & 0x7f | 0x40;
+    Ok(serial)
+}
+
+#[cfg(not(target_vendor = "apple"))]
+fn generate_serial_number() -> Result<Vec<u8>> {
+    Err(Error::from_code(errSecParam))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::der::unix_from_time;
+    use crate::rsa;
+    use std::net::{IpAddr, Ipv4Addr};
+    use x509_parser::certificate::X509Certificate;
+    use x509_parser::prelude::FromDer;
+
+    fn server_spki() -> Vec<u8> {
+        let server = Parsed::from_der(include_bytes!("../../test/server.der")).unwrap();
+        server.public_key_info().der().to_vec()
+    }
+
+    fn verify(cert: &Parsed, issuer: &Parsed) -> bool {
+        let key = rsa::PublicKey::from_pkcs1(issuer.public_key_info().public_key()).unwrap();
+        key.verify_pkcs1(DigestAlgorithm::Sha256, cert.tbs_certificate(), cert.signature())
+    }
+
+    #[test]
+    fn self_signed_ca() {
+        let signer = rsa::server_key();
+        let mut builder = CertificateBuilder::new("CN=Test CA,O=Example,C=US".parse().unwrap());
+        builder
+            .set_serial_number(&[1, 2, 3])
+            .set_validity(der::time_from_unix(1_700_000_000), der::time_from_unix(2_600_000_000))
+            .set_basic_constraints(BasicConstraints::ca(Some(0)))
+            .set_key_usage(KeyUsage::KEY_CERT_SIGN | KeyUsage::CRL_SIGN)
+            .set_public_key_info(&server_spki()).unwrap();
+        let der = builder.sign(&signer).unwrap();
+
+        let cert = Parsed::from_der(&der).unwrap();
+        assert!(verify(&cert, &cert));
+        assert_eq!(cert.version(), 3);
+        assert_eq!(cert.serial_number(), [1, 2, 3]);
+        assert_eq!(cert.subject().to_string(), "CN=Test CA,O=Example,C=US");
+        assert_eq!(cert.issuer_der(), cert.subject_der());
+        assert_eq!(unix_from_time(cert.not_before()), 1_700_000_000);
+        assert_eq!(unix_from_time(cert.not_after()), 2_600_000_000);
+        assert_eq!(cert.basic_constraints().unwrap(), Some(BasicConstraints::ca(Some(0))));
+        assert_eq!(cert.key_usage().unwrap(), Some(KeyUsage::KEY_CERT_SIGN | KeyUsage::CRL_SIGN));
+        assert_eq!(cert.subject_key_identifier().unwrap().as_deref(), cert.authority_key_identifier().unwrap().unwrap().key_identifier());
+        assert_eq!(cert.signature_algorithm(), &AlgorithmIdentifier::rsa_pkcs1(DigestAlgorithm::Sha256));
+
+        // cross-check the encoding with an independent parser
+        let (rest, x509) = X509Certificate::from_der(&der).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(x509.subject().to_string(), "C=US, O=Example, CN=Test CA");
+        assert!(x509.is_ca());
+        assert_eq!(x509.validity().not_after.timestamp(), 2_600_000_000);
+    }
+
+    #[test]
+    fn issued_by_ca() {
+        let signer = rsa::server_key();
+        let mut ca_builder = CertificateBuilder::new("CN=Test CA".parse().unwrap());
+        ca_builder.set_serial_number(&[1]).set_basic_constraints(BasicConstraints::ca(None)).set_public_key_info(&server_spki()).unwrap();
+        let ca = Parsed::from_der(&ca_builder.sign(&signer).unwrap()).unwrap();
+
+        let mut builder = CertificateBuilder::new(DistinguishedName::default());
+        builder
+            .set_serial_number(&[0x7f; 16])
+            .set_issuer(&ca).unwrap()
+            .add_subject_alt_name(GeneralName::DnsName("example.com".into()))
+            .add_subject_alt_name(GeneralName::IpAddress(IpAddr::V4(Ipv4Addr::LOCALHOST)))
+            .set_extended_key_usage(&[Oid::SERVER_AUTH, Oid::CLIENT_AUTH])
+            .add_extension(Extension::new("1.2.3.4".parse().unwrap(), false, vec![5, 0]))
+            .set_public_key_info(&server_spki()).unwrap();
+        let der = builder.sign(&signer).unwrap();
+        let cert = Parsed::from_der(&der).unwrap();
+
+        assert!(verify(&cert, &ca));
+        assert_eq!(cert.issuer(), ca.subject());
+        assert!(cert.subject().is_empty());
+        assert_eq!(cert.serial_number(), [0x7f; 16]);
+        assert!(cert.matches_host("example.com"));
+        assert!(cert.matches_host("127.0.0.1"));
+        assert!(cert.extension(&Oid::SUBJECT_ALT_NAME).unwrap().is_critical());
+        assert_eq!(cert.extended_key_usage().unwrap().unwrap(), [Oid::SERVER_AUTH, Oid::CLIENT_AUTH]);
+        assert_eq!(cert.authority_key_identifier().unwrap().unwrap().key_identifier(), ca.subject_key_identifier().unwrap().as_deref());
+        assert_eq!(cert.extensions().last().unwrap().value(), [5, 0]);
+        assert!(X509Certificate::from_der(&der).is_ok());
+    }
+
+    #[test]
+    fn issuer_without_key_identifier() {
+        // a v1 certificate, which has no extensions
+        let signer = rsa::server_key();
+        let name = "CN=Legacy CA".parse::<DistinguishedName>().unwrap().to_der();
+        let algorithm = signer.algorithm().unwrap();
+        let tbs = der::encode_sequence(&[
+            &der::encode_unsigned(&[1]),
+            &algorithm.to_der(),
+            &name,
+            &der::encode_sequence(&[&der::encode_time(der::time_from_unix(1)), &der::encode_time(der::time_from_unix(2_600_000_000))]),
+            &name,
+            &server_spki(),
+        ]);
+        let signature = signer.sign(&tbs).unwrap();
+        let ca = Parsed::from_der(&der::encode_sequence(&[&tbs, &algorithm.to_der(), &der::encode_bit_string(&signature)])).unwrap();
+        assert_eq!(ca.subject_key_identifier().unwrap(), None);
+
+        let mut builder = CertificateBuilder::new("CN=Leaf".parse().unwrap());
+        builder.set_serial_number(&[2]).set_issuer(&ca).unwrap().set_public_key_info(&server_spki()).unwrap();
+        let cert = Parsed::from_der(&builder.sign(&signer).unwrap()).unwrap();
+        assert!(verify(&cert, &ca));
+        assert!(cert.subject_key_identifier().unwrap().is_some());
+        assert!(cert.authority_key_identifier().unwrap().is_none());
+    }
+
+    #[test]
+    fn invalid() {
+        let signer = rsa::server_key();
+        let builder = CertificateBuilder::new(DistinguishedName::default());
+        assert!(builder.sign(&signer).is_err());
+
+        let mut builder = CertificateBuilder::new(DistinguishedName::default());
+        builder.set_public_key_info(&server_spki()).unwrap();
+        builder.set_validity(der::time_from_unix(2), der::time_from_unix(1));
+        assert!(builder.sign(&signer).is_err());
+        builder.set_validity(der::time_from_unix(1), der::time_from_unix(2)).set_serial_number(&[1; 21]);
+        assert!(builder.sign(&signer).is_err());
+        // the leading zero DER needs in front of the high bit makes this 21 octets
+        builder.set_serial_number(&[0x80; 20]);
+        assert!(builder.sign(&signer).is_err());
+        builder.set_serial_number(&[0]);
+        assert!(builder.sign(&signer).is_err());
+        builder.set_serial_number(&[]);
+        assert!(builder.sign(&signer).is_err());
+        builder.set_serial_number(&[0x7f; 20]);
+        assert!(builder.sign(&signer).is_ok());
+        assert!(builder.set_public_key_info(b"junk").is_err());
+    }
+
+    #[test]
+    #[cfg(target_vendor = "apple")]
+    fn serial_numbers_are_random() {
+        let a = generate_serial_number().unwrap();
+        let b = generate_serial_number().unwrap();
+        assert_ne!(a, b);
+        assert_eq!(der::encode_unsigned(&a).len(), 18);
+    }
+
+    #[test]
+    #[cfg(not(target_vendor = "apple"))]
+    fn serial_number_required() {
+        let mut builder = CertificateBuilder::new(DistinguishedName::default());
+        builder.set_public_key_info(&server_spki()).unwrap();
+        assert!(builder.sign(&rsa::server_key()).is_err());
+        builder.set_serial_number(&[1]);
+        assert!(builder.sign(&rsa::server_key()).is_ok());
+    }
+}
@@ -0,0 +1,328 @@
+//! Ordering an unordered set of certificates into a chain.
+
+#[cfg(target_vendor = "apple")]
+use super::SecCertificate;
+use super::Parsed;
+#[cfg(target_vendor = "apple")]
+use crate::base::Result;
+use std::cmp::Reverse;
+
+/// Longest chain that will be built, including the leaf
+const MAX_CHAIN_LENGTH: usize = 10;
+
+/// How many issuers are tried before giving up, since cross-signed certificates can make
+/// the number of paths grow exponentially
+const MAX_LINKING_STEPS: usize = 1024;
+
+/// Orders certificates into a path from a leaf towards a root.
+///
+/// Certificates are linked by issuer and subject names, and by authority and subject key identifiers
+/// when present. Signatures are not checked, so this doesn't make the chain trusted, but it gives
+/// `ClientBuilder::identity` and `ServerBuilder::new` their certificates in the order TLS requires.
+///
+/// ```rust,ignore
+/// let chain = ChainBuilder::from_certificates(&leaf, &bag)?.build();
+/// if let Some(cert) = chain.missing_issuer_of() {
+///     eprintln!("no issuer for {}", cert.subject());
+/// }
+/// let builder = ServerBuilder::new(&identity, &chain.intermediate_certificates()?);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ChainBuilder {
+    leaf: Parsed,
+    candidates: Vec<Parsed>,
+}
+
+impl ChainBuilder {
+    /// Starts a chain at `leaf`.
+    #[must_use]
+    pub fn new(leaf: Parsed) -> Self {
+        Self { leaf, candidates: Vec::new() }
+    }
+
+    /// Parses `leaf` and `certificates`, which may be in any order.
+    #[cfg(target_vendor = "apple")]
+    pub fn from_certificates(leaf: &SecCertificate, certificates: &[SecCertificate]) -> Result<Self> {
+        let mut builder = Self::new(leaf.parse()?);
+        for certificate in certificates {
+            builder.add_certificate(certificate.parse()?);
+        }
+        Ok(builder)
+    }
+
+    /// Adds a certificate that may be part of the chain. Duplicates are ignored.
+    pub fn add_certificate(&mut self, certificate: Parsed) -> &mut Self {
+        if certificate.der() != self.leaf.der() && !self.candidates.iter().any(|c| c.der() == certificate.der()) {
+            self.candidates.push(certificate);
+        }
+        self
+    }
+
+    /// Adds certificates that may be part of the chain.
+    pub fn add_certificates(&mut self, certificates: impl IntoIterator<Item = Parsed>) -> &mut Self {
+        for certificate in certificates {
+            self.add_certificate(certificate);
+        }
+        self
+    }
+
+    /// Finds the path from the leaf to a root.
+    ///
+    /// If several certificates could be the issuer, ones with a matching key identifier
+    /// are preferred, then CA certificates, then ones that expire later.
+    /// If no path reaches a root, the longest one found is returned. The search gives up
+    /// after trying a fixed number of issuers.
+    #[must_use]
+    pub fn build(&self) -> Chain {
+        let (path, complete) = link(&self.leaf, &self.candidates);
+        let mut certificates = vec![self.leaf.clone()];
+        certificates.extend(path.iter().map(|&i| self.candidates[i].clone()));
+        let unused = (0..self.candidates.len())
+            .filter(|i| !path.contains(i))
+            .map(|i| self.candidates[i].clone())
+            .collect();
+        Chain { certificates, complete, unused }
+    }
+}
+
+/// An ordered certificate chain, from [`ChainBuilder::build`].
+#[derive(Debug, Clone)]
+pub struct Chain {
+    certificates: Vec<Parsed>,
+    complete: bool,
+    unused: Vec<Parsed>,
+}
+
+impl Chain {
+    /// All certificates in the chain, starting with the leaf.
+    #[inline]
+    #[must_use]
+    pub fn certificates(&self) -> &[Parsed] {
+        &self.certificates
+    }
+
+    /// The certificate the chain was built for.
+    #[inline]
+    #[must_use]
+    pub fn leaf(&self) -> &Parsed {
+        &self.certificates[0]
+    }
+
+    /// Whether the chain ends with a self-signed root.
+    #[inline]
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// The self-signed root, if the chain is complete.
+    #[must_use]
+    pub fn root(&self) -> Option<&Parsed> {
+        self.certificates.last().filter(|_| self.complete)
+    }
+
+    /// Where the chain is broken: the last certificate, whose issuer wasn't found.
+    ///
+    /// Its [`issuer`](Parsed::issuer) and [`authority_key_identifier`](Parsed::authority_key_identifier)
+    /// describe the missing certificate.
+    #[must_use]
+    pub fn missing_issuer_of(&self) -> Option<&Parsed> {
+        self.certificates.last().filter(|_| !self.complete)
+    }
+
+    /// The certificates between the leaf and the root, which a TLS peer should send.
+    ///
+    /// If the chain is incomplete, this is everything after the leaf.
+    #[must_use]
+    pub fn intermediates(&self) -> &[Parsed] {
+        let end = self.certificates.len() - usize::from(self.complete && self.certificates.len() > 1);
+        &self.certificates[1..end]
+    }
+
+    /// Certificates that were added but aren't part of the chain.
+    #[inline]
+    #[must_use]
+    pub fn unused(&self) -> &[Parsed] {
+        &self.unused
+    }
+
+    /// [`Chain::intermediates`] as `SecCertificate`s, for `ClientBuilder::identity` and `ServerBuilder::new`.
+    #[cfg(target_vendor = "apple")]
+    pub fn intermediate_certificates(&self) -> Result<Vec<SecCertificate>> {
+        self.intermediates().iter().map(|c| SecCertificate::from_der(c.der())).collect()
+    }
+}
+
+/// A self-signed root: self-issued, and not identifying a different key as its issuer.
+fn is_root(cert: &Parsed) -> bool {
+    cert.is_self_issued() && cert.is_issued_by(cert)
+}
+
+/// Returns indices into `candidates` of the path above `leaf`, and whether it ends with a root.
+fn link(leaf: &Parsed, candidates: &[Parsed]) -> (Vec<usize>, bool) {
+    let mut path = Vec::new();
+    let mut best = Vec::new();
+    let mut steps = MAX_LINKING_STEPS;
+    let complete = extend(leaf, candidates, &mut path, &mut best, &mut steps);
+    (best, complete)
+}
+
+/// Depth-first search for a path to a root, keeping the longest partial path in `best`.
+///
+/// Stops when `steps` reaches zero.
+fn extend(cert: &Parsed, candidates: &[Parsed], path: &mut Vec<usize>, best: &mut Vec<usize>, steps: &mut usize) -> bool {
+    if is_root(cert) {
+        best.clone_from(path);
+        return true;
+    }
+    if path.len() > best.len() {
+        best.clone_from(path);
+    }
+    if path.len() + 1 >= MAX_CHAIN_LENGTH {
+        return false;
+    }
+
+    let key_id = cert.authority_key_identifier().ok().flatten().and_then(|aki| aki.key_identifier().map(<[u8]>::to_vec));
+    let mut issuers: Vec<usize> = (0..candidates.len())
+        .filter(|i| !path.contains(i) && cert.is_issued_by(&candidates[*i]))
+        .collect();
+    issuers.sort_by_cached_key(|&i| {
+        let issuer = &candidates[i];
+        let key_matches = key_id.is_some() && issuer.subject_key_identifier().ok().flatten() == key_id;
+        let is_ca = issuer.basic_constraints().ok().flatten().is_some_and(|bc| bc.is_ca());
+        Reverse((key_matches, is_ca, issuer.not_after()))
+    });
+
+    for i in issuers {
+        if *steps == 0 {
+            return false;
+        }
+        *steps -= 1;
+        path.push(i);
+        if extend(&candidates[i], candidates, path, best, steps) {
+            return true;
+        }
+        path.pop();
+    }
+    false
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::certificate::fixtures::{ca, server, Issue};
+
+    fn issue(subject: &str, key: u8, issuer: Option<&Parsed>, not_after: i64) -> Parsed {
+        Issue { not_after, ..Issue::new(subject, key, issuer) }.build()
+    }
+
+    #[test]
+    fn fixtures() {
+        let unrelated = issue("CN=Unrelated", 1, None, 2_000_000_000);
+        let mut builder = ChainBuilder::new(server());
+        builder.add_certificates([ca(), unrelated.clone(), ca(), server()]);
+        let chain = builder.build();
+        assert_eq!(chain.certificates(), [server(), ca()]);
+        assert!(chain.is_complete());
+        assert_eq!(chain.root(), Some(&ca()));
+        assert_eq!(chain.missing_issuer_of(), None);
+        assert!(chain.intermediates().is_empty());
+        assert_eq!(chain.unused(), [unrelated]);
+
+        // the root on its own
+        let chain = ChainBuilder::new(ca()).build();
+        assert!(chain.is_complete());
+        assert_eq!(chain.certificates(), [ca()]);
+        assert!(chain.intermediates().is_empty());
+    }
+
+    #[test]
+    fn gap() {
+        let chain = ChainBuilder::new(server()).build();
+        assert!(!chain.is_complete());
+        assert_eq!(chain.root(), None);
+        assert_eq!(chain.missing_issuer_of(), Some(&server()));
+        assert_eq!(chain.missing_issuer_of().unwrap().issuer(), ca().subject());
+
+        let root = issue("CN=Root", 1, None, 2_000_000_000);
+        let intermediate = issue("CN=Intermediate", 2, Some(&root), 2_000_000_000);
+        let leaf = issue("CN=Leaf", 3, Some(&intermediate), 2_000_000_000);
+        let mut builder = ChainBuilder::new(leaf.clone());
+        builder.add_certificate(intermediate.clone());
+        let chain = builder.build();
+        assert_eq!(chain.certificates(), [leaf, intermediate.clone()]);
+        assert_eq!(chain.intermediates(), std::slice::from_ref(&intermediate));
+        assert_eq!(chain.missing_issuer_of(), Some(&intermediate));
+    }
+
+    #[test]
+    fn ordering() {
+        let root = issue("CN=Root", 1, None, 2_000_000_000);
+        let intermediate = issue("CN=Intermediate", 2, Some(&root), 2_000_000_000);
+        let leaf = issue("CN=Leaf", 3, Some(&intermediate), 2_000_000_000);
+        // same name as the real intermediate, but a different key
+        let impostor = issue("CN=Intermediate", 4, Some(&root), 2_100_000_000);
+
+        let mut builder = ChainBuilder::new(leaf.clone());
+        builder.add_certificates([root.clone(), impostor.clone(), intermediate.clone(), server(), ca()]);
+        let chain = builder.build();
+        assert_eq!(chain.certificates(), [leaf, intermediate.clone(), root.clone()]);
+        assert_eq!(chain.intermediates(), [intermediate]);
+        assert_eq!(chain.root(), Some(&root));
+        assert_eq!(chain.unused(), [impostor, server(), ca()]);
+    }
+
+    #[test]
+    fn backtracking() {
+        let root = issue("CN=Root", 1, None, 2_000_000_000);
+        let other_root = issue("CN=Other Root", 2, None, 2_000_000_000);
+        // the same intermediate key cross-signed by both roots; the newer one is tried first
+        let cross_signed = issue("CN=Intermediate", 3, Some(&other_root), 2_100_000_000);
+        let intermediate = issue("CN=Intermediate", 3, Some(&root), 2_000_000_000);
+        let leaf = issue("CN=Leaf", 4, Some(&intermediate), 2_000_000_000);
+
+        let mut builder = ChainBuilder::new(leaf.clone());
+        builder.add_certificates([cross_signed.clone(), intermediate.clone(), root.clone()]);
+        let chain = builder.build();
+        assert!(chain.is_complete());
+        assert_eq!(chain.certificates(), [leaf.clone(), intermediate.clone(), root]);
+        assert_eq!(chain.unused(), std::slice::from_ref(&cross_signed));
+
+        // without any root, the longest path is returned
+        let mut builder = ChainBuilder::new(leaf.clone());
+        builder.add_certificates([intermediate.clone(), cross_signed.clone()]);
+        let chain = builder.build();
+        assert!(!chain.is_complete());
+        assert_eq!(chain.certificates(), [leaf, cross_signed]);
+    }
+
+    #[test]
+    fn linking_budget() {
+        // layers of five interchangeable issuers with no root on top, which would take millions of paths to rule out
+        let missing = issue("CN=Missing", 20, None, 2_000_000_000);
+        let mut layers: Vec<Vec<Parsed>> = Vec::new();
+        for layer in (1..=9u8).rev() {
+            let issuer = layers.last().map_or(&missing, |above| &above[0]).clone();
+            layers.push((0..5).map(|n| issue(&format!("CN=Layer {layer}"), layer, Some(&issuer), 2_000_000_000 + n)).collect());
+        }
+        let leaf = issue("CN=Leaf", 10, Some(&layers.last().unwrap()[0]), 2_000_000_000);
+
+        let mut builder = ChainBuilder::new(leaf);
+        builder.add_certificates(layers.into_iter().flatten());
+        let chain = builder.build();
+        assert!(!chain.is_complete());
+        assert_eq!(chain.certificates().len(), MAX_CHAIN_LENGTH);
+        assert_eq!(chain.missing_issuer_of().unwrap().subject().to_string(), "CN=Layer 9");
+    }
+
+    #[test]
+    fn linking() {
+        assert!(server().is_issued_by(&ca()));
+        assert!(!ca().is_issued_by(&server()));
+        assert!(is_root(&ca()));
+        assert!(!is_root(&server()));
+        // the fixtures share a name, so only the key identifiers tell them apart
+        assert!(server().is_self_issued());
+        assert!(!server().is_issued_by(&server()));
+    }
+}
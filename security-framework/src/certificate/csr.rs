@@ -0,0 +1,264 @@
+//! Creating PKCS #10 certificate signing requests (RFC 2986).
+
+use security_framework_sys::base::errSecParam;
+
+use super::alt_name::GeneralName;
+use super::builder::{ExtensionSet, Signer};
+use super::extensions::{BasicConstraints, KeyUsage};
+use super::name::{self, DistinguishedName};
+use super::parsed::{Extension, SubjectPublicKeyInfo};
+use crate::base::{Error, Result};
+use crate::der::{self, Oid, Reader};
+use crate::pem;
+#[cfg(target_vendor = "apple")]
+use crate::key::SecKey;
+
+/// Builds and signs a PKCS #10 certificate signing request.
+///
+/// Extensions are sent to the CA in an `extensionRequest` attribute.
+/// Whether they end up in the certificate is up to the CA.
+///
+/// ```rust,ignore
+/// let key = SecKey::new(&options)?; // e.g. in the Secure Enclave
+/// let mut builder = CsrBuilder::new("CN=device-1234,O=Example".parse()?);
+/// builder
+///     .set_public_key(&key.public_key().unwrap())?
+///     .add_subject_alt_name(GeneralName::DnsName("device-1234.example.com".into()));
+/// let pem = builder.sign_pem(&key)?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct CsrBuilder {
+    subject: DistinguishedName,
+    public_key_info: Option<Vec<u8>>,
+    extensions: ExtensionSet,
+    challenge_password: Option<String>,
+    attributes: Vec<(Oid, Vec<Vec<u8>>)>,
+}
+
+impl CsrBuilder {
+    /// Starts a request for `subject`.
+    #[must_use]
+    pub fn new(subject: DistinguishedName) -> Self {
+        Self {
+            subject,
+            public_key_info: None,
+            extensions: ExtensionSet::default(),
+            challenge_password: None,
+            attributes: Vec::new(),
+        }
+    }
+
+    /// Sets the public key from a DER-encoded `SubjectPublicKeyInfo`.
+    pub fn set_public_key_info(&mut self, public_key_info: &[u8]) -> Result<&mut Self> {
+        let mut reader = Reader::new(public_key_info);
+        SubjectPublicKeyInfo::parse(&mut reader)?;
+        reader.finish()?;
+        self.public_key_info = Some(public_key_info.to_vec());
+        Ok(self)
+    }
+
+    /// Sets the public key, which is usually `private_key.public_key()`.
+    ///
+    /// Supports 2048 and 4096-bit RSA keys and P-256 and P-384 EC keys.
+    #[cfg(target_vendor = "apple")]
+    pub fn set_public_key(&mut self, public_key: &SecKey) -> Result<&mut Self> {
+        let der = super::public_key_info_der(public_key).ok_or_else(|| Error::from_code(errSecParam))?;
+        self.set_public_key_info(&der)
+    }
+
+    /// Requests an entry in the subject alternative name extension.
+    pub fn add_subject_alt_name(&mut self, name: GeneralName) -> &mut Self {
+        self.extensions.subject_alt_names.push(name);
+        self
+    }
+
+    /// Requests the key usage extension.
+    pub fn set_key_usage(&mut self, key_usage: KeyUsage) -> &mut Self {
+        self.extensions.key_usage = Some(key_usage);
+        self
+    }
+
+    /// Requests the extended key usage extension, e.g. [`Oid::CLIENT_AUTH`].
+    pub fn set_extended_key_usage(&mut self, usages: &[Oid]) -> &mut Self {
+        self.extensions.extended_key_usage = usages.to_vec();
+        self
+    }
+
+    /// Requests the basic constraints extension.
+    pub fn set_basic_constraints(&mut self, constraints: BasicConstraints) -> &mut Self {
+        self.extensions.basic_constraints = Some(constraints);
+        self
+    }
+
+    /// Requests another extension.
+    pub fn add_extension(&mut self, extension: Extension) -> &mut Self {
+        self.extensions.other.push(extension);
+        self
+    }
+
+    /// Sets the PKCS #9 challenge password, which some CAs (e.g. SCEP servers) use to authorize the request.
+    ///
+    /// Note that it is sent in the clear.
+    pub fn set_challenge_password(&mut self, password: &str) -> &mut Self {
+        self.challenge_password = Some(password.into());
+        self
+    }
+
+    /// Adds another attribute, with DER-encoded values.
+    pub fn add_attribute(&mut self, oid: Oid, values: Vec<Vec<u8>>) -> &mut Self {
+        self.attributes.push((oid, values));
+        self
+    }
+
+    /// Encodes the `CertificationRequestInfo`, i.e. the part of the request that is signed.
+    pub fn certification_request_info(&self) -> Result<Vec<u8>> {
+        let public_key_info = self.public_key_info.as_deref().ok_or_else(|| Error::from_code(errSecParam))?;
+
+        let mut attributes = Vec::new();
+        if let Some(password) = &self.challenge_password {
+            // DirectoryString, preferably PrintableString
+            let tag = if password.bytes().all(name::is_printable) { der::PRINTABLE_STRING } else { der::UTF8_STRING };
+            attributes.push(encode_attribute(&Oid::CHALLENGE_PASSWORD, &[der::encode(tag, password.as_bytes())]));
+        }
+        if !self.extensions.is_empty() {
+            attributes.push(encode_attribute(&Oid::EXTENSION_REQUEST, &[self.extensions.encode(&self.subject, &[])]));
+        }
+        for (oid, values) in &self.attributes {
+            attributes.push(encode_attribute(oid, values));
+        }
+        // SET OF is sorted in DER
+        attributes.sort();
+
+        Ok(der::encode_sequence(&[
+            &der::encode_uint(0),
+            &self.subject.to_der(),
+            public_key_info,
+            &der::encode(der::context(0), &attributes.concat()),
+        ]))
+    }
+
+    /// Signs the request and returns its DER encoding.
+    pub fn sign(&self, signer: &dyn Signer) -> Result<Vec<u8>> {
+        let algorithm = signer.algorithm()?;
+        let info = self.certification_request_info()?;
+        let signature = signer.sign(&info)?;
+        Ok(der::encode_sequence(&[&info, &algorithm.to_der(), &der::encode_bit_string(&signature)]))
+    }
+
+    /// Signs the request and returns it as a `CERTIFICATE REQUEST` PEM block.
+    pub fn sign_pem(&self, signer: &dyn Signer) -> Result<String> {
+        Ok(pem::encode(pem::CERTIFICATE_REQUEST, &self.sign(signer)?))
+    }
+}
+
+fn encode_attribute(oid: &Oid, values: &[Vec<u8>]) -> Vec<u8> {
+    let mut values = values.to_vec();
+    values.sort();
+    der::encode_sequence(&[&der::encode_oid(oid), &der::encode(der::SET, &values.concat())])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::certificate::Parsed;
+    use crate::digest::DigestAlgorithm;
+    use crate::rsa;
+    use std::net::{IpAddr, Ipv6Addr};
+    use x509_parser::certification_request::X509CertificationRequest;
+    use x509_parser::extensions::{GeneralName as X509GeneralName, ParsedExtension};
+    use x509_parser::prelude::FromDer;
+
+    fn server_spki() -> Vec<u8> {
+        let server = Parsed::from_der(include_bytes!("../../test/server.der")).unwrap();
+        server.public_key_info().der().to_vec()
+    }
+
+    #[test]
+    fn round_trip() {
+        let key = rsa::server_key();
+        let mut builder = CsrBuilder::new("CN=device-1234,O=Example,C=US".parse().unwrap());
+        builder
+            .set_public_key_info(&server_spki()).unwrap()
+            .add_subject_alt_name(GeneralName::DnsName("device-1234.example.com".into()))
+            .add_subject_alt_name(GeneralName::IpAddress(IpAddr::V6(Ipv6Addr::LOCALHOST)))
+            .set_key_usage(KeyUsage::DIGITAL_SIGNATURE)
+            .set_extended_key_usage(&[Oid::CLIENT_AUTH])
+            .set_challenge_password("s3cret");
+        let pem = builder.sign_pem(&key).unwrap();
+        assert!(pem.starts_with("-----BEGIN CERTIFICATE REQUEST-----\n"));
+        let der = pem::parse_first(pem.as_bytes(), pem::CERTIFICATE_REQUEST).unwrap();
+        assert_eq!(der, builder.sign(&key).unwrap());
+
+        let (rest, csr) = X509CertificationRequest::from_der(&der).unwrap();
+        assert!(rest.is_empty());
+        let info = &csr.certification_request_info;
+        assert_eq!(info.version.0, 0);
+        assert_eq!(info.subject.to_string(), "C=US, O=Example, CN=device-1234");
+        assert_eq!(info.subject_pki.raw, server_spki());
+
+        let extensions: Vec<_> = csr.requested_extensions().unwrap().collect();
+        assert_eq!(extensions.len(), 3);
+        assert!(matches!(extensions[0], ParsedExtension::KeyUsage(ku) if ku.digital_signature() && ku.flags == 1));
+        assert!(matches!(extensions[1], ParsedExtension::ExtendedKeyUsage(eku) if eku.client_auth && !eku.server_auth));
+        let ParsedExtension::SubjectAlternativeName(san) = extensions[2] else { panic!() };
+        assert_eq!(san.general_names[0], X509GeneralName::DNSName("device-1234.example.com"));
+        assert_eq!(san.general_names[1], X509GeneralName::IPAddress(&Ipv6Addr::LOCALHOST.octets()));
+
+        let password = info.find_attribute(&x509_parser::oid_registry::OID_PKCS9_CHALLENGE_PASSWORD).unwrap();
+        assert_eq!(password.value, der::encode(der::SET, &der::encode(der::PRINTABLE_STRING, b"s3cret")));
+
+        // the signature covers the encoded request info
+        assert_eq!(csr.signature_algorithm.algorithm.as_bytes(), Oid::SHA256_WITH_RSA.as_der_content());
+        let public_key = rsa::PublicKey::from_pkcs1(&info.subject_pki.subject_public_key.data).unwrap();
+        let signed = builder.certification_request_info().unwrap();
+        assert_eq!(info.raw, signed);
+        assert!(public_key.verify_pkcs1(DigestAlgorithm::Sha256, &signed, &csr.signature_value.data));
+    }
+
+    #[test]
+    fn minimal() {
+        let mut builder = CsrBuilder::new("CN=minimal".parse().unwrap());
+        assert!(builder.sign(&rsa::server_key()).is_err());
+        builder.set_public_key_info(&server_spki()).unwrap();
+        let der = builder.sign(&rsa::server_key()).unwrap();
+        let (_, csr) = X509CertificationRequest::from_der(&der).unwrap();
+        assert!(csr.requested_extensions().is_none());
+        assert!(csr.certification_request_info.attributes().is_empty());
+        // attributes are present even when empty
+        let info = builder.certification_request_info().unwrap();
+        assert!(info.ends_with(&[0xa0, 0]));
+    }
+
+    #[test]
+    fn attribute_order() {
+        let mut builder = CsrBuilder::new(DistinguishedName::default());
+        builder
+            .set_public_key_info(&server_spki()).unwrap()
+            .set_basic_constraints(BasicConstraints::end_entity())
+            .add_attribute("1.2.840.113549.1.9.2".parse().unwrap(), vec![der::encode(der::UTF8_STRING, b"name")])
+            .set_challenge_password("pässword");
+        let info = builder.certification_request_info().unwrap();
+        let mut reader = Reader::new(&info);
+        let mut seq = reader.read_sequence().unwrap();
+        seq.read_tlv().unwrap();
+        seq.read_tlv().unwrap();
+        seq.read_tlv().unwrap();
+        let mut attributes = seq.read_nested(der::context(0)).unwrap();
+        let mut encoded = Vec::new();
+        let mut oids = Vec::new();
+        while !attributes.is_empty() {
+            let tlv = attributes.read_tlv().unwrap();
+            encoded.push(tlv.raw);
+            let mut attribute = Reader::new(tlv.content);
+            let oid = attribute.read_oid().unwrap();
+            if oid == Oid::CHALLENGE_PASSWORD {
+                let mut values = attribute.read_nested(der::SET).unwrap();
+                assert_eq!(values.read_tlv().unwrap().tag, der::UTF8_STRING);
+            }
+            oids.push(oid.to_string());
+        }
+        assert!(encoded.is_sorted());
+        oids.sort();
+        assert_eq!(oids, ["1.2.840.113549.1.9.14", "1.2.840.113549.1.9.2", "1.2.840.113549.1.9.7"]);
+    }
+}
@@ -0,0 +1,327 @@
+//! Finding certificates and identities that are about to expire.
+//!
+//! ```rust,ignore
+//! let identities = ItemSearchOptions::new()
+//!     .class(ItemClass::identity())
+//!     .load_refs(true)
+//!     .limit(Limit::All)
+//!     .search()?
+//!     .into_iter()
+//!     .filter_map(|item| match item {
+//!         SearchResult::Ref(Reference::Identity(identity)) => Some(identity),
+//!         _ => None,
+//!     });
+//! for (identity, report) in ExpiryMonitor::new().expiring_within(identities, 30)? {
+//!     println!("{} expires in {} days", report.subject(), report.days_remaining());
+//! }
+//! ```
+
+use std::time::SystemTime;
+
+#[cfg(target_vendor = "apple")]
+use super::SecCertificate;
+use super::{DistinguishedName, Parsed};
+#[cfg(target_vendor = "apple")]
+use crate::base::Result;
+use crate::der::unix_from_time;
+#[cfg(target_vendor = "apple")]
+use crate::identity::SecIdentity;
+
+const SECONDS_PER_DAY: i64 = 86400;
+
+/// A source of the current time, so that expiry checks can be tested with fixed times.
+pub trait Clock {
+    /// The current time.
+    fn now(&self) -> SystemTime;
+}
+
+/// The system's wall clock.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    #[inline]
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// A clock that always returns the same time.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FixedClock(pub SystemTime);
+
+impl Clock for FixedClock {
+    #[inline]
+    fn now(&self) -> SystemTime {
+        self.0
+    }
+}
+
+/// Where the current time falls relative to a certificate's validity period.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ExpiryStatus {
+    /// Before `notBefore`.
+    NotYetValid,
+    /// Within the validity period.
+    Valid,
+    /// After `notAfter`.
+    Expired,
+}
+
+impl ExpiryStatus {
+    /// Classifies `now` against a validity period. Both ends are inclusive, as in RFC 5280.
+    #[must_use]
+    pub fn at(not_before: SystemTime, not_after: SystemTime, now: SystemTime) -> Self {
+        if now < not_before {
+            Self::NotYetValid
+        } else if now > not_after {
+            Self::Expired
+        } else {
+            Self::Valid
+        }
+    }
+}
+
+/// The expiry state of one certificate at a point in time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpiryReport {
+    subject: DistinguishedName,
+    issuer: DistinguishedName,
+    serial_number: Vec<u8>,
+    not_before: SystemTime,
+    not_after: SystemTime,
+    status: ExpiryStatus,
+    days_remaining: i64,
+}
+
+impl ExpiryReport {
+    /// Checks `certificate` at `now`.
+    #[must_use]
+    pub fn new(certificate: &Parsed, now: SystemTime) -> Self {
+        let not_before = certificate.not_before();
+        let not_after = certificate.not_after();
+        Self {
+            subject: certificate.subject().clone(),
+            issuer: certificate.issuer().clone(),
+            serial_number: certificate.serial_number().to_vec(),
+            not_before,
+            not_after,
+            status: ExpiryStatus::at(not_before, not_after, now),
+            days_remaining: days_between(now, not_after),
+        }
+    }
+
+    /// The certificate's subject.
+    #[inline]
+    #[must_use]
+    pub fn subject(&self) -> &DistinguishedName {
+        &self.subject
+    }
+
+    /// The certificate's issuer, e.g. to tell which CA to renew with.
+    #[inline]
+    #[must_use]
+    pub fn issuer(&self) -> &DistinguishedName {
+        &self.issuer
+    }
+
+    /// The certificate's serial number.
+    #[inline]
+    #[must_use]
+    pub fn serial_number(&self) -> &[u8] {
+        &self.serial_number
+    }
+
+    /// Start of the validity period.
+    #[inline]
+    #[must_use]
+    pub fn not_before(&self) -> SystemTime {
+        self.not_before
+    }
+
+    /// End of the validity period.
+    #[inline]
+    #[must_use]
+    pub fn not_after(&self) -> SystemTime {
+        self.not_after
+    }
+
+    /// Whether the certificate was valid when checked.
+    #[inline]
+    #[must_use]
+    pub fn status(&self) -> ExpiryStatus {
+        self.status
+    }
+
+    /// Whole days until `notAfter`, rounded down. Negative once expired:
+    /// -1 during the first day after expiry.
+    #[inline]
+    #[must_use]
+    pub fn days_remaining(&self) -> i64 {
+        self.days_remaining
+    }
+
+    /// Whether the certificate expires within `days` days, including ones that already expired.
+    #[must_use]
+    pub fn expires_within(&self, days: u32) -> bool {
+        self.days_remaining < i64::from(days)
+    }
+}
+
+/// Floor of the number of days from `from` to `to`.
+fn days_between(from: SystemTime, to: SystemTime) -> i64 {
+    (unix_from_time(to) - unix_from_time(from)).div_euclid(SECONDS_PER_DAY)
+}
+
+/// Something with a certificate: a [`SecCertificate`] or a [`SecIdentity`].
+#[cfg(target_vendor = "apple")]
+pub trait HasCertificate {
+    /// The certificate.
+    fn certificate(&self) -> Result<SecCertificate>;
+}
+
+#[cfg(target_vendor = "apple")]
+impl HasCertificate for SecCertificate {
+    #[inline]
+    fn certificate(&self) -> Result<SecCertificate> {
+        Ok(self.clone())
+    }
+}
+
+#[cfg(target_vendor = "apple")]
+impl HasCertificate for SecIdentity {
+    #[inline]
+    fn certificate(&self) -> Result<SecCertificate> {
+        Self::certificate(self)
+    }
+}
+
+/// Checks certificates against a [`Clock`].
+#[derive(Debug, Clone, Default)]
+pub struct ExpiryMonitor<C = SystemClock> {
+    clock: C,
+}
+
+impl ExpiryMonitor {
+    /// Checks against the system clock.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self { clock: SystemClock }
+    }
+}
+
+impl<C: Clock> ExpiryMonitor<C> {
+    /// Checks against `clock`.
+    #[inline]
+    pub fn with_clock(clock: C) -> Self {
+        Self { clock }
+    }
+
+    /// Checks one parsed certificate.
+    #[must_use]
+    pub fn check(&self, certificate: &Parsed) -> ExpiryReport {
+        ExpiryReport::new(certificate, self.clock.now())
+    }
+
+    /// Checks parsed certificates, returning those that expire within `days` days along with their reports.
+    pub fn expiring_parsed_within<'a>(&self, certificates: impl IntoIterator<Item = &'a Parsed>, days: u32) -> Vec<(&'a Parsed, ExpiryReport)> {
+        let now = self.clock.now();
+        certificates
+            .into_iter()
+            .map(|certificate| (certificate, ExpiryReport::new(certificate, now)))
+            .filter(|(_, report)| report.expires_within(days))
+            .collect()
+    }
+
+    /// Checks a certificate or identity.
+    #[cfg(target_vendor = "apple")]
+    pub fn check_item(&self, item: &impl HasCertificate) -> Result<ExpiryReport> {
+        Ok(self.check(&item.certificate()?.parse()?))
+    }
+
+    /// Checks every item, in order.
+    #[cfg(target_vendor = "apple")]
+    pub fn report<T: HasCertificate>(&self, items: impl IntoIterator<Item = T>) -> Result<Vec<(T, ExpiryReport)>> {
+        items
+            .into_iter()
+            .map(|item| {
+                let report = self.check_item(&item)?;
+                Ok((item, report))
+            })
+            .collect()
+    }
+
+    /// Returns the items that expire within `days` days, or already have, with their reports.
+    #[cfg(target_vendor = "apple")]
+    pub fn expiring_within<T: HasCertificate>(&self, items: impl IntoIterator<Item = T>, days: u32) -> Result<Vec<(T, ExpiryReport)>> {
+        let mut reports = self.report(items)?;
+        reports.retain(|(_, report)| report.expires_within(days));
+        Ok(reports)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::der::time_from_unix;
+
+    // valid from 2024-01-01 to 2034-01-01
+    const NOT_BEFORE: i64 = 1_704_067_200;
+    const NOT_AFTER: i64 = 2_019_686_400;
+
+    fn leaf() -> Parsed {
+        Parsed::from_der(include_bytes!("../../test/sct/leaf.der")).unwrap()
+    }
+
+    fn at(secs: i64) -> ExpiryReport {
+        ExpiryMonitor::with_clock(FixedClock(time_from_unix(secs))).check(&leaf())
+    }
+
+    #[test]
+    fn status() {
+        let report = at(NOT_BEFORE - 1);
+        assert_eq!(report.status(), ExpiryStatus::NotYetValid);
+        assert_eq!(report.days_remaining(), 3653);
+        assert_eq!(report.subject().to_string(), "CN=ct.example.com");
+        assert_eq!(report.issuer().to_string(), "CN=CT Test CA,O=Foobar LLC");
+        assert_eq!(report.serial_number(), [0x05, 0xc7]);
+        assert_eq!(report.not_before(), time_from_unix(NOT_BEFORE));
+        assert_eq!(report.not_after(), time_from_unix(NOT_AFTER));
+
+        assert_eq!(at(NOT_BEFORE).status(), ExpiryStatus::Valid);
+        assert_eq!(at(NOT_AFTER).status(), ExpiryStatus::Valid);
+        assert_eq!(at(NOT_AFTER + 1).status(), ExpiryStatus::Expired);
+    }
+
+    #[test]
+    fn days_remaining() {
+        assert_eq!(at(NOT_AFTER).days_remaining(), 0);
+        assert_eq!(at(NOT_AFTER - 1).days_remaining(), 0);
+        assert_eq!(at(NOT_AFTER - SECONDS_PER_DAY).days_remaining(), 1);
+        assert_eq!(at(NOT_AFTER - 30 * SECONDS_PER_DAY - 1).days_remaining(), 30);
+        assert_eq!(at(NOT_AFTER + 1).days_remaining(), -1);
+        assert_eq!(at(NOT_AFTER + 10 * SECONDS_PER_DAY).days_remaining(), -10);
+
+        let report = at(NOT_AFTER - 29 * SECONDS_PER_DAY);
+        assert!(report.expires_within(30));
+        assert!(!report.expires_within(29));
+        assert!(at(NOT_AFTER + 1).expires_within(0));
+        assert!(!at(NOT_AFTER).expires_within(0));
+    }
+
+    #[test]
+    fn monitor() {
+        let leaf = leaf();
+        let ca = Parsed::from_der(include_bytes!("../../test/sct/sct_ca.der")).unwrap();
+        // the test CA was issued for ten years when the fixtures were generated, long after the leaf
+        assert!(ca.not_after() > leaf.not_after());
+
+        let monitor = ExpiryMonitor::with_clock(FixedClock(time_from_unix(NOT_AFTER - 7 * SECONDS_PER_DAY)));
+        let expiring = monitor.expiring_parsed_within([&leaf, &ca], 30);
+        assert_eq!(expiring.len(), 1);
+        assert_eq!(expiring[0].0, &leaf);
+        assert_eq!(expiring[0].1.days_remaining(), 7);
+        assert!(monitor.expiring_parsed_within([&leaf, &ca], 7).is_empty());
+    }
+}
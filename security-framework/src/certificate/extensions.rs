@@ -0,0 +1,182 @@
+//! Key usage, extended key usage and basic constraints extensions (RFC 5280 4.2.1).
+
+use crate::base::Result;
+use crate::der::{self, Oid, Reader};
+
+bitflags::bitflags! {
+    /// The key usage extension, which restricts what the certified key may be used for.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct KeyUsage: u16 {
+        /// Verifying signatures other than on certificates and CRLs.
+        const DIGITAL_SIGNATURE = 1 << 0;
+        /// Verifying signatures for non-repudiation (also called content commitment).
+        const NON_REPUDIATION = 1 << 1;
+        /// Encrypting keys, e.g. RSA key transport.
+        const KEY_ENCIPHERMENT = 1 << 2;
+        /// Encrypting data directly.
+        const DATA_ENCIPHERMENT = 1 << 3;
+        /// Key agreement, e.g. ECDH.
+        const KEY_AGREEMENT = 1 << 4;
+        /// Verifying signatures on certificates. Only for CAs.
+        const KEY_CERT_SIGN = 1 << 5;
+        /// Verifying signatures on CRLs.
+        const CRL_SIGN = 1 << 6;
+        /// With key agreement, only for encryption.
+        const ENCIPHER_ONLY = 1 << 7;
+        /// With key agreement, only for decryption.
+        const DECIPHER_ONLY = 1 << 8;
+    }
+}
+
+impl KeyUsage {
+    /// Decodes the extension value, a named BIT STRING where bit 0 is the most significant bit.
+    pub(crate) fn parse(value: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(value);
+        let (_, bytes) = reader.read_bit_string()?;
+        reader.finish()?;
+        let bits = bytes.iter().take(2).enumerate()
+            .fold(0u16, |acc, (i, &b)| acc | u16::from(b.reverse_bits()) << (8 * i));
+        Ok(Self::from_bits_truncate(bits))
+    }
+
+    /// Encodes the extension value, without trailing zero bits as DER requires.
+    pub(crate) fn to_der(self) -> Vec<u8> {
+        let bits = self.bits();
+        let len = (16 - bits.leading_zeros() as usize).div_ceil(8);
+        let bytes: Vec<u8> = (0..len).map(|i| (bits >> (8 * i)) as u8).map(u8::reverse_bits).collect();
+        let unused = bytes.last().map_or(0, |b| b.trailing_zeros() as u8);
+        let mut content = vec![unused];
+        content.extend(bytes);
+        der::encode(der::BIT_STRING, &content)
+    }
+}
+
+/// The basic constraints extension, which says whether the subject is a CA.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct BasicConstraints {
+    ca: bool,
+    path_len_constraint: Option<u32>,
+}
+
+impl BasicConstraints {
+    /// Constraints for a CA, optionally limiting how many intermediate CAs may follow it.
+    #[must_use]
+    pub fn ca(path_len_constraint: Option<u32>) -> Self {
+        Self { ca: true, path_len_constraint }
+    }
+
+    /// Constraints for an end-entity certificate.
+    #[must_use]
+    pub fn end_entity() -> Self {
+        Self::default()
+    }
+
+    /// Whether the subject may issue certificates.
+    #[inline]
+    #[must_use]
+    pub fn is_ca(&self) -> bool {
+        self.ca
+    }
+
+    /// Maximum number of non-self-issued intermediate certificates that may follow this one.
+    #[inline]
+    #[must_use]
+    pub fn path_len_constraint(&self) -> Option<u32> {
+        self.path_len_constraint
+    }
+
+    pub(crate) fn parse(value: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(value);
+        let mut seq = reader.read_sequence()?;
+        reader.finish()?;
+        let ca = seq.read_optional_bool()?;
+        let path_len_constraint = if seq.is_empty() {
+            None
+        } else {
+            Some(u32::try_from(seq.read_small_uint()?).map_err(|_| der::decode_error())?)
+        };
+        seq.finish()?;
+        Ok(Self { ca, path_len_constraint })
+    }
+
+    pub(crate) fn to_der(self) -> Vec<u8> {
+        let mut content = Vec::new();
+        if self.ca {
+            content.extend(der::encode_bool(true));
+        }
+        if let Some(len) = self.path_len_constraint {
+            content.extend(der::encode_uint(len.into()));
+        }
+        der::encode(der::SEQUENCE, &content)
+    }
+}
+
+/// Decodes the extended key usage extension value, a non-empty SEQUENCE of OIDs.
+pub(crate) fn parse_extended_key_usage(value: &[u8]) -> Result<Vec<Oid>> {
+    let mut reader = Reader::new(value);
+    let mut seq = reader.read_sequence()?;
+    reader.finish()?;
+    let mut usages = Vec::new();
+    while !seq.is_empty() {
+        usages.push(seq.read_oid()?);
+    }
+    if usages.is_empty() {
+        return Err(der::decode_error());
+    }
+    Ok(usages)
+}
+
+pub(crate) fn encode_extended_key_usage(usages: &[Oid]) -> Vec<u8> {
+    let oids: Vec<_> = usages.iter().map(der::encode_oid).collect();
+    der::encode(der::SEQUENCE, &oids.concat())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::certificate::Parsed;
+
+    #[test]
+    fn fixtures() {
+        let server = Parsed::from_der(include_bytes!("../../test/server.der")).unwrap();
+        assert_eq!(server.basic_constraints().unwrap(), Some(BasicConstraints::end_entity()));
+        assert_eq!(server.key_usage().unwrap(), Some(KeyUsage::DIGITAL_SIGNATURE | KeyUsage::KEY_ENCIPHERMENT));
+        assert_eq!(server.extended_key_usage().unwrap().unwrap(), [Oid::SERVER_AUTH]);
+
+        let ca = Parsed::from_der(include_bytes!("../../test/ca.der")).unwrap();
+        assert_eq!(ca.basic_constraints().unwrap(), Some(BasicConstraints::ca(None)));
+        assert_eq!(ca.key_usage().unwrap(), Some(KeyUsage::KEY_CERT_SIGN | KeyUsage::CRL_SIGN));
+        assert_eq!(ca.extended_key_usage().unwrap(), None);
+
+        // re-encoding gives the original extension values
+        for cert in [&server, &ca] {
+            let ku = cert.extension(&Oid::KEY_USAGE).unwrap().value();
+            assert_eq!(KeyUsage::parse(ku).unwrap().to_der(), ku);
+            let bc = cert.extension(&Oid::BASIC_CONSTRAINTS).unwrap().value();
+            assert_eq!(BasicConstraints::parse(bc).unwrap().to_der(), bc);
+        }
+        let eku = server.extension(&Oid::EXTENDED_KEY_USAGE).unwrap().value();
+        assert_eq!(encode_extended_key_usage(&parse_extended_key_usage(eku).unwrap()), eku);
+    }
+
+    #[test]
+    fn key_usage_bits() {
+        assert_eq!(KeyUsage::DIGITAL_SIGNATURE.to_der(), [3, 2, 7, 0x80]);
+        assert_eq!(KeyUsage::DECIPHER_ONLY.to_der(), [3, 3, 7, 0, 0x80]);
+        assert_eq!(KeyUsage::empty().to_der(), [3, 1, 0]);
+        assert_eq!((KeyUsage::KEY_CERT_SIGN | KeyUsage::CRL_SIGN).to_der(), [3, 2, 1, 0x06]);
+        for bits in 0..0x200 {
+            let usage = KeyUsage::from_bits_truncate(bits);
+            assert_eq!(KeyUsage::parse(&usage.to_der()).unwrap(), usage);
+        }
+    }
+
+    #[test]
+    fn basic_constraints() {
+        assert_eq!(BasicConstraints::ca(Some(0)).to_der(), [0x30, 6, 1, 1, 0xff, 2, 1, 0]);
+        assert_eq!(BasicConstraints::end_entity().to_der(), [0x30, 0]);
+        assert_eq!(BasicConstraints::parse(&[0x30, 6, 1, 1, 0xff, 2, 1, 3]).unwrap(), BasicConstraints::ca(Some(3)));
+        assert!(BasicConstraints::parse(&[0x30, 3, 2, 1, 0xff]).is_err());
+        assert!(parse_extended_key_usage(&[0x30, 0]).is_err());
+    }
+}
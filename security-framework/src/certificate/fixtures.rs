@@ -0,0 +1,90 @@
+//! Certificates for tests, all signed with `rsa::server_key()`.
+
+use super::{BasicConstraints, CertificateBuilder, Extension, GeneralName, KeyUsage, Oid, Parsed};
+use crate::der::time_from_unix;
+use crate::rsa;
+
+/// `test/server.der`
+pub fn server() -> Parsed {
+    Parsed::from_der(include_bytes!("../../test/server.der")).unwrap()
+}
+
+/// `test/ca.der`, the issuer of `server()`
+pub fn ca() -> Parsed {
+    Parsed::from_der(include_bytes!("../../test/ca.der")).unwrap()
+}
+
+/// A certificate to issue, valid from 2023-11-14 until `not_after`.
+///
+/// With a `key` byte, the server's public key is altered so that each certificate gets a distinct
+/// key (and the signatures of whatever it issues aren't valid). Without one, the certificate has
+/// the server's key, so that its signatures really verify.
+pub struct Issue<'a> {
+    pub subject: &'a str,
+    pub key: Option<u8>,
+    pub issuer: Option<&'a Parsed>,
+    pub not_after: i64,
+    pub constraints: Option<BasicConstraints>,
+    pub names: Vec<GeneralName>,
+    pub key_usage: Option<KeyUsage>,
+    pub usages: Vec<Oid>,
+    pub extensions: Vec<Extension>,
+}
+
+impl<'a> Issue<'a> {
+    /// A CA certificate with a distinct key
+    pub fn new(subject: &'a str, key: u8, issuer: Option<&'a Parsed>) -> Self {
+        Self { key: Some(key), ..Self::signed(subject, issuer) }
+    }
+
+    /// An end-entity certificate with a distinct key and the given subject alternative names
+    pub fn leaf(subject: &'a str, key: u8, issuer: &'a Parsed, names: Vec<GeneralName>) -> Self {
+        Self { constraints: None, names, ..Self::new(subject, key, Some(issuer)) }
+    }
+
+    /// A CA certificate with the server's key
+    pub fn signed(subject: &'a str, issuer: Option<&'a Parsed>) -> Self {
+        Self {
+            subject,
+            key: None,
+            issuer,
+            not_after: 2_000_000_000,
+            constraints: Some(BasicConstraints::ca(None)),
+            names: Vec::new(),
+            key_usage: None,
+            usages: Vec::new(),
+            extensions: Vec::new(),
+        }
+    }
+
+    pub fn build(self) -> Parsed {
+        let mut spki = server().public_key_info().der().to_vec();
+        if let Some(key) = self.key {
+            spki[100] = key;
+        }
+        let mut builder = CertificateBuilder::new(self.subject.parse().unwrap());
+        builder
+            .set_serial_number(&[self.key.unwrap_or(1)])
+            .set_public_key_info(&spki).unwrap()
+            .set_validity(time_from_unix(1_700_000_000), time_from_unix(self.not_after));
+        if let Some(constraints) = self.constraints {
+            builder.set_basic_constraints(constraints);
+        }
+        if let Some(issuer) = self.issuer {
+            builder.set_issuer(issuer).unwrap();
+        }
+        for name in self.names {
+            builder.add_subject_alt_name(name);
+        }
+        if let Some(key_usage) = self.key_usage {
+            builder.set_key_usage(key_usage);
+        }
+        if !self.usages.is_empty() {
+            builder.set_extended_key_usage(&self.usages);
+        }
+        for extension in self.extensions {
+            builder.add_extension(extension);
+        }
+        Parsed::from_der(&builder.sign(&rsa::server_key()).unwrap()).unwrap()
+    }
+}
@@ -0,0 +1,126 @@
+//! Subject and authority key identifier extensions (RFC 5280 4.2.1.1 and 4.2.1.2).
+
+use super::alt_name::GeneralName;
+use crate::base::Result;
+use crate::der::{self, Reader};
+
+/// The authority key identifier extension, which identifies the key that signed a certificate.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct AuthorityKeyIdentifier {
+    key_identifier: Option<Vec<u8>>,
+    issuer: Vec<GeneralName>,
+    serial_number: Option<Vec<u8>>,
+}
+
+impl AuthorityKeyIdentifier {
+    /// Decodes the extension value.
+    pub(crate) fn parse(value: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(value);
+        let mut seq = reader.read_sequence()?;
+        reader.finish()?;
+        let key_identifier = seq.read_optional(der::context_primitive(0))?.map(<[u8]>::to_vec);
+        let mut issuer = Vec::new();
+        if let Some(names) = seq.read_optional(der::context(1))? {
+            let mut names = Reader::new(names);
+            while !names.is_empty() {
+                issuer.push(GeneralName::parse(&mut names)?);
+            }
+        }
+        let serial_number = seq.read_optional(der::context_primitive(2))?.map(<[u8]>::to_vec);
+        seq.finish()?;
+        // the issuer and serial number must be present together
+        if issuer.is_empty() != serial_number.is_none() {
+            return Err(der::decode_error());
+        }
+        Ok(Self { key_identifier, issuer, serial_number })
+    }
+
+    /// The issuer's subject key identifier. This is what's normally used.
+    #[inline]
+    #[must_use]
+    pub fn key_identifier(&self) -> Option<&[u8]> {
+        self.key_identifier.as_deref()
+    }
+
+    /// Names of the issuer's issuer, if the issuer certificate is identified by name and serial number.
+    #[inline]
+    #[must_use]
+    pub fn authority_cert_issuer(&self) -> &[GeneralName] {
+        &self.issuer
+    }
+
+    /// Serial number of the issuer certificate, if it is identified by name and serial number.
+    #[inline]
+    #[must_use]
+    pub fn authority_cert_serial_number(&self) -> Option<&[u8]> {
+        self.serial_number.as_deref()
+    }
+}
+
+/// Decodes the subject key identifier extension value, which is an OCTET STRING.
+pub(crate) fn parse_subject_key_identifier(value: &[u8]) -> Result<Vec<u8>> {
+    let mut reader = Reader::new(value);
+    let id = reader.read(der::OCTET_STRING)?;
+    reader.finish()?;
+    Ok(id.to_vec())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::certificate::Parsed;
+    use crate::digest::DigestAlgorithm;
+
+    #[test]
+    fn server_der() {
+        let cert = Parsed::from_der(include_bytes!("../../test/server.der")).unwrap();
+        assert_eq!(
+            hex::encode(cert.subject_key_identifier().unwrap().unwrap()),
+            "4a1585f179d48900bf3e9967a17270291b39360a"
+        );
+        let aki = cert.authority_key_identifier().unwrap().unwrap();
+        assert_eq!(hex::encode(aki.key_identifier().unwrap()), "0c3da1881f0f67ed13d47588a3d180495b9b8cc6");
+        assert!(aki.authority_cert_issuer().is_empty());
+        assert_eq!(aki.authority_cert_serial_number(), None);
+
+        let ca = Parsed::from_der(include_bytes!("../../test/ca.der")).unwrap();
+        assert_eq!(ca.subject_key_identifier().unwrap().as_deref(), aki.key_identifier());
+    }
+
+    #[test]
+    fn fingerprints() {
+        let cert = Parsed::from_der(include_bytes!("../../test/server.der")).unwrap();
+        // openssl x509 -fingerprint
+        assert_eq!(cert.fingerprint(DigestAlgorithm::Sha1).to_string(), "CA:C2:DD:80:94:52:A4:83:4F:54:95:00:55:9C:A3:E7:F7:92:E5:45");
+        assert_eq!(
+            cert.fingerprint(DigestAlgorithm::Sha256).to_hex(),
+            "f9a873f5027ed5681c41d3f9225ae774385f73f58679173bd04b72d5704bea54"
+        );
+        assert_eq!(
+            cert.fingerprint(DigestAlgorithm::Sha384).to_hex(),
+            "43e953474a46da4ced549638602c67e0762271daed7e8d6d5ced897620dc606831b5d4dd6830e61ba1e4d30db6b8a7fa"
+        );
+        assert_eq!(
+            cert.fingerprint(DigestAlgorithm::Sha512).to_hex(),
+            "2df444782d8a9aafb5a9eb7b5ef5c4924d22c1fdcb866454cc90ab9b0c48de081fd9f3265ada1ef24d0f4c3827a74703\
+             80498f8ed0d65c183777c916c3ea67ad"
+        );
+        // openssl pkey -pubin -outform der | openssl dgst -sha256 -binary | base64
+        assert_eq!(cert.spki_sha256_pin(), "bB7vC0XX1gJaYSDgYzK0ua9gRpduQgzYidZVUofVwso=");
+    }
+
+    #[test]
+    fn authority_by_issuer_and_serial() {
+        // keyIdentifier 0102, authorityCertIssuer dNSName "ca", authorityCertSerialNumber 05
+        let value = hex::decode("300d80020102a10482026361820105").unwrap();
+        let aki = AuthorityKeyIdentifier::parse(&value).unwrap();
+        assert_eq!(aki.key_identifier(), Some(&[1, 2][..]));
+        assert_eq!(aki.authority_cert_issuer(), [GeneralName::DnsName("ca".into())]);
+        assert_eq!(aki.authority_cert_serial_number(), Some(&[5][..]));
+
+        // serial number without issuer
+        assert!(AuthorityKeyIdentifier::parse(&hex::decode("3003820105").unwrap()).is_err());
+        assert_eq!(AuthorityKeyIdentifier::parse(&[0x30, 0]).unwrap(), AuthorityKeyIdentifier::default());
+        assert!(parse_subject_key_identifier(&[0x04, 0x01]).is_err());
+    }
+}
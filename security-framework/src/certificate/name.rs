@@ -0,0 +1,553 @@
+//! X.501 names, as used for certificate issuers and subjects.
+
+use security_framework_sys::base::errSecParam;
+use std::fmt::{self, Write as _};
+use std::str::FromStr;
+
+use crate::base::{Error, Result};
+use crate::der::{self, Oid, Reader};
+
+/// Short names from RFC 4514, used for formatting
+const SHORT_NAMES: [(&str, Oid); 9] = [
+    ("CN", Oid::COMMON_NAME),
+    ("L", Oid::LOCALITY_NAME),
+    ("ST", Oid::STATE_OR_PROVINCE_NAME),
+    ("O", Oid::ORGANIZATION_NAME),
+    ("OU", Oid::ORGANIZATIONAL_UNIT_NAME),
+    ("C", Oid::COUNTRY_NAME),
+    ("STREET", Oid::STREET_ADDRESS),
+    ("DC", Oid::DOMAIN_COMPONENT),
+    ("UID", Oid::USER_ID),
+];
+
+/// Other names accepted when parsing, but not produced by formatting
+const EXTRA_NAMES: [(&str, Oid); 3] = [
+    ("SERIALNUMBER", Oid::SERIAL_NUMBER),
+    ("emailAddress", Oid::EMAIL_ADDRESS),
+    ("E", Oid::EMAIL_ADDRESS),
+];
+
+fn short_name(oid: &Oid) -> Option<&'static str> {
+    SHORT_NAMES.iter().find(|(_, o)| o == oid).map(|(name, _)| *name)
+}
+
+fn oid_for_name(name: &str) -> Option<Oid> {
+    SHORT_NAMES.iter().chain(&EXTRA_NAMES)
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, oid)| oid.clone())
+}
+
+/// One attribute of a name, e.g. `CN=example.com`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttributeTypeAndValue {
+    oid: Oid,
+    tag: u8,
+    value: Vec<u8>,
+    text: Option<String>,
+}
+
+impl AttributeTypeAndValue {
+    pub(crate) fn parse(reader: &mut Reader<'_>) -> Result<Self> {
+        let mut seq = reader.read_sequence()?;
+        let oid = seq.read_oid()?;
+        let value = seq.read_tlv()?;
+        seq.finish()?;
+        Ok(Self {
+            oid,
+            tag: value.tag,
+            value: value.content.to_vec(),
+            text: der::decode_string(value.tag, value.content),
+        })
+    }
+
+    /// Creates a string attribute.
+    ///
+    /// The value is encoded as `PrintableString` for countries, `IA5String` for domain components,
+    /// and `UTF8String` otherwise.
+    #[must_use]
+    pub fn new(oid: Oid, value: &str) -> Self {
+        let tag = if oid == Oid::COUNTRY_NAME && value.bytes().all(is_printable) {
+            der::PRINTABLE_STRING
+        } else if oid == Oid::DOMAIN_COMPONENT && value.is_ascii() {
+            der::IA5_STRING
+        } else {
+            der::UTF8_STRING
+        };
+        Self {
+            oid,
+            tag,
+            value: value.as_bytes().to_vec(),
+            text: Some(value.into()),
+        }
+    }
+
+    /// The attribute type, e.g. `2.5.4.3` for the common name.
+    #[inline]
+    #[must_use]
+    pub fn oid(&self) -> &Oid {
+        &self.oid
+    }
+
+    /// The DER tag of the value, e.g. `0x0c` for `UTF8String`.
+    #[inline]
+    #[must_use]
+    pub fn value_tag(&self) -> u8 {
+        self.tag
+    }
+
+    /// The encoded contents of the value, without tag and length.
+    #[inline]
+    #[must_use]
+    pub fn value_bytes(&self) -> &[u8] {
+        &self.value
+    }
+
+    /// The value, if it is one of the string types.
+    #[inline]
+    #[must_use]
+    pub fn value_str(&self) -> Option<&str> {
+        self.text.as_deref()
+    }
+
+    /// DER encoding of the `AttributeTypeAndValue` SEQUENCE.
+    pub(crate) fn to_der(&self) -> Vec<u8> {
+        der::encode_sequence(&[&der::encode_oid(&self.oid), &der::encode(self.tag, &self.value)])
+    }
+
+    /// Compares the type and value, with string values compared case-insensitively
+    /// and ignoring insignificant whitespace.
+    #[must_use]
+    pub fn matches(&self, other: &Self) -> bool {
+        if self.oid != other.oid {
+            return false;
+        }
+        match (&self.text, &other.text) {
+            (Some(a), Some(b)) => fold(a) == fold(b),
+            _ => self.tag == other.tag && self.value == other.value,
+        }
+    }
+}
+
+/// Lowercase with runs of whitespace collapsed, for comparisons
+fn fold(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+pub(super) fn is_printable(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || b" '()+,-./:=?".contains(&c)
+}
+
+impl fmt::Display for AttributeTypeAndValue {
+    /// Formats as `type=value` according to RFC 4514.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (short_name(&self.oid), &self.text) {
+            (Some(name), Some(text)) => {
+                write!(f, "{name}=")?;
+                write_escaped(f, text)
+            },
+            (name, _) => {
+                match name {
+                    Some(name) => write!(f, "{name}=#")?,
+                    None => write!(f, "{}=#", self.oid)?,
+                }
+                // hex of the whole BER encoding of the value
+                let mut encoded = vec![self.tag];
+                der::write_length(&mut encoded, self.value.len());
+                encoded.extend_from_slice(&self.value);
+                encoded.iter().try_for_each(|b| write!(f, "{b:02x}"))
+            },
+        }
+    }
+}
+
+fn write_escaped(f: &mut fmt::Formatter<'_>, text: &str) -> fmt::Result {
+    let last = text.chars().count().saturating_sub(1);
+    for (i, c) in text.chars().enumerate() {
+        match c {
+            '"' | '+' | ',' | ';' | '<' | '>' | '\\' => write!(f, "\\{c}")?,
+            ' ' | '#' if i == 0 => write!(f, "\\{c}")?,
+            ' ' if i == last => f.write_str("\\ ")?,
+            '\0' => f.write_str("\\00")?,
+            c => f.write_char(c)?,
+        }
+    }
+    Ok(())
+}
+
+/// A set of attributes forming one component of a name. It usually has only one attribute.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelativeDistinguishedName(Vec<AttributeTypeAndValue>);
+
+impl RelativeDistinguishedName {
+    /// Creates a component from one or more attributes.
+    #[must_use]
+    pub fn new(attributes: Vec<AttributeTypeAndValue>) -> Self {
+        Self(attributes)
+    }
+
+    /// The attributes in this component.
+    #[inline]
+    #[must_use]
+    pub fn attributes(&self) -> &[AttributeTypeAndValue] {
+        &self.0
+    }
+
+    /// DER encoding of the SET, with the attributes sorted as DER requires.
+    pub(crate) fn to_der(&self) -> Vec<u8> {
+        let mut attributes: Vec<_> = self.0.iter().map(AttributeTypeAndValue::to_der).collect();
+        attributes.sort();
+        der::encode(der::SET, &attributes.concat())
+    }
+
+    /// Compares the attributes as sets, using [`AttributeTypeAndValue::matches`].
+    #[must_use]
+    pub fn matches(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len() && self.0.iter().all(|a| other.0.iter().any(|b| a.matches(b)))
+    }
+}
+
+impl fmt::Display for RelativeDistinguishedName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, attr) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str("+")?;
+            }
+            fmt::Display::fmt(attr, f)?;
+        }
+        Ok(())
+    }
+}
+
+/// An X.501 distinguished name, such as a certificate's subject or issuer.
+///
+/// Formats to and parses from the RFC 4514 string form, e.g. `CN=foo,O=Bar,C=US`.
+/// Note that the string form lists the components in reverse order of the encoding.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DistinguishedName(Vec<RelativeDistinguishedName>);
+
+impl DistinguishedName {
+    /// Creates a name from the components, in encoding order (i.e. country first).
+    #[must_use]
+    pub fn new(rdns: Vec<RelativeDistinguishedName>) -> Self {
+        Self(rdns)
+    }
+
+    /// Decodes a DER-encoded `Name`, such as the result of `SecCertificate::subject()`.
+    pub fn from_der(der: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(der);
+        let name = reader.read_sequence()?;
+        reader.finish()?;
+        Self::parse_content(name)
+    }
+
+    /// Encodes the name as a DER `Name` SEQUENCE.
+    ///
+    /// Names decoded with [`DistinguishedName::from_der`] are re-encoded unchanged.
+    #[must_use]
+    pub fn to_der(&self) -> Vec<u8> {
+        let rdns: Vec<_> = self.0.iter().map(RelativeDistinguishedName::to_der).collect();
+        der::encode(der::SEQUENCE, &rdns.concat())
+    }
+
+    /// Parses the contents of a `Name` SEQUENCE.
+    pub(crate) fn parse_content(mut reader: Reader<'_>) -> Result<Self> {
+        let mut rdns = Vec::new();
+        while !reader.is_empty() {
+            let mut set = reader.read_nested(der::SET)?;
+            let mut attributes = Vec::new();
+            while !set.is_empty() {
+                attributes.push(AttributeTypeAndValue::parse(&mut set)?);
+            }
+            if attributes.is_empty() {
+                return Err(der::decode_error());
+            }
+            rdns.push(RelativeDistinguishedName(attributes));
+        }
+        Ok(Self(rdns))
+    }
+
+    /// The components in encoding order, which is the reverse of the string form.
+    #[inline]
+    #[must_use]
+    pub fn rdns(&self) -> &[RelativeDistinguishedName] {
+        &self.0
+    }
+
+    /// Returns `true` if there are no components.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// All values of the given attribute type, in encoding order.
+    pub fn attribute_values<'a>(&'a self, oid: &'a Oid) -> impl Iterator<Item = &'a str> + 'a {
+        self.0.iter()
+            .flat_map(|rdn| rdn.attributes())
+            .filter(move |attr| attr.oid() == oid)
+            .filter_map(|attr| attr.value_str())
+    }
+
+    /// The most specific (last encoded) value of the given attribute type.
+    #[must_use]
+    pub fn attribute(&self, oid: &Oid) -> Option<&str> {
+        self.0.iter()
+            .flat_map(|rdn| rdn.attributes())
+            .filter(|attr| attr.oid() == oid)
+            .filter_map(|attr| attr.value_str())
+            .next_back()
+    }
+
+    /// The common name (`CN`).
+    #[must_use]
+    pub fn common_name(&self) -> Option<&str> {
+        self.attribute(&Oid::COMMON_NAME)
+    }
+
+    /// The organization (`O`).
+    #[must_use]
+    pub fn organization(&self) -> Option<&str> {
+        self.attribute(&Oid::ORGANIZATION_NAME)
+    }
+
+    /// The organizational unit (`OU`).
+    #[must_use]
+    pub fn organizational_unit(&self) -> Option<&str> {
+        self.attribute(&Oid::ORGANIZATIONAL_UNIT_NAME)
+    }
+
+    /// The country code (`C`).
+    #[must_use]
+    pub fn country(&self) -> Option<&str> {
+        self.attribute(&Oid::COUNTRY_NAME)
+    }
+
+    /// The state or province (`ST`).
+    #[must_use]
+    pub fn state_or_province(&self) -> Option<&str> {
+        self.attribute(&Oid::STATE_OR_PROVINCE_NAME)
+    }
+
+    /// The locality (`L`).
+    #[must_use]
+    pub fn locality(&self) -> Option<&str> {
+        self.attribute(&Oid::LOCALITY_NAME)
+    }
+
+    /// Compares names component by component, with string values compared
+    /// case-insensitively and ignoring insignificant whitespace.
+    #[must_use]
+    pub fn matches(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len() && self.0.iter().zip(&other.0).all(|(a, b)| a.matches(b))
+    }
+}
+
+impl fmt::Display for DistinguishedName {
+    /// Formats according to RFC 4514, most specific component first.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, rdn) in self.0.iter().rev().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            fmt::Display::fmt(rdn, f)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for DistinguishedName {
+    type Err = Error;
+
+    /// Parses the RFC 4514 string form. Spaces around separators are tolerated.
+    fn from_str(s: &str) -> Result<Self> {
+        let mut rdns = Vec::new();
+        let mut attributes = Vec::new();
+        let mut rest = s.trim_start();
+        if rest.is_empty() {
+            return Ok(Self::default());
+        }
+        loop {
+            let eq = rest.find('=').ok_or_else(param_error)?;
+            let name = rest[..eq].trim();
+            let oid = match oid_for_name(name) {
+                Some(oid) => oid,
+                None => name.strip_prefix("oid.").or(name.strip_prefix("OID.")).unwrap_or(name).parse()?,
+            };
+            let (attr, sep, remaining) = parse_value(oid, rest[eq + 1..].trim_start())?;
+            attributes.push(attr);
+            if sep != Some(b'+') {
+                rdns.push(RelativeDistinguishedName(std::mem::take(&mut attributes)));
+            }
+            match sep {
+                None => break,
+                Some(_) => rest = remaining.trim_start(),
+            }
+        }
+        rdns.reverse();
+        Ok(Self(rdns))
+    }
+}
+
+#[cold]
+fn param_error() -> Error {
+    Error::from_code(errSecParam)
+}
+
+/// Parses one value, and returns it with the separator that ended it and the remaining input.
+fn parse_value(oid: Oid, input: &str) -> Result<(AttributeTypeAndValue, Option<u8>, &str)> {
+    let bytes = input.as_bytes();
+    let end = |pos: usize| match bytes.get(pos) {
+        Some(&c @ (b',' | b';' | b'+')) => Ok((Some(if c == b';' { b',' } else { c }), &input[pos + 1..])),
+        None => Ok((None, "")),
+        Some(_) => Err(param_error()),
+    };
+
+    if let Some(hex) = input.strip_prefix('#') {
+        let len = hex.bytes().take_while(u8::is_ascii_hexdigit).count();
+        let encoded = (0..len / 2)
+            .map(|i| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| param_error())?;
+        if len % 2 != 0 {
+            return Err(param_error());
+        }
+        let mut reader = Reader::new(&encoded);
+        let tlv = reader.read_tlv().map_err(|_| param_error())?;
+        reader.finish().map_err(|_| param_error())?;
+        let attr = AttributeTypeAndValue {
+            oid,
+            tag: tlv.tag,
+            value: tlv.content.to_vec(),
+            text: der::decode_string(tlv.tag, tlv.content),
+        };
+        let after = input[1 + len..].trim_start();
+        let (sep, rest) = end(input.len() - after.len())?;
+        return Ok((attr, sep, rest));
+    }
+
+    let mut value = Vec::new();
+    // length of the value without unescaped trailing spaces
+    let mut significant_len = 0;
+    let mut pos = 0;
+    while let Some(&c) = bytes.get(pos) {
+        match c {
+            b',' | b';' | b'+' => break,
+            b'\\' => {
+                let next = *bytes.get(pos + 1).ok_or_else(param_error)?;
+                if next.is_ascii_hexdigit() {
+                    let pair = input.get(pos + 1..pos + 3).ok_or_else(param_error)?;
+                    value.push(u8::from_str_radix(pair, 16).map_err(|_| param_error())?);
+                    pos += 3;
+                } else if b" \"#+,;<=>\\".contains(&next) {
+                    value.push(next);
+                    pos += 2;
+                } else {
+                    return Err(param_error());
+                }
+                significant_len = value.len();
+                continue;
+            },
+            b'"' => return Err(param_error()),
+            c => {
+                value.push(c);
+                if c != b' ' {
+                    significant_len = value.len();
+                }
+            },
+        }
+        pos += 1;
+    }
+    value.truncate(significant_len);
+    let value = String::from_utf8(value).map_err(|_| param_error())?;
+    let (sep, rest) = end(pos)?;
+    Ok((AttributeTypeAndValue::new(oid, &value), sep, rest))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn foobar() -> DistinguishedName {
+        let der = include_bytes!("../../test/server.der");
+        crate::certificate::Parsed::from_der(der).unwrap().subject().clone()
+    }
+
+    #[test]
+    fn format() {
+        let name = foobar();
+        assert_eq!(name.to_string(), "CN=foobar.com,OU=Dev Land,O=Foobar LLC,L=Palo Alto,ST=California,C=US");
+        assert_eq!(name.common_name(), Some("foobar.com"));
+        assert_eq!(name.organization(), Some("Foobar LLC"));
+        assert_eq!(name.organizational_unit(), Some("Dev Land"));
+        assert_eq!(name.country(), Some("US"));
+        assert_eq!(name.state_or_province(), Some("California"));
+        assert_eq!(name.locality(), Some("Palo Alto"));
+    }
+
+    #[test]
+    fn round_trip() {
+        let name = foobar();
+        let parsed: DistinguishedName = name.to_string().parse().unwrap();
+        assert_eq!(parsed, name);
+        assert!(parsed.matches(&name));
+
+        let cert = crate::certificate::Parsed::from_der(include_bytes!("../../test/server.der")).unwrap();
+        assert_eq!(name.to_der(), cert.subject_der());
+        assert_eq!(DistinguishedName::from_der(&name.to_der()).unwrap(), name);
+    }
+
+    #[test]
+    fn escaping() {
+        let name = DistinguishedName::new(vec![
+            RelativeDistinguishedName::new(vec![AttributeTypeAndValue::new(Oid::COMMON_NAME, " #a,b+c\\d\"<>; ")]),
+        ]);
+        let s = name.to_string();
+        assert_eq!(s, r#"CN=\ #a\,b\+c\\d\"\<\>\;\ "#);
+        assert_eq!(s.parse::<DistinguishedName>().unwrap(), name);
+
+        let name: DistinguishedName = r"CN=caf\C3\A9 , O = x ; C=PL".parse().unwrap();
+        assert_eq!(name.common_name(), Some("café"));
+        assert_eq!(name.organization(), Some("x"));
+        assert_eq!(name.country(), Some("PL"));
+        assert_eq!(name.rdns()[0].attributes()[0].value_tag(), der::PRINTABLE_STRING);
+    }
+
+    #[test]
+    fn multi_valued_and_hex() {
+        let name: DistinguishedName = "OU=Sales+CN=J. Smith,DC=example,DC=net".parse().unwrap();
+        assert_eq!(name.rdns().len(), 3);
+        assert_eq!(name.rdns()[2].attributes().len(), 2);
+        assert_eq!(name.to_string(), "OU=Sales+CN=J. Smith,DC=example,DC=net");
+        assert_eq!(name.attribute_values(&Oid::DOMAIN_COMPONENT).collect::<Vec<_>>(), ["net", "example"]);
+
+        let name: DistinguishedName = "1.3.6.1.4.1.1466.0=#04024869,O=Test".parse().unwrap();
+        let attr = &name.rdns()[1].attributes()[0];
+        assert_eq!(attr.value_tag(), der::OCTET_STRING);
+        assert_eq!(attr.value_bytes(), b"Hi");
+        assert_eq!(name.to_string(), "1.3.6.1.4.1.1466.0=#04024869,O=Test");
+
+        let name: DistinguishedName = "emailAddress=a@b.c,CN=x".parse().unwrap();
+        assert_eq!(name.to_string(), "1.2.840.113549.1.9.1=#0c056140622e63,CN=x");
+    }
+
+    #[test]
+    fn case_insensitive() {
+        let a: DistinguishedName = "CN=Foo  Bar,O=Example".parse().unwrap();
+        let b: DistinguishedName = "cn=foo bar, o=EXAMPLE".parse().unwrap();
+        assert!(a.matches(&b));
+        assert_ne!(a, b);
+        let c: DistinguishedName = "CN=Foo Bar".parse().unwrap();
+        assert!(!a.matches(&c));
+    }
+
+    #[test]
+    fn invalid() {
+        assert!("CN".parse::<DistinguishedName>().is_err());
+        assert!("CN=a\\".parse::<DistinguishedName>().is_err());
+        assert!("CN=a\\x".parse::<DistinguishedName>().is_err());
+        assert!("XYZ=a".parse::<DistinguishedName>().is_err());
+        assert!("CN=#12".parse::<DistinguishedName>().is_err());
+        assert!("CN=\"quoted\"".parse::<DistinguishedName>().is_err());
+        assert!("".parse::<DistinguishedName>().unwrap().is_empty());
+    }
+}
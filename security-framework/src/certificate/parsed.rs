@@ -0,0 +1,545 @@
+//! Platform-independent view of the fields of an X.509 certificate.
+
+use security_framework_sys::base::errSecUnimplemented;
+use std::time::SystemTime;
+
+use super::alt_name::{self, GeneralName};
+use super::extensions::{self, BasicConstraints, KeyUsage};
+use super::key_id::{self, AuthorityKeyIdentifier};
+use super::name::DistinguishedName;
+use crate::base::{Error, Result};
+use crate::der::{self, Oid, Reader};
+use crate::digest::{DigestAlgorithm, Fingerprint};
+
+/// An algorithm OID with its optional parameters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlgorithmIdentifier {
+    oid: Oid,
+    parameters: Option<Vec<u8>>,
+}
+
+impl AlgorithmIdentifier {
+    /// Creates an identifier from an OID and the DER encoding of its parameters.
+    #[must_use]
+    pub fn new(oid: Oid, parameters: Option<Vec<u8>>) -> Self {
+        Self { oid, parameters }
+    }
+
+    /// RSA PKCS #1 v1.5 signatures with the given hash, e.g. `sha256WithRSAEncryption`.
+    #[must_use]
+    pub fn rsa_pkcs1(digest: DigestAlgorithm) -> Self {
+        let oid = match digest {
+            DigestAlgorithm::Sha1 => Oid::SHA1_WITH_RSA,
+            DigestAlgorithm::Sha256 => Oid::SHA256_WITH_RSA,
+            DigestAlgorithm::Sha384 => Oid::SHA384_WITH_RSA,
+            DigestAlgorithm::Sha512 => Oid::SHA512_WITH_RSA,
+        };
+        // the parameters are an explicit NULL
+        Self::new(oid, Some(vec![0x05, 0x00]))
+    }
+
+    /// ECDSA signatures with the given hash, e.g. `ecdsa-with-SHA256`.
+    ///
+    /// SHA-1 is not supported, and fails with `errSecUnimplemented`.
+    pub fn ecdsa(digest: DigestAlgorithm) -> Result<Self> {
+        let oid = match digest {
+            DigestAlgorithm::Sha1 => return Err(Error::from_code(errSecUnimplemented)),
+            DigestAlgorithm::Sha256 => Oid::ECDSA_WITH_SHA256,
+            DigestAlgorithm::Sha384 => Oid::ECDSA_WITH_SHA384,
+            DigestAlgorithm::Sha512 => Oid::ECDSA_WITH_SHA512,
+        };
+        Ok(Self::new(oid, None))
+    }
+
+    pub(crate) fn parse(reader: &mut Reader<'_>) -> Result<Self> {
+        let mut seq = reader.read_sequence()?;
+        let oid = seq.read_oid()?;
+        let parameters = if seq.is_empty() { None } else { Some(seq.read_tlv()?.raw.to_vec()) };
+        seq.finish()?;
+        Ok(Self { oid, parameters })
+    }
+
+    /// The algorithm, e.g. `1.2.840.113549.1.1.11` for `sha256WithRSAEncryption`.
+    #[inline]
+    #[must_use]
+    pub fn oid(&self) -> &Oid {
+        &self.oid
+    }
+
+    /// DER encoding of the parameters (including tag), if present.
+    ///
+    /// For RSA this is usually `NULL`, for EC keys it's the curve OID.
+    #[inline]
+    #[must_use]
+    pub fn parameters(&self) -> Option<&[u8]> {
+        self.parameters.as_deref()
+    }
+
+    pub(crate) fn to_der(&self) -> Vec<u8> {
+        der::encode_sequence(&[&der::encode_oid(&self.oid), self.parameters.as_deref().unwrap_or_default()])
+    }
+}
+
+/// The public key of a certificate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubjectPublicKeyInfo {
+    algorithm: AlgorithmIdentifier,
+    public_key: Vec<u8>,
+    der: Vec<u8>,
+}
+
+impl SubjectPublicKeyInfo {
+    pub(crate) fn parse(reader: &mut Reader<'_>) -> Result<Self> {
+        let tlv = reader.read_tlv()?;
+        if tlv.tag != der::SEQUENCE {
+            return Err(der::decode_error());
+        }
+        let mut seq = Reader::new(tlv.content);
+        let algorithm = AlgorithmIdentifier::parse(&mut seq)?;
+        let (_, public_key) = seq.read_bit_string()?;
+        seq.finish()?;
+        Ok(Self {
+            algorithm,
+            public_key: public_key.to_vec(),
+            der: tlv.raw.to_vec(),
+        })
+    }
+
+    /// The key algorithm, e.g. `1.2.840.10045.2.1` for EC keys.
+    #[inline]
+    #[must_use]
+    pub fn algorithm(&self) -> &AlgorithmIdentifier {
+        &self.algorithm
+    }
+
+    /// The key bytes. For RSA this is `RSAPublicKey` DER, for EC an uncompressed point.
+    #[inline]
+    #[must_use]
+    pub fn public_key(&self) -> &[u8] {
+        &self.public_key
+    }
+
+    /// The whole DER-encoded `SubjectPublicKeyInfo`, e.g. for pinning.
+    #[inline]
+    #[must_use]
+    pub fn der(&self) -> &[u8] {
+        &self.der
+    }
+}
+
+/// A certificate extension, not interpreted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Extension {
+    oid: Oid,
+    critical: bool,
+    value: Vec<u8>,
+}
+
+impl Extension {
+    /// Creates an extension from its DER-encoded value.
+    #[must_use]
+    pub fn new(oid: Oid, critical: bool, value: Vec<u8>) -> Self {
+        Self { oid, critical, value }
+    }
+
+    pub(crate) fn to_der(&self) -> Vec<u8> {
+        let critical = if self.critical { der::encode_bool(true) } else { Vec::new() };
+        der::encode_sequence(&[&der::encode_oid(&self.oid), &critical, &der::encode(der::OCTET_STRING, &self.value)])
+    }
+
+    fn parse(reader: &mut Reader<'_>) -> Result<Self> {
+        let mut seq = reader.read_sequence()?;
+        let oid = seq.read_oid()?;
+        let critical = seq.read_optional_bool()?;
+        let value = seq.read(der::OCTET_STRING)?.to_vec();
+        seq.finish()?;
+        Ok(Self { oid, critical, value })
+    }
+
+    /// Reads an `Extensions` SEQUENCE.
+    pub(crate) fn parse_list(reader: &mut Reader<'_>) -> Result<Vec<Self>> {
+        let mut list = reader.read_sequence()?;
+        let mut extensions = Vec::new();
+        while !list.is_empty() {
+            extensions.push(Self::parse(&mut list)?);
+        }
+        Ok(extensions)
+    }
+
+    /// The extension type, e.g. [`Oid::SUBJECT_ALT_NAME`].
+    #[inline]
+    #[must_use]
+    pub fn oid(&self) -> &Oid {
+        &self.oid
+    }
+
+    /// Whether the extension must be understood by verifiers.
+    #[inline]
+    #[must_use]
+    pub fn is_critical(&self) -> bool {
+        self.critical
+    }
+
+    /// The DER-encoded extension value (contents of `extnValue`).
+    #[inline]
+    #[must_use]
+    pub fn value(&self) -> &[u8] {
+        &self.value
+    }
+}
+
+/// Fields of an X.509 certificate, decoded without Security.framework.
+///
+/// This behaves the same on all platforms. Use [`Parsed::from_der`],
+/// or `SecCertificate::parse()` on Apple platforms.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Parsed {
+    version: u8,
+    serial_number: Vec<u8>,
+    signature_algorithm: AlgorithmIdentifier,
+    issuer: DistinguishedName,
+    issuer_der: Vec<u8>,
+    not_before: SystemTime,
+    not_after: SystemTime,
+    subject: DistinguishedName,
+    subject_der: Vec<u8>,
+    public_key_info: SubjectPublicKeyInfo,
+    extensions: Vec<Extension>,
+    tbs_der: Vec<u8>,
+    signature: Vec<u8>,
+    der: Vec<u8>,
+}
+
+impl Parsed {
+    /// Parses a DER-encoded certificate.
+    pub fn from_der(der: &[u8]) -> Result<Self> {
+        let mut outer = Reader::new(der);
+        let mut cert = outer.read_sequence()?;
+        outer.finish()?;
+
+        let tbs = cert.read_tlv()?;
+        if tbs.tag != der::SEQUENCE {
+            return Err(der::decode_error());
+        }
+        let outer_algorithm = AlgorithmIdentifier::parse(&mut cert)?;
+        let (_, signature) = cert.read_bit_string()?;
+        cert.finish()?;
+
+        let mut tbs_reader = Reader::new(tbs.content);
+        let version = match tbs_reader.read_optional(der::context(0))? {
+            Some(explicit) => {
+                let mut explicit = Reader::new(explicit);
+                let v = explicit.read_small_uint()?;
+                explicit.finish()?;
+                v
+            },
+            None => 0,
+        };
+        if version > 2 {
+            return Err(der::decode_error());
+        }
+        let serial_number = tbs_reader.read_integer()?.to_vec();
+        let signature_algorithm = AlgorithmIdentifier::parse(&mut tbs_reader)?;
+        if signature_algorithm != outer_algorithm {
+            return Err(der::decode_error());
+        }
+        let issuer = tbs_reader.read_tlv()?;
+        let mut validity = tbs_reader.read_sequence()?;
+        let not_before = validity.read_time()?;
+        let not_after = validity.read_time()?;
+        validity.finish()?;
+        let subject = tbs_reader.read_tlv()?;
+        if issuer.tag != der::SEQUENCE || subject.tag != der::SEQUENCE {
+            return Err(der::decode_error());
+        }
+        let public_key_info = SubjectPublicKeyInfo::parse(&mut tbs_reader)?;
+        tbs_reader.read_optional(der::context_primitive(1))?;
+        tbs_reader.read_optional(der::context_primitive(2))?;
+        let mut extensions = Vec::new();
+        if let Some(explicit) = tbs_reader.read_optional(der::context(3))? {
+            let mut explicit = Reader::new(explicit);
+            extensions = Extension::parse_list(&mut explicit)?;
+            explicit.finish()?;
+        }
+        tbs_reader.finish()?;
+
+        Ok(Self {
+            version: version as u8 + 1,
+            serial_number,
+            signature_algorithm,
+            issuer: DistinguishedName::parse_content(Reader::new(issuer.content))?,
+            issuer_der: issuer.raw.to_vec(),
+            not_before,
+            not_after,
+            subject: DistinguishedName::parse_content(Reader::new(subject.content))?,
+            subject_der: subject.raw.to_vec(),
+            public_key_info,
+            extensions,
+            tbs_der: tbs.raw.to_vec(),
+            signature: signature.to_vec(),
+            der: der.to_vec(),
+        })
+    }
+
+    /// The X.509 version: 1, 2 or 3.
+    #[inline]
+    #[must_use]
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// The serial number as big-endian two's complement bytes.
+    #[inline]
+    #[must_use]
+    pub fn serial_number(&self) -> &[u8] {
+        &self.serial_number
+    }
+
+    /// The algorithm the issuer used to sign this certificate.
+    #[inline]
+    #[must_use]
+    pub fn signature_algorithm(&self) -> &AlgorithmIdentifier {
+        &self.signature_algorithm
+    }
+
+    /// The issuer name.
+    #[inline]
+    #[must_use]
+    pub fn issuer(&self) -> &DistinguishedName {
+        &self.issuer
+    }
+
+    /// The DER-encoded issuer name, as it appears in the certificate.
+    #[inline]
+    #[must_use]
+    pub fn issuer_der(&self) -> &[u8] {
+        &self.issuer_der
+    }
+
+    /// The subject name.
+    #[inline]
+    #[must_use]
+    pub fn subject(&self) -> &DistinguishedName {
+        &self.subject
+    }
+
+    /// The DER-encoded subject name, as it appears in the certificate.
+    #[inline]
+    #[must_use]
+    pub fn subject_der(&self) -> &[u8] {
+        &self.subject_der
+    }
+
+    /// Start of the validity period.
+    #[inline]
+    #[must_use]
+    pub fn not_before(&self) -> SystemTime {
+        self.not_before
+    }
+
+    /// End of the validity period (inclusive).
+    #[inline]
+    #[must_use]
+    pub fn not_after(&self) -> SystemTime {
+        self.not_after
+    }
+
+    /// The subject's public key.
+    #[inline]
+    #[must_use]
+    pub fn public_key_info(&self) -> &SubjectPublicKeyInfo {
+        &self.public_key_info
+    }
+
+    /// All extensions, in encoding order.
+    #[inline]
+    #[must_use]
+    pub fn extensions(&self) -> &[Extension] {
+        &self.extensions
+    }
+
+    /// Finds an extension by its OID.
+    #[must_use]
+    pub fn extension(&self, oid: &Oid) -> Option<&Extension> {
+        self.extensions.iter().find(|e| e.oid == *oid)
+    }
+
+    /// Decodes the basic constraints extension, if present.
+    pub fn basic_constraints(&self) -> Result<Option<BasicConstraints>> {
+        self.extension(&Oid::BASIC_CONSTRAINTS)
+            .map(|ext| BasicConstraints::parse(ext.value()))
+            .transpose()
+    }
+
+    /// Decodes the key usage extension, if present.
+    pub fn key_usage(&self) -> Result<Option<KeyUsage>> {
+        self.extension(&Oid::KEY_USAGE)
+            .map(|ext| KeyUsage::parse(ext.value()))
+            .transpose()
+    }
+
+    /// Decodes the extended key usage extension, if present.
+    pub fn extended_key_usage(&self) -> Result<Option<Vec<Oid>>> {
+        self.extension(&Oid::EXTENDED_KEY_USAGE)
+            .map(|ext| extensions::parse_extended_key_usage(ext.value()))
+            .transpose()
+    }
+
+    /// Decodes the subject key identifier extension, if present.
+    pub fn subject_key_identifier(&self) -> Result<Option<Vec<u8>>> {
+        self.extension(&Oid::SUBJECT_KEY_IDENTIFIER)
+            .map(|ext| key_id::parse_subject_key_identifier(ext.value()))
+            .transpose()
+    }
+
+    /// Decodes the authority key identifier extension, if present.
+    pub fn authority_key_identifier(&self) -> Result<Option<AuthorityKeyIdentifier>> {
+        self.extension(&Oid::AUTHORITY_KEY_IDENTIFIER)
+            .map(|ext| AuthorityKeyIdentifier::parse(ext.value()))
+            .transpose()
+    }
+
+    /// Decodes the subject alternative name extension. Returns an empty list if there is none.
+    pub fn subject_alt_names(&self) -> Result<Vec<GeneralName>> {
+        match self.extension(&Oid::SUBJECT_ALT_NAME) {
+            Some(ext) => alt_name::parse_general_names(ext.value()),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Checks whether the certificate is valid for `host`, a DNS name or IP address,
+    /// using [`matches_host`](super::matches_host). Fails closed if the extension can't be decoded.
+    #[must_use]
+    pub fn matches_host(&self, host: &str) -> bool {
+        self.subject_alt_names().is_ok_and(|names| alt_name::matches_host(&names, host))
+    }
+
+    /// Whether the issuer and subject names are the same, as for roots
+    /// (and for intermediates used in key rollover).
+    #[must_use]
+    pub fn is_self_issued(&self) -> bool {
+        self.issuer.matches(&self.subject)
+    }
+
+    /// Whether `issuer` looks like the issuer of this certificate: its subject matches this certificate's
+    /// issuer name, and its subject key identifier matches the authority key identifier if both are present.
+    ///
+    /// This doesn't check the signature.
+    #[must_use]
+    pub fn is_issued_by(&self, issuer: &Self) -> bool {
+        if !self.issuer.matches(&issuer.subject) {
+            return false;
+        }
+        let key_id = self.authority_key_identifier().ok().flatten();
+        match (key_id.as_ref().and_then(|id| id.key_identifier()), issuer.subject_key_identifier().ok().flatten()) {
+            (Some(expected), Some(actual)) => expected == actual,
+            _ => true,
+        }
+    }
+
+    /// The DER-encoded `TBSCertificate`, which is the signed part of the certificate.
+    #[inline]
+    #[must_use]
+    pub fn tbs_certificate(&self) -> &[u8] {
+        &self.tbs_der
+    }
+
+    /// The issuer's signature over [`Parsed::tbs_certificate`].
+    #[inline]
+    #[must_use]
+    pub fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+
+    /// The whole DER-encoded certificate.
+    #[inline]
+    #[must_use]
+    pub fn der(&self) -> &[u8] {
+        &self.der
+    }
+
+    /// Hash of the whole DER-encoded certificate.
+    #[must_use]
+    pub fn fingerprint(&self, algorithm: DigestAlgorithm) -> Fingerprint {
+        Fingerprint::new(algorithm, &self.der)
+    }
+
+    /// Base64 SHA-256 hash of the `SubjectPublicKeyInfo`, the format used by HPKP
+    /// (`pin-sha256`) and most certificate pinning libraries.
+    #[must_use]
+    pub fn spki_sha256_pin(&self) -> String {
+        Fingerprint::new(DigestAlgorithm::Sha256, self.public_key_info.der()).to_base64()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::der::unix_from_time;
+
+    #[test]
+    fn server_der() {
+        let cert = Parsed::from_der(include_bytes!("../../test/server.der")).unwrap();
+        assert_eq!(cert.version(), 3);
+        assert_eq!(hex::encode(cert.serial_number()), "0b063c84031623f8ad7787720c0e5a4b21884602");
+        assert_eq!(cert.signature_algorithm().oid().to_string(), "1.2.840.113549.1.1.11");
+        assert_eq!(cert.signature_algorithm().parameters(), Some(&[5, 0][..]));
+        assert_eq!(unix_from_time(cert.not_before()), 1771530760);
+        assert_eq!(unix_from_time(cert.not_after()), 1842810760);
+
+        let subject: Vec<_> = cert.subject().rdns().iter()
+            .map(|rdn| (rdn.attributes()[0].oid().to_string(), rdn.attributes()[0].value_str().unwrap()))
+            .collect();
+        assert_eq!(subject, [
+            ("2.5.4.6".to_string(), "US"),
+            ("2.5.4.8".to_string(), "California"),
+            ("2.5.4.7".to_string(), "Palo Alto"),
+            ("2.5.4.10".to_string(), "Foobar LLC"),
+            ("2.5.4.11".to_string(), "Dev Land"),
+            ("2.5.4.3".to_string(), "foobar.com"),
+        ]);
+        assert_eq!(cert.issuer(), cert.subject());
+
+        let spki = cert.public_key_info();
+        assert_eq!(spki.algorithm().oid().to_string(), "1.2.840.113549.1.1.1");
+        assert_eq!(spki.public_key().len(), 270);
+        assert_eq!(spki.der().len(), 294);
+
+        let oids: Vec<_> = cert.extensions().iter().map(|e| e.oid().to_string()).collect();
+        assert_eq!(oids, ["2.5.29.19", "2.5.29.15", "2.5.29.37", "2.5.29.14", "2.5.29.17", "2.5.29.35"]);
+        assert!(cert.extension(&Oid::KEY_USAGE).unwrap().is_critical());
+        assert!(!cert.extension(&Oid::SUBJECT_ALT_NAME).unwrap().is_critical());
+        assert_eq!(cert.signature().len(), 256);
+    }
+
+    #[test]
+    fn ca_der() {
+        let cert = Parsed::from_der(include_bytes!("../../test/ca.der")).unwrap();
+        assert_eq!(cert.version(), 3);
+        assert_eq!(hex::encode(cert.serial_number()), "28ede83d12a6a7d2bca4462e2417cff5f388f74b");
+        assert_eq!(cert.issuer_der(), cert.subject_der());
+        assert!(cert.extension(&Oid::BASIC_CONSTRAINTS).unwrap().is_critical());
+        assert_eq!(
+            cert.extension(&Oid::SUBJECT_KEY_IDENTIFIER).unwrap().value(),
+            hex::decode("04140c3da1881f0f67ed13d47588a3d180495b9b8cc6").unwrap()
+        );
+    }
+
+    #[test]
+    fn ecdsa_algorithms() {
+        let cert = Parsed::from_der(include_bytes!("../../test/ecdsa/leaf.der")).unwrap();
+        assert_eq!(cert.signature_algorithm(), &AlgorithmIdentifier::ecdsa(DigestAlgorithm::Sha384).unwrap());
+        assert_eq!(AlgorithmIdentifier::ecdsa(DigestAlgorithm::Sha256).unwrap().oid(), &Oid::ECDSA_WITH_SHA256);
+        assert!(AlgorithmIdentifier::ecdsa(DigestAlgorithm::Sha1).is_err());
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        let der = include_bytes!("../../test/server.der");
+        assert!(Parsed::from_der(&der[..der.len() - 1]).is_err());
+        let mut trailing = der.to_vec();
+        trailing.push(0);
+        assert!(Parsed::from_der(&trailing).is_err());
+        assert!(Parsed::from_der(&[]).is_err());
+    }
+}
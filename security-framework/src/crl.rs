@@ -0,0 +1,652 @@
+//! Certificate revocation lists (RFC 5280 section 5).
+//!
+//! CRLs are parsed and checked without Security.framework, so revocation can be checked
+//! against locally supplied lists, e.g. on machines without network access.
+
+use security_framework_sys::base::{errSecCertificateRevoked, errSecIncompleteCertRevocationCheck};
+use std::time::SystemTime;
+
+use crate::base::{Error, Result};
+use crate::certificate::{AlgorithmIdentifier, AuthorityKeyIdentifier, DistinguishedName, Extension, KeyUsage, Parsed};
+#[cfg(target_vendor = "apple")]
+use crate::certificate::SecCertificate;
+use crate::der::{self, Oid, Reader};
+use crate::pem;
+use crate::signature;
+#[cfg(all(target_vendor = "apple", any(feature = "macos-12", not(target_os = "macos"))))]
+use crate::base::DomainError;
+#[cfg(all(target_vendor = "apple", any(feature = "macos-12", not(target_os = "macos"))))]
+use crate::trust::SecTrust;
+
+/// Why a certificate was revoked (`CRLReason`).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum RevocationReason {
+    /// No specific reason.
+    Unspecified,
+    /// The private key was compromised.
+    KeyCompromise,
+    /// The CA's private key was compromised.
+    CaCompromise,
+    /// The subject's name or other information changed.
+    AffiliationChanged,
+    /// The certificate was replaced.
+    Superseded,
+    /// The certificate is no longer needed.
+    CessationOfOperation,
+    /// The certificate is temporarily suspended.
+    CertificateHold,
+    /// In a delta CRL, the certificate is no longer revoked or on hold.
+    RemoveFromCrl,
+    /// A privilege granted by the certificate was withdrawn.
+    PrivilegeWithdrawn,
+    /// An attribute authority's key was compromised.
+    AaCompromise,
+}
+
+impl RevocationReason {
+    pub(crate) fn from_code(code: u64) -> Option<Self> {
+        Some(match code {
+            0 => Self::Unspecified,
+            1 => Self::KeyCompromise,
+            2 => Self::CaCompromise,
+            3 => Self::AffiliationChanged,
+            4 => Self::Superseded,
+            5 => Self::CessationOfOperation,
+            6 => Self::CertificateHold,
+            8 => Self::RemoveFromCrl,
+            9 => Self::PrivilegeWithdrawn,
+            10 => Self::AaCompromise,
+            _ => return None,
+        })
+    }
+}
+
+/// An entry in a CRL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RevokedCertificate {
+    serial_number: Vec<u8>,
+    revocation_date: SystemTime,
+    reason: Option<RevocationReason>,
+    invalidity_date: Option<SystemTime>,
+    extensions: Vec<Extension>,
+}
+
+impl RevokedCertificate {
+    fn parse(reader: &mut Reader<'_>) -> Result<Self> {
+        let mut seq = reader.read_sequence()?;
+        let serial_number = seq.read_integer()?.to_vec();
+        let revocation_date = seq.read_time()?;
+        let extensions = if seq.is_empty() { Vec::new() } else { Extension::parse_list(&mut seq)? };
+        seq.finish()?;
+
+        let mut reason = None;
+        let mut invalidity_date = None;
+        for extension in &extensions {
+            let mut value = Reader::new(extension.value());
+            if *extension.oid() == Oid::CRL_REASON {
+                let code = der::uint_from_content(value.read(der::ENUMERATED)?)?;
+                reason = Some(RevocationReason::from_code(code).ok_or_else(der::decode_error)?);
+            } else if *extension.oid() == Oid::INVALIDITY_DATE {
+                invalidity_date = Some(value.read_time()?);
+            } else {
+                continue;
+            }
+            value.finish()?;
+        }
+        Ok(Self { serial_number, revocation_date, reason, invalidity_date, extensions })
+    }
+
+    /// The serial number of the revoked certificate, as big-endian two's complement bytes.
+    #[inline]
+    #[must_use]
+    pub fn serial_number(&self) -> &[u8] {
+        &self.serial_number
+    }
+
+    /// When the CA revoked the certificate.
+    #[inline]
+    #[must_use]
+    pub fn revocation_date(&self) -> SystemTime {
+        self.revocation_date
+    }
+
+    /// The reason code, if the entry has one.
+    #[inline]
+    #[must_use]
+    pub fn reason(&self) -> Option<RevocationReason> {
+        self.reason
+    }
+
+    /// When the key is known or suspected to have been compromised, if the entry says.
+    #[inline]
+    #[must_use]
+    pub fn invalidity_date(&self) -> Option<SystemTime> {
+        self.invalidity_date
+    }
+
+    /// All entry extensions.
+    #[inline]
+    #[must_use]
+    pub fn extensions(&self) -> &[Extension] {
+        &self.extensions
+    }
+}
+
+/// A parsed certificate revocation list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Crl {
+    version: u8,
+    signature_algorithm: AlgorithmIdentifier,
+    issuer: DistinguishedName,
+    issuer_der: Vec<u8>,
+    this_update: SystemTime,
+    next_update: Option<SystemTime>,
+    revoked: Vec<RevokedCertificate>,
+    extensions: Vec<Extension>,
+    tbs_der: Vec<u8>,
+    signature: Vec<u8>,
+}
+
+impl Crl {
+    /// Parses a DER-encoded `CertificateList`.
+    pub fn from_der(der: &[u8]) -> Result<Self> {
+        let mut outer = Reader::new(der);
+        let mut list = outer.read_sequence()?;
+        outer.finish()?;
+
+        let tbs = list.read_tlv()?;
+        if tbs.tag != der::SEQUENCE {
+            return Err(der::decode_error());
+        }
+        let outer_algorithm = AlgorithmIdentifier::parse(&mut list)?;
+        let (_, signature) = list.read_bit_string()?;
+        list.finish()?;
+
+        let mut tbs_reader = Reader::new(tbs.content);
+        let version = if tbs_reader.peek_tag() == Some(der::INTEGER) { tbs_reader.read_small_uint()? } else { 0 };
+        if version > 1 {
+            return Err(der::decode_error());
+        }
+        let signature_algorithm = AlgorithmIdentifier::parse(&mut tbs_reader)?;
+        if signature_algorithm != outer_algorithm {
+            return Err(der::decode_error());
+        }
+        let issuer = tbs_reader.read_tlv()?;
+        if issuer.tag != der::SEQUENCE {
+            return Err(der::decode_error());
+        }
+        let this_update = tbs_reader.read_time()?;
+        let next_update = match tbs_reader.peek_tag() {
+            Some(der::UTC_TIME | der::GENERALIZED_TIME) => Some(tbs_reader.read_time()?),
+            _ => None,
+        };
+        let mut revoked = Vec::new();
+        if tbs_reader.peek_tag() == Some(der::SEQUENCE) {
+            let mut entries = tbs_reader.read_sequence()?;
+            while !entries.is_empty() {
+                revoked.push(RevokedCertificate::parse(&mut entries)?);
+            }
+        }
+        let mut extensions = Vec::new();
+        if let Some(explicit) = tbs_reader.read_optional(der::context(0))? {
+            let mut explicit = Reader::new(explicit);
+            extensions = Extension::parse_list(&mut explicit)?;
+            explicit.finish()?;
+        }
+        tbs_reader.finish()?;
+
+        Ok(Self {
+            version: version as u8 + 1,
+            signature_algorithm,
+            issuer: DistinguishedName::parse_content(Reader::new(issuer.content))?,
+            issuer_der: issuer.raw.to_vec(),
+            this_update,
+            next_update,
+            revoked,
+            extensions,
+            tbs_der: tbs.raw.to_vec(),
+            signature: signature.to_vec(),
+        })
+    }
+
+    /// Parses the first `X509 CRL` block of a PEM file.
+    pub fn from_pem(pem: &[u8]) -> Result<Self> {
+        Self::from_der(&pem::parse_first(pem, pem::X509_CRL)?)
+    }
+
+    /// The CRL version: 1 or 2.
+    #[inline]
+    #[must_use]
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// The algorithm the issuer signed the CRL with.
+    #[inline]
+    #[must_use]
+    pub fn signature_algorithm(&self) -> &AlgorithmIdentifier {
+        &self.signature_algorithm
+    }
+
+    /// The CA that issued the CRL.
+    #[inline]
+    #[must_use]
+    pub fn issuer(&self) -> &DistinguishedName {
+        &self.issuer
+    }
+
+    /// The DER-encoded issuer name.
+    #[inline]
+    #[must_use]
+    pub fn issuer_der(&self) -> &[u8] {
+        &self.issuer_der
+    }
+
+    /// When the CRL was issued.
+    #[inline]
+    #[must_use]
+    pub fn this_update(&self) -> SystemTime {
+        self.this_update
+    }
+
+    /// When the next CRL will be issued, after which this one is stale.
+    #[inline]
+    #[must_use]
+    pub fn next_update(&self) -> Option<SystemTime> {
+        self.next_update
+    }
+
+    /// Whether the CRL is current at `time`, i.e. issued and not past its next update.
+    /// CRLs without a next update are treated as current.
+    #[must_use]
+    pub fn is_current_at(&self, time: SystemTime) -> bool {
+        self.this_update <= time && self.next_update.is_none_or(|next| time <= next)
+    }
+
+    /// All entries.
+    #[inline]
+    #[must_use]
+    pub fn revoked_certificates(&self) -> &[RevokedCertificate] {
+        &self.revoked
+    }
+
+    /// Finds the entry for a serial number, as returned by [`Parsed::serial_number`].
+    #[must_use]
+    pub fn find(&self, serial_number: &[u8]) -> Option<&RevokedCertificate> {
+        self.revoked.iter().find(|r| r.serial_number == serial_number)
+    }
+
+    /// All CRL extensions.
+    #[inline]
+    #[must_use]
+    pub fn extensions(&self) -> &[Extension] {
+        &self.extensions
+    }
+
+    /// The extension with the given type, if present.
+    #[must_use]
+    pub fn extension(&self, oid: &Oid) -> Option<&Extension> {
+        self.extensions.iter().find(|e| e.oid() == oid)
+    }
+
+    /// The CRL number, as big-endian bytes. Later CRLs from the same issuer have larger numbers.
+    pub fn crl_number(&self) -> Result<Option<Vec<u8>>> {
+        self.extension(&Oid::CRL_NUMBER).map(|ext| {
+            let mut reader = Reader::new(ext.value());
+            let number = reader.read_integer()?.to_vec();
+            reader.finish()?;
+            Ok(number)
+        }).transpose()
+    }
+
+    /// Whether this is a delta CRL, which only lists changes since a base CRL.
+    #[must_use]
+    pub fn is_delta(&self) -> bool {
+        self.extension(&Oid::DELTA_CRL_INDICATOR).is_some()
+    }
+
+    /// The authority key identifier extension, which identifies the key that signed the CRL.
+    pub fn authority_key_identifier(&self) -> Result<Option<AuthorityKeyIdentifier>> {
+        self.extension(&Oid::AUTHORITY_KEY_IDENTIFIER).map(|ext| AuthorityKeyIdentifier::parse(ext.value())).transpose()
+    }
+
+    /// The DER-encoded `TBSCertList`, which is the signed part of the CRL.
+    #[inline]
+    #[must_use]
+    pub fn tbs_cert_list(&self) -> &[u8] {
+        &self.tbs_der
+    }
+
+    /// The issuer's signature over [`Crl::tbs_cert_list`].
+    #[inline]
+    #[must_use]
+    pub fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+
+    /// Whether the CRL is for certificates issued by `issuer`, going by name and key identifier.
+    #[must_use]
+    pub fn is_issued_by(&self, issuer: &Parsed) -> bool {
+        if !self.issuer.matches(issuer.subject()) {
+            return false;
+        }
+        let key_id = self.authority_key_identifier().ok().flatten();
+        match (key_id.as_ref().and_then(|id| id.key_identifier()), issuer.subject_key_identifier().ok().flatten()) {
+            (Some(expected), Some(actual)) => expected == actual,
+            _ => true,
+        }
+    }
+
+    /// Checks that `issuer` signed the CRL and may sign CRLs.
+    ///
+    /// RSA and ECDSA P-256 and P-384 signatures are supported; others fail with `errSecUnimplemented`.
+    pub fn verify_signature(&self, issuer: &Parsed) -> Result<bool> {
+        if issuer.key_usage()?.is_some_and(|usage| !usage.contains(KeyUsage::CRL_SIGN)) {
+            return Ok(false);
+        }
+        signature::verify_signature(issuer.public_key_info(), &self.signature_algorithm, &self.tbs_der, &self.signature)
+    }
+
+    /// Whether the CRL has critical extensions that this crate doesn't understand,
+    /// in which case it mustn't be used to decide revocation status.
+    fn has_unknown_critical_extensions(&self) -> bool {
+        let known = [Oid::CRL_NUMBER, Oid::AUTHORITY_KEY_IDENTIFIER, Oid::DELTA_CRL_INDICATOR];
+        self.extensions.iter().any(|e| e.is_critical() && !known.contains(e.oid()))
+            || self.revoked.iter().flat_map(|r| &r.extensions).any(|e| e.is_critical() && *e.oid() != Oid::CRL_REASON && *e.oid() != Oid::INVALIDITY_DATE)
+    }
+}
+
+/// The result of checking a certificate against CRLs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RevocationStatus {
+    /// A current CRL from the issuer doesn't list the certificate.
+    Good,
+    /// The certificate is listed in a current CRL from its issuer.
+    Revoked(RevokedCertificate),
+    /// No usable CRL covers the certificate.
+    Unknown,
+    /// A current CRL from the issuer covers the certificate, but its signature couldn't be
+    /// checked, e.g. because the algorithm isn't supported, and no CRL lists the certificate.
+    Unverifiable,
+}
+
+/// A set of CRLs to check certificates against.
+#[derive(Debug, Clone, Default)]
+pub struct CrlStore {
+    crls: Vec<Crl>,
+    require_crl: bool,
+}
+
+impl CrlStore {
+    /// Creates an empty store.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a CRL.
+    pub fn add(&mut self, crl: Crl) -> &mut Self {
+        self.crls.push(crl);
+        self
+    }
+
+    /// Adds every `X509 CRL` block in a PEM file.
+    pub fn add_pem(&mut self, pem: &[u8]) -> Result<&mut Self> {
+        for block in pem::parse(pem)? {
+            if block.label() == pem::X509_CRL {
+                self.crls.push(Crl::from_der(block.der())?);
+            }
+        }
+        Ok(self)
+    }
+
+    /// Makes [`CrlStore::check_chain`] fail when a certificate isn't covered by any CRL.
+    /// By default such certificates are allowed.
+    pub fn set_require_crl(&mut self, require: bool) -> &mut Self {
+        self.require_crl = require;
+        self
+    }
+
+    /// The CRLs in the store.
+    #[inline]
+    #[must_use]
+    pub fn crls(&self) -> &[Crl] {
+        &self.crls
+    }
+
+    /// Checks `certificate` against the CRLs that are current at `time`.
+    ///
+    /// If `issuer` is given, only CRLs signed by it are used. Otherwise CRLs are matched
+    /// by issuer name and key identifier alone, so they must come from a trusted source.
+    /// Delta CRLs and entries for other issuers (indirect CRLs) are not supported.
+    #[must_use]
+    pub fn check(&self, certificate: &Parsed, issuer: Option<&Parsed>, time: SystemTime) -> RevocationStatus {
+        let key_id = certificate.authority_key_identifier().ok().flatten();
+        let key_id = key_id.as_ref().and_then(|id| id.key_identifier());
+        let mut status = RevocationStatus::Unknown;
+        for crl in &self.crls {
+            if !crl.issuer.matches(certificate.issuer()) || crl.is_delta() || !crl.is_current_at(time) || crl.has_unknown_critical_extensions() {
+                continue;
+            }
+            let crl_key_id = crl.authority_key_identifier().ok().flatten();
+            if let (Some(expected), Some(actual)) = (key_id, crl_key_id.as_ref().and_then(|id| id.key_identifier())) {
+                if expected != actual {
+                    continue;
+                }
+            }
+            if let Some(issuer) = issuer {
+                if !crl.is_issued_by(issuer) {
+                    continue;
+                }
+                match crl.verify_signature(issuer) {
+                    Ok(true) => {},
+                    Ok(false) => continue,
+                    // it might list the certificate, so other CRLs can't make it good
+                    Err(_) => {
+                        status = RevocationStatus::Unverifiable;
+                        continue;
+                    },
+                }
+            }
+            match crl.find(certificate.serial_number()) {
+                Some(entry) if entry.reason != Some(RevocationReason::RemoveFromCrl) => {
+                    return RevocationStatus::Revoked(entry.clone());
+                },
+                _ if status == RevocationStatus::Unverifiable => {},
+                _ => status = RevocationStatus::Good,
+            }
+        }
+        status
+    }
+
+    /// Checks every certificate in a chain ordered from the leaf to the root,
+    /// using the next certificate as the issuer. The root itself isn't checked.
+    ///
+    /// Fails with `errSecCertificateRevoked` if any certificate is revoked, and with
+    /// `errSecIncompleteCertRevocationCheck` if one is covered by a CRL whose signature can't be
+    /// checked, or isn't covered and [`CrlStore::set_require_crl`] is enabled.
+    pub fn check_chain(&self, chain: &[Parsed], time: SystemTime) -> Result<()> {
+        for pair in chain.windows(2) {
+            match self.check(&pair[0], Some(&pair[1]), time) {
+                RevocationStatus::Revoked(_) => return Err(Error::from_code(errSecCertificateRevoked)),
+                RevocationStatus::Unverifiable => return Err(Error::from_code(errSecIncompleteCertRevocationCheck)),
+                RevocationStatus::Unknown if self.require_crl => {
+                    return Err(Error::from_code(errSecIncompleteCertRevocationCheck));
+                },
+                RevocationStatus::Good | RevocationStatus::Unknown => {},
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks a certificate against CRLs current now. See [`CrlStore::check`].
+    #[cfg(target_vendor = "apple")]
+    pub fn check_certificate(&self, certificate: &SecCertificate, issuer: Option<&SecCertificate>) -> Result<RevocationStatus> {
+        let issuer = issuer.map(SecCertificate::parse).transpose()?;
+        Ok(self.check(&certificate.parse()?, issuer.as_ref(), SystemTime::now()))
+    }
+
+    /// Evaluates `trust`, then checks the resulting chain against the CRLs with [`CrlStore::check_chain`].
+    ///
+    /// To avoid network access during evaluation, disable it with `SecTrust::set_network_fetch_allowed`.
+    ///
+    /// Evaluation failures keep the details of the `CFError`.
+    #[cfg(all(target_vendor = "apple", any(feature = "macos-12", not(target_os = "macos"))))]
+    pub fn evaluate(&self, trust: &SecTrust) -> Result<(), DomainError> {
+        trust.evaluate_with_error().map_err(DomainError::from)?;
+        let chain = trust.chain().iter().map(SecCertificate::parse).collect::<Result<Vec<_>>>()?;
+        Ok(self.check_chain(&chain, SystemTime::now())?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::der::{time_from_unix, unix_from_time};
+    use std::time::Duration;
+
+    fn crl() -> Crl {
+        Crl::from_der(include_bytes!("../test/crl/crl.der")).unwrap()
+    }
+
+    fn cert(der: &[u8]) -> Parsed {
+        Parsed::from_der(der).unwrap()
+    }
+
+    fn fixtures() -> (Parsed, Parsed, Parsed) {
+        (
+            cert(include_bytes!("../test/crl/crl_ca.der")),
+            cert(include_bytes!("../test/crl/good.der")),
+            cert(include_bytes!("../test/crl/revoked.der")),
+        )
+    }
+
+    #[test]
+    fn parse() {
+        let crl = crl();
+        assert_eq!(crl, Crl::from_pem(include_bytes!("../test/crl/crl.pem")).unwrap());
+        assert_eq!(crl.version(), 2);
+        assert_eq!(crl.issuer().to_string(), "CN=CRL Test CA,O=Foobar LLC");
+        assert_eq!(*crl.signature_algorithm().oid(), Oid::SHA256_WITH_RSA);
+        let this_update = unix_from_time(crl.this_update());
+        assert_eq!(unix_from_time(crl.next_update().unwrap()) - this_update, 3650 * 86400);
+        assert_eq!(crl.crl_number().unwrap(), Some(vec![0x10, 0x00]));
+        assert!(!crl.is_delta());
+        assert_eq!(
+            hex::encode(crl.authority_key_identifier().unwrap().unwrap().key_identifier().unwrap()),
+            "ebaaaf14f48f33415147f5b3cca17f7260b2d708"
+        );
+
+        let revoked = crl.revoked_certificates();
+        assert_eq!(revoked.len(), 3);
+        assert_eq!(revoked[0].serial_number(), [0x0d, 0x0e]);
+        assert_eq!(unix_from_time(revoked[0].revocation_date()), 1_706_832_000);
+        assert_eq!(revoked[0].reason(), Some(RevocationReason::Superseded));
+        assert_eq!(revoked[1].serial_number(), [0x0a, 0x0b, 0x0c]);
+        assert_eq!(revoked[1].reason(), None);
+        assert_eq!(revoked[1].invalidity_date(), None);
+        assert_eq!(revoked[2].reason(), Some(RevocationReason::KeyCompromise));
+        assert_eq!(crl.find(&[0x0a, 0x0b, 0x0c]), Some(&revoked[1]));
+        assert_eq!(crl.find(&[0x0a, 0x0b]), None);
+
+        assert!(Crl::from_der(include_bytes!("../test/ca.der")).is_err());
+        assert!(Crl::from_pem(include_bytes!("../test/server.key")).is_err());
+    }
+
+    #[test]
+    fn signature() {
+        let (ca, good, _) = fixtures();
+        let crl = crl();
+        assert!(crl.is_issued_by(&ca));
+        assert!(crl.verify_signature(&ca).unwrap());
+        assert!(!crl.is_issued_by(&good));
+        assert!(!crl.verify_signature(&good).unwrap());
+        let mut tampered = crl.clone();
+        tampered.tbs_der[20] ^= 1;
+        assert!(!tampered.verify_signature(&ca).unwrap());
+    }
+
+    #[test]
+    fn check() {
+        let (ca, good, revoked) = fixtures();
+        let crl = crl();
+        let now = crl.this_update() + Duration::from_secs(86400);
+        let mut store = CrlStore::new();
+        assert_eq!(store.check(&good, Some(&ca), now), RevocationStatus::Unknown);
+        store.add_pem(include_bytes!("../test/crl/crl.pem")).unwrap();
+        assert_eq!(store.crls().len(), 1);
+
+        assert_eq!(store.check(&good, Some(&ca), now), RevocationStatus::Good);
+        assert_eq!(store.check(&good, None, now), RevocationStatus::Good);
+        let RevocationStatus::Revoked(entry) = store.check(&revoked, Some(&ca), now) else { panic!() };
+        assert_eq!(entry.reason(), Some(RevocationReason::KeyCompromise));
+        assert_eq!(entry.serial_number(), revoked.serial_number());
+
+        // stale or not yet issued
+        assert_eq!(store.check(&revoked, Some(&ca), crl.next_update().unwrap() + Duration::from_secs(1)), RevocationStatus::Unknown);
+        assert_eq!(store.check(&revoked, Some(&ca), time_from_unix(0)), RevocationStatus::Unknown);
+        // a CRL from another issuer doesn't cover the certificate
+        let server = cert(include_bytes!("../test/server.der"));
+        assert_eq!(store.check(&server, None, now), RevocationStatus::Unknown);
+        // the CRL isn't signed by the claimed issuer
+        assert_eq!(store.check(&revoked, Some(&good), now), RevocationStatus::Unknown);
+    }
+
+    #[test]
+    fn check_chain() {
+        let (ca, good, revoked) = fixtures();
+        let crl = crl();
+        let now = crl.this_update() + Duration::from_secs(86400);
+        let mut store = CrlStore::new();
+        store.add(crl);
+        assert!(store.check_chain(&[good.clone(), ca.clone()], now).is_ok());
+        let err = store.check_chain(&[revoked, ca.clone()], now).unwrap_err();
+        assert_eq!(err.code(), errSecCertificateRevoked);
+
+        let server = cert(include_bytes!("../test/server.der"));
+        let server_ca = cert(include_bytes!("../test/ca.der"));
+        assert!(store.check_chain(&[server.clone(), server_ca.clone()], now).is_ok());
+        store.set_require_crl(true);
+        let err = store.check_chain(&[server, server_ca], now).unwrap_err();
+        assert_eq!(err.code(), errSecIncompleteCertRevocationCheck);
+        assert!(store.check_chain(&[good, ca], now).is_ok());
+    }
+
+    #[test]
+    fn unverifiable() {
+        let (ca, good, revoked) = fixtures();
+        let crl = crl();
+        let now = crl.this_update() + Duration::from_secs(86400);
+        // Ed25519 isn't supported
+        let mut unsupported = crl.clone();
+        unsupported.signature_algorithm = AlgorithmIdentifier::new("1.3.101.112".parse().unwrap(), None);
+        assert!(unsupported.verify_signature(&ca).is_err());
+
+        let mut store = CrlStore::new();
+        store.add(unsupported.clone());
+        assert_eq!(store.check(&good, Some(&ca), now), RevocationStatus::Unverifiable);
+        // without an issuer, the signature isn't checked
+        assert_eq!(store.check(&good, None, now), RevocationStatus::Good);
+        let err = store.check_chain(&[good.clone(), ca.clone()], now).unwrap_err();
+        assert_eq!(err.code(), errSecIncompleteCertRevocationCheck);
+
+        // a verified CRL can still revoke, but not vouch for the certificate
+        store.add(crl);
+        assert!(matches!(store.check(&revoked, Some(&ca), now), RevocationStatus::Revoked(_)));
+        assert_eq!(store.check(&good, Some(&ca), now), RevocationStatus::Unverifiable);
+    }
+
+    #[test]
+    fn unknown_critical_extension() {
+        let mut crl = crl();
+        assert!(!crl.has_unknown_critical_extensions());
+        crl.extensions.push(Extension::new(Oid::ISSUING_DISTRIBUTION_POINT, true, vec![0x30, 0]));
+        assert!(crl.has_unknown_critical_extensions());
+        let (_, _, revoked) = fixtures();
+        let mut store = CrlStore::new();
+        store.add(crl.clone());
+        assert_eq!(store.check(&revoked, None, crl.this_update()), RevocationStatus::Unknown);
+    }
+}
@@ -0,0 +1,718 @@
+//! Minimal DER reader for the X.509 structures used by this crate.
+//!
+//! Only the subset of DER used by certificates is supported: single-byte tags,
+//! definite lengths up to 4 bytes, and no BER constructs.
+
+use security_framework_sys::base::{errSecDecode, errSecParam};
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::base::{Error, Result};
+
+pub(crate) const BOOLEAN: u8 = 0x01;
+pub(crate) const INTEGER: u8 = 0x02;
+pub(crate) const BIT_STRING: u8 = 0x03;
+pub(crate) const OCTET_STRING: u8 = 0x04;
+pub(crate) const OBJECT_IDENTIFIER: u8 = 0x06;
+pub(crate) const ENUMERATED: u8 = 0x0a;
+pub(crate) const UTF8_STRING: u8 = 0x0c;
+pub(crate) const PRINTABLE_STRING: u8 = 0x13;
+pub(crate) const T61_STRING: u8 = 0x14;
+pub(crate) const IA5_STRING: u8 = 0x16;
+pub(crate) const UTC_TIME: u8 = 0x17;
+pub(crate) const GENERALIZED_TIME: u8 = 0x18;
+pub(crate) const UNIVERSAL_STRING: u8 = 0x1c;
+pub(crate) const BMP_STRING: u8 = 0x1e;
+pub(crate) const SEQUENCE: u8 = 0x30;
+pub(crate) const SET: u8 = 0x31;
+
+/// Tag of a constructed, context-specific element, e.g. `[0] EXPLICIT`
+#[inline]
+pub(crate) const fn context(n: u8) -> u8 {
+    0xa0 | n
+}
+
+/// Tag of a primitive, context-specific element, e.g. `[2] IMPLICIT IA5String`
+#[inline]
+pub(crate) const fn context_primitive(n: u8) -> u8 {
+    0x80 | n
+}
+
+#[cold]
+pub(crate) fn decode_error() -> Error {
+    Error::from_code(errSecDecode)
+}
+
+/// Appends a DER length to `out`.
+pub(crate) fn write_length(out: &mut Vec<u8>, len: usize) {
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes = len.to_be_bytes();
+        let skip = bytes.iter().take_while(|&&b| b == 0).count();
+        out.push(0x80 | (bytes.len() - skip) as u8);
+        out.extend_from_slice(&bytes[skip..]);
+    }
+}
+
+/// Encodes an element with the given tag.
+pub(crate) fn encode(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(content.len() + 6);
+    out.push(tag);
+    write_length(&mut out, content.len());
+    out.extend_from_slice(content);
+    out
+}
+
+/// Encodes a SEQUENCE of already-encoded elements.
+pub(crate) fn encode_sequence(elements: &[&[u8]]) -> Vec<u8> {
+    encode(SEQUENCE, &elements.concat())
+}
+
+/// Encodes a non-negative INTEGER from big-endian unsigned bytes.
+pub(crate) fn encode_unsigned(bytes: &[u8]) -> Vec<u8> {
+    let skip = bytes.iter().take_while(|&&b| b == 0).count();
+    let bytes = &bytes[skip..];
+    let mut content = Vec::with_capacity(bytes.len() + 1);
+    if bytes.first().is_none_or(|&b| b >= 0x80) {
+        content.push(0);
+    }
+    content.extend_from_slice(bytes);
+    encode(INTEGER, &content)
+}
+
+pub(crate) fn encode_uint(value: u64) -> Vec<u8> {
+    encode_unsigned(&value.to_be_bytes())
+}
+
+pub(crate) fn encode_bool(value: bool) -> Vec<u8> {
+    encode(BOOLEAN, &[if value { 0xff } else { 0 }])
+}
+
+pub(crate) fn encode_oid(oid: &Oid) -> Vec<u8> {
+    encode(OBJECT_IDENTIFIER, oid.as_der_content())
+}
+
+/// Encodes a BIT STRING of whole bytes.
+pub(crate) fn encode_bit_string(bytes: &[u8]) -> Vec<u8> {
+    let mut content = Vec::with_capacity(bytes.len() + 1);
+    content.push(0);
+    content.extend_from_slice(bytes);
+    encode(BIT_STRING, &content)
+}
+
+/// Encodes a time as `UTCTime` until 2049, and as `GeneralizedTime` after that, as required by RFC 5280.
+pub(crate) fn encode_time(time: SystemTime) -> Vec<u8> {
+    let secs = unix_from_time(time);
+    let (year, month, day) = civil_from_days(secs.div_euclid(86400));
+    let rem = secs.rem_euclid(86400);
+    let clock = format!("{month:02}{day:02}{:02}{:02}{:02}Z", rem / 3600, rem / 60 % 60, rem % 60);
+    if (1950..2050).contains(&year) {
+        encode(UTC_TIME, format!("{:02}{clock}", year % 100).as_bytes())
+    } else {
+        encode(GENERALIZED_TIME, format!("{year:04}{clock}").as_bytes())
+    }
+}
+
+/// A single encoded element.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Tlv<'a> {
+    pub tag: u8,
+    pub content: &'a [u8],
+    /// The whole element, including tag and length
+    pub raw: &'a [u8],
+}
+
+/// Reads consecutive DER elements from a buffer.
+#[derive(Debug, Clone)]
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    #[inline]
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    #[inline]
+    pub fn peek_tag(&self) -> Option<u8> {
+        self.data.first().copied()
+    }
+
+    /// Fails if there is unread data left.
+    pub fn finish(&self) -> Result<()> {
+        if self.data.is_empty() { Ok(()) } else { Err(decode_error()) }
+    }
+
+    pub fn read_tlv(&mut self) -> Result<Tlv<'a>> {
+        let data = self.data;
+        let (&tag, rest) = data.split_first().ok_or_else(decode_error)?;
+        // multi-byte tags are not used by any supported structure
+        if tag & 0x1f == 0x1f {
+            return Err(decode_error());
+        }
+        let (&first, mut rest) = rest.split_first().ok_or_else(decode_error)?;
+        let len = if first < 0x80 {
+            usize::from(first)
+        } else {
+            let n = usize::from(first & 0x7f);
+            if n == 0 || n > 4 || rest.len() < n {
+                return Err(decode_error());
+            }
+            let (len_bytes, r) = rest.split_at(n);
+            rest = r;
+            let len = len_bytes.iter().fold(0usize, |acc, &b| (acc << 8) | usize::from(b));
+            // DER requires the shortest form
+            if len < 0x80 || len_bytes[0] == 0 {
+                return Err(decode_error());
+            }
+            len
+        };
+        if rest.len() < len {
+            return Err(decode_error());
+        }
+        let header_len = data.len() - rest.len();
+        let (raw, remaining) = data.split_at(header_len + len);
+        self.data = remaining;
+        Ok(Tlv { tag, content: &raw[header_len..], raw })
+    }
+
+    /// Reads an element that must have the given tag, and returns its contents.
+    pub fn read(&mut self, tag: u8) -> Result<&'a [u8]> {
+        let tlv = self.read_tlv()?;
+        if tlv.tag != tag {
+            return Err(decode_error());
+        }
+        Ok(tlv.content)
+    }
+
+    /// Reads the element only if it has the given tag.
+    pub fn read_optional(&mut self, tag: u8) -> Result<Option<&'a [u8]>> {
+        if self.peek_tag() == Some(tag) {
+            self.read(tag).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Reads a constructed element and returns a reader for its contents.
+    #[inline]
+    pub fn read_nested(&mut self, tag: u8) -> Result<Self> {
+        self.read(tag).map(Reader::new)
+    }
+
+    #[inline]
+    pub fn read_sequence(&mut self) -> Result<Self> {
+        self.read_nested(SEQUENCE)
+    }
+
+    pub fn read_oid(&mut self) -> Result<Oid> {
+        let content = self.read(OBJECT_IDENTIFIER)?;
+        Oid::from_der_content(content)
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool> {
+        match self.read(BOOLEAN)? {
+            [0] => Ok(false),
+            [0xff] => Ok(true),
+            _ => Err(decode_error()),
+        }
+    }
+
+    /// Reads a `BOOLEAN DEFAULT FALSE`
+    pub fn read_optional_bool(&mut self) -> Result<bool> {
+        if self.peek_tag() == Some(BOOLEAN) { self.read_bool() } else { Ok(false) }
+    }
+
+    /// Reads an INTEGER and returns its big-endian two's complement bytes.
+    pub fn read_integer(&mut self) -> Result<&'a [u8]> {
+        let content = self.read(INTEGER)?;
+        check_integer(content)?;
+        Ok(content)
+    }
+
+    /// Reads a non-negative INTEGER that fits in `u64`.
+    pub fn read_small_uint(&mut self) -> Result<u64> {
+        uint_from_content(self.read_integer()?)
+    }
+
+    /// Returns the number of unused bits in the last byte, and the bytes.
+    pub fn read_bit_string(&mut self) -> Result<(u8, &'a [u8])> {
+        let content = self.read(BIT_STRING)?;
+        let (&unused, bytes) = content.split_first().ok_or_else(decode_error)?;
+        if unused > 7 || (bytes.is_empty() && unused != 0) {
+            return Err(decode_error());
+        }
+        Ok((unused, bytes))
+    }
+
+    /// Reads a `UTCTime` or `GeneralizedTime`.
+    pub fn read_time(&mut self) -> Result<SystemTime> {
+        let tlv = self.read_tlv()?;
+        parse_time(tlv.tag, tlv.content)
+    }
+}
+
+fn check_integer(content: &[u8]) -> Result<()> {
+    match content {
+        [] => Err(decode_error()),
+        // non-minimal encodings are not DER
+        [0, next, ..] if *next < 0x80 => Err(decode_error()),
+        [0xff, next, ..] if *next >= 0x80 => Err(decode_error()),
+        _ => Ok(()),
+    }
+}
+
+pub(crate) fn uint_from_content(content: &[u8]) -> Result<u64> {
+    if content.first().is_some_and(|&b| b >= 0x80) {
+        return Err(decode_error());
+    }
+    let content = if content.first() == Some(&0) { &content[1..] } else { content };
+    if content.len() > 8 {
+        return Err(decode_error());
+    }
+    Ok(content.iter().fold(0, |acc, &b| (acc << 8) | u64::from(b)))
+}
+
+fn digits(s: &[u8]) -> Result<u32> {
+    s.iter().try_fold(0u32, |acc, &c| {
+        if !c.is_ascii_digit() {
+            return Err(decode_error());
+        }
+        acc.checked_mul(10).and_then(|acc| acc.checked_add(u32::from(c - b'0'))).ok_or_else(decode_error)
+    })
+}
+
+pub(crate) fn parse_time(tag: u8, content: &[u8]) -> Result<SystemTime> {
+    let (year, rest) = match tag {
+        UTC_TIME if content.len() == 13 => {
+            let yy = digits(&content[..2])?;
+            (if yy >= 50 { 1900 + yy } else { 2000 + yy }, &content[2..])
+        },
+        GENERALIZED_TIME if content.len() >= 15 => (digits(&content[..4])?, &content[4..]),
+        _ => return Err(decode_error()),
+    };
+    let (&last, rest) = rest.split_last().ok_or_else(decode_error)?;
+    if last != b'Z' {
+        return Err(decode_error());
+    }
+    // fractional seconds are allowed in GeneralizedTime, but ignored
+    let rest = match rest.iter().position(|&c| c == b'.') {
+        Some(pos) if tag == GENERALIZED_TIME => {
+            let fraction = &rest[pos + 1..];
+            if fraction.is_empty() || !fraction.iter().all(u8::is_ascii_digit) {
+                return Err(decode_error());
+            }
+            &rest[..pos]
+        },
+        _ => rest,
+    };
+    if rest.len() != 10 {
+        return Err(decode_error());
+    }
+    let month = digits(&rest[0..2])?;
+    let day = digits(&rest[2..4])?;
+    let hour = digits(&rest[4..6])?;
+    let minute = digits(&rest[6..8])?;
+    let second = digits(&rest[8..10])?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return Err(decode_error());
+    }
+    let days = days_from_civil(i64::from(year), month, day);
+    let secs = days * 86400 + i64::from(hour * 3600 + minute * 60 + second);
+    Ok(time_from_unix(secs))
+}
+
+/// Days since 1970-01-01 in the proleptic Gregorian calendar
+pub(crate) fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let month = i64::from(month);
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+pub(crate) fn time_from_unix(secs: i64) -> SystemTime {
+    if secs >= 0 {
+        UNIX_EPOCH + Duration::from_secs(secs as u64)
+    } else {
+        UNIX_EPOCH - Duration::from_secs(secs.unsigned_abs())
+    }
+}
+
+pub(crate) fn unix_from_time(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs_f64().ceil() as i64),
+    }
+}
+
+/// Inverse of [`days_from_civil`]
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Decodes the X.509 string types to UTF-8.
+pub(crate) fn decode_string(tag: u8, content: &[u8]) -> Option<String> {
+    match tag {
+        UTF8_STRING => String::from_utf8(content.to_vec()).ok(),
+        PRINTABLE_STRING | IA5_STRING => content.is_ascii().then(|| String::from_utf8_lossy(content).into_owned()),
+        // Treated as Latin-1, which is what it's used for in practice
+        T61_STRING => Some(content.iter().map(|&b| char::from(b)).collect()),
+        BMP_STRING => {
+            if content.len() % 2 != 0 {
+                return None;
+            }
+            let units = content.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]]));
+            char::decode_utf16(units).collect::<Result<_, _>>().ok()
+        },
+        UNIVERSAL_STRING => {
+            if content.len() % 4 != 0 {
+                return None;
+            }
+            content.chunks_exact(4).map(|c| char::from_u32(u32::from_be_bytes([c[0], c[1], c[2], c[3]]))).collect()
+        },
+        _ => None,
+    }
+}
+
+/// An ASN.1 object identifier.
+///
+/// Displays in the dotted-decimal form, e.g. `2.5.29.17`.
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Oid(Cow<'static, [u8]>);
+
+impl Oid {
+    /// `id-at-commonName`
+    pub const COMMON_NAME: Self = Self::from_static(&[0x55, 0x04, 0x03]);
+    /// `id-at-serialNumber`
+    pub const SERIAL_NUMBER: Self = Self::from_static(&[0x55, 0x04, 0x05]);
+    /// `id-at-countryName`
+    pub const COUNTRY_NAME: Self = Self::from_static(&[0x55, 0x04, 0x06]);
+    /// `id-at-localityName`
+    pub const LOCALITY_NAME: Self = Self::from_static(&[0x55, 0x04, 0x07]);
+    /// `id-at-stateOrProvinceName`
+    pub const STATE_OR_PROVINCE_NAME: Self = Self::from_static(&[0x55, 0x04, 0x08]);
+    /// `id-at-streetAddress`
+    pub const STREET_ADDRESS: Self = Self::from_static(&[0x55, 0x04, 0x09]);
+    /// `id-at-organizationName`
+    pub const ORGANIZATION_NAME: Self = Self::from_static(&[0x55, 0x04, 0x0a]);
+    /// `id-at-organizationalUnitName`
+    pub const ORGANIZATIONAL_UNIT_NAME: Self = Self::from_static(&[0x55, 0x04, 0x0b]);
+    /// `domainComponent` (RFC 4519)
+    pub const DOMAIN_COMPONENT: Self = Self::from_static(&[0x09, 0x92, 0x26, 0x89, 0x93, 0xf2, 0x2c, 0x64, 0x01, 0x19]);
+    /// `uid` (RFC 4519)
+    pub const USER_ID: Self = Self::from_static(&[0x09, 0x92, 0x26, 0x89, 0x93, 0xf2, 0x2c, 0x64, 0x01, 0x01]);
+    /// `emailAddress` (PKCS #9)
+    pub const EMAIL_ADDRESS: Self = Self::from_static(&[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x01]);
+    /// `challengePassword` (PKCS #9)
+    pub const CHALLENGE_PASSWORD: Self = Self::from_static(&[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x07]);
+    /// `extensionRequest` (PKCS #9)
+    pub const EXTENSION_REQUEST: Self = Self::from_static(&[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x0e]);
+    /// `id-ce-subjectKeyIdentifier`
+    pub const SUBJECT_KEY_IDENTIFIER: Self = Self::from_static(&[0x55, 0x1d, 0x0e]);
+    /// `id-ce-keyUsage`
+    pub const KEY_USAGE: Self = Self::from_static(&[0x55, 0x1d, 0x0f]);
+    /// `id-ce-subjectAltName`
+    pub const SUBJECT_ALT_NAME: Self = Self::from_static(&[0x55, 0x1d, 0x11]);
+    /// `id-ce-basicConstraints`
+    pub const BASIC_CONSTRAINTS: Self = Self::from_static(&[0x55, 0x1d, 0x13]);
+    /// `id-ce-cRLNumber`
+    pub const CRL_NUMBER: Self = Self::from_static(&[0x55, 0x1d, 0x14]);
+    /// `id-ce-cRLReasons`
+    pub const CRL_REASON: Self = Self::from_static(&[0x55, 0x1d, 0x15]);
+    /// `id-ce-invalidityDate`
+    pub const INVALIDITY_DATE: Self = Self::from_static(&[0x55, 0x1d, 0x18]);
+    /// `id-ce-deltaCRLIndicator`
+    pub const DELTA_CRL_INDICATOR: Self = Self::from_static(&[0x55, 0x1d, 0x1b]);
+    /// `id-ce-issuingDistributionPoint`
+    pub const ISSUING_DISTRIBUTION_POINT: Self = Self::from_static(&[0x55, 0x1d, 0x1c]);
+    /// `id-ce-certificateIssuer`
+    pub const CERTIFICATE_ISSUER: Self = Self::from_static(&[0x55, 0x1d, 0x1d]);
+    /// `id-ce-nameConstraints`
+    pub const NAME_CONSTRAINTS: Self = Self::from_static(&[0x55, 0x1d, 0x1e]);
+    /// `id-ce-authorityKeyIdentifier`
+    pub const AUTHORITY_KEY_IDENTIFIER: Self = Self::from_static(&[0x55, 0x1d, 0x23]);
+    /// `id-ce-extKeyUsage`
+    pub const EXTENDED_KEY_USAGE: Self = Self::from_static(&[0x55, 0x1d, 0x25]);
+    /// `anyExtendedKeyUsage`
+    pub const ANY_EXTENDED_KEY_USAGE: Self = Self::from_static(&[0x55, 0x1d, 0x25, 0x00]);
+    /// `id-kp-serverAuth`
+    pub const SERVER_AUTH: Self = Self::from_static(&[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x03, 0x01]);
+    /// `id-kp-clientAuth`
+    pub const CLIENT_AUTH: Self = Self::from_static(&[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x03, 0x02]);
+    /// `id-kp-codeSigning`
+    pub const CODE_SIGNING: Self = Self::from_static(&[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x03, 0x03]);
+    /// `id-kp-emailProtection`
+    pub const EMAIL_PROTECTION: Self = Self::from_static(&[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x03, 0x04]);
+    /// `id-kp-timeStamping`
+    pub const TIME_STAMPING: Self = Self::from_static(&[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x03, 0x08]);
+    /// `id-kp-OCSPSigning`
+    pub const OCSP_SIGNING: Self = Self::from_static(&[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x03, 0x09]);
+    /// `id-pkix-ocsp-basic`
+    pub const OCSP_BASIC: Self = Self::from_static(&[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x30, 0x01, 0x01]);
+    /// `id-pkix-ocsp-nonce`
+    pub const OCSP_NONCE: Self = Self::from_static(&[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x30, 0x01, 0x02]);
+    /// Certificate Transparency signed certificate timestamp list, embedded in certificates
+    pub const CT_PRECERT_SCTS: Self = Self::from_static(&[0x2b, 0x06, 0x01, 0x04, 0x01, 0xd6, 0x79, 0x02, 0x04, 0x02]);
+    /// `rsaEncryption`
+    pub const RSA_ENCRYPTION: Self = Self::from_static(&[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01]);
+    /// `sha1WithRSAEncryption`
+    pub const SHA1_WITH_RSA: Self = Self::from_static(&[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x05]);
+    /// `sha256WithRSAEncryption`
+    pub const SHA256_WITH_RSA: Self = Self::from_static(&[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b]);
+    /// `sha384WithRSAEncryption`
+    pub const SHA384_WITH_RSA: Self = Self::from_static(&[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0c]);
+    /// `sha512WithRSAEncryption`
+    pub const SHA512_WITH_RSA: Self = Self::from_static(&[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0d]);
+    /// `id-ecPublicKey`
+    pub const EC_PUBLIC_KEY: Self = Self::from_static(&[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01]);
+    /// `ecdsa-with-SHA256`
+    pub const ECDSA_WITH_SHA256: Self = Self::from_static(&[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02]);
+    /// `ecdsa-with-SHA384`
+    pub const ECDSA_WITH_SHA384: Self = Self::from_static(&[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x03]);
+    /// `ecdsa-with-SHA512`
+    pub const ECDSA_WITH_SHA512: Self = Self::from_static(&[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x04]);
+
+    /// Uses already-encoded contents of an OBJECT IDENTIFIER, without tag and length.
+    #[inline]
+    #[must_use]
+    pub const fn from_static(der_content: &'static [u8]) -> Self {
+        Self(Cow::Borrowed(der_content))
+    }
+
+    /// Parses the contents of an OBJECT IDENTIFIER, without tag and length.
+    pub fn from_der_content(der_content: &[u8]) -> Result<Self> {
+        // each arc must end with a byte without the continuation bit, have no leading 0x80, and fit in a u64
+        if der_content.last().is_none_or(|&b| b & 0x80 != 0) {
+            return Err(decode_error());
+        }
+        let mut value = 0u64;
+        for &b in der_content {
+            if value == 0 && b == 0x80 {
+                return Err(decode_error());
+            }
+            value = value.checked_mul(0x80).ok_or_else(decode_error)? | u64::from(b & 0x7f);
+            if b & 0x80 == 0 {
+                value = 0;
+            }
+        }
+        Ok(Self(Cow::Owned(der_content.to_vec())))
+    }
+
+    /// The encoded contents, without tag and length.
+    #[inline]
+    #[must_use]
+    pub fn as_der_content(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Decodes the numeric arcs, e.g. `[2, 5, 29, 17]`.
+    #[must_use]
+    pub fn arcs(&self) -> Vec<u64> {
+        let mut arcs = Vec::new();
+        let mut value = 0u64;
+        for &b in self.0.iter() {
+            value = (value << 7) | u64::from(b & 0x7f);
+            if b & 0x80 == 0 {
+                if arcs.is_empty() {
+                    let first = (value / 40).min(2);
+                    arcs.push(first);
+                    arcs.push(value - first * 40);
+                } else {
+                    arcs.push(value);
+                }
+                value = 0;
+            }
+        }
+        arcs
+    }
+}
+
+impl fmt::Display for Oid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, arc) in self.arcs().into_iter().enumerate() {
+            if i > 0 {
+                f.write_str(".")?;
+            }
+            write!(f, "{arc}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Oid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Oid({self})")
+    }
+}
+
+impl FromStr for Oid {
+    type Err = Error;
+
+    /// Parses the dotted-decimal form, e.g. `1.2.840.113549.1.1.11`.
+    fn from_str(s: &str) -> Result<Self> {
+        let arcs = s
+            .split('.')
+            .map(|arc| arc.parse::<u64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| Error::from_code(errSecParam))?;
+        let [first, second, rest @ ..] = &arcs[..] else {
+            return Err(Error::from_code(errSecParam));
+        };
+        if *first > 2 || (*first < 2 && *second >= 40) {
+            return Err(Error::from_code(errSecParam));
+        }
+        let mut out = Vec::new();
+        let first = first.checked_mul(40).and_then(|first| first.checked_add(*second)).ok_or_else(|| Error::from_code(errSecParam))?;
+        for &arc in std::iter::once(&first).chain(rest) {
+            let mut shift = (arc | 1).ilog2() / 7 * 7;
+            while shift > 0 {
+                out.push(0x80 | (arc >> shift) as u8 & 0x7f);
+                shift -= 7;
+            }
+            out.push(arc as u8 & 0x7f);
+        }
+        Ok(Self(Cow::Owned(out)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn lengths() {
+        let mut r = Reader::new(&[0x04, 0x81, 0x80]);
+        assert!(r.read_tlv().is_err());
+
+        let mut data = vec![0x04, 0x81, 0x80];
+        data.extend([7; 0x80]);
+        data.extend([0x05, 0x00]);
+        let mut r = Reader::new(&data);
+        assert_eq!(r.read(OCTET_STRING).unwrap().len(), 0x80);
+        assert!(r.read(0x05).unwrap().is_empty());
+        r.finish().unwrap();
+
+        // non-minimal length
+        assert!(Reader::new(&[0x04, 0x81, 0x01, 0x00]).read_tlv().is_err());
+        // indefinite length
+        assert!(Reader::new(&[0x30, 0x80, 0x00, 0x00]).read_tlv().is_err());
+    }
+
+    #[test]
+    fn integers() {
+        assert_eq!(Reader::new(&[2, 1, 5]).read_small_uint().unwrap(), 5);
+        assert_eq!(Reader::new(&[2, 2, 0, 0x80]).read_small_uint().unwrap(), 128);
+        assert!(Reader::new(&[2, 2, 0, 0x7f]).read_small_uint().is_err());
+        assert!(Reader::new(&[2, 1, 0xff]).read_small_uint().is_err());
+    }
+
+    #[test]
+    fn oids() {
+        let oid: Oid = "2.5.29.17".parse().unwrap();
+        assert_eq!(oid, Oid::SUBJECT_ALT_NAME);
+        let oid: Oid = "1.2.840.113549.1.1.11".parse().unwrap();
+        assert_eq!(oid.as_der_content(), [0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b]);
+        assert_eq!(oid.to_string(), "1.2.840.113549.1.1.11");
+        assert_eq!("2.999.3".parse::<Oid>().unwrap().to_string(), "2.999.3");
+        assert_eq!("1.3.0".parse::<Oid>().unwrap().as_der_content(), [0x2b, 0]);
+        assert!("1".parse::<Oid>().is_err());
+        assert!("1.40".parse::<Oid>().is_err());
+        assert!("1.2.x".parse::<Oid>().is_err());
+        assert!(Oid::from_der_content(&[0x2a, 0x86]).is_err());
+        assert!(Oid::from_der_content(&[0x2a, 0x80, 0x01]).is_err());
+
+        assert_eq!("1.2.18446744073709551615".parse::<Oid>().unwrap().arcs(), [1, 2, u64::MAX]);
+        assert_eq!("2.18446744073709551535".parse::<Oid>().unwrap().arcs(), [2, u64::MAX - 80]);
+        assert_eq!("2.18446744073709551615".parse::<Oid>().unwrap_err().code(), errSecParam);
+        assert!("CN=x,oid.2.18446744073709551615=y".parse::<crate::certificate::DistinguishedName>().is_err());
+        // an arc of more than 64 bits
+        assert!(Oid::from_der_content(&[0x2a, 0x82, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00]).is_err());
+        assert!(Oid::from_der_content(&[0x2a, 0x81, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f]).is_ok());
+    }
+
+    #[test]
+    fn times() {
+        let t = parse_time(UTC_TIME, b"260219195240Z").unwrap();
+        assert_eq!(unix_from_time(t), 1771530760);
+        let t = parse_time(GENERALIZED_TIME, b"20280524195240Z").unwrap();
+        assert_eq!(unix_from_time(t), 1842810760);
+        let t = parse_time(UTC_TIME, b"500101000000Z").unwrap();
+        assert_eq!(unix_from_time(t), -631152000);
+        let t = parse_time(GENERALIZED_TIME, b"99991231235959.5Z").unwrap();
+        assert_eq!(unix_from_time(t), 253402300799);
+        assert!(parse_time(UTC_TIME, b"2602191952Z").is_err());
+        assert!(parse_time(UTC_TIME, b"261319195240Z").is_err());
+        assert!(parse_time(GENERALIZED_TIME, b"20280524195240+0100").is_err());
+        let t = parse_time(GENERALIZED_TIME, b"20280524195240.12345678901Z").unwrap();
+        assert_eq!(unix_from_time(t), 1842810760);
+        assert!(parse_time(GENERALIZED_TIME, b"20280524195240.Z").is_err());
+        assert!(parse_time(GENERALIZED_TIME, b"20280524195240.1xZ").is_err());
+        assert_eq!(digits(b"4294967295").unwrap(), u32::MAX);
+        assert!(digits(b"4294967296").is_err());
+    }
+
+    #[test]
+    fn encoding() {
+        assert_eq!(encode_uint(0), [2, 1, 0]);
+        assert_eq!(encode_uint(128), [2, 2, 0, 0x80]);
+        assert_eq!(encode_unsigned(&[0, 0, 0x7f]), [2, 1, 0x7f]);
+        assert_eq!(encode_bool(true), [1, 1, 0xff]);
+        assert_eq!(encode_bit_string(&[0x05]), [3, 2, 0, 5]);
+        assert_eq!(encode_oid(&Oid::KEY_USAGE), [6, 3, 0x55, 0x1d, 0x0f]);
+        assert_eq!(encode(OCTET_STRING, &[0; 200])[..4], [4, 0x81, 200, 0]);
+        assert_eq!(encode(OCTET_STRING, &[0; 300])[..5], [4, 0x82, 1, 44, 0]);
+        let seq = encode_sequence(&[&encode_uint(1), &encode_bool(false)]);
+        let mut reader = Reader::new(&seq);
+        let mut inner = reader.read_sequence().unwrap();
+        assert_eq!(inner.read_small_uint().unwrap(), 1);
+        assert!(!inner.read_bool().unwrap());
+    }
+
+    #[test]
+    fn encode_times() {
+        for (secs, encoded) in [
+            (1771530760, &b"\x17\x0d260219195240Z"[..]),
+            (-631152000, b"\x17\x0d500101000000Z"),
+            (2524608000, b"\x18\x0f20500101000000Z"),
+            (-631152001, b"\x18\x0f19491231235959Z"),
+            (253402300799, b"\x18\x0f99991231235959Z"),
+        ] {
+            let time = time_from_unix(secs);
+            assert_eq!(encode_time(time), encoded);
+            assert_eq!(Reader::new(encoded).read_time().unwrap(), time);
+        }
+        for days in -1000..1000 {
+            let (y, m, d) = civil_from_days(days * 97);
+            assert_eq!(days_from_civil(y, m, d), days * 97);
+        }
+    }
+
+    #[test]
+    fn strings() {
+        assert_eq!(decode_string(UTF8_STRING, "zażółć".as_bytes()).unwrap(), "zażółć");
+        assert_eq!(decode_string(BMP_STRING, &[0, b'h', 0, b'i']).unwrap(), "hi");
+        assert_eq!(decode_string(T61_STRING, &[0xe9]).unwrap(), "é");
+        assert!(decode_string(PRINTABLE_STRING, &[0xe9]).is_none());
+    }
+}
@@ -0,0 +1,185 @@
+//! Message digests used for certificate fingerprints and key identifiers.
+//!
+//! These don't use Security.framework, so they behave the same on all platforms.
+
+use sha2::Digest as _;
+use std::fmt::{self, Write as _};
+
+use crate::der::Oid;
+
+/// A hash function.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DigestAlgorithm {
+    /// SHA-1. Broken for collision resistance, but still common for fingerprints.
+    Sha1,
+    /// SHA-256
+    Sha256,
+    /// SHA-384
+    Sha384,
+    /// SHA-512
+    Sha512,
+}
+
+impl DigestAlgorithm {
+    /// Hashes `data`.
+    #[must_use]
+    pub fn digest(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Sha1 => sha1::Sha1::digest(data).to_vec(),
+            Self::Sha256 => sha2::Sha256::digest(data).to_vec(),
+            Self::Sha384 => sha2::Sha384::digest(data).to_vec(),
+            Self::Sha512 => sha2::Sha512::digest(data).to_vec(),
+        }
+    }
+
+    /// Length of the digest in bytes.
+    #[must_use]
+    pub fn output_len(self) -> usize {
+        match self {
+            Self::Sha1 => 20,
+            Self::Sha256 => 32,
+            Self::Sha384 => 48,
+            Self::Sha512 => 64,
+        }
+    }
+
+    /// The conventional name, e.g. `SHA-256`.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Sha1 => "SHA-1",
+            Self::Sha256 => "SHA-256",
+            Self::Sha384 => "SHA-384",
+            Self::Sha512 => "SHA-512",
+        }
+    }
+
+    /// The algorithm's object identifier, as used in `AlgorithmIdentifier`s.
+    pub(crate) fn oid(self) -> Oid {
+        match self {
+            Self::Sha1 => Oid::from_static(&[0x2b, 0x0e, 0x03, 0x02, 0x1a]),
+            Self::Sha256 => Oid::from_static(&[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01]),
+            Self::Sha384 => Oid::from_static(&[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x02]),
+            Self::Sha512 => Oid::from_static(&[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x03]),
+        }
+    }
+
+    pub(crate) fn from_oid(oid: &Oid) -> Option<Self> {
+        [Self::Sha1, Self::Sha256, Self::Sha384, Self::Sha512].into_iter().find(|alg| alg.oid() == *oid)
+    }
+}
+
+impl fmt::Display for DigestAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A digest of a certificate or key.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Fingerprint {
+    algorithm: DigestAlgorithm,
+    bytes: Vec<u8>,
+}
+
+impl Fingerprint {
+    /// Hashes `data` with `algorithm`.
+    #[must_use]
+    pub fn new(algorithm: DigestAlgorithm, data: &[u8]) -> Self {
+        Self { algorithm, bytes: algorithm.digest(data) }
+    }
+
+    /// The hash function used.
+    #[inline]
+    #[must_use]
+    pub fn algorithm(&self) -> DigestAlgorithm {
+        self.algorithm
+    }
+
+    /// The raw digest.
+    #[inline]
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Lowercase hex without separators.
+    #[must_use]
+    pub fn to_hex(&self) -> String {
+        self.bytes.iter().fold(String::with_capacity(self.bytes.len() * 2), |mut out, b| {
+            let _ = write!(out, "{b:02x}");
+            out
+        })
+    }
+
+    /// Standard base64, as used by HPKP-style pins.
+    #[must_use]
+    pub fn to_base64(&self) -> String {
+        crate::base64::encode(&self.bytes)
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    /// Uppercase hex separated by colons, as shown by Keychain Access and `openssl x509 -fingerprint`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, b) in self.bytes.iter().enumerate() {
+            if i > 0 {
+                f.write_str(":")?;
+            }
+            write!(f, "{b:02X}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn hex_digest(algorithm: DigestAlgorithm, data: &[u8]) -> String {
+        Fingerprint::new(algorithm, data).to_hex()
+    }
+
+    #[test]
+    fn known_answers() {
+        // FIPS 180-4 examples
+        let abc = b"abc";
+        let two_block = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+        let sha512_two_block = b"abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu";
+
+        assert_eq!(hex_digest(DigestAlgorithm::Sha1, b""), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        assert_eq!(hex_digest(DigestAlgorithm::Sha1, abc), "a9993e364706816aba3e25717850c26c9cd0d89d");
+        assert_eq!(hex_digest(DigestAlgorithm::Sha1, two_block), "84983e441c3bd26ebaae4aa1f95129e5e54670f1");
+
+        assert_eq!(hex_digest(DigestAlgorithm::Sha256, b""), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+        assert_eq!(hex_digest(DigestAlgorithm::Sha256, abc), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        assert_eq!(hex_digest(DigestAlgorithm::Sha256, two_block), "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1");
+
+        assert_eq!(
+            hex_digest(DigestAlgorithm::Sha384, abc),
+            "cb00753f45a35e8bb5a03d699ac65007272c32ab0eded1631a8b605a43ff5bed8086072ba1e7cc2358baeca134c825a7"
+        );
+        assert_eq!(
+            hex_digest(DigestAlgorithm::Sha384, sha512_two_block),
+            "09330c33f71147e83d192fc782cd1b4753111b173b3b05d22fa08086e3b0f712fcc7c71a557e2db966c3e9fa91746039"
+        );
+        assert_eq!(
+            hex_digest(DigestAlgorithm::Sha512, abc),
+            "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f"
+        );
+        assert_eq!(
+            hex_digest(DigestAlgorithm::Sha512, sha512_two_block),
+            "8e959b75dae313da8cf4f72814fc143f8f7779c6eb9f7fa17299aeadb6889018501d289e4900f7e4331b99dec4b5433ac7d329eeb6dd26545e96e55b874be909"
+        );
+    }
+
+    #[test]
+    fn formatting() {
+        let fingerprint = Fingerprint::new(DigestAlgorithm::Sha1, b"abc");
+        assert_eq!(fingerprint.to_string(), "A9:99:3E:36:47:06:81:6A:BA:3E:25:71:78:50:C2:6C:9C:D0:D8:9D");
+        assert_eq!(fingerprint.to_base64(), "qZk+NkcGgWq6PiVxeFDCbJzQ2J0=");
+        assert_eq!(fingerprint.algorithm().to_string(), "SHA-1");
+        assert_eq!(fingerprint.as_bytes().len(), 20);
+    }
+}
@@ -0,0 +1,333 @@
+//! Minimal ECDSA verification on the P-256 and P-384 curves, for checking certificates,
+//! timestamps and responses without Security.framework.
+//!
+//! This is only used with public data, and is not constant-time.
+
+#![allow(clippy::many_single_char_names)] // variable names follow the usual curve notation
+
+use security_framework_sys::base::errSecUnimplemented;
+
+use crate::base::{Error, Result};
+use crate::bignum::{Montgomery, Uint};
+use crate::certificate::SubjectPublicKeyInfo;
+use crate::der::{self, Oid, Reader};
+use crate::digest::DigestAlgorithm;
+
+/// A short Weierstrass curve `y² = x³ - 3x + b` over a prime field.
+struct Curve {
+    /// The OID of the named curve
+    oid: Oid,
+    /// The length of field elements and scalars in bytes
+    len: usize,
+    /// The field prime, in little-endian limbs
+    p: &'static [u64],
+    /// The order of the generator
+    n: &'static [u64],
+    b: &'static [u64],
+    gx: &'static [u64],
+    gy: &'static [u64],
+}
+
+/// `secp256r1`, from SEC 2
+static P256: Curve = Curve {
+    oid: Oid::from_static(&[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07]),
+    len: 32,
+    p: &[0xffff_ffff_ffff_ffff, 0x0000_0000_ffff_ffff, 0x0000_0000_0000_0000, 0xffff_ffff_0000_0001],
+    n: &[0xf3b9_cac2_fc63_2551, 0xbce6_faad_a717_9e84, 0xffff_ffff_ffff_ffff, 0xffff_ffff_0000_0000],
+    b: &[0x3bce_3c3e_27d2_604b, 0x651d_06b0_cc53_b0f6, 0xb3eb_bd55_7698_86bc, 0x5ac6_35d8_aa3a_93e7],
+    gx: &[0xf4a1_3945_d898_c296, 0x7703_7d81_2deb_33a0, 0xf8bc_e6e5_63a4_40f2, 0x6b17_d1f2_e12c_4247],
+    gy: &[0xcbb6_4068_37bf_51f5, 0x2bce_3357_6b31_5ece, 0x8ee7_eb4a_7c0f_9e16, 0x4fe3_42e2_fe1a_7f9b],
+};
+
+/// `secp384r1`, from SEC 2
+static P384: Curve = Curve {
+    oid: Oid::from_static(&[0x2b, 0x81, 0x04, 0x00, 0x22]),
+    len: 48,
+    p: &[
+        0x0000_0000_ffff_ffff, 0xffff_ffff_0000_0000, 0xffff_ffff_ffff_fffe,
+        0xffff_ffff_ffff_ffff, 0xffff_ffff_ffff_ffff, 0xffff_ffff_ffff_ffff,
+    ],
+    n: &[
+        0xecec_196a_ccc5_2973, 0x581a_0db2_48b0_a77a, 0xc763_4d81_f437_2ddf,
+        0xffff_ffff_ffff_ffff, 0xffff_ffff_ffff_ffff, 0xffff_ffff_ffff_ffff,
+    ],
+    b: &[
+        0x2a85_c8ed_d3ec_2aef, 0xc656_398d_8a2e_d19d, 0x0314_088f_5013_875a,
+        0x181d_9c6e_fe81_4112, 0x988e_056b_e3f8_2d19, 0xb331_2fa7_e23e_e7e4,
+    ],
+    gx: &[
+        0x3a54_5e38_7276_0ab7, 0x5502_f25d_bf55_296c, 0x59f7_41e0_8254_2a38,
+        0x6e1d_3b62_8ba7_9b98, 0x8eb1_c71e_f320_ad74, 0xaa87_ca22_be8b_0537,
+    ],
+    gy: &[
+        0x7a43_1d7c_90ea_0e5f, 0x0a60_b1ce_1d7e_819d, 0xe9da_3113_b5f0_b8c0,
+        0xf8f4_1dbd_289a_147c, 0x5d9e_98bf_9292_dc29, 0x3617_de4a_9626_2c6f,
+    ],
+};
+
+/// A point in Jacobian coordinates `(X/Z², Y/Z³)`, in Montgomery form. `None` is the point at infinity.
+type Point = Option<[Vec<u64>; 3]>;
+
+/// Point arithmetic on a curve.
+struct Arithmetic {
+    field: Montgomery,
+    curve: &'static Curve,
+}
+
+impl Arithmetic {
+    fn double(&self, point: &Point) -> Point {
+        let f = &self.field;
+        let [x, y, z] = point.as_ref()?;
+        if y.iter().all(|&limb| limb == 0) {
+            return None;
+        }
+        // dbl-2001-b, for a = -3
+        let delta = f.mul(z, z);
+        let gamma = f.mul(y, y);
+        let beta = f.mul(x, &gamma);
+        let t = f.mul(&f.sub(x, &delta), &f.add(x, &delta));
+        let alpha = f.add(&f.add(&t, &t), &t);
+        let beta2 = f.add(&beta, &beta);
+        let beta4 = f.add(&beta2, &beta2);
+        let x3 = f.sub(&f.mul(&alpha, &alpha), &f.add(&beta4, &beta4));
+        let yz = f.add(y, z);
+        let z3 = f.sub(&f.sub(&f.mul(&yz, &yz), &gamma), &delta);
+        let gamma_squared = f.mul(&gamma, &gamma);
+        let gamma_squared2 = f.add(&gamma_squared, &gamma_squared);
+        let gamma_squared4 = f.add(&gamma_squared2, &gamma_squared2);
+        let y3 = f.sub(&f.mul(&alpha, &f.sub(&beta4, &x3)), &f.add(&gamma_squared4, &gamma_squared4));
+        Some([x3, y3, z3])
+    }
+
+    fn add(&self, p: &Point, q: &Point) -> Point {
+        let f = &self.field;
+        let (Some([x1, y1, z1]), Some([x2, y2, z2])) = (p, q) else {
+            return p.clone().or_else(|| q.clone());
+        };
+        // add-2007-bl
+        let z1z1 = f.mul(z1, z1);
+        let z2z2 = f.mul(z2, z2);
+        let u1 = f.mul(x1, &z2z2);
+        let u2 = f.mul(x2, &z1z1);
+        let s1 = f.mul(&f.mul(y1, z2), &z2z2);
+        let s2 = f.mul(&f.mul(y2, z1), &z1z1);
+        let h = f.sub(&u2, &u1);
+        let r = f.sub(&s2, &s1);
+        if h.iter().all(|&limb| limb == 0) {
+            return if r.iter().all(|&limb| limb == 0) { self.double(p) } else { None };
+        }
+        let h2 = f.add(&h, &h);
+        let i = f.mul(&h2, &h2);
+        let j = f.mul(&h, &i);
+        let r = f.add(&r, &r);
+        let v = f.mul(&u1, &i);
+        let x3 = f.sub(&f.sub(&f.mul(&r, &r), &j), &f.add(&v, &v));
+        let s1j = f.mul(&s1, &j);
+        let y3 = f.sub(&f.mul(&r, &f.sub(&v, &x3)), &f.add(&s1j, &s1j));
+        let z12 = f.add(z1, z2);
+        let z3 = f.mul(&f.sub(&f.sub(&f.mul(&z12, &z12), &z1z1), &z2z2), &h);
+        Some([x3, y3, z3])
+    }
+
+    /// `a·P + b·Q`, with Shamir's trick
+    fn mul_add(&self, a: &Uint, p: &Point, b: &Uint, q: &Point) -> Point {
+        let pq = self.add(p, q);
+        let mut acc = None;
+        for i in (0..a.bits().max(b.bits())).rev() {
+            acc = self.double(&acc);
+            acc = match (a.bit(i), b.bit(i)) {
+                (true, true) => self.add(&acc, &pq),
+                (true, false) => self.add(&acc, p),
+                (false, true) => self.add(&acc, q),
+                (false, false) => acc,
+            };
+        }
+        acc
+    }
+
+    /// The affine x coordinate.
+    fn x(&self, point: &Point) -> Option<Uint> {
+        let f = &self.field;
+        let [x, _, z] = point.as_ref()?;
+        let z_inv = f.to_montgomery(&f.inverse(&f.to_normal(z)));
+        Some(f.to_normal(&f.mul(x, &f.mul(&z_inv, &z_inv))))
+    }
+
+    /// Checks that affine `(x, y)` is on the curve, and returns it as a point.
+    fn point(&self, x: &Uint, y: &Uint) -> Option<Point> {
+        let f = &self.field;
+        let p = Uint(self.curve.p.to_vec());
+        if !x.less_than(&p) || !y.less_than(&p) {
+            return None;
+        }
+        let (x, y) = (f.to_montgomery(x), f.to_montgomery(y));
+        let b = f.to_montgomery(&Uint(self.curve.b.to_vec()));
+        let x3 = f.mul(&f.mul(&x, &x), &x);
+        let x_3 = f.add(&f.add(&x, &x), &x);
+        if f.mul(&y, &y) != f.add(&f.sub(&x3, &x_3), &b) {
+            return None;
+        }
+        Some(Some([x, y, f.to_montgomery(&Uint(vec![1]))]))
+    }
+}
+
+/// An ECDSA public key on a supported curve.
+pub(crate) struct PublicKey {
+    curve: &'static Curve,
+    x: Uint,
+    y: Uint,
+}
+
+impl PublicKey {
+    /// Parses an `id-ecPublicKey` `SubjectPublicKeyInfo` with a named curve and an uncompressed point.
+    ///
+    /// Fails with `errSecUnimplemented` for other curves and compressed points.
+    pub fn from_public_key_info(public_key_info: &SubjectPublicKeyInfo) -> Result<Self> {
+        let algorithm = public_key_info.algorithm();
+        if *algorithm.oid() != Oid::EC_PUBLIC_KEY {
+            return Err(Error::from_code(errSecUnimplemented));
+        }
+        let mut reader = Reader::new(algorithm.parameters().ok_or_else(der::decode_error)?);
+        let named_curve = reader.read_oid()?;
+        reader.finish()?;
+        let curve = [&P256, &P384]
+            .into_iter()
+            .find(|curve| curve.oid == named_curve)
+            .ok_or_else(|| Error::from_code(errSecUnimplemented))?;
+
+        let point = public_key_info.public_key();
+        match point.split_first() {
+            Some((0x04, coordinates)) if coordinates.len() == 2 * curve.len => {
+                let (x, y) = coordinates.split_at(curve.len);
+                let key = Self { curve, x: Uint::from_be_bytes(x), y: Uint::from_be_bytes(y) };
+                key.arithmetic().point(&key.x, &key.y).ok_or_else(der::decode_error)?;
+                Ok(key)
+            },
+            Some((0x02 | 0x03, _)) => Err(Error::from_code(errSecUnimplemented)),
+            _ => Err(der::decode_error()),
+        }
+    }
+
+    fn arithmetic(&self) -> Arithmetic {
+        Arithmetic { field: Montgomery::new(&Uint(self.curve.p.to_vec())), curve: self.curve }
+    }
+
+    /// Checks a DER-encoded `ECDSA-Sig-Value` over `message`.
+    pub fn verify(&self, digest: DigestAlgorithm, message: &[u8], signature: &[u8]) -> bool {
+        let Some((r, s)) = parse_signature(signature) else {
+            return false;
+        };
+        let n = Uint(self.curve.n.to_vec());
+        if r.is_zero() || s.is_zero() || !r.less_than(&n) || !s.less_than(&n) {
+            return false;
+        }
+        let scalars = Montgomery::new(&n);
+        // the leftmost bits of the hash, as many as the order has
+        let hash = digest.digest(message);
+        let mut e = Uint::from_be_bytes(&hash[..hash.len().min(self.curve.len)]);
+        if !e.less_than(&n) {
+            let mut limbs = e.0.clone();
+            crate::bignum::sub_in_place(&mut limbs, &n.0);
+            e = Uint(limbs);
+            e.normalize();
+        }
+        let w = scalars.to_montgomery(&scalars.inverse(&s));
+        let mut u1 = Uint(scalars.mul(&w, &pad(&e, n.0.len())));
+        let mut u2 = Uint(scalars.mul(&w, &pad(&r, n.0.len())));
+        u1.normalize();
+        u2.normalize();
+
+        let arithmetic = self.arithmetic();
+        let generator = Uint(self.curve.gx.to_vec());
+        let (Some(g), Some(q)) = (arithmetic.point(&generator, &Uint(self.curve.gy.to_vec())), arithmetic.point(&self.x, &self.y)) else {
+            return false;
+        };
+        let Some(mut x) = arithmetic.x(&arithmetic.mul_add(&u1, &g, &u2, &q)) else {
+            return false;
+        };
+        // x < p < 2n
+        if !x.less_than(&n) {
+            let mut limbs = x.0.clone();
+            limbs.resize(n.0.len(), 0);
+            crate::bignum::sub_in_place(&mut limbs, &n.0);
+            x = Uint(limbs);
+            x.normalize();
+        }
+        x == r
+    }
+}
+
+fn pad(value: &Uint, limbs: usize) -> Vec<u64> {
+    let mut padded = value.0.clone();
+    padded.resize(limbs, 0);
+    padded
+}
+
+/// Parses `SEQUENCE { r INTEGER, s INTEGER }`, rejecting negative values.
+fn parse_signature(signature: &[u8]) -> Option<(Uint, Uint)> {
+    let mut reader = Reader::new(signature);
+    let mut seq = reader.read_sequence().ok()?;
+    reader.finish().ok()?;
+    let r = seq.read_integer().ok()?;
+    let s = seq.read_integer().ok()?;
+    seq.finish().ok()?;
+    if r[0] & 0x80 != 0 || s[0] & 0x80 != 0 {
+        return None;
+    }
+    Some((Uint::from_be_bytes(r), Uint::from_be_bytes(s)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn key(spki: &str) -> Result<PublicKey> {
+        let der = hex::decode(spki).unwrap();
+        PublicKey::from_public_key_info(&SubjectPublicKeyInfo::parse(&mut Reader::new(&der)).unwrap())
+    }
+
+    const P256_KEY: &str = "3059301306072a8648ce3d020106082a8648ce3d03010703420004567d5b823b84434aeed9b0c9ccf4b128cd320a6000779a00ff7e670e5179cd4cff390c980acc1d69f7a6cbbf8d106ff25a78f90d0277ab2aca88042e34aeadd4";
+
+    #[test]
+    fn p256() {
+        let key = key(P256_KEY).unwrap();
+        let signature = hex::decode("3045022100b3ae56a7490f5fbcb20c1170625340c4c507552caf9d69932ee2101043185d1c02202f42ab08e60c1cf7ece609bffcf08d161e34c7a15d9c84ea0b8655fa54764bcf").unwrap();
+        assert!(key.verify(DigestAlgorithm::Sha256, b"hello", &signature));
+        assert!(!key.verify(DigestAlgorithm::Sha256, b"hellO", &signature));
+        assert!(!key.verify(DigestAlgorithm::Sha384, b"hello", &signature));
+        let mut tampered = signature.clone();
+        tampered[10] ^= 1;
+        assert!(!key.verify(DigestAlgorithm::Sha256, b"hello", &tampered));
+        assert!(!key.verify(DigestAlgorithm::Sha256, b"hello", &signature[..signature.len() - 1]));
+    }
+
+    #[test]
+    fn p256_truncated_hash() {
+        let key = key("3059301306072a8648ce3d020106082a8648ce3d03010703420004592c0d59dd09f5de2d024026eb98ffc23ce965cfd33b13c5c63bdf9167ea6fccd6bcd112efe0a114bdd22c47e29b68aac6553d205d91e3bbbf30bc347b0c4a02").unwrap();
+        let signature = hex::decode("30450220362436541fdc0a957ee6437e9ffc2a308bdbaf73a1d26ae0f58df7d32ad07088022100cbcdf079566bd4732f2ecb3805b5a4082ffec412a1d3b94a7253c7307d0edd9b").unwrap();
+        assert!(key.verify(DigestAlgorithm::Sha512, b"hello", &signature));
+        assert!(!key.verify(DigestAlgorithm::Sha256, b"hello", &signature));
+    }
+
+    #[test]
+    fn p384() {
+        let key = key("3076301006072a8648ce3d020106052b8104002203620004f39829f7a2b0209b9feaf7f6fe9ca08f09678006dfab57b1e2c6e09f2a6b36c41824551b83dfa7b0f95c5d96f4e7d1febb0d628efcf2e89ed5edb4d025b8455547f0f1b1b0555fc5dcb90fade6ad496a3e0d59710710358eba7d80110611a468").unwrap();
+        let signature = hex::decode("3066023100da02255d1770285e39296eba1a05eb95aae366a19abcc3fa0ef7d309443e5f7ce2d49f8280a1223576c985ee342fd1ba023100d31df378c0e471201276f377a8c99fc042741bfc840e39a5a9cc0834ca704f0c3866e678356f9cf899e46aba574ba1aa").unwrap();
+        assert!(key.verify(DigestAlgorithm::Sha384, b"hello", &signature));
+        assert!(!key.verify(DigestAlgorithm::Sha384, b"hellO", &signature));
+    }
+
+    #[test]
+    fn invalid_keys() {
+        // not on the curve
+        let mut off_curve = P256_KEY.to_string();
+        off_curve.replace_range(off_curve.len() - 2.., "d5");
+        assert!(key(&off_curve).is_err());
+        // compressed
+        let compressed = P256_KEY.replace("03420004567d", "03420002567d");
+        assert_eq!(key(&compressed).err().unwrap().code(), errSecUnimplemented);
+        // secp256k1
+        let k1 = "3056301006072a8648ce3d020106052b8104000a034200044d8e6c3e3a1e5c4f1c2b6b2f8f7c4f3b9a2d0f1e6c5b4a39281706f5e4d3c2b1a0918273645546372819aabbccddeeff00112233445566778899aabbccddeeff00";
+        assert_eq!(key(k1).err().unwrap().code(), errSecUnimplemented);
+    }
+}
@@ -0,0 +1,523 @@
+//! Compiled-in catalogue of Security framework status codes.
+//!
+//! This doesn't depend on Security.framework, so it works on any platform.
+
+use core_foundation_sys::base::OSStatus;
+use codes::{
+    errAuthorizationCanceled, errAuthorizationDenied, errAuthorizationInteractionNotAllowed, errAuthorizationInvalidFlags,
+    errSecCSCancelled, errSecCSInvalidFlags, errSecCSNoSuchCode, errSecCSNotSupported, errSecCSStaticCodeNotFound,
+    errSecCSUnimplemented,
+};
+use security_framework_sys::base::{
+    errSecAuthFailed, errSecBadReq, errSecCertificateExpired, errSecCertificateNameNotAllowed, errSecCertificateNotValidYet,
+    errSecCertificateRevoked, errSecCreateChainFailed, errSecDataNotAvailable, errSecDiskFull, errSecDuplicateCallback,
+    errSecDuplicateItem, errSecDuplicateKeychain, errSecHostNameMismatch, errSecIO, errSecInDarkWake,
+    errSecIncompleteCertRevocationCheck, errSecInteractionNotAllowed, errSecInteractionRequired, errSecInvalidExtendedKeyUsage,
+    errSecInvalidKeyUsageForPolicy, errSecItemNotFound, errSecMissingEntitlement, errSecNoBasicConstraintsCA,
+    errSecNoDefaultKeychain, errSecNoSuchAttr, errSecNoSuchClass, errSecNoSuchKeychain, errSecNotAvailable, errSecNotSigner,
+    errSecNotTrusted, errSecParam, errSecPathLengthConstraintExceeded, errSecPkcs12VerifyFailure, errSecRestrictedAPI,
+    errSecTrustSettingDeny, errSecUnimplemented, errSecUserCanceled, errSecVerifyFailed, errSecWrPerm,
+};
+use security_framework_sys::secure_transport::{
+    errSSLBadCert, errSSLCertExpired, errSSLCertNotYetValid, errSSLClientHelloReceived, errSSLClosedAbort,
+    errSSLClosedGraceful, errSSLClosedNoNotify, errSSLConnectionRefused, errSSLHostNameMismatch, errSSLIllegalParam,
+    errSSLNoRootCert, errSSLClientCertRequested, errSSLPeerAuthCompleted, errSSLPeerBadCert, errSSLPeerCertExpired, errSSLPeerCertRevoked, errSSLPeerCertUnknown,
+    errSSLPeerUnknownCA, errSSLPeerUnsupportedCert, errSSLPeerUserCancelled, errSSLProtocol, errSSLUnknownRootCert,
+    errSSLWouldBlock, errSSLXCertChainInvalid,
+};
+
+use crate::base::ErrorCategory::{self, Authorization, CodeSigning, General, Keychain, Tls, Trust};
+use crate::base::ErrorKind;
+use std::io;
+
+/// A known status code.
+pub(crate) struct Entry {
+    pub code: OSStatus,
+    pub name: &'static str,
+    pub description: &'static str,
+    pub category: ErrorCategory,
+}
+
+macro_rules! catalogue {
+    ($($code:literal $name:ident $category:ident $description:literal,)*) => {
+        pub(crate) static CATALOGUE: &[Entry] = &[
+            $(Entry { code: $code, name: stringify!($name), description: $description, category: $category },)*
+        ];
+    };
+}
+
+/// Finds the catalogue entry for the given status code.
+pub(crate) fn lookup(code: OSStatus) -> Option<&'static Entry> {
+    CATALOGUE.iter().find(|e| e.code == code)
+}
+
+/// Authorization.h and CSCommon.h codes, which `security_framework_sys` only has on macOS, if at all
+mod codes {
+    #![allow(non_upper_case_globals)]
+    use core_foundation_sys::base::OSStatus;
+
+    pub const errAuthorizationDenied: OSStatus = -60005;
+    pub const errAuthorizationCanceled: OSStatus = -60006;
+    pub const errAuthorizationInteractionNotAllowed: OSStatus = -60007;
+    pub const errAuthorizationInvalidFlags: OSStatus = -60011;
+    pub const errSecCSCancelled: OSStatus = -67006;
+    pub const errSecCSNotSupported: OSStatus = -67037;
+    pub const errSecCSNoSuchCode: OSStatus = -67065;
+    pub const errSecCSStaticCodeNotFound: OSStatus = -67068;
+    pub const errSecCSInvalidFlags: OSStatus = -67070;
+    pub const errSecCSUnimplemented: OSStatus = -67072;
+}
+
+/// Classifies a status code. Unknown codes are `ErrorKind::Other`.
+pub(crate) fn kind(code: OSStatus) -> ErrorKind {
+    match code {
+        errSecItemNotFound | errSecNoSuchAttr | errSecNoSuchClass | errSecNoSuchKeychain | errSecNoDefaultKeychain
+        | errSecCSStaticCodeNotFound | errSecCSNoSuchCode => ErrorKind::NotFound,
+        errSecDuplicateItem | errSecDuplicateKeychain | errSecDuplicateCallback => ErrorKind::Duplicate,
+        errSecUserCanceled | errAuthorizationCanceled | errSecCSCancelled | errSSLPeerUserCancelled => ErrorKind::UserCanceled,
+        errSecAuthFailed | errAuthorizationDenied | errSecPkcs12VerifyFailure => ErrorKind::AuthFailed,
+        errSecInteractionNotAllowed | errSecInteractionRequired | errAuthorizationInteractionNotAllowed => {
+            ErrorKind::InteractionNotAllowed
+        },
+        errSecNotAvailable | errSecDataNotAvailable | errSecInDarkWake => ErrorKind::Locked,
+        errSecMissingEntitlement | errSecRestrictedAPI => ErrorKind::MissingEntitlement,
+        errSecParam | errSecBadReq | errSSLIllegalParam | errAuthorizationInvalidFlags | errSecCSInvalidFlags => {
+            ErrorKind::InvalidParameter
+        },
+        errSecUnimplemented | errSecCSUnimplemented | errSecCSNotSupported => ErrorKind::Unimplemented,
+        errSecIO | errSecDiskFull | errSecWrPerm => ErrorKind::Io,
+        // Not failures: the handshake stopped so the caller can act on it, and is then resumed
+        errSSLWouldBlock | errSSLPeerAuthCompleted | errSSLClientCertRequested | errSSLClientHelloReceived => {
+            ErrorKind::WouldBlock
+        },
+        errSSLClosedGraceful | errSSLClosedAbort | errSSLClosedNoNotify | errSSLConnectionRefused => ErrorKind::ConnectionClosed,
+        // Certificate and trust failures, local or reported by the peer
+        errSSLXCertChainInvalid | errSSLBadCert | errSSLUnknownRootCert | errSSLNoRootCert | errSSLCertExpired
+        | errSSLCertNotYetValid | errSSLPeerBadCert | errSSLPeerUnsupportedCert | errSSLPeerCertRevoked
+        | errSSLPeerCertExpired | errSSLPeerCertUnknown | errSSLPeerUnknownCA | errSSLHostNameMismatch
+        | errSecCreateChainFailed | errSecNotSigner | errSecHostNameMismatch | errSecNoBasicConstraintsCA
+        | errSecInvalidKeyUsageForPolicy | errSecInvalidExtendedKeyUsage | errSecPathLengthConstraintExceeded
+        | errSecIncompleteCertRevocationCheck | errSecTrustSettingDeny | errSecVerifyFailed | errSecCertificateExpired
+        | errSecCertificateNotValidYet | errSecCertificateRevoked | errSecNotTrusted | errSecCertificateNameNotAllowed => {
+            ErrorKind::CertificateRejected
+        },
+        errSSLClientHelloReceived..=errSSLProtocol => ErrorKind::TlsProtocol,
+        _ => ErrorKind::Other,
+    }
+}
+
+/// The nearest `std::io` equivalent of the error kind.
+pub(crate) fn io_kind(kind: ErrorKind) -> io::ErrorKind {
+    match kind {
+        ErrorKind::NotFound => io::ErrorKind::NotFound,
+        ErrorKind::Duplicate => io::ErrorKind::AlreadyExists,
+        ErrorKind::AuthFailed |
+        ErrorKind::InteractionNotAllowed |
+        ErrorKind::Locked |
+        ErrorKind::MissingEntitlement => io::ErrorKind::PermissionDenied,
+        ErrorKind::InvalidParameter => io::ErrorKind::InvalidInput,
+        ErrorKind::Unimplemented => io::ErrorKind::Unsupported,
+        ErrorKind::WouldBlock => io::ErrorKind::WouldBlock,
+        ErrorKind::ConnectionClosed => io::ErrorKind::ConnectionAborted,
+        ErrorKind::TlsProtocol | ErrorKind::CertificateRejected => io::ErrorKind::InvalidData,
+        ErrorKind::UserCanceled | ErrorKind::Io | ErrorKind::Other => io::ErrorKind::Other,
+    }
+}
+
+catalogue! {
+    -4 errSecUnimplemented General "Function or operation not implemented.",
+    -34 errSecDiskFull General "The disk is full.",
+    -36 errSecIO General "I/O error.",
+    -49 errSecOpWr General "File already open with write permission.",
+    -50 errSecParam General "One or more parameters passed to a function were not valid.",
+    -61 errSecWrPerm General "Write permissions error.",
+    -108 errSecAllocate General "Failed to allocate memory.",
+    -128 errSecUserCanceled General "User canceled the operation.",
+    -909 errSecBadReq General "Bad parameter or invalid state for operation.",
+    -2070 errSecInternalComponent General "An internal component failed.",
+    -4960 errSecCoreFoundationUnknown General "An unknown Core Foundation error occurred.",
+    -26275 errSecDecode General "Unable to decode the provided data.",
+    -67594 errSecConversionError General "A conversion error has occurred.",
+    -67673 errSecInvalidData General "The data is not valid.",
+
+    -25243 errSecNoAccessForItem Keychain "The specified item has no access control.",
+    -25244 errSecInvalidOwnerEdit Keychain "Invalid attempt to change the owner of this item.",
+    -25245 errSecTrustNotAvailable Keychain "No trust results are available.",
+    -25256 errSecUnsupportedFormat Keychain "Import/Export format unsupported.",
+    -25257 errSecUnknownFormat Keychain "Unknown format in import.",
+    -25258 errSecKeyIsSensitive Keychain "Key material must be wrapped for export.",
+    -25259 errSecMultiplePrivKeys Keychain "An attempt was made to import multiple private keys.",
+    -25260 errSecPassphraseRequired Keychain "Passphrase is required for import/export.",
+    -25261 errSecInvalidPasswordRef Keychain "The password reference was invalid.",
+    -25264 errSecPkcs12VerifyFailure Keychain "MAC verification failed during PKCS12 import (wrong password?).",
+    -25291 errSecNotAvailable Keychain "No keychain is available.",
+    -25292 errSecReadOnly Keychain "Read only error.",
+    -25293 errSecAuthFailed Keychain "Authorization/Authentication failed.",
+    -25294 errSecNoSuchKeychain Keychain "The keychain does not exist.",
+    -25295 errSecInvalidKeychain Keychain "The keychain is not valid.",
+    -25296 errSecDuplicateKeychain Keychain "A keychain with the same name already exists.",
+    -25297 errSecDuplicateCallback Keychain "The specified callback is already installed.",
+    -25298 errSecInvalidCallback Keychain "The specified callback is not valid.",
+    -25299 errSecDuplicateItem Keychain "The item already exists.",
+    -25300 errSecItemNotFound Keychain "The item cannot be found.",
+    -25301 errSecBufferTooSmall Keychain "The buffer is too small.",
+    -25302 errSecDataTooLarge Keychain "The data is too large for the particular data type.",
+    -25303 errSecNoSuchAttr Keychain "The attribute does not exist.",
+    -25304 errSecInvalidItemRef Keychain "The item reference is invalid.",
+    -25305 errSecInvalidSearchRef Keychain "The search reference is invalid.",
+    -25306 errSecNoSuchClass Keychain "The keychain item class does not exist.",
+    -25307 errSecNoDefaultKeychain Keychain "A default keychain does not exist.",
+    -25308 errSecInteractionNotAllowed Keychain "User interaction is not allowed.",
+    -25309 errSecReadOnlyAttr Keychain "The attribute is read only.",
+    -25310 errSecWrongSecVersion Keychain "The version is incorrect.",
+    -25311 errSecKeySizeNotAllowed Keychain "The key size is not allowed.",
+    -25312 errSecNoStorageModule Keychain "There is no storage module available.",
+    -25313 errSecNoCertificateModule Keychain "There is no certificate module available.",
+    -25314 errSecNoPolicyModule Keychain "There is no policy module available.",
+    -25315 errSecInteractionRequired Keychain "User interaction is required.",
+    -25316 errSecDataNotAvailable Keychain "The data is not available.",
+    -25317 errSecDataNotModifiable Keychain "The data is not modifiable.",
+    -25320 errSecInDarkWake Keychain "The operation is not allowed while the system is in dark wake.",
+    -34018 errSecMissingEntitlement Keychain "A required entitlement is missing.",
+    -34020 errSecRestrictedAPI Keychain "The API is restricted.",
+
+    -25262 errSecInvalidTrustSettings Trust "The trust settings record was corrupted.",
+    -25263 errSecNoTrustSettings Trust "No trust settings were found.",
+    -25318 errSecCreateChainFailed Trust "The attempt to create a certificate chain failed.",
+    -26267 errSecNotSigner Trust "The certificate was not signed by its proposed parent.",
+    -67602 errSecHostNameMismatch Trust "A host name mismatch has occurred.",
+    -67605 errSecNoBasicConstraintsCA Trust "The basic constraints extension does not allow a CA.",
+    -67608 errSecInvalidKeyUsageForPolicy Trust "The key usage is not valid for the specified policy.",
+    -67609 errSecInvalidExtendedKeyUsage Trust "The extended key usage is not valid.",
+    -67611 errSecPathLengthConstraintExceeded Trust "The path length constraint was exceeded.",
+    -67635 errSecIncompleteCertRevocationCheck Trust "An incomplete certificate revocation check occurred.",
+    -67654 errSecTrustSettingDeny Trust "The trust setting for this policy was set to Deny.",
+    -67808 errSecVerifyFailed Trust "A cryptographic verification failure has occurred.",
+    -67818 errSecCertificateExpired Trust "An expired certificate was detected.",
+    -67819 errSecCertificateNotValidYet Trust "The certificate is not yet valid.",
+    -67820 errSecCertificateRevoked Trust "The certificate was revoked.",
+    -67843 errSecNotTrusted Trust "The trust policy was not trusted.",
+    -67900 errSecCertificateNameNotAllowed Trust "The requested name isn't allowed for this certificate.",
+
+    -9800 errSSLProtocol Tls "SSL protocol error.",
+    -9801 errSSLNegotiation Tls "Cipher suite negotiation failure.",
+    -9802 errSSLFatalAlert Tls "A fatal alert was received.",
+    -9803 errSSLWouldBlock Tls "I/O would block (not fatal).",
+    -9804 errSSLSessionNotFound Tls "Attempt to restore an unknown session.",
+    -9805 errSSLClosedGraceful Tls "Connection closed gracefully.",
+    -9806 errSSLClosedAbort Tls "Connection closed via error.",
+    -9807 errSSLXCertChainInvalid Tls "Invalid certificate chain.",
+    -9808 errSSLBadCert Tls "Bad certificate format.",
+    -9809 errSSLCrypto Tls "Underlying cryptographic error.",
+    -9810 errSSLInternal Tls "Internal error.",
+    -9811 errSSLModuleAttach Tls "Module attach failure.",
+    -9812 errSSLUnknownRootCert Tls "Valid certificate chain, untrusted root.",
+    -9813 errSSLNoRootCert Tls "Certificate chain not verified by root.",
+    -9814 errSSLCertExpired Tls "Chain had an expired certificate.",
+    -9815 errSSLCertNotYetValid Tls "Chain had a certificate that is not yet valid.",
+    -9816 errSSLClosedNoNotify Tls "Server closed session with no notification.",
+    -9817 errSSLBufferOverflow Tls "Insufficient buffer provided.",
+    -9818 errSSLBadCipherSuite Tls "Bad SSL cipher suite.",
+    -9819 errSSLPeerUnexpectedMsg Tls "Unexpected message received.",
+    -9820 errSSLPeerBadRecordMac Tls "Bad MAC.",
+    -9821 errSSLPeerDecryptionFail Tls "Decryption failed.",
+    -9822 errSSLPeerRecordOverflow Tls "Record overflow.",
+    -9823 errSSLPeerDecompressFail Tls "Decompression failure.",
+    -9824 errSSLPeerHandshakeFail Tls "Handshake failure.",
+    -9825 errSSLPeerBadCert Tls "Misc. bad certificate.",
+    -9826 errSSLPeerUnsupportedCert Tls "Bad unsupported certificate format.",
+    -9827 errSSLPeerCertRevoked Tls "Certificate revoked.",
+    -9828 errSSLPeerCertExpired Tls "Certificate expired.",
+    -9829 errSSLPeerCertUnknown Tls "Unknown certificate.",
+    -9830 errSSLIllegalParam Tls "Illegal parameter.",
+    -9831 errSSLPeerUnknownCA Tls "Unknown certificate authority.",
+    -9832 errSSLPeerAccessDenied Tls "Access denied.",
+    -9833 errSSLPeerDecodeError Tls "Decoding error.",
+    -9834 errSSLPeerDecryptError Tls "Decryption error.",
+    -9835 errSSLPeerExportRestriction Tls "Export restriction.",
+    -9836 errSSLPeerProtocolVersion Tls "Bad protocol version.",
+    -9837 errSSLPeerInsufficientSecurity Tls "Insufficient security.",
+    -9838 errSSLPeerInternalError Tls "Internal error.",
+    -9839 errSSLPeerUserCancelled Tls "User canceled.",
+    -9840 errSSLPeerNoRenegotiation Tls "No renegotiation allowed.",
+    -9841 errSSLPeerAuthCompleted Tls "Peer certificate is valid, or was ignored if verification disabled.",
+    -9842 errSSLClientCertRequested Tls "Server has requested a client certificate.",
+    -9843 errSSLHostNameMismatch Tls "Peer host name mismatch.",
+    -9844 errSSLConnectionRefused Tls "Peer dropped connection before responding.",
+    -9845 errSSLDecryptionFail Tls "Decryption failure.",
+    -9846 errSSLBadRecordMac Tls "Bad MAC.",
+    -9847 errSSLRecordOverflow Tls "Record overflow.",
+    -9848 errSSLBadConfiguration Tls "Configuration error.",
+    -9849 errSSLUnexpectedRecord Tls "Unexpected (skipped) record in DTLS.",
+    -9850 errSSLWeakPeerEphemeralDHKey Tls "Weak ephemeral Diffie-Hellman key.",
+    -9851 errSSLClientHelloReceived Tls "SNI: ClientHello received.",
+
+    -60001 errAuthorizationInvalidSet Authorization "The authorization rights are invalid.",
+    -60002 errAuthorizationInvalidRef Authorization "The authorization reference is invalid.",
+    -60003 errAuthorizationInvalidTag Authorization "The authorization tag is invalid.",
+    -60004 errAuthorizationInvalidPointer Authorization "The returned authorization is invalid.",
+    -60005 errAuthorizationDenied Authorization "The authorization was denied.",
+    -60006 errAuthorizationCanceled Authorization "The authorization was canceled by the user.",
+    -60007 errAuthorizationInteractionNotAllowed Authorization "The authorization was denied since no user interaction was possible.",
+    -60008 errAuthorizationInternal Authorization "Unable to obtain authorization for this operation.",
+    -60009 errAuthorizationExternalizeNotAllowed Authorization "The authorization is not allowed to be converted to an external format.",
+    -60010 errAuthorizationInternalizeNotAllowed Authorization "The authorization is not allowed to be created from an external format.",
+    -60011 errAuthorizationInvalidFlags Authorization "The provided option flag(s) are invalid for this authorization operation.",
+    -60031 errAuthorizationToolExecuteFailure Authorization "The specified program could not be executed.",
+    -60032 errAuthorizationToolEnvironmentError Authorization "An invalid status was returned during execution of a privileged tool.",
+    -60033 errAuthorizationBadAddress Authorization "The requested socket address is invalid (must be 0-1023 inclusive).",
+
+    -67000 errSecCSUnsupportedDigestAlgorithm CodeSigning "A signature used an unsupported digest algorithm.",
+    -67001 errSecCSBadDiskImageFormat CodeSigning "The disk image has an invalid or unsupported format.",
+    -67002 errSecCSNotAppLike CodeSigning "The code is valid but does not seem to be an app.",
+    -67003 errSecCSInvalidSymlink CodeSigning "An invalid symlink was encountered in the bundle.",
+    -67004 errSecCSTooBig CodeSigning "The code is too big for current signing format.",
+    -67005 errSecCSInvalidPlatform CodeSigning "An invalid platform was specified.",
+    -67006 errSecCSCancelled CodeSigning "The operation was cancelled.",
+    -67007 errSecCSWeakResourceEnvelope CodeSigning "The resource envelope is obsolete.",
+    -67008 errSecCSUnsealedFrameworkRoot CodeSigning "Unsealed contents present in the root directory of an embedded framework.",
+    -67009 errSecCSBadFrameworkVersion CodeSigning "An embedded framework contains a modified or invalid version.",
+    -67010 errSecCSBadMainExecutable CodeSigning "The main executable or Info.plist must be a regular file.",
+    -67011 errSecCSAmbiguousBundleFormat CodeSigning "The bundle format is ambiguous (could be app or framework).",
+    -67012 errSecCSDSStoreSymlink CodeSigning "A .DS_Store file is a symbolic link.",
+    -67013 errSecCSWeakResourceRules CodeSigning "The resource rules are too permissive.",
+    -67014 errSecCSUnsealedAppRoot CodeSigning "Unsealed contents present in the bundle root.",
+    -67015 errSecCSRegularFile CodeSigning "The code must be a regular file.",
+    -67016 errSecCSResourceNotSupported CodeSigning "A resource is not supported for this kind of code.",
+    -67017 errSecCSBadLVArch CodeSigning "Library validation failed due to an architecture mismatch.",
+    -67018 errSecCSVetoed CodeSigning "The operation was vetoed.",
+    -67019 errSecCSHelperFailed CodeSigning "A helper program failed.",
+    -67020 errSecCSBadCallbackValue CodeSigning "A callback returned an invalid value.",
+    -67021 errSecCSBadNestedCode CodeSigning "Nested code is modified or invalid.",
+    -67022 errSecCSUnsignedNestedCode CodeSigning "Nested code is not signed.",
+    -67023 errSecCSResourceDirectoryFailed CodeSigning "Invalid resource directory (directory or signature have been modified).",
+    -67024 errSecCSDbCorrupt CodeSigning "The system policy database is corrupted.",
+    -67025 errSecCSOutdated CodeSigning "The presented data is out of date.",
+    -67026 errSecCSFileHardQuarantined CodeSigning "The file is hard-quarantined and cannot be executed.",
+    -67027 errSecCSNoMatches CodeSigning "The search found no matching entries.",
+    -67028 errSecCSBadBundleFormat CodeSigning "The bundle format is unrecognized, invalid, or unsuitable.",
+    -67029 errSecCSNoMainExecutable CodeSigning "The code has no main executable file.",
+    -67030 errSecCSInfoPlistFailed CodeSigning "The Info.plist is invalid or was modified.",
+    -67031 errSecCSHostProtocolInvalidAttribute CodeSigning "An invalid guest attribute was specified.",
+    -67032 errSecCSDBAccess CodeSigning "Cannot access the system policy database.",
+    -67033 errSecCSDBDenied CodeSigning "Access to the system policy database was denied.",
+    -67034 errSecCSStaticCodeChanged CodeSigning "The code on disk does not match what is running.",
+    -67035 errSecCSHostProtocolInvalidHash CodeSigning "The code directory hash is invalid.",
+    -67036 errSecCSCMSTooLarge CodeSigning "The signature is too large to embed.",
+    -67037 errSecCSNotSupported CodeSigning "The operation is not supported for this type of code.",
+    -67039 errSecCSHostProtocolUnrelated CodeSigning "The guest is not related to the host.",
+    -67040 errSecCSHostProtocolStateError CodeSigning "The host is in the wrong state for the operation.",
+    -67041 errSecCSHostProtocolNotProxy CodeSigning "The host has proxy hosting mode set.",
+    -67042 errSecCSHostProtocolDedicationError CodeSigning "The host is dedicated to another hosting mode.",
+    -67043 errSecCSHostProtocolContradiction CodeSigning "Contradictory hosting modes were requested.",
+    -67044 errSecCSHostProtocolRelativePath CodeSigning "The host protocol path was relative.",
+    -67045 errSecCSSignatureInvalid CodeSigning "The code signature is invalid.",
+    -67046 errSecCSNotAHost CodeSigning "The code is not a host.",
+    -67047 errSecCSHostReject CodeSigning "The host rejected the guest.",
+    -67048 errSecCSInternalError CodeSigning "An internal error occurred in code signing.",
+    -67049 errSecCSBadObjectFormat CodeSigning "The object file format is unrecognized, invalid, or unsuitable.",
+    -67050 errSecCSReqFailed CodeSigning "The code failed to satisfy the specified code requirement(s).",
+    -67051 errSecCSReqUnsupported CodeSigning "The code requirement is unsupported.",
+    -67052 errSecCSReqInvalid CodeSigning "The code requirement is invalid.",
+    -67053 errSecCSResourceRulesInvalid CodeSigning "The resource rules are invalid.",
+    -67054 errSecCSBadResource CodeSigning "A sealed resource is missing or invalid.",
+    -67055 errSecCSResourcesInvalid CodeSigning "The code's resources are invalid.",
+    -67056 errSecCSResourcesNotFound CodeSigning "The code has no resources but its signature indicates they must be present.",
+    -67057 errSecCSResourcesNotSealed CodeSigning "The code's resources are not sealed.",
+    -67058 errSecCSBadDictionaryFormat CodeSigning "A dictionary has an invalid format.",
+    -67059 errSecCSSignatureUnsupported CodeSigning "The signature format is unsupported.",
+    -67060 errSecCSSignatureNotVerifiable CodeSigning "The code signature cannot be verified.",
+    -67061 errSecCSSignatureFailed CodeSigning "The code signature verification failed.",
+    -67062 errSecCSUnsigned CodeSigning "The code object is not signed at all.",
+    -67063 errSecCSGuestInvalid CodeSigning "The code is no longer valid.",
+    -67064 errSecCSMultipleGuests CodeSigning "The guest attributes matched multiple guests.",
+    -67065 errSecCSNoSuchCode CodeSigning "No code could be found matching the specified attributes.",
+    -67066 errSecCSInvalidAttributeValues CodeSigning "The specified attribute values are invalid.",
+    -67067 errSecCSUnsupportedGuestAttributes CodeSigning "The host does not support the requested guest attributes.",
+    -67068 errSecCSStaticCodeNotFound CodeSigning "The code on disk could not be found.",
+    -67069 errSecCSObjectRequired CodeSigning "A required object was not provided.",
+    -67070 errSecCSInvalidFlags CodeSigning "Invalid flags were passed.",
+    -67071 errSecCSInvalidObjectRef CodeSigning "An invalid object reference was passed.",
+    -67072 errSecCSUnimplemented CodeSigning "The operation is not implemented.",
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn unique_codes_and_names() {
+        let mut codes = HashSet::new();
+        let mut names = HashSet::new();
+        for e in CATALOGUE {
+            assert!(codes.insert(e.code), "{}", e.code);
+            assert!(names.insert(e.name), "{}", e.name);
+            assert!(e.description.ends_with('.'), "{}", e.name);
+        }
+    }
+
+    #[test]
+    fn covers_sys_constants() {
+        use security_framework_sys::base::*;
+        use security_framework_sys::secure_transport::*;
+
+        for (code, name) in [
+            (errSecUnimplemented, "errSecUnimplemented"),
+            (errSecIO, "errSecIO"),
+            (errSecParam, "errSecParam"),
+            (errSecBadReq, "errSecBadReq"),
+            (errSecNoTrustSettings, "errSecNoTrustSettings"),
+            (errSecAuthFailed, "errSecAuthFailed"),
+            (errSecDuplicateItem, "errSecDuplicateItem"),
+            (errSecItemNotFound, "errSecItemNotFound"),
+            (errSecCreateChainFailed, "errSecCreateChainFailed"),
+            (errSecNotSigner, "errSecNotSigner"),
+            (errSecConversionError, "errSecConversionError"),
+            (errSecHostNameMismatch, "errSecHostNameMismatch"),
+            (errSecNoBasicConstraintsCA, "errSecNoBasicConstraintsCA"),
+            (errSecInvalidKeyUsageForPolicy, "errSecInvalidKeyUsageForPolicy"),
+            (errSecInvalidExtendedKeyUsage, "errSecInvalidExtendedKeyUsage"),
+            (errSecPathLengthConstraintExceeded, "errSecPathLengthConstraintExceeded"),
+            (errSecTrustSettingDeny, "errSecTrustSettingDeny"),
+            (errSecCertificateRevoked, "errSecCertificateRevoked"),
+            (errSecNotTrusted, "errSecNotTrusted"),
+            (errSecCertificateNameNotAllowed, "errSecCertificateNameNotAllowed"),
+            (errSecInternalComponent, "errSecInternalComponent"),
+            (errSSLProtocol, "errSSLProtocol"),
+            (errSSLXCertChainInvalid, "errSSLXCertChainInvalid"),
+            (errSSLPeerUnknownCA, "errSSLPeerUnknownCA"),
+            (errSSLClientHelloReceived, "errSSLClientHelloReceived"),
+        ] {
+            assert_eq!(lookup(code).unwrap().name, name);
+        }
+        for code in errSSLClientHelloReceived..=errSSLProtocol {
+            let entry = lookup(code).unwrap();
+            assert!(entry.name.starts_with("errSSL"));
+            assert_eq!(entry.category, Tls);
+        }
+    }
+
+    #[test]
+    fn kinds() {
+        use security_framework_sys::base::*;
+        use security_framework_sys::secure_transport::*;
+
+        assert_eq!(kind(errSecItemNotFound), ErrorKind::NotFound);
+        assert_eq!(kind(errSecDuplicateItem), ErrorKind::Duplicate);
+        assert_eq!(kind(errSecUserCanceled), ErrorKind::UserCanceled);
+        assert_eq!(kind(errSecAuthFailed), ErrorKind::AuthFailed);
+        assert_eq!(kind(errSecInteractionNotAllowed), ErrorKind::InteractionNotAllowed);
+        assert_eq!(kind(errSecParam), ErrorKind::InvalidParameter);
+        assert_eq!(kind(errSecNotTrusted), ErrorKind::CertificateRejected);
+        assert_eq!(kind(errSecHostNameMismatch), ErrorKind::CertificateRejected);
+        assert_eq!(kind(errSecPathLengthConstraintExceeded), ErrorKind::CertificateRejected);
+        assert_eq!(kind(errSecCertificateNameNotAllowed), ErrorKind::CertificateRejected);
+        assert_eq!(kind(errSSLXCertChainInvalid), ErrorKind::CertificateRejected);
+        assert_eq!(kind(errSSLPeerCertExpired), ErrorKind::CertificateRejected);
+        assert_eq!(kind(errSSLCertNotYetValid), ErrorKind::CertificateRejected);
+        assert_eq!(kind(errSSLPeerUnknownCA), ErrorKind::CertificateRejected);
+        assert_eq!(kind(errSSLHostNameMismatch), ErrorKind::CertificateRejected);
+        assert_eq!(kind(errSSLPeerProtocolVersion), ErrorKind::TlsProtocol);
+        assert_eq!(kind(errSSLNegotiation), ErrorKind::TlsProtocol);
+        assert_eq!(kind(errSSLPeerAccessDenied), ErrorKind::TlsProtocol);
+        assert_eq!(kind(errSSLWouldBlock), ErrorKind::WouldBlock);
+        assert_eq!(kind(errSSLClosedAbort), ErrorKind::ConnectionClosed);
+        assert_eq!(kind(12345), ErrorKind::Other);
+        assert_eq!(kind(errSSLModuleAttach), ErrorKind::TlsProtocol);
+
+
+        use ErrorKind::{CertificateRejected, ConnectionClosed, InvalidParameter, TlsProtocol, UserCanceled, WouldBlock};
+        let tls = [
+            (errSSLProtocol, TlsProtocol),
+            (errSSLNegotiation, TlsProtocol),
+            (errSSLFatalAlert, TlsProtocol),
+            (errSSLWouldBlock, WouldBlock),
+            (errSSLSessionNotFound, TlsProtocol),
+            (errSSLClosedGraceful, ConnectionClosed),
+            (errSSLClosedAbort, ConnectionClosed),
+            (errSSLXCertChainInvalid, CertificateRejected),
+            (errSSLBadCert, CertificateRejected),
+            (errSSLCrypto, TlsProtocol),
+            (errSSLInternal, TlsProtocol),
+            (errSSLModuleAttach, TlsProtocol),
+            (errSSLUnknownRootCert, CertificateRejected),
+            (errSSLNoRootCert, CertificateRejected),
+            (errSSLCertExpired, CertificateRejected),
+            (errSSLCertNotYetValid, CertificateRejected),
+            (errSSLClosedNoNotify, ConnectionClosed),
+            (errSSLBufferOverflow, TlsProtocol),
+            (errSSLBadCipherSuite, TlsProtocol),
+            (errSSLPeerUnexpectedMsg, TlsProtocol),
+            (errSSLPeerBadRecordMac, TlsProtocol),
+            (errSSLPeerDecryptionFail, TlsProtocol),
+            (errSSLPeerRecordOverflow, TlsProtocol),
+            (errSSLPeerDecompressFail, TlsProtocol),
+            (errSSLPeerHandshakeFail, TlsProtocol),
+            (errSSLPeerBadCert, CertificateRejected),
+            (errSSLPeerUnsupportedCert, CertificateRejected),
+            (errSSLPeerCertRevoked, CertificateRejected),
+            (errSSLPeerCertExpired, CertificateRejected),
+            (errSSLPeerCertUnknown, CertificateRejected),
+            (errSSLIllegalParam, InvalidParameter),
+            (errSSLPeerUnknownCA, CertificateRejected),
+            (errSSLPeerAccessDenied, TlsProtocol),
+            (errSSLPeerDecodeError, TlsProtocol),
+            (errSSLPeerDecryptError, TlsProtocol),
+            (errSSLPeerExportRestriction, TlsProtocol),
+            (errSSLPeerProtocolVersion, TlsProtocol),
+            (errSSLPeerInsufficientSecurity, TlsProtocol),
+            (errSSLPeerInternalError, TlsProtocol),
+            (errSSLPeerUserCancelled, UserCanceled),
+            (errSSLPeerNoRenegotiation, TlsProtocol),
+            (errSSLPeerAuthCompleted, WouldBlock),
+            (errSSLClientCertRequested, WouldBlock),
+            (errSSLHostNameMismatch, CertificateRejected),
+            (errSSLConnectionRefused, ConnectionClosed),
+            (errSSLDecryptionFail, TlsProtocol),
+            (errSSLBadRecordMac, TlsProtocol),
+            (errSSLRecordOverflow, TlsProtocol),
+            (errSSLBadConfiguration, TlsProtocol),
+            (-9849, TlsProtocol), // errSSLUnexpectedRecord, not in security_framework_sys
+            (-9850, TlsProtocol), // errSSLWeakPeerEphemeralDHKey, likewise
+            (errSSLClientHelloReceived, WouldBlock),
+        ];
+        for (i, (code, expected)) in tls.into_iter().enumerate() {
+            assert_eq!(code, errSSLProtocol - OSStatus::try_from(i).unwrap());
+            assert_eq!(kind(code), expected, "{}", lookup(code).unwrap().name);
+        }
+    }
+
+    #[test]
+    fn io_kinds() {
+        assert_eq!(io_kind(kind(-25300)), io::ErrorKind::NotFound);
+        assert_eq!(io_kind(kind(-25299)), io::ErrorKind::AlreadyExists);
+        assert_eq!(io_kind(kind(-25293)), io::ErrorKind::PermissionDenied);
+        assert_eq!(io_kind(kind(-50)), io::ErrorKind::InvalidInput);
+        assert_eq!(io_kind(kind(-9803)), io::ErrorKind::WouldBlock);
+        assert_eq!(io_kind(kind(-9806)), io::ErrorKind::ConnectionAborted);
+        assert_eq!(io_kind(kind(-9807)), io::ErrorKind::InvalidData);
+        assert_eq!(io_kind(kind(12345)), io::ErrorKind::Other);
+    }
+
+    #[test]
+    #[cfg(target_os = "macos")]
+    fn covers_authorization_constants() {
+        use security_framework_sys::authorization as sys;
+
+        for code in [
+            sys::errAuthorizationInvalidSet,
+            sys::errAuthorizationDenied,
+            sys::errAuthorizationCanceled,
+            sys::errAuthorizationInteractionNotAllowed,
+            sys::errAuthorizationToolEnvironmentError,
+            sys::errAuthorizationBadAddress,
+        ] {
+            assert_eq!(lookup(code).unwrap().category, Authorization);
+        }
+        assert_eq!(codes::errAuthorizationDenied, sys::errAuthorizationDenied);
+        assert_eq!(codes::errAuthorizationCanceled, sys::errAuthorizationCanceled);
+        assert_eq!(codes::errAuthorizationInteractionNotAllowed, sys::errAuthorizationInteractionNotAllowed);
+        assert_eq!(codes::errAuthorizationInvalidFlags, sys::errAuthorizationInvalidFlags);
+    }
+}
@@ -1,6 +1,7 @@
-#![cfg(target_vendor = "apple")]
-
 //! Wrappers around the macOS Security Framework.
+//!
+//! Most of the crate is only available on Apple platforms. Modules that
+//! don't call into Security.framework are available everywhere.
 #![warn(missing_docs)]
 #![allow(non_upper_case_globals)]
 #![allow(clippy::manual_non_exhaustive)] // MSRV
@@ -9,12 +10,15 @@
 #![allow(clippy::unreadable_literal)]
 #![allow(clippy::ignore_without_reason)]
 
+#[cfg(target_vendor = "apple")]
 use core_foundation_sys::base::OSStatus;
+#[cfg(target_vendor = "apple")]
 use security_framework_sys::base::errSecSuccess;
 
+#[cfg(target_vendor = "apple")]
 use crate::base::{Error, Result};
 
-#[cfg(test)]
+#[cfg(all(test, target_vendor = "apple"))]
 macro_rules! p {
     ($e:expr) => {
         match $e {
@@ -24,29 +28,54 @@ macro_rules! p {
     };
 }
 
+#[cfg(target_vendor = "apple")]
 pub mod access_control;
 #[cfg(target_os = "macos")]
 pub mod authorization;
 pub mod base;
 pub mod certificate;
+#[cfg(target_vendor = "apple")]
 pub mod cipher_suite;
 #[cfg(target_os = "macos")]
 pub mod cms;
+pub mod crl;
+pub mod digest;
+#[cfg(target_vendor = "apple")]
 pub mod identity;
+#[cfg(target_vendor = "apple")]
 pub mod import_export;
+#[cfg(target_vendor = "apple")]
 pub mod item;
+#[cfg(target_vendor = "apple")]
 pub mod key;
+pub mod ocsp;
+#[cfg(target_vendor = "apple")]
 pub mod os;
+#[cfg(target_vendor = "apple")]
 pub mod passwords;
+#[cfg(target_vendor = "apple")]
 #[doc(hidden)]
 pub mod passwords_options;
+pub mod pem;
+pub mod plist;
 pub mod policy;
+#[cfg(target_vendor = "apple")]
 pub mod random;
+pub mod sct;
+#[cfg(target_vendor = "apple")]
 pub mod secure_transport;
 pub mod trust;
-#[cfg(target_os = "macos")]
 pub mod trust_settings;
 
+mod base64;
+mod bignum;
+mod der;
+mod ecdsa;
+mod error_codes;
+mod rsa;
+mod signature;
+
+#[cfg(target_vendor = "apple")]
 #[inline(always)]
 fn cvt(err: OSStatus) -> Result<()> {
     match err {
@@ -55,7 +84,7 @@ fn cvt(err: OSStatus) -> Result<()> {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, target_vendor = "apple"))]
 mod test {
     use crate::certificate::SecCertificate;
 
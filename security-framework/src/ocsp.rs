@@ -0,0 +1,647 @@
+//! Online Certificate Status Protocol messages (RFC 6960).
+//!
+//! This only encodes requests and decodes responses; sending them (usually an HTTP POST with
+//! `Content-Type: application/ocsp-request`) is up to the caller. Fresh responses can be
+//! stapled with `SecTrust::set_trust_ocsp_response`.
+
+use std::time::SystemTime;
+
+use crate::base::Result;
+#[cfg(target_vendor = "apple")]
+use crate::certificate::SecCertificate;
+use crate::certificate::{AlgorithmIdentifier, DistinguishedName, Extension, Parsed};
+use crate::crl::RevocationReason;
+use crate::der::{self, Oid, Reader};
+use crate::digest::DigestAlgorithm;
+use crate::signature;
+
+/// Identifies a certificate by hashes of its issuer and its serial number.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CertId {
+    hash_algorithm: DigestAlgorithm,
+    issuer_name_hash: Vec<u8>,
+    issuer_key_hash: Vec<u8>,
+    serial_number: Vec<u8>,
+}
+
+impl CertId {
+    /// Identifies `certificate`, which was issued by `issuer`.
+    ///
+    /// Most responders only support SHA-1, which is what [`OcspRequest::new`] uses.
+    #[must_use]
+    pub fn new(certificate: &Parsed, issuer: &Parsed, hash_algorithm: DigestAlgorithm) -> Self {
+        Self {
+            hash_algorithm,
+            issuer_name_hash: hash_algorithm.digest(issuer.subject_der()),
+            issuer_key_hash: hash_algorithm.digest(issuer.public_key_info().public_key()),
+            serial_number: certificate.serial_number().to_vec(),
+        }
+    }
+
+    fn parse(reader: &mut Reader<'_>) -> Result<Self> {
+        let mut seq = reader.read_sequence()?;
+        let algorithm = AlgorithmIdentifier::parse(&mut seq)?;
+        let hash_algorithm = DigestAlgorithm::from_oid(algorithm.oid()).ok_or_else(der::decode_error)?;
+        let issuer_name_hash = seq.read(der::OCTET_STRING)?.to_vec();
+        let issuer_key_hash = seq.read(der::OCTET_STRING)?.to_vec();
+        let serial_number = seq.read_integer()?.to_vec();
+        seq.finish()?;
+        Ok(Self { hash_algorithm, issuer_name_hash, issuer_key_hash, serial_number })
+    }
+
+    fn to_der(&self) -> Vec<u8> {
+        let algorithm = AlgorithmIdentifier::new(self.hash_algorithm.oid(), Some(vec![5, 0]));
+        der::encode_sequence(&[
+            &algorithm.to_der(),
+            &der::encode(der::OCTET_STRING, &self.issuer_name_hash),
+            &der::encode(der::OCTET_STRING, &self.issuer_key_hash),
+            &der::encode(der::INTEGER, &self.serial_number),
+        ])
+    }
+
+    /// Whether this identifies `certificate` issued by `issuer`, using this ID's hash algorithm.
+    #[must_use]
+    pub fn matches(&self, certificate: &Parsed, issuer: &Parsed) -> bool {
+        *self == Self::new(certificate, issuer, self.hash_algorithm)
+    }
+
+    /// The hash algorithm of the issuer hashes.
+    #[inline]
+    #[must_use]
+    pub fn hash_algorithm(&self) -> DigestAlgorithm {
+        self.hash_algorithm
+    }
+
+    /// Hash of the issuer's DER-encoded subject name.
+    #[inline]
+    #[must_use]
+    pub fn issuer_name_hash(&self) -> &[u8] {
+        &self.issuer_name_hash
+    }
+
+    /// Hash of the issuer's public key (the `subjectPublicKey` bits).
+    #[inline]
+    #[must_use]
+    pub fn issuer_key_hash(&self) -> &[u8] {
+        &self.issuer_key_hash
+    }
+
+    /// The certificate's serial number, as big-endian two's complement bytes.
+    #[inline]
+    #[must_use]
+    pub fn serial_number(&self) -> &[u8] {
+        &self.serial_number
+    }
+}
+
+/// An unsigned OCSP request for one or more certificates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OcspRequest {
+    cert_ids: Vec<CertId>,
+    nonce: Option<Vec<u8>>,
+}
+
+impl OcspRequest {
+    /// A request for the status of `certificate`, which was issued by `issuer`.
+    #[must_use]
+    pub fn new(certificate: &Parsed, issuer: &Parsed) -> Self {
+        Self { cert_ids: vec![CertId::new(certificate, issuer, DigestAlgorithm::Sha1)], nonce: None }
+    }
+
+    /// A request for the status of `certificate`, which was issued by `issuer`.
+    #[cfg(target_vendor = "apple")]
+    pub fn for_certificate(certificate: &SecCertificate, issuer: &SecCertificate) -> Result<Self> {
+        Ok(Self::new(&certificate.parse()?, &issuer.parse()?))
+    }
+
+    /// Also asks about another certificate. Responders may only answer the first one.
+    pub fn add_cert_id(&mut self, cert_id: CertId) -> &mut Self {
+        self.cert_ids.push(cert_id);
+        self
+    }
+
+    /// Sets a nonce, which the responder should echo to prove the response is fresh.
+    ///
+    /// Many responders ignore nonces and return cached responses.
+    pub fn set_nonce(&mut self, nonce: &[u8]) -> &mut Self {
+        self.nonce = Some(nonce.to_vec());
+        self
+    }
+
+    /// The certificates asked about.
+    #[inline]
+    #[must_use]
+    pub fn cert_ids(&self) -> &[CertId] {
+        &self.cert_ids
+    }
+
+    /// The nonce, if set.
+    #[inline]
+    #[must_use]
+    pub fn nonce(&self) -> Option<&[u8]> {
+        self.nonce.as_deref()
+    }
+
+    /// Encodes the `OCSPRequest`.
+    #[must_use]
+    pub fn to_der(&self) -> Vec<u8> {
+        let requests: Vec<_> = self.cert_ids.iter().map(|id| der::encode_sequence(&[&id.to_der()])).collect();
+        let mut tbs = der::encode(der::SEQUENCE, &requests.concat());
+        if let Some(nonce) = &self.nonce {
+            let extension = Extension::new(Oid::OCSP_NONCE, false, der::encode(der::OCTET_STRING, nonce));
+            tbs.extend(der::encode(der::context(2), &der::encode_sequence(&[&extension.to_der()])));
+        }
+        der::encode_sequence(&[&der::encode(der::SEQUENCE, &tbs)])
+    }
+}
+
+/// Whether the responder could process the request (`OCSPResponseStatus`).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum OcspResponseStatus {
+    /// The response has valid confirmations.
+    Successful,
+    /// The request wasn't understood.
+    MalformedRequest,
+    /// The responder had an internal error.
+    InternalError,
+    /// Try again later.
+    TryLater,
+    /// The responder requires signed requests.
+    SigRequired,
+    /// The responder isn't authorized to answer for the certificate.
+    Unauthorized,
+}
+
+/// The status of one certificate.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CertStatus {
+    /// Not revoked.
+    Good,
+    /// Revoked at `time`.
+    Revoked {
+        /// When the certificate was revoked.
+        time: SystemTime,
+        /// Why the certificate was revoked, if the responder says.
+        reason: Option<RevocationReason>,
+    },
+    /// The responder doesn't know about the certificate.
+    Unknown,
+}
+
+/// The response about one certificate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SingleResponse {
+    cert_id: CertId,
+    status: CertStatus,
+    this_update: SystemTime,
+    next_update: Option<SystemTime>,
+}
+
+impl SingleResponse {
+    fn parse(reader: &mut Reader<'_>) -> Result<Self> {
+        let mut seq = reader.read_sequence()?;
+        let cert_id = CertId::parse(&mut seq)?;
+        let status = seq.read_tlv()?;
+        let status = match status.tag {
+            0x80 => CertStatus::Good,
+            0xa1 => {
+                let mut info = Reader::new(status.content);
+                let time = info.read_time()?;
+                let reason = match info.read_optional(der::context(0))? {
+                    Some(explicit) => {
+                        let mut explicit = Reader::new(explicit);
+                        let code = der::uint_from_content(explicit.read(der::ENUMERATED)?)?;
+                        explicit.finish()?;
+                        Some(RevocationReason::from_code(code).ok_or_else(der::decode_error)?)
+                    },
+                    None => None,
+                };
+                info.finish()?;
+                CertStatus::Revoked { time, reason }
+            },
+            0x82 => CertStatus::Unknown,
+            _ => return Err(der::decode_error()),
+        };
+        let this_update = seq.read_time()?;
+        let next_update = match seq.read_optional(der::context(0))? {
+            Some(explicit) => {
+                let mut explicit = Reader::new(explicit);
+                let time = explicit.read_time()?;
+                explicit.finish()?;
+                Some(time)
+            },
+            None => None,
+        };
+        seq.read_optional(der::context(1))?;
+        seq.finish()?;
+        Ok(Self { cert_id, status, this_update, next_update })
+    }
+
+    /// Which certificate this is about.
+    #[inline]
+    #[must_use]
+    pub fn cert_id(&self) -> &CertId {
+        &self.cert_id
+    }
+
+    /// The certificate's status.
+    #[inline]
+    #[must_use]
+    pub fn status(&self) -> CertStatus {
+        self.status
+    }
+
+    /// When the status was known to be correct.
+    #[inline]
+    #[must_use]
+    pub fn this_update(&self) -> SystemTime {
+        self.this_update
+    }
+
+    /// When newer information will be available.
+    #[inline]
+    #[must_use]
+    pub fn next_update(&self) -> Option<SystemTime> {
+        self.next_update
+    }
+
+    /// Whether the response is usable at `time`: between this update and next update.
+    ///
+    /// Responses without a next update are never considered fresh, since newer
+    /// information is always available for them.
+    #[must_use]
+    pub fn is_fresh_at(&self, time: SystemTime) -> bool {
+        self.this_update <= time && self.next_update.is_some_and(|next| time <= next)
+    }
+}
+
+/// How the responder identifies itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResponderId {
+    /// The responder certificate's subject.
+    ByName(DistinguishedName),
+    /// SHA-1 hash of the responder's public key.
+    ByKey(Vec<u8>),
+}
+
+impl ResponderId {
+    fn matches(&self, signer: &Parsed) -> bool {
+        match self {
+            Self::ByName(name) => name.matches(signer.subject()),
+            Self::ByKey(hash) => *hash == DigestAlgorithm::Sha1.digest(signer.public_key_info().public_key()),
+        }
+    }
+}
+
+/// A decoded `OCSPResponse`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OcspResponse {
+    status: OcspResponseStatus,
+    basic: Option<BasicResponse>,
+    der: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BasicResponse {
+    responder_id: ResponderId,
+    produced_at: SystemTime,
+    responses: Vec<SingleResponse>,
+    extensions: Vec<Extension>,
+    tbs_der: Vec<u8>,
+    signature_algorithm: AlgorithmIdentifier,
+    signature: Vec<u8>,
+    certs: Vec<Parsed>,
+}
+
+impl BasicResponse {
+    fn parse(der: &[u8]) -> Result<Self> {
+        let mut outer = Reader::new(der);
+        let mut basic = outer.read_sequence()?;
+        outer.finish()?;
+        let tbs = basic.read_tlv()?;
+        if tbs.tag != der::SEQUENCE {
+            return Err(der::decode_error());
+        }
+        let signature_algorithm = AlgorithmIdentifier::parse(&mut basic)?;
+        let (_, signature) = basic.read_bit_string()?;
+        let mut certs = Vec::new();
+        if let Some(explicit) = basic.read_optional(der::context(0))? {
+            let mut explicit = Reader::new(explicit);
+            let mut list = explicit.read_sequence()?;
+            explicit.finish()?;
+            while !list.is_empty() {
+                certs.push(Parsed::from_der(list.read_tlv()?.raw)?);
+            }
+        }
+        basic.finish()?;
+
+        let mut data = Reader::new(tbs.content);
+        if let Some(explicit) = data.read_optional(der::context(0))? {
+            // only v1 exists
+            if explicit != [der::INTEGER, 1, 0] {
+                return Err(der::decode_error());
+            }
+        }
+        let responder_id = match data.read_tlv()? {
+            der::Tlv { tag: 0xa1, content, .. } => {
+                let mut explicit = Reader::new(content);
+                let name = explicit.read_sequence()?;
+                explicit.finish()?;
+                ResponderId::ByName(DistinguishedName::parse_content(name)?)
+            },
+            der::Tlv { tag: 0xa2, content, .. } => {
+                let mut explicit = Reader::new(content);
+                let hash = explicit.read(der::OCTET_STRING)?.to_vec();
+                explicit.finish()?;
+                ResponderId::ByKey(hash)
+            },
+            _ => return Err(der::decode_error()),
+        };
+        let produced_at = data.read_time()?;
+        let mut list = data.read_sequence()?;
+        let mut responses = Vec::new();
+        while !list.is_empty() {
+            responses.push(SingleResponse::parse(&mut list)?);
+        }
+        let mut extensions = Vec::new();
+        if let Some(explicit) = data.read_optional(der::context(1))? {
+            let mut explicit = Reader::new(explicit);
+            extensions = Extension::parse_list(&mut explicit)?;
+            explicit.finish()?;
+        }
+        data.finish()?;
+
+        Ok(Self {
+            responder_id,
+            produced_at,
+            responses,
+            extensions,
+            tbs_der: tbs.raw.to_vec(),
+            signature_algorithm,
+            signature: signature.to_vec(),
+            certs,
+        })
+    }
+}
+
+impl OcspResponse {
+    /// Parses a DER-encoded `OCSPResponse`.
+    ///
+    /// Unsuccessful responses parse fine; check [`OcspResponse::status`].
+    /// Only basic responses are supported.
+    pub fn parse(der: &[u8]) -> Result<Self> {
+        let mut outer = Reader::new(der);
+        let mut response = outer.read_sequence()?;
+        outer.finish()?;
+        let status = match der::uint_from_content(response.read(der::ENUMERATED)?)? {
+            0 => OcspResponseStatus::Successful,
+            1 => OcspResponseStatus::MalformedRequest,
+            2 => OcspResponseStatus::InternalError,
+            3 => OcspResponseStatus::TryLater,
+            5 => OcspResponseStatus::SigRequired,
+            6 => OcspResponseStatus::Unauthorized,
+            _ => return Err(der::decode_error()),
+        };
+        let basic = match response.read_optional(der::context(0))? {
+            Some(explicit) => {
+                let mut explicit = Reader::new(explicit);
+                let mut bytes = explicit.read_sequence()?;
+                explicit.finish()?;
+                if bytes.read_oid()? != Oid::OCSP_BASIC {
+                    return Err(der::decode_error());
+                }
+                let basic = bytes.read(der::OCTET_STRING)?;
+                bytes.finish()?;
+                Some(BasicResponse::parse(basic)?)
+            },
+            None => None,
+        };
+        response.finish()?;
+        if (status == OcspResponseStatus::Successful) != basic.is_some() {
+            return Err(der::decode_error());
+        }
+        Ok(Self { status, basic, der: der.to_vec() })
+    }
+
+    /// Whether the responder processed the request.
+    #[inline]
+    #[must_use]
+    pub fn status(&self) -> OcspResponseStatus {
+        self.status
+    }
+
+    /// The responder, for successful responses.
+    #[must_use]
+    pub fn responder_id(&self) -> Option<&ResponderId> {
+        self.basic.as_ref().map(|b| &b.responder_id)
+    }
+
+    /// When the response was signed, for successful responses.
+    #[must_use]
+    pub fn produced_at(&self) -> Option<SystemTime> {
+        self.basic.as_ref().map(|b| b.produced_at)
+    }
+
+    /// The per-certificate responses.
+    #[must_use]
+    pub fn responses(&self) -> &[SingleResponse] {
+        self.basic.as_ref().map_or(&[], |b| &b.responses)
+    }
+
+    /// Finds the response about `certificate` issued by `issuer`.
+    #[must_use]
+    pub fn find(&self, certificate: &Parsed, issuer: &Parsed) -> Option<&SingleResponse> {
+        self.responses().iter().find(|r| r.cert_id.matches(certificate, issuer))
+    }
+
+    /// Response extensions.
+    #[must_use]
+    pub fn extensions(&self) -> &[Extension] {
+        self.basic.as_ref().map_or(&[], |b| &b.extensions)
+    }
+
+    /// The nonce echoed from the request, if any.
+    #[must_use]
+    pub fn nonce(&self) -> Option<&[u8]> {
+        let extension = self.extensions().iter().find(|e| *e.oid() == Oid::OCSP_NONCE)?;
+        let mut reader = Reader::new(extension.value());
+        let nonce = reader.read(der::OCTET_STRING).ok()?;
+        reader.finish().ok()?;
+        Some(nonce)
+    }
+
+    /// Certificates included by the responder, e.g. a delegated responder certificate.
+    #[must_use]
+    pub fn certificates(&self) -> &[Parsed] {
+        self.basic.as_ref().map_or(&[], |b| &b.certs)
+    }
+
+    /// Whether the response is successful and every response in it is fresh at `time`.
+    /// Use this to decide whether a cached response can still be stapled.
+    #[must_use]
+    pub fn is_fresh_at(&self, time: SystemTime) -> bool {
+        !self.responses().is_empty() && self.responses().iter().all(|r| r.is_fresh_at(time))
+    }
+
+    /// Checks that the response was signed by `issuer`, or by a responder certificate
+    /// included in the response that `issuer` issued for OCSP signing, and that was
+    /// valid when the response was produced.
+    ///
+    /// RSA and ECDSA P-256 and P-384 signatures are supported; others fail with `errSecUnimplemented`.
+    pub fn verify_signature(&self, issuer: &Parsed) -> Result<bool> {
+        let Some(basic) = &self.basic else { return Ok(false) };
+        let signer = if basic.responder_id.matches(issuer) {
+            issuer
+        } else {
+            let delegated = basic.certs.iter().find(|cert| basic.responder_id.matches(cert));
+            let Some(delegated) = delegated else { return Ok(false) };
+            if !is_authorized_responder(delegated, issuer, basic.produced_at)? {
+                return Ok(false);
+            }
+            delegated
+        };
+        signature::verify_signature(signer.public_key_info(), &basic.signature_algorithm, &basic.tbs_der, &basic.signature)
+    }
+
+    /// The whole DER-encoded response, e.g. for stapling.
+    #[inline]
+    #[must_use]
+    pub fn der(&self) -> &[u8] {
+        &self.der
+    }
+}
+
+/// Whether `responder` is a delegated responder of `issuer` at `time`
+fn is_authorized_responder(responder: &Parsed, issuer: &Parsed, time: SystemTime) -> Result<bool> {
+    Ok(responder.not_before() <= time
+        && time <= responder.not_after()
+        && responder.is_issued_by(issuer)
+        && responder.extended_key_usage()?.is_some_and(|usages| usages.contains(&Oid::OCSP_SIGNING))
+        && signature::verify_signature(issuer.public_key_info(), responder.signature_algorithm(), responder.tbs_certificate(), responder.signature())?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::der::unix_from_time;
+    use std::time::Duration;
+
+    fn cert(der: &[u8]) -> Parsed {
+        Parsed::from_der(der).unwrap()
+    }
+
+    fn fixtures() -> [Parsed; 5] {
+        [
+            cert(include_bytes!("../test/ocsp/ocsp_ca.der")),
+            cert(include_bytes!("../test/ocsp/good.der")),
+            cert(include_bytes!("../test/ocsp/revoked.der")),
+            cert(include_bytes!("../test/ocsp/unknown.der")),
+            cert(include_bytes!("../test/ocsp/responder.der")),
+        ]
+    }
+
+    #[test]
+    fn request() {
+        let [ca, good, revoked, unknown, _] = fixtures();
+        let request = OcspRequest::new(&good, &ca);
+        // openssl ocsp -issuer ocsp_ca.crt -cert good.crt -no_nonce -reqout request.der
+        assert_eq!(request.to_der(), include_bytes!("../test/ocsp/request.der"));
+        let id = &request.cert_ids()[0];
+        assert_eq!(id.hash_algorithm(), DigestAlgorithm::Sha1);
+        assert_eq!(hex::encode(id.issuer_name_hash()), "f9ab2e79e296c65a637ec634adf924c525e714b1");
+        assert_eq!(hex::encode(id.issuer_key_hash()), "aa5825a0f10869096893557811acabbe793d75b1");
+        assert!(id.matches(&good, &ca));
+        assert!(!id.matches(&revoked, &ca));
+
+        let mut request = OcspRequest::new(&good, &ca);
+        request
+            .add_cert_id(CertId::new(&revoked, &ca, DigestAlgorithm::Sha1))
+            .add_cert_id(CertId::new(&unknown, &ca, DigestAlgorithm::Sha1))
+            .set_nonce(&hex::decode("5211ed9ee6cf7b30b1c7428a94eefc3b").unwrap());
+        assert_eq!(request.to_der(), include_bytes!("../test/ocsp/request-all.der"));
+
+        let sha256 = CertId::new(&good, &ca, DigestAlgorithm::Sha256);
+        assert_eq!(sha256.issuer_key_hash().len(), 32);
+        assert!(sha256.matches(&good, &ca));
+        assert_ne!(sha256, *id);
+    }
+
+    #[test]
+    fn response() {
+        let [ca, good, revoked, unknown, responder] = fixtures();
+        let response = OcspResponse::parse(include_bytes!("../test/ocsp/response.der")).unwrap();
+        assert_eq!(response.status(), OcspResponseStatus::Successful);
+        assert_eq!(response.responder_id(), Some(&ResponderId::ByName(ca.subject().clone())));
+        assert_eq!(response.responses().len(), 3);
+        assert_eq!(response.nonce().map(hex::encode).as_deref(), Some("5211ed9ee6cf7b30b1c7428a94eefc3b"));
+        // openssl includes the signing certificate
+        assert_eq!(response.certificates(), std::slice::from_ref(&ca));
+
+        let produced_at = response.produced_at().unwrap();
+        let single = response.find(&good, &ca).unwrap();
+        assert_eq!(single.status(), CertStatus::Good);
+        assert_eq!(single.this_update(), produced_at);
+        assert_eq!(unix_from_time(single.next_update().unwrap()) - unix_from_time(produced_at), 7 * 86400);
+        assert_eq!(
+            response.find(&revoked, &ca).unwrap().status(),
+            CertStatus::Revoked { time: der::time_from_unix(1_704_067_200), reason: Some(RevocationReason::KeyCompromise) }
+        );
+        assert_eq!(response.find(&unknown, &ca).unwrap().status(), CertStatus::Unknown);
+        assert_eq!(response.find(&responder, &ca), None);
+
+        assert!(response.verify_signature(&ca).unwrap());
+        assert!(!response.verify_signature(&good).unwrap());
+        assert_eq!(response.der(), include_bytes!("../test/ocsp/response.der"));
+    }
+
+    #[test]
+    fn delegated() {
+        let [ca, good, _, _, responder] = fixtures();
+        let response = OcspResponse::parse(include_bytes!("../test/ocsp/response-delegated.der")).unwrap();
+        let Some(ResponderId::ByKey(hash)) = response.responder_id() else { panic!() };
+        assert_eq!(hex::encode(hash), "d705d074973fc7c8e9c8d6821db2f30ddee94146");
+        assert_eq!(response.certificates(), std::slice::from_ref(&responder));
+        assert_eq!(response.find(&good, &ca).unwrap().status(), CertStatus::Good);
+        assert_eq!(response.nonce(), None);
+        assert!(response.verify_signature(&ca).unwrap());
+
+        // a responder certificate that the issuer didn't sign isn't trusted
+        let other_ca = cert(include_bytes!("../test/ca.der"));
+        assert!(!response.verify_signature(&other_ca).unwrap());
+
+        // nor is one that had expired or wasn't valid yet when the response was produced
+        let produced_at = response.produced_at().unwrap();
+        assert!(is_authorized_responder(&responder, &ca, produced_at).unwrap());
+        assert!(!is_authorized_responder(&responder, &ca, responder.not_before() - Duration::from_secs(1)).unwrap());
+        assert!(!is_authorized_responder(&responder, &ca, responder.not_after() + Duration::from_secs(1)).unwrap());
+        assert!(!is_authorized_responder(&good, &ca, produced_at).unwrap());
+    }
+
+    #[test]
+    fn freshness() {
+        let response = OcspResponse::parse(include_bytes!("../test/ocsp/response.der")).unwrap();
+        let produced_at = response.produced_at().unwrap();
+        assert!(response.is_fresh_at(produced_at));
+        assert!(response.is_fresh_at(produced_at + Duration::from_secs(6 * 86400)));
+        assert!(!response.is_fresh_at(produced_at + Duration::from_secs(8 * 86400)));
+        assert!(!response.is_fresh_at(produced_at - Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn errors() {
+        // tryLater, without responseBytes
+        let response = OcspResponse::parse(&[0x30, 0x03, 0x0a, 0x01, 0x03]).unwrap();
+        assert_eq!(response.status(), OcspResponseStatus::TryLater);
+        assert!(response.responses().is_empty());
+        assert_eq!(response.produced_at(), None);
+        assert!(!response.is_fresh_at(SystemTime::now()));
+        assert!(!response.verify_signature(&fixtures()[0]).unwrap());
+
+        // successful, but without responseBytes
+        assert!(OcspResponse::parse(&[0x30, 0x03, 0x0a, 0x01, 0x00]).is_err());
+        // unassigned status
+        assert!(OcspResponse::parse(&[0x30, 0x03, 0x0a, 0x01, 0x04]).is_err());
+        assert!(OcspResponse::parse(include_bytes!("../test/ocsp/request.der")).is_err());
+    }
+}
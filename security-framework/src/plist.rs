@@ -0,0 +1,445 @@
+//! Property list values.
+//!
+//! [`Value`] mirrors the Core Foundation property list types in plain Rust, so that dictionaries
+//! returned by Security.framework can be decoded, and tested, without Core Foundation. Values
+//! can be read from and written to XML and binary property list files on all platforms.
+
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[cfg(target_vendor = "apple")]
+use core_foundation::array::CFArray;
+#[cfg(target_vendor = "apple")]
+use core_foundation::base::{CFType, TCFType};
+#[cfg(target_vendor = "apple")]
+use core_foundation::boolean::CFBoolean;
+#[cfg(target_vendor = "apple")]
+use core_foundation::data::CFData;
+#[cfg(target_vendor = "apple")]
+use core_foundation::date::CFDate;
+#[cfg(target_vendor = "apple")]
+use core_foundation::dictionary::CFDictionary;
+#[cfg(target_vendor = "apple")]
+use core_foundation::number::CFNumber;
+#[cfg(target_vendor = "apple")]
+use core_foundation::string::CFString;
+#[cfg(target_vendor = "apple")]
+use core_foundation_sys::number::CFNumberIsFloatType;
+use security_framework_sys::base::errSecDecode;
+
+use crate::base::{Error, Result};
+
+mod binary;
+mod xml;
+
+/// Seconds from the Unix epoch to the Core Foundation epoch, 2001-01-01
+const CF_EPOCH: u64 = 978_307_200;
+/// Nesting limit when reading files
+const MAX_DEPTH: usize = 512;
+
+/// A property list value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// `CFBoolean`.
+    Bool(bool),
+    /// An integer `CFNumber`.
+    Integer(i64),
+    /// A floating point `CFNumber`.
+    Real(f64),
+    /// `CFString`.
+    String(String),
+    /// `CFData`.
+    Data(Vec<u8>),
+    /// `CFDate`.
+    Date(SystemTime),
+    /// `CFArray`.
+    Array(Vec<Value>),
+    /// `CFDictionary` with string keys.
+    Dictionary(BTreeMap<String, Value>),
+}
+
+impl Value {
+    /// The boolean, if this is one.
+    #[must_use]
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Self::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// The integer, if this is one.
+    #[must_use]
+    pub fn as_integer(&self) -> Option<i64> {
+        match *self {
+            Self::Integer(i) => Some(i),
+            _ => None,
+        }
+    }
+
+    /// The number, if this is an integer or a real.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn as_real(&self) -> Option<f64> {
+        match *self {
+            Self::Real(r) => Some(r),
+            Self::Integer(i) => Some(i as f64),
+            _ => None,
+        }
+    }
+
+    /// The string, if this is one.
+    #[must_use]
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// The bytes, if this is data.
+    #[must_use]
+    pub fn as_data(&self) -> Option<&[u8]> {
+        match self {
+            Self::Data(d) => Some(d),
+            _ => None,
+        }
+    }
+
+    /// The date, if this is one.
+    #[must_use]
+    pub fn as_date(&self) -> Option<SystemTime> {
+        match *self {
+            Self::Date(d) => Some(d),
+            _ => None,
+        }
+    }
+
+    /// The elements, if this is an array.
+    #[must_use]
+    pub fn as_array(&self) -> Option<&[Self]> {
+        match self {
+            Self::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// The entries, if this is a dictionary.
+    #[must_use]
+    pub fn as_dictionary(&self) -> Option<&BTreeMap<String, Self>> {
+        match self {
+            Self::Dictionary(d) => Some(d),
+            _ => None,
+        }
+    }
+
+    /// Looks up `key` if this is a dictionary.
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&Self> {
+        self.as_dictionary()?.get(key)
+    }
+
+    /// Reads a property list file in either the XML or the binary format.
+    ///
+    /// Fails with `errSecDecode` if the file is malformed or uses types without a [`Value`]
+    /// equivalent, such as the keyed archiver's UIDs.
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.starts_with(binary::MAGIC) {
+            Self::from_binary(data)
+        } else {
+            Self::from_xml(data)
+        }
+    }
+
+    /// Reads an XML property list.
+    pub fn from_xml(xml: &[u8]) -> Result<Self> {
+        xml::read(xml)
+    }
+
+    /// Writes an XML property list, in the layout of `plutil -convert xml1`.
+    ///
+    /// Dates are written with whole seconds.
+    #[must_use]
+    pub fn to_xml(&self) -> String {
+        xml::write(self)
+    }
+
+    /// Reads a `bplist00` binary property list.
+    pub fn from_binary(data: &[u8]) -> Result<Self> {
+        binary::read(data)
+    }
+
+    /// Writes a `bplist00` binary property list.
+    #[must_use]
+    pub fn to_binary(&self) -> Vec<u8> {
+        binary::write(self)
+    }
+
+    /// Converts a Core Foundation property list object.
+    ///
+    /// Returns `None` for other types. Such elements of arrays and entries of dictionaries, and
+    /// dictionary entries with non-string keys, are skipped.
+    #[cfg(target_vendor = "apple")]
+    #[must_use]
+    pub fn from_cf(value: &CFType) -> Option<Self> {
+        if let Some(b) = value.downcast::<CFBoolean>() {
+            Some(Self::Bool(b.into()))
+        } else if let Some(n) = value.downcast::<CFNumber>() {
+            if unsafe { CFNumberIsFloatType(n.as_concrete_TypeRef()) } != 0 {
+                n.to_f64().map(Self::Real)
+            } else {
+                n.to_i64().map(Self::Integer)
+            }
+        } else if let Some(s) = value.downcast::<CFString>() {
+            Some(Self::String(s.to_string()))
+        } else if let Some(d) = value.downcast::<CFData>() {
+            Some(Self::Data(d.bytes().to_vec()))
+        } else if let Some(d) = value.downcast::<CFDate>() {
+            Some(Self::Date(time_from_cf_absolute(d.abs_time())))
+        } else if let Some(a) = value.downcast::<CFArray>() {
+            let a = unsafe { CFArray::<CFType>::wrap_under_get_rule(a.as_concrete_TypeRef()) };
+            Some(Self::Array(a.iter().filter_map(|v| Self::from_cf(&v)).collect()))
+        } else if let Some(d) = value.downcast::<CFDictionary>() {
+            let (keys, values) = d.get_keys_and_values();
+            let entries = keys.into_iter().zip(values).filter_map(|(k, v)| {
+                let k = unsafe { CFType::wrap_under_get_rule(k) }.downcast::<CFString>()?;
+                let v = Self::from_cf(&unsafe { CFType::wrap_under_get_rule(v) })?;
+                Some((k.to_string(), v))
+            });
+            Some(Self::Dictionary(entries.collect()))
+        } else {
+            None
+        }
+    }
+
+    /// Converts to a Core Foundation property list object.
+    #[cfg(target_vendor = "apple")]
+    #[must_use]
+    pub fn to_cf(&self) -> CFType {
+        match self {
+            Self::Bool(b) => CFBoolean::from(*b).into_CFType(),
+            Self::Integer(i) => CFNumber::from(*i).into_CFType(),
+            Self::Real(r) => CFNumber::from(*r).into_CFType(),
+            Self::String(s) => CFString::new(s).into_CFType(),
+            Self::Data(d) => CFData::from_buffer(d).into_CFType(),
+            Self::Date(d) => CFDate::new(cf_absolute_from_time(*d)).into_CFType(),
+            Self::Array(a) => CFArray::from_CFTypes(&a.iter().map(Self::to_cf).collect::<Vec<_>>()).into_CFType(),
+            Self::Dictionary(d) => {
+                let pairs: Vec<_> = d.iter().map(|(k, v)| (CFString::new(k).into_CFType(), v.to_cf())).collect();
+                CFDictionary::from_CFType_pairs(&pairs).into_CFType()
+            },
+        }
+    }
+}
+
+/// Converts seconds since 2001-01-01, as used by `CFDate` and binary property lists.
+pub(crate) fn time_from_cf_absolute(secs: f64) -> SystemTime {
+    let cf_epoch = UNIX_EPOCH + Duration::from_secs(CF_EPOCH);
+    if secs >= 0. {
+        cf_epoch + Duration::from_secs_f64(secs)
+    } else {
+        cf_epoch - Duration::from_secs_f64(-secs)
+    }
+}
+
+/// Converts to seconds since 2001-01-01.
+pub(crate) fn cf_absolute_from_time(time: SystemTime) -> f64 {
+    let cf_epoch = UNIX_EPOCH + Duration::from_secs(CF_EPOCH);
+    match time.duration_since(cf_epoch) {
+        Ok(d) => d.as_secs_f64(),
+        Err(e) => -e.duration().as_secs_f64(),
+    }
+}
+
+fn decode_error() -> Error {
+    Error::from_code(errSecDecode)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn accessors() {
+        let value = Value::Dictionary(BTreeMap::from([
+            ("flag".into(), Value::Bool(true)),
+            ("count".into(), Value::Integer(3)),
+            ("name".into(), Value::String("x".into())),
+            ("list".into(), Value::Array(vec![Value::Data(vec![1, 2])])),
+        ]));
+        assert_eq!(value.get("flag").and_then(Value::as_bool), Some(true));
+        assert_eq!(value.get("count").and_then(Value::as_integer), Some(3));
+        assert_eq!(value.get("count").and_then(Value::as_real), Some(3.));
+        assert_eq!(value.get("name").and_then(Value::as_str), Some("x"));
+        assert_eq!(value.get("list").and_then(Value::as_array).unwrap()[0].as_data(), Some(&[1, 2][..]));
+        assert_eq!(value.get("name").and_then(Value::as_bool), None);
+        assert_eq!(value.get("missing"), None);
+        assert_eq!(Value::Bool(false).get("flag"), None);
+    }
+
+    #[test]
+    fn cf_absolute_time() {
+        assert_eq!(time_from_cf_absolute(0.), UNIX_EPOCH + Duration::from_secs(CF_EPOCH));
+        assert_eq!(time_from_cf_absolute(-978_307_200.), UNIX_EPOCH);
+        assert_eq!(time_from_cf_absolute(1.5), UNIX_EPOCH + Duration::from_millis(CF_EPOCH * 1000 + 1500));
+        let time = UNIX_EPOCH + Duration::from_millis(1_704_067_200_500);
+        assert_eq!(time_from_cf_absolute(cf_absolute_from_time(time)), time);
+    }
+
+    fn sample() -> Value {
+        Value::Dictionary(BTreeMap::from([
+            (
+                "a".into(),
+                Value::Array(vec![
+                    Value::Bool(true),
+                    Value::Integer(-1),
+                    Value::Integer(300),
+                    Value::Real(0.5),
+                    Value::String("\u{e9}".into()),
+                ]),
+            ),
+            ("d".into(), Value::Data(vec![1, 2])),
+            ("t".into(), Value::Date(UNIX_EPOCH + Duration::from_secs(1_704_067_200))),
+        ]))
+    }
+
+    const SAMPLE_XML: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">
+<plist version=\"1.0\">
+<dict>
+\t<key>a</key>
+\t<array>
+\t\t<true/>
+\t\t<integer>-1</integer>
+\t\t<integer>300</integer>
+\t\t<real>0.5</real>
+\t\t<string>\u{e9}</string>
+\t</array>
+\t<key>d</key>
+\t<data>
+\tAQI=
+\t</data>
+\t<key>t</key>
+\t<date>2024-01-01T00:00:00Z</date>
+</dict>
+</plist>
+";
+
+    #[test]
+    fn xml() {
+        assert_eq!(sample().to_xml(), SAMPLE_XML);
+        assert_eq!(Value::from_xml(SAMPLE_XML.as_bytes()).unwrap(), sample());
+        assert_eq!(Value::parse(SAMPLE_XML.as_bytes()).unwrap(), sample());
+
+        let value = Value::Array(vec![
+            Value::String("<a & b>".into()),
+            Value::String(String::new()),
+            Value::Data(vec![0xab; 100]),
+            Value::Array(vec![]),
+            Value::Dictionary(BTreeMap::new()),
+            Value::Real(-2.),
+            Value::Integer(i64::MIN),
+            Value::Bool(false),
+            Value::Date(UNIX_EPOCH - Duration::from_secs(86_401)),
+        ]);
+        assert_eq!(Value::from_xml(value.to_xml().as_bytes()).unwrap(), value);
+    }
+
+    #[test]
+    fn xml_hand_written() {
+        let xml = "<?xml version='1.0'?><!-- generated -->
+            <plist version='1.0'><dict>
+              <key>s</key><string>a &amp;&#x20;b<!-- c --><![CDATA[<d>]]></string>
+              <key>i</key><integer> 0x10 </integer>
+              <key>e</key><string/>
+              <key>f</key><false></false>
+            </dict></plist>";
+        assert_eq!(
+            Value::from_xml(xml.as_bytes()).unwrap(),
+            Value::Dictionary(BTreeMap::from([
+                ("s".into(), Value::String("a & b<d>".into())),
+                ("i".into(), Value::Integer(16)),
+                ("e".into(), Value::String(String::new())),
+                ("f".into(), Value::Bool(false)),
+            ]))
+        );
+        assert_eq!(Value::from_xml(b"<integer>7</integer>").unwrap(), Value::Integer(7));
+
+        for xml in [
+            "<plist><dict><key>a</key></dict></plist>",
+            "<plist><integer>x</integer></plist>",
+            "<plist><string>a</plist>",
+            "<plist><true/><true/></plist>",
+            "<plist><uid>1</uid></plist>",
+            "<plist><date>2024-13-01T00:00:00Z</date></plist>",
+            "<plist><string>&bogus;</string></plist>",
+            "<plist><integer>--170141183460469231731687303715884105728</integer></plist>",
+            "<plist><integer>-0x-80000000000000000000000000000000</integer></plist>",
+            "<plist><integer>+-1</integer></plist>",
+            "<plist><integer>0x</integer></plist>",
+        ] {
+            assert!(Value::from_xml(xml.as_bytes()).is_err(), "{xml}");
+        }
+        let nested = "<array>".repeat(MAX_DEPTH + 2);
+        assert!(Value::from_xml(nested.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn binary() {
+        // written by Python's plistlib, which shares the `true` object
+        let data = hex::decode(
+            "62706c6973743030d3010203040a0b516151645174a505060708090913ffffffffffffffff11012c233fe0000000000000\
+             6100e94201023341c5a11c00000000080f1113151b1c25283134370000000000000101000000000000000c0000000000\
+             0000000000000000000040",
+        )
+        .unwrap();
+        assert_eq!(Value::from_binary(&data).unwrap(), sample());
+        assert_eq!(Value::parse(&data).unwrap(), sample());
+
+        let binary = sample().to_binary();
+        assert!(binary.starts_with(b"bplist00"));
+        assert_eq!(Value::parse(&binary).unwrap(), sample());
+
+        let value = Value::Array(vec![
+            Value::Integer(i64::MAX),
+            Value::Integer(-70_000),
+            Value::String("x".repeat(300)),
+            Value::Array((0..300).map(Value::Integer).collect()),
+            Value::Dictionary(BTreeMap::from([("\u{1f600}".into(), Value::Real(-0.25))])),
+            Value::Date(UNIX_EPOCH + Duration::from_millis(500)),
+        ]);
+        assert_eq!(Value::from_binary(&value.to_binary()).unwrap(), value);
+    }
+
+    #[test]
+    fn binary_malformed() {
+        let data = sample().to_binary();
+        assert!(Value::from_binary(&data[..data.len() - 1]).is_err());
+        assert!(Value::from_binary(b"bplist00").is_err());
+
+        // an array containing itself
+        let mut cycle = b"bplist00".to_vec();
+        cycle.extend_from_slice(&[0xa1, 0x00, 0x08]);
+        cycle.extend_from_slice(&[0, 0, 0, 0, 0, 0, 1, 1]);
+        cycle.extend_from_slice(&1u64.to_be_bytes());
+        cycle.extend_from_slice(&0u64.to_be_bytes());
+        cycle.extend_from_slice(&10u64.to_be_bytes());
+        assert!(Value::from_binary(&cycle).is_err());
+        cycle[8] = 0x08;
+        assert_eq!(Value::from_binary(&cycle).unwrap(), Value::Bool(false));
+    }
+
+    #[test]
+    #[cfg(target_vendor = "apple")]
+    fn cf_round_trip() {
+        let value = Value::Dictionary(BTreeMap::from([
+            ("b".into(), Value::Bool(true)),
+            ("i".into(), Value::Integer(-3)),
+            ("r".into(), Value::Real(0.5)),
+            ("d".into(), Value::Data(vec![1, 2])),
+            ("t".into(), Value::Date(UNIX_EPOCH + Duration::from_secs(CF_EPOCH + 10))),
+            ("a".into(), Value::Array(vec![Value::String("s".into())])),
+        ]));
+        assert_eq!(Value::from_cf(&value.to_cf()), Some(value));
+    }
+}
@@ -0,0 +1,306 @@
+//! The binary property list format, `bplist00`.
+
+use std::collections::BTreeMap;
+
+use super::{cf_absolute_from_time, decode_error, time_from_cf_absolute, Value, MAX_DEPTH};
+use crate::base::Result;
+
+pub(super) const MAGIC: &[u8] = b"bplist00";
+const TRAILER_LEN: usize = 32;
+/// Upper bound on decoded objects, since shared references can expand exponentially
+const MAX_OBJECTS: usize = 1 << 20;
+
+pub(super) fn read(data: &[u8]) -> Result<Value> {
+    if !data.starts_with(MAGIC) || data.len() < MAGIC.len() + 1 + TRAILER_LEN {
+        return Err(decode_error());
+    }
+    let trailer = &data[data.len() - TRAILER_LEN..];
+    let offset_size = usize::from(trailer[6]);
+    let ref_size = usize::from(trailer[7]);
+    let num_objects = to_usize(read_uint(&trailer[8..16]))?;
+    let top = to_usize(read_uint(&trailer[16..24]))?;
+    let table = to_usize(read_uint(&trailer[24..32]))?;
+
+    let table_end = num_objects.checked_mul(offset_size).and_then(|len| len.checked_add(table));
+    if !(1..=8).contains(&offset_size)
+        || !(1..=8).contains(&ref_size)
+        || top >= num_objects
+        || table < MAGIC.len()
+        || table_end.is_none_or(|end| end > data.len() - TRAILER_LEN)
+    {
+        return Err(decode_error());
+    }
+    let mut reader = Reader { data, offset_size, ref_size, table, num_objects, stack: Vec::new(), budget: MAX_OBJECTS };
+    reader.object(top)
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset_size: usize,
+    ref_size: usize,
+    table: usize,
+    num_objects: usize,
+    /// Containers being decoded, to detect cycles
+    stack: Vec<usize>,
+    budget: usize,
+}
+
+impl Reader<'_> {
+    fn bytes(&self, start: usize, len: usize) -> Result<&[u8]> {
+        let end = start.checked_add(len).ok_or_else(decode_error)?;
+        // objects can't overlap the offset table
+        self.data.get(start..end).filter(|_| end <= self.table).ok_or_else(decode_error)
+    }
+
+    fn object(&mut self, index: usize) -> Result<Value> {
+        if index >= self.num_objects || self.stack.contains(&index) || self.stack.len() > MAX_DEPTH {
+            return Err(decode_error());
+        }
+        self.budget = self.budget.checked_sub(1).ok_or_else(decode_error)?;
+        let entry = self.table + index * self.offset_size;
+        let offset = to_usize(read_uint(&self.data[entry..entry + self.offset_size]))?;
+        let marker = *self.bytes(offset, 1)?.first().ok_or_else(decode_error)?;
+        let info = marker & 0x0f;
+        Ok(match marker >> 4 {
+            0x0 => match info {
+                0x8 => Value::Bool(false),
+                0x9 => Value::Bool(true),
+                _ => return Err(decode_error()),
+            },
+            0x1 => Value::Integer(self.integer(offset)?.0),
+            0x2 => {
+                let bytes = self.bytes(offset + 1, 1 << info)?;
+                Value::Real(match bytes.len() {
+                    4 => f32::from_be_bytes(bytes.try_into().map_err(|_| decode_error())?).into(),
+                    8 => f64::from_be_bytes(bytes.try_into().map_err(|_| decode_error())?),
+                    _ => return Err(decode_error()),
+                })
+            },
+            0x3 if info == 3 => {
+                let bytes = self.bytes(offset + 1, 8)?.try_into().map_err(|_| decode_error())?;
+                let secs = f64::from_be_bytes(bytes);
+                if !secs.is_finite() || secs.abs() > 1e15 {
+                    return Err(decode_error());
+                }
+                Value::Date(time_from_cf_absolute(secs))
+            },
+            0x4 => {
+                let (len, start) = self.length(offset)?;
+                Value::Data(self.bytes(start, len)?.to_vec())
+            },
+            0x5 => {
+                let (len, start) = self.length(offset)?;
+                let bytes = self.bytes(start, len)?;
+                if !bytes.is_ascii() {
+                    return Err(decode_error());
+                }
+                Value::String(String::from_utf8_lossy(bytes).into_owned())
+            },
+            0x6 => {
+                let (len, start) = self.length(offset)?;
+                let bytes = self.bytes(start, len.checked_mul(2).ok_or_else(decode_error)?)?;
+                let units = bytes.chunks_exact(2).map(|unit| u16::from_be_bytes([unit[0], unit[1]]));
+                Value::String(char::decode_utf16(units).collect::<Result<_, _>>().map_err(|_| decode_error())?)
+            },
+            0xa => {
+                let refs = self.refs(offset, 1)?;
+                self.stack.push(index);
+                let array = refs.into_iter().map(|r| self.object(r)).collect::<Result<_>>()?;
+                self.stack.pop();
+                Value::Array(array)
+            },
+            0xd => {
+                let refs = self.refs(offset, 2)?;
+                let (keys, values) = refs.split_at(refs.len() / 2);
+                self.stack.push(index);
+                let mut dictionary = BTreeMap::new();
+                for (&key, &value) in keys.iter().zip(values) {
+                    let Value::String(key) = self.object(key)? else {
+                        return Err(decode_error());
+                    };
+                    dictionary.insert(key, self.object(value)?);
+                }
+                self.stack.pop();
+                Value::Dictionary(dictionary)
+            },
+            // null, fill, UIDs and sets have no property list equivalent
+            _ => return Err(decode_error()),
+        })
+    }
+
+    /// Decodes the integer object at `offset`, returning it and the offset following it.
+    fn integer(&self, offset: usize) -> Result<(i64, usize)> {
+        let marker = self.bytes(offset, 1)?[0];
+        if marker >> 4 != 0x1 {
+            return Err(decode_error());
+        }
+        let len = 1usize << (marker & 0x0f).min(5);
+        let bytes = self.bytes(offset + 1, len)?;
+        let value = match len {
+            1 | 2 | 4 => i64::try_from(read_uint(bytes)).map_err(|_| decode_error())?,
+            8 => i64::from_be_bytes(bytes.try_into().map_err(|_| decode_error())?),
+            16 => {
+                let value = i128::from_be_bytes(bytes.try_into().map_err(|_| decode_error())?);
+                i64::try_from(value).map_err(|_| decode_error())?
+            },
+            _ => return Err(decode_error()),
+        };
+        Ok((value, offset + 1 + len))
+    }
+
+    /// The element count of the object at `offset`, and the offset of its contents.
+    fn length(&self, offset: usize) -> Result<(usize, usize)> {
+        match self.bytes(offset, 1)?[0] & 0x0f {
+            0xf => {
+                let (len, start) = self.integer(offset + 1)?;
+                Ok((usize::try_from(len).map_err(|_| decode_error())?, start))
+            },
+            len => Ok((usize::from(len), offset + 1)),
+        }
+    }
+
+    /// Reads `per_element` object references for each element of the container at `offset`.
+    fn refs(&self, offset: usize, per_element: usize) -> Result<Vec<usize>> {
+        let (len, start) = self.length(offset)?;
+        let count = len.checked_mul(per_element).ok_or_else(decode_error)?;
+        let bytes = self.bytes(start, count.checked_mul(self.ref_size).ok_or_else(decode_error)?)?;
+        bytes.chunks_exact(self.ref_size).map(|r| to_usize(read_uint(r))).collect()
+    }
+}
+
+fn read_uint(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0, |value, &b| (value << 8) | u64::from(b))
+}
+
+fn to_usize(value: u64) -> Result<usize> {
+    usize::try_from(value).map_err(|_| decode_error())
+}
+
+pub(super) fn write(value: &Value) -> Vec<u8> {
+    let num_objects = count(value);
+    let mut writer = Writer { ref_size: uint_size(num_objects as u64), objects: Vec::with_capacity(num_objects) };
+    writer.object(value);
+
+    let mut out = MAGIC.to_vec();
+    let mut offsets = Vec::with_capacity(num_objects);
+    for object in &writer.objects {
+        offsets.push(out.len() as u64);
+        out.extend_from_slice(object);
+    }
+    let table = out.len() as u64;
+    let offset_size = uint_size(offsets.last().copied().unwrap_or_default());
+    for offset in offsets {
+        push_uint(&mut out, offset, offset_size);
+    }
+    out.extend_from_slice(&[0; 6]);
+    out.push(offset_size as u8);
+    out.push(writer.ref_size as u8);
+    out.extend_from_slice(&(num_objects as u64).to_be_bytes());
+    out.extend_from_slice(&0u64.to_be_bytes());
+    out.extend_from_slice(&table.to_be_bytes());
+    out
+}
+
+/// The number of objects `value` is written as, with every dictionary key a separate object.
+fn count(value: &Value) -> usize {
+    match value {
+        Value::Array(a) => 1 + a.iter().map(count).sum::<usize>(),
+        Value::Dictionary(d) => 1 + d.values().map(|v| 1 + count(v)).sum::<usize>(),
+        _ => 1,
+    }
+}
+
+/// The number of bytes needed to store `value`: 1, 2, 4 or 8.
+fn uint_size(value: u64) -> usize {
+    match value {
+        0..=0xff => 1,
+        0x100..=0xffff => 2,
+        0x1_0000..=0xffff_ffff => 4,
+        _ => 8,
+    }
+}
+
+fn push_uint(out: &mut Vec<u8>, value: u64, size: usize) {
+    out.extend_from_slice(&value.to_be_bytes()[8 - size..]);
+}
+
+struct Writer {
+    ref_size: usize,
+    objects: Vec<Vec<u8>>,
+}
+
+impl Writer {
+    /// Appends `value` and its children, returning its index.
+    fn object(&mut self, value: &Value) -> usize {
+        let index = self.objects.len();
+        self.objects.push(Vec::new());
+        let mut out = Vec::new();
+        match value {
+            Value::Bool(b) => out.push(if *b { 0x09 } else { 0x08 }),
+            Value::Integer(i) => push_integer(&mut out, *i),
+            Value::Real(r) => {
+                out.push(0x23);
+                out.extend_from_slice(&r.to_be_bytes());
+            },
+            Value::Date(d) => {
+                out.push(0x33);
+                out.extend_from_slice(&cf_absolute_from_time(*d).to_be_bytes());
+            },
+            Value::Data(d) => {
+                push_header(&mut out, 0x40, d.len());
+                out.extend_from_slice(d);
+            },
+            Value::String(s) => push_string(&mut out, s),
+            Value::Array(a) => {
+                let refs: Vec<_> = a.iter().map(|element| self.object(element)).collect();
+                push_header(&mut out, 0xa0, refs.len());
+                self.push_refs(&mut out, &refs);
+            },
+            Value::Dictionary(d) => {
+                let keys: Vec<_> = d.keys().map(|key| self.object(&Value::String(key.clone()))).collect();
+                let values: Vec<_> = d.values().map(|value| self.object(value)).collect();
+                push_header(&mut out, 0xd0, keys.len());
+                self.push_refs(&mut out, &keys);
+                self.push_refs(&mut out, &values);
+            },
+        }
+        self.objects[index] = out;
+        index
+    }
+
+    fn push_refs(&self, out: &mut Vec<u8>, refs: &[usize]) {
+        for &r in refs {
+            push_uint(out, r as u64, self.ref_size);
+        }
+    }
+}
+
+fn push_integer(out: &mut Vec<u8>, value: i64) {
+    // only 8-byte integers are signed
+    let size = u64::try_from(value).map_or(8, uint_size);
+    out.push(0x10 | size.trailing_zeros() as u8);
+    push_uint(out, u64::from_ne_bytes(value.to_ne_bytes()), size);
+}
+
+/// The marker with the element count, which is followed by an integer object if it doesn't fit.
+fn push_header(out: &mut Vec<u8>, marker: u8, len: usize) {
+    if len < 0xf {
+        out.push(marker | len as u8);
+    } else {
+        out.push(marker | 0xf);
+        push_integer(out, len as i64);
+    }
+}
+
+fn push_string(out: &mut Vec<u8>, s: &str) {
+    if s.is_ascii() {
+        push_header(out, 0x50, s.len());
+        out.extend_from_slice(s.as_bytes());
+    } else {
+        let units: Vec<u16> = s.encode_utf16().collect();
+        push_header(out, 0x60, units.len());
+        for unit in units {
+            out.extend_from_slice(&unit.to_be_bytes());
+        }
+    }
+}
@@ -0,0 +1,327 @@
+//! The XML property list format, as written by `plutil -convert xml1`.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use super::{decode_error, Value, MAX_DEPTH};
+use crate::base::Result;
+use crate::der::{civil_from_days, days_from_civil, time_from_unix, unix_from_time};
+
+const HEADER: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+    <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+    <plist version=\"1.0\">\n";
+
+pub(super) fn write(value: &Value) -> String {
+    let mut out = String::from(HEADER);
+    write_value(&mut out, value, 0);
+    out.push_str("</plist>\n");
+    out
+}
+
+fn write_value(out: &mut String, value: &Value, depth: usize) {
+    let indent = "\t".repeat(depth);
+    out.push_str(&indent);
+    match value {
+        Value::Bool(true) => out.push_str("<true/>"),
+        Value::Bool(false) => out.push_str("<false/>"),
+        Value::Integer(i) => {
+            let _ = write!(out, "<integer>{i}</integer>");
+        },
+        Value::Real(r) if r.is_nan() => out.push_str("<real>nan</real>"),
+        Value::Real(r) if r.is_infinite() => {
+            let _ = write!(out, "<real>{}infinity</real>", if *r > 0. { '+' } else { '-' });
+        },
+        Value::Real(r) => {
+            let _ = write!(out, "<real>{r}</real>");
+        },
+        Value::String(s) => {
+            let _ = write!(out, "<string>{}</string>", escape(s));
+        },
+        Value::Data(d) if d.is_empty() => out.push_str("<data></data>"),
+        Value::Data(d) => {
+            out.push_str("<data>\n");
+            let encoded = crate::base64::encode(d);
+            for line in encoded.as_bytes().chunks(68) {
+                out.push_str(&indent);
+                out.push_str(std::str::from_utf8(line).unwrap_or_default());
+                out.push('\n');
+            }
+            out.push_str(&indent);
+            out.push_str("</data>");
+        },
+        Value::Date(d) => {
+            let secs = unix_from_time(*d);
+            let (year, month, day) = civil_from_days(secs.div_euclid(86400));
+            let secs = secs.rem_euclid(86400);
+            let _ = write!(
+                out,
+                "<date>{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z</date>",
+                secs / 3600,
+                secs / 60 % 60,
+                secs % 60
+            );
+        },
+        Value::Array(a) if a.is_empty() => out.push_str("<array/>"),
+        Value::Array(a) => {
+            out.push_str("<array>\n");
+            for element in a {
+                write_value(out, element, depth + 1);
+            }
+            out.push_str(&indent);
+            out.push_str("</array>");
+        },
+        Value::Dictionary(d) if d.is_empty() => out.push_str("<dict/>"),
+        Value::Dictionary(d) => {
+            out.push_str("<dict>\n");
+            for (key, value) in d {
+                let _ = writeln!(out, "{indent}\t<key>{}</key>", escape(key));
+                write_value(out, value, depth + 1);
+            }
+            out.push_str(&indent);
+            out.push_str("</dict>");
+        },
+    }
+    out.push('\n');
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+pub(super) fn read(xml: &[u8]) -> Result<Value> {
+    let input = std::str::from_utf8(xml).map_err(|_| decode_error())?;
+    let mut reader = Reader { input: input.strip_prefix('\u{feff}').unwrap_or(input), pos: 0 };
+    let mut tag = reader.next_tag()?;
+    // the <plist> wrapper is optional
+    let wrapped = matches!(tag, Tag::Start("plist", false));
+    if wrapped {
+        tag = reader.next_tag()?;
+    }
+    let value = reader.value(tag, 0)?;
+    if wrapped && !matches!(reader.next_tag()?, Tag::End("plist")) {
+        return Err(decode_error());
+    }
+    reader.skip_misc()?;
+    if reader.pos != reader.input.len() {
+        return Err(decode_error());
+    }
+    Ok(value)
+}
+
+#[derive(Debug)]
+enum Tag<'a> {
+    /// An opening tag, and whether it is also closing, as in `<true/>`
+    Start(&'a str, bool),
+    End(&'a str),
+}
+
+struct Reader<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    /// Skips whitespace, comments, processing instructions and the document type declaration.
+    fn skip_misc(&mut self) -> Result<()> {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+            let end = if trimmed.starts_with("<!--") {
+                "-->"
+            } else if trimmed.starts_with("<?") {
+                "?>"
+            } else if trimmed.starts_with("<!DOCTYPE") {
+                ">"
+            } else {
+                return Ok(());
+            };
+            self.pos += trimmed.find(end).ok_or_else(decode_error)? + end.len();
+        }
+    }
+
+    fn next_tag(&mut self) -> Result<Tag<'a>> {
+        self.skip_misc()?;
+        let rest = self.rest().strip_prefix('<').ok_or_else(decode_error)?;
+        let close = find_tag_end(rest)?;
+        self.pos += close + 2;
+        let inner = &rest[..close];
+        if let Some(name) = inner.strip_prefix('/') {
+            return Ok(Tag::End(name.trim_end()));
+        }
+        let (inner, empty) = match inner.strip_suffix('/') {
+            Some(inner) => (inner, true),
+            None => (inner, false),
+        };
+        let name = inner.split(|c: char| c.is_ascii_whitespace()).next().unwrap_or_default();
+        if name.is_empty() {
+            return Err(decode_error());
+        }
+        Ok(Tag::Start(name, empty))
+    }
+
+    /// Reads character data up to the closing tag of `name`, resolving entities.
+    fn text(&mut self, name: &str) -> Result<String> {
+        let mut text = String::new();
+        loop {
+            let rest = self.rest();
+            let next = rest.find(['<', '&']).ok_or_else(decode_error)?;
+            text.push_str(&rest[..next]);
+            self.pos += next;
+            let rest = self.rest();
+            if let Some(entity) = rest.strip_prefix('&') {
+                let end = entity.find(';').ok_or_else(decode_error)?;
+                text.push(resolve_entity(&entity[..end])?);
+                self.pos += end + 2;
+            } else if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+                let end = cdata.find("]]>").ok_or_else(decode_error)?;
+                text.push_str(&cdata[..end]);
+                self.pos += "<![CDATA[".len() + end + 3;
+            } else if rest.starts_with("<!--") {
+                self.pos += rest.find("-->").ok_or_else(decode_error)? + 3;
+            } else {
+                return match self.next_tag()? {
+                    Tag::End(end) if end == name => Ok(text),
+                    _ => Err(decode_error()),
+                };
+            }
+        }
+    }
+
+    fn value(&mut self, tag: Tag<'a>, depth: usize) -> Result<Value> {
+        let Tag::Start(name, empty) = tag else {
+            return Err(decode_error());
+        };
+        if depth > MAX_DEPTH {
+            return Err(decode_error());
+        }
+        let text = |reader: &mut Self| if empty { Ok(String::new()) } else { reader.text(name) };
+        Ok(match name {
+            "true" | "false" => {
+                if !text(self)?.trim().is_empty() {
+                    return Err(decode_error());
+                }
+                Value::Bool(name == "true")
+            },
+            "integer" => Value::Integer(parse_integer(text(self)?.trim()).ok_or_else(decode_error)?),
+            "real" => Value::Real(parse_real(text(self)?.trim()).ok_or_else(decode_error)?),
+            "string" => Value::String(text(self)?),
+            "data" => Value::Data(crate::base64::decode(text(self)?.as_bytes()).ok_or_else(decode_error)?),
+            "date" => Value::Date(parse_date(text(self)?.trim()).ok_or_else(decode_error)?),
+            "array" => {
+                let mut array = Vec::new();
+                if !empty {
+                    loop {
+                        match self.next_tag()? {
+                            Tag::End("array") => break,
+                            tag => array.push(self.value(tag, depth + 1)?),
+                        }
+                    }
+                }
+                Value::Array(array)
+            },
+            "dict" => {
+                let mut dictionary = BTreeMap::new();
+                if !empty {
+                    loop {
+                        let key = match self.next_tag()? {
+                            Tag::End("dict") => break,
+                            Tag::Start("key", false) => self.text("key")?,
+                            Tag::Start("key", true) => String::new(),
+                            _ => return Err(decode_error()),
+                        };
+                        let tag = self.next_tag()?;
+                        dictionary.insert(key, self.value(tag, depth + 1)?);
+                    }
+                }
+                Value::Dictionary(dictionary)
+            },
+            _ => return Err(decode_error()),
+        })
+    }
+}
+
+/// The position of the `>` ending a tag, skipping quoted attribute values.
+fn find_tag_end(tag: &str) -> Result<usize> {
+    let mut quote = None;
+    for (i, c) in tag.char_indices() {
+        match (quote, c) {
+            (None, '>') => return Ok(i),
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            _ => {},
+        }
+    }
+    Err(decode_error())
+}
+
+fn resolve_entity(entity: &str) -> Result<char> {
+    Ok(match entity {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        _ => {
+            let code = if let Some(hex) = entity.strip_prefix("#x") {
+                u32::from_str_radix(hex, 16)
+            } else if let Some(decimal) = entity.strip_prefix('#') {
+                decimal.parse()
+            } else {
+                return Err(decode_error());
+            };
+            code.ok().and_then(char::from_u32).ok_or_else(decode_error)?
+        },
+    })
+}
+
+fn parse_integer(text: &str) -> Option<i64> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    // a second sign would be accepted by the parsers below
+    let magnitude = match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        Some(hex) if !hex.is_empty() && hex.bytes().all(|b| b.is_ascii_hexdigit()) => i128::from_str_radix(hex, 16).ok()?,
+        None if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) => digits.parse::<i128>().ok()?,
+        _ => return None,
+    };
+    i64::try_from(if negative { magnitude.checked_neg()? } else { magnitude }).ok()
+}
+
+fn parse_real(text: &str) -> Option<f64> {
+    match text.to_ascii_lowercase().as_str() {
+        "nan" => Some(f64::NAN),
+        "inf" | "infinity" | "+infinity" => Some(f64::INFINITY),
+        "-inf" | "-infinity" => Some(f64::NEG_INFINITY),
+        text => text.parse().ok(),
+    }
+}
+
+/// Parses the `YYYY-MM-DDTHH:MM:SSZ` form.
+fn parse_date(text: &str) -> Option<std::time::SystemTime> {
+    let text = text.strip_suffix('Z')?;
+    let (date, time) = text.split_once('T')?;
+    let mut date = date.splitn(3, '-').map(str::parse::<u32>);
+    let (year, month, day) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
+    let mut time = time.splitn(3, ':').map(str::parse::<u32>);
+    let (hour, minute, second) = (time.next()?.ok()?, time.next()?.ok()?, time.next()?.ok()?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    let days = days_from_civil(i64::from(year), month, day);
+    Some(time_from_unix(days * 86400 + i64::from(hour * 3600 + minute * 60 + second)))
+}
@@ -1,24 +1,48 @@
 //! Security Policies support.
-use core_foundation::base::{CFOptionFlags, TCFType};
+//!
+//! [`RevocationPolicy`] and [`PolicyProperties`] are pure Rust and available on all platforms.
+use core_foundation::base::CFOptionFlags;
+#[cfg(target_vendor = "apple")]
+use core_foundation::base::{CFType, TCFType};
+#[cfg(target_vendor = "apple")]
 use core_foundation::string::CFString;
+#[cfg(target_vendor = "apple")]
 use core_foundation::{declare_TCFType, impl_TCFType};
-use security_framework_sys::base::{errSecParam, SecPolicyRef};
+#[cfg(target_vendor = "apple")]
+use security_framework_sys::base::{errSecDecode, errSecParam, SecPolicyRef};
 use security_framework_sys::policy::*;
+#[cfg(target_vendor = "apple")]
+use std::collections::BTreeMap;
+#[cfg(target_vendor = "apple")]
 use std::fmt;
+#[cfg(target_vendor = "apple")]
 use std::ptr;
 
+#[cfg(target_vendor = "apple")]
+use crate::plist::Value;
+#[cfg(target_vendor = "apple")]
 use crate::secure_transport::SslProtocolSide;
+#[cfg(target_vendor = "apple")]
 use crate::Error;
 
+mod properties;
+
+pub use properties::{PolicyKind, PolicyProperties};
+
+#[cfg(target_vendor = "apple")]
 declare_TCFType! {
     /// A type representing a certificate validation policy.
     SecPolicy, SecPolicyRef
 }
+#[cfg(target_vendor = "apple")]
 impl_TCFType!(SecPolicy, SecPolicyRef, SecPolicyGetTypeID);
 
+#[cfg(target_vendor = "apple")]
 unsafe impl Sync for SecPolicy {}
+#[cfg(target_vendor = "apple")]
 unsafe impl Send for SecPolicy {}
 
+#[cfg(target_vendor = "apple")]
 impl fmt::Debug for SecPolicy {
     #[cold]
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -45,6 +69,7 @@ bitflags::bitflags! {
     }
 }
 
+#[cfg(target_vendor = "apple")]
 impl SecPolicy {
     /// Creates a `SecPolicy` for evaluating SSL certificate chains.
     ///
@@ -85,15 +110,122 @@ impl SecPolicy {
             Self::wrap_under_create_rule(policy)
         }
     }
+
+    /// Creates a policy of any kind from its typed properties.
+    pub fn create_with_properties(properties: &PolicyProperties) -> crate::Result<Self> {
+        Self::create_with_oid(properties.kind().oid(), properties.to_dictionary()?)
+    }
+
+    pub(crate) fn create_with_oid(oid: &str, properties: BTreeMap<String, Value>) -> crate::Result<Self> {
+        let properties = Value::Dictionary(properties).to_cf();
+        let oid = CFString::new(oid);
+        let policy = unsafe { SecPolicyCreateWithProperties(oid.as_CFTypeRef(), properties.as_CFTypeRef().cast()) };
+
+        if policy.is_null() {
+            Err(Error::from_code(errSecParam))
+        } else {
+            Ok(unsafe { Self::wrap_under_create_rule(policy) })
+        }
+    }
+
+    /// Creates a `SecPolicy` for evaluating S/MIME certificates, optionally for a specific email address.
+    pub fn create_smime(email: Option<&str>) -> crate::Result<Self> {
+        Self::create_with_properties(PolicyProperties::new(PolicyKind::Smime).set_name(email))
+    }
+
+    /// Creates a `SecPolicy` for evaluating EAP (802.1X) certificates.
+    ///
+    /// As with [`create_ssl`](Self::create_ssl), pass `SslProtocolSide::SERVER` to evaluate a server's certificate.
+    pub fn create_eap(protocol_side: SslProtocolSide, name: Option<&str>) -> crate::Result<Self> {
+        let client = protocol_side != SslProtocolSide::SERVER;
+        Self::create_with_properties(PolicyProperties::new(PolicyKind::Eap).set_name(name).set_client(client))
+    }
+
+    /// Creates a `SecPolicy` for evaluating IPsec certificates.
+    ///
+    /// As with [`create_ssl`](Self::create_ssl), pass `SslProtocolSide::SERVER` to evaluate a server's certificate.
+    pub fn create_ipsec(protocol_side: SslProtocolSide, hostname: Option<&str>) -> crate::Result<Self> {
+        let client = protocol_side != SslProtocolSide::SERVER;
+        Self::create_with_properties(PolicyProperties::new(PolicyKind::Ipsec).set_name(hostname).set_client(client))
+    }
+
+    /// Creates a `SecPolicy` for evaluating code signing certificates.
+    pub fn create_code_signing() -> crate::Result<Self> {
+        Self::create_with_properties(&PolicyProperties::new(PolicyKind::CodeSigning))
+    }
+
+    /// Creates a `SecPolicy` for evaluating RFC 3161 timestamping certificates.
+    pub fn create_time_stamping() -> crate::Result<Self> {
+        Self::create_with_properties(&PolicyProperties::new(PolicyKind::TimeStamping))
+    }
+
+    /// Creates a `SecPolicy` for evaluating Apple ID certificates.
+    pub fn create_apple_id_validation() -> crate::Result<Self> {
+        Self::create_with_properties(&PolicyProperties::new(PolicyKind::AppleIdValidation))
+    }
+
+    /// Creates a `SecPolicy` for evaluating Apple Wallet pass signing certificates of a team,
+    /// optionally for a specific card issuer.
+    pub fn create_passbook_signing(team_identifier: &str, card_issuer: Option<&str>) -> crate::Result<Self> {
+        Self::create_with_properties(
+            PolicyProperties::new(PolicyKind::PassbookSigning)
+                .set_team_identifier(Some(team_identifier))
+                .set_name(card_issuer),
+        )
+    }
+
+    /// Returns the kind of policy and its options.
+    ///
+    /// Fails with `errSecUnimplemented` for kinds of policies not in [`PolicyKind`].
+    pub fn properties(&self) -> crate::Result<PolicyProperties> {
+        PolicyProperties::from_dictionary(&self.properties_dictionary()?)
+    }
+
+    pub(crate) fn properties_dictionary(&self) -> crate::Result<BTreeMap<String, Value>> {
+        let dictionary = unsafe { SecPolicyCopyProperties(self.0) };
+        if dictionary.is_null() {
+            return Err(Error::from_code(errSecDecode));
+        }
+        let dictionary = unsafe { CFType::wrap_under_create_rule(dictionary.cast()) };
+        match Value::from_cf(&dictionary) {
+            Some(Value::Dictionary(dictionary)) => Ok(dictionary),
+            _ => Err(Error::from_code(errSecDecode)),
+        }
+    }
 }
 
-#[cfg(test)]
+#[cfg(all(test, target_vendor = "apple"))]
 mod test {
-    use crate::policy::SecPolicy;
+    use crate::policy::{PolicyKind, RevocationPolicy, SecPolicy};
     use crate::secure_transport::SslProtocolSide;
 
     #[test]
     fn create_ssl() {
         SecPolicy::create_ssl(SslProtocolSide::SERVER, Some("certifi.org"));
     }
+
+    #[test]
+    fn properties() {
+        let properties = SecPolicy::create_ssl(SslProtocolSide::SERVER, Some("certifi.org")).properties().unwrap();
+        assert_eq!(properties.kind(), PolicyKind::Ssl);
+        assert_eq!(properties.name(), Some("certifi.org"));
+        assert!(!properties.client());
+
+        let properties = SecPolicy::create_eap(SslProtocolSide::CLIENT, Some("radius.example")).unwrap().properties().unwrap();
+        assert_eq!(properties.kind(), PolicyKind::Eap);
+        assert!(properties.client());
+
+        let properties = SecPolicy::create_smime(Some("user@example.com")).unwrap().properties().unwrap();
+        assert_eq!(properties.name(), Some("user@example.com"));
+
+        let properties = SecPolicy::create_revocation(RevocationPolicy::OCSP_METHOD).unwrap().properties().unwrap();
+        assert_eq!(properties.kind(), PolicyKind::Revocation);
+
+        assert_eq!(SecPolicy::create_code_signing().unwrap().properties().unwrap().kind(), PolicyKind::CodeSigning);
+        assert_eq!(SecPolicy::create_x509().properties().unwrap().kind(), PolicyKind::X509Basic);
+        SecPolicy::create_ipsec(SslProtocolSide::SERVER, Some("vpn.example")).unwrap();
+        SecPolicy::create_time_stamping().unwrap();
+        SecPolicy::create_apple_id_validation().unwrap();
+        SecPolicy::create_passbook_signing("ABCDE12345", None).unwrap();
+    }
 }
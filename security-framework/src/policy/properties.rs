@@ -0,0 +1,388 @@
+//! Typed policy properties (`SecPolicyCreateWithProperties` and `SecPolicyCopyProperties`).
+
+use std::collections::BTreeMap;
+
+use security_framework_sys::base::{errSecDecode, errSecParam, errSecUnimplemented};
+
+use super::RevocationPolicy;
+use crate::base::{Error, Result};
+use crate::plist::Value;
+
+// Values of the `kSecPolicy*` property keys
+const OID: &str = "SecPolicyOid";
+const NAME: &str = "SecPolicyName";
+const CLIENT: &str = "SecPolicyClient";
+const REVOCATION_FLAGS: &str = "SecPolicyRevocationFlags";
+const TEAM_IDENTIFIER: &str = "SecPolicyTeamIdentifier";
+
+/// The policies that can be created from properties.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum PolicyKind {
+    /// Basic X.509 path validation.
+    X509Basic,
+    /// TLS. The name is the hostname.
+    Ssl,
+    /// S/MIME. The name is the email address.
+    Smime,
+    /// EAP (802.1X). The names are the acceptable server names.
+    Eap,
+    /// IPsec. The name is the peer's hostname.
+    Ipsec,
+    /// Kerberos PKINIT client certificates.
+    PkinitClient,
+    /// Kerberos PKINIT server certificates.
+    PkinitServer,
+    /// Code signing.
+    CodeSigning,
+    /// Apple ID validation.
+    AppleIdValidation,
+    /// RFC 3161 timestamping.
+    TimeStamping,
+    /// Revocation checking, with required revocation flags.
+    Revocation,
+    /// Apple Wallet passes, with a required team identifier. The name is the card issuer.
+    PassbookSigning,
+}
+
+/// Policy identifiers, the values of the `kSecPolicyApple*` constants
+const KINDS: [(PolicyKind, &str); 12] = [
+    (PolicyKind::X509Basic, "1.2.840.113635.100.1.2"),
+    (PolicyKind::Ssl, "1.2.840.113635.100.1.3"),
+    (PolicyKind::Smime, "1.2.840.113635.100.1.8"),
+    (PolicyKind::Eap, "1.2.840.113635.100.1.9"),
+    (PolicyKind::Ipsec, "1.2.840.113635.100.1.11"),
+    (PolicyKind::PkinitClient, "1.2.840.113635.100.1.14"),
+    (PolicyKind::PkinitServer, "1.2.840.113635.100.1.15"),
+    (PolicyKind::CodeSigning, "1.2.840.113635.100.1.16"),
+    (PolicyKind::AppleIdValidation, "1.2.840.113635.100.1.18"),
+    (PolicyKind::TimeStamping, "1.2.840.113635.100.1.20"),
+    (PolicyKind::Revocation, "1.2.840.113635.100.1.21"),
+    (PolicyKind::PassbookSigning, "1.2.840.113635.100.1.22"),
+];
+
+impl PolicyKind {
+    /// The policy identifier.
+    #[must_use]
+    pub fn oid(self) -> &'static str {
+        KINDS.iter().find(|k| k.0 == self).map_or("", |k| k.1)
+    }
+
+    /// The kind with the policy identifier `oid`.
+    #[must_use]
+    pub fn from_oid(oid: &str) -> Option<Self> {
+        KINDS.iter().find(|k| k.1 == oid).map(|k| k.0)
+    }
+
+    fn supports_name(self) -> bool {
+        matches!(self, Self::Ssl | Self::Smime | Self::Eap | Self::Ipsec | Self::PassbookSigning)
+    }
+
+    fn supports_client(self) -> bool {
+        matches!(self, Self::Ssl | Self::Eap | Self::Ipsec)
+    }
+}
+
+/// A policy and its options.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyProperties {
+    kind: PolicyKind,
+    names: Vec<String>,
+    client: bool,
+    revocation: Option<RevocationPolicy>,
+    team_identifier: Option<String>,
+}
+
+impl PolicyProperties {
+    /// A policy of `kind` without options.
+    #[must_use]
+    pub fn new(kind: PolicyKind) -> Self {
+        Self { kind, names: Vec::new(), client: false, revocation: None, team_identifier: None }
+    }
+
+    /// Sets the name to match: a hostname, email address or card issuer, depending on the kind.
+    pub fn set_name(&mut self, name: Option<&str>) -> &mut Self {
+        self.names = name.into_iter().map(String::from).collect();
+        self
+    }
+
+    /// Sets several names to match, any of which is accepted. Only EAP policies support more than one.
+    pub fn set_names(&mut self, names: &[&str]) -> &mut Self {
+        self.names = names.iter().map(|&name| name.to_owned()).collect();
+        self
+    }
+
+    /// Evaluates a client's certificate rather than a server's.
+    pub fn set_client(&mut self, client: bool) -> &mut Self {
+        self.client = client;
+        self
+    }
+
+    /// Sets the revocation checking options of a [`PolicyKind::Revocation`] policy.
+    pub fn set_revocation(&mut self, revocation: Option<RevocationPolicy>) -> &mut Self {
+        self.revocation = revocation;
+        self
+    }
+
+    /// Sets the team identifier of a [`PolicyKind::PassbookSigning`] policy.
+    pub fn set_team_identifier(&mut self, team_identifier: Option<&str>) -> &mut Self {
+        self.team_identifier = team_identifier.map(String::from);
+        self
+    }
+
+    /// The kind of policy.
+    #[inline]
+    #[must_use]
+    pub fn kind(&self) -> PolicyKind {
+        self.kind
+    }
+
+    /// The name to match, or the first one if there are several.
+    #[inline]
+    #[must_use]
+    pub fn name(&self) -> Option<&str> {
+        self.names.first().map(String::as_str)
+    }
+
+    /// All the names to match.
+    #[inline]
+    #[must_use]
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Whether a client's certificate is evaluated.
+    #[inline]
+    #[must_use]
+    pub fn client(&self) -> bool {
+        self.client
+    }
+
+    /// The revocation checking options.
+    #[inline]
+    #[must_use]
+    pub fn revocation(&self) -> Option<RevocationPolicy> {
+        self.revocation
+    }
+
+    /// The team identifier.
+    #[inline]
+    #[must_use]
+    pub fn team_identifier(&self) -> Option<&str> {
+        self.team_identifier.as_deref()
+    }
+
+    /// The properties to pass to `SecPolicyCreateWithProperties` along with the kind's identifier.
+    ///
+    /// Fails with `errSecParam` if an option isn't supported by the kind of policy, or a required one is missing.
+    pub fn to_dictionary(&self) -> Result<BTreeMap<String, Value>> {
+        let kind = self.kind;
+        let unsupported = (!self.names.is_empty() && !kind.supports_name())
+            || (self.names.len() > 1 && kind != PolicyKind::Eap)
+            || (self.client && !kind.supports_client())
+            || (self.revocation.is_some() != (kind == PolicyKind::Revocation))
+            || (self.team_identifier.is_some() != (kind == PolicyKind::PassbookSigning));
+        if unsupported {
+            return Err(Error::from_code(errSecParam));
+        }
+
+        let mut dictionary = BTreeMap::new();
+        match self.names.as_slice() {
+            [] => {},
+            [name] => {
+                dictionary.insert(NAME.into(), Value::String(name.clone()));
+            },
+            names => {
+                dictionary.insert(NAME.into(), Value::Array(names.iter().cloned().map(Value::String).collect()));
+            },
+        }
+        if kind.supports_client() {
+            dictionary.insert(CLIENT.into(), Value::Bool(self.client));
+        }
+        if let Some(revocation) = self.revocation {
+            let flags = i64::try_from(revocation.bits()).map_err(|_| Error::from_code(errSecParam))?;
+            dictionary.insert(REVOCATION_FLAGS.into(), Value::Integer(flags));
+        }
+        if let Some(team_identifier) = &self.team_identifier {
+            dictionary.insert(TEAM_IDENTIFIER.into(), Value::String(team_identifier.clone()));
+        }
+        Ok(dictionary)
+    }
+
+    /// Decodes the dictionary returned by `SecPolicyCopyProperties`.
+    ///
+    /// Fails with `errSecUnimplemented` for other kinds of policies, and `errSecDecode` if a value has the wrong type.
+    pub fn from_dictionary(dictionary: &BTreeMap<String, Value>) -> Result<Self> {
+        let string = |key| match dictionary.get(key) {
+            None => Ok(None),
+            Some(Value::String(s)) => Ok(Some(s.clone())),
+            Some(_) => Err(decode_error()),
+        };
+        let names = match dictionary.get(NAME) {
+            None => Vec::new(),
+            Some(Value::String(name)) => vec![name.clone()],
+            // EAP policies can have several names
+            Some(Value::Array(names)) => {
+                names.iter().map(|name| name.as_str().map(String::from).ok_or_else(decode_error)).collect::<Result<_>>()?
+            },
+            Some(_) => return Err(decode_error()),
+        };
+
+        let oid = dictionary.get(OID).ok_or_else(decode_error)?.as_str().ok_or_else(decode_error)?;
+        let kind = PolicyKind::from_oid(oid).ok_or_else(|| Error::from_code(errSecUnimplemented))?;
+        let client = match dictionary.get(CLIENT) {
+            Some(client) => client.as_bool().ok_or_else(decode_error)?,
+            None => false,
+        };
+        let revocation = match dictionary.get(REVOCATION_FLAGS) {
+            Some(flags) => {
+                let flags = flags.as_integer().and_then(|flags| flags.try_into().ok()).ok_or_else(decode_error)?;
+                Some(RevocationPolicy::from_bits_retain(flags))
+            },
+            None => None,
+        };
+        Ok(Self { kind, names, client, revocation, team_identifier: string(TEAM_IDENTIFIER)? })
+    }
+}
+
+fn decode_error() -> Error {
+    Error::from_code(errSecDecode)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn kinds() {
+        for (kind, oid) in KINDS {
+            assert_eq!(kind.oid(), oid);
+            assert_eq!(PolicyKind::from_oid(oid), Some(kind));
+        }
+        assert_eq!(PolicyKind::from_oid("1.2.3"), None);
+    }
+
+    #[test]
+    fn to_dictionary() {
+        let mut ssl = PolicyProperties::new(PolicyKind::Ssl);
+        ssl.set_name(Some("foobar.com"));
+        assert_eq!(
+            ssl.to_dictionary().unwrap(),
+            BTreeMap::from([(NAME.into(), Value::String("foobar.com".into())), (CLIENT.into(), Value::Bool(false))])
+        );
+
+        let mut revocation = PolicyProperties::new(PolicyKind::Revocation);
+        revocation.set_revocation(Some(RevocationPolicy::OCSP_METHOD | RevocationPolicy::REQUIRE_POSITIVE_RESPONSE));
+        assert_eq!(revocation.to_dictionary().unwrap(), BTreeMap::from([(REVOCATION_FLAGS.into(), Value::Integer(9))]));
+
+        let mut passbook = PolicyProperties::new(PolicyKind::PassbookSigning);
+        passbook.set_team_identifier(Some("ABCDE12345")).set_name(Some("pass.com.example"));
+        assert_eq!(passbook.to_dictionary().unwrap().len(), 2);
+
+        assert!(PolicyProperties::new(PolicyKind::CodeSigning).to_dictionary().unwrap().is_empty());
+    }
+
+    #[test]
+    fn invalid_options() {
+        let mut properties = PolicyProperties::new(PolicyKind::CodeSigning);
+        properties.set_name(Some("x"));
+        assert_eq!(properties.to_dictionary().unwrap_err().code(), errSecParam);
+
+        let mut properties = PolicyProperties::new(PolicyKind::Ssl);
+        properties.set_names(&["a.example", "b.example"]);
+        assert_eq!(properties.to_dictionary().unwrap_err().code(), errSecParam);
+
+        let mut properties = PolicyProperties::new(PolicyKind::Smime);
+        properties.set_client(true);
+        assert_eq!(properties.to_dictionary().unwrap_err().code(), errSecParam);
+
+        // required options
+        assert_eq!(PolicyProperties::new(PolicyKind::Revocation).to_dictionary().unwrap_err().code(), errSecParam);
+        assert_eq!(PolicyProperties::new(PolicyKind::PassbookSigning).to_dictionary().unwrap_err().code(), errSecParam);
+
+        let mut properties = PolicyProperties::new(PolicyKind::Ssl);
+        properties.set_revocation(Some(RevocationPolicy::CRL_METHOD));
+        assert_eq!(properties.to_dictionary().unwrap_err().code(), errSecParam);
+    }
+
+    #[test]
+    fn from_dictionary() {
+        let mut eap = PolicyProperties::new(PolicyKind::Eap);
+        eap.set_name(Some("radius.example")).set_client(true);
+        let mut dictionary = eap.to_dictionary().unwrap();
+        dictionary.insert(OID.into(), Value::String(PolicyKind::Eap.oid().into()));
+        assert_eq!(PolicyProperties::from_dictionary(&dictionary).unwrap(), eap);
+
+        eap.set_names(&["radius.example", "other"]);
+        let names = Value::Array(vec![Value::String("radius.example".into()), Value::String("other".into())]);
+        assert_eq!(eap.to_dictionary().unwrap()[NAME], names);
+        dictionary.insert(NAME.into(), names);
+        let properties = PolicyProperties::from_dictionary(&dictionary).unwrap();
+        assert_eq!(properties, eap);
+        assert_eq!(properties.name(), Some("radius.example"));
+        assert_eq!(properties.names(), ["radius.example", "other"]);
+
+        let dictionary = BTreeMap::from([
+            (OID.into(), Value::String(PolicyKind::Revocation.oid().into())),
+            (REVOCATION_FLAGS.into(), Value::Integer(3)),
+        ]);
+        let properties = PolicyProperties::from_dictionary(&dictionary).unwrap();
+        assert_eq!(properties.kind(), PolicyKind::Revocation);
+        assert_eq!(properties.revocation(), Some(RevocationPolicy::USE_ANY_METHOD_AVAILABLE));
+        assert_eq!(properties.name(), None);
+        assert!(!properties.client());
+    }
+
+    #[test]
+    fn malformed() {
+        let oid = |kind: PolicyKind| (OID.to_owned(), Value::String(kind.oid().into()));
+        for (dictionary, code) in [
+            (BTreeMap::new(), errSecDecode),
+            (BTreeMap::from([(OID.into(), Value::String("1.2.3".into()))]), errSecUnimplemented),
+            (BTreeMap::from([(OID.into(), Value::Integer(1))]), errSecDecode),
+            (BTreeMap::from([oid(PolicyKind::Ssl), (CLIENT.into(), Value::Integer(1))]), errSecDecode),
+            (BTreeMap::from([oid(PolicyKind::Ssl), (NAME.into(), Value::Data(vec![]))]), errSecDecode),
+            (BTreeMap::from([oid(PolicyKind::Eap), (NAME.into(), Value::Array(vec![Value::Integer(1)]))]), errSecDecode),
+            (BTreeMap::from([oid(PolicyKind::Revocation), (REVOCATION_FLAGS.into(), Value::Integer(-1))]), errSecDecode),
+        ] {
+            assert_eq!(PolicyProperties::from_dictionary(&dictionary).unwrap_err().code(), code, "{dictionary:?}");
+        }
+    }
+
+    #[test]
+    #[cfg(target_vendor = "apple")]
+    fn constants() {
+        use core_foundation::base::TCFType;
+        use core_foundation::string::CFString;
+        use security_framework_sys::policy::*;
+
+        let string = |s| unsafe { CFString::wrap_under_get_rule(s) }.to_string();
+        unsafe {
+            for (key, expected) in [
+                (kSecPolicyOid, OID),
+                (kSecPolicyName, NAME),
+                (kSecPolicyClient, CLIENT),
+                (kSecPolicyRevocationFlags, REVOCATION_FLAGS),
+                (kSecPolicyTeamIdentifier, TEAM_IDENTIFIER),
+            ] {
+                assert_eq!(string(key), expected);
+            }
+            for (oid, kind) in [
+                (kSecPolicyAppleX509Basic, PolicyKind::X509Basic),
+                (kSecPolicyAppleSSL, PolicyKind::Ssl),
+                (kSecPolicyAppleSMIME, PolicyKind::Smime),
+                (kSecPolicyAppleEAP, PolicyKind::Eap),
+                (kSecPolicyAppleIPsec, PolicyKind::Ipsec),
+                (kSecPolicyApplePKINITClient, PolicyKind::PkinitClient),
+                (kSecPolicyApplePKINITServer, PolicyKind::PkinitServer),
+                (kSecPolicyAppleCodeSigning, PolicyKind::CodeSigning),
+                (kSecPolicyAppleIDValidation, PolicyKind::AppleIdValidation),
+                (kSecPolicyAppleTimeStamping, PolicyKind::TimeStamping),
+                (kSecPolicyAppleRevocation, PolicyKind::Revocation),
+                (kSecPolicyApplePassbookSigning, PolicyKind::PassbookSigning),
+            ] {
+                assert_eq!(string(oid), kind.oid());
+            }
+        }
+    }
+}
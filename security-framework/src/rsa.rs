@@ -0,0 +1,156 @@
+//! Minimal RSA with PKCS #1 v1.5 signatures, for checking certificates without Security.framework.
+//!
+//! This is only used with public data or test keys, and is not constant-time.
+
+#![allow(clippy::many_single_char_names)] // variable names follow the usual bignum notation
+
+use crate::base::Result;
+use crate::bignum::{Montgomery, Uint};
+use crate::der::{self, Reader};
+#[cfg(test)]
+use crate::der::Oid;
+use crate::digest::DigestAlgorithm;
+
+/// An RSA public key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PublicKey {
+    n: Uint,
+    e: Uint,
+}
+
+impl PublicKey {
+    /// Parses a PKCS #1 `RSAPublicKey`, which is the key in an RSA `SubjectPublicKeyInfo`.
+    pub fn from_pkcs1(der: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(der);
+        let mut seq = reader.read_sequence()?;
+        reader.finish()?;
+        let n = Uint::from_be_bytes(seq.read_integer()?);
+        let e = Uint::from_be_bytes(seq.read_integer()?);
+        seq.finish()?;
+        if !n.is_odd() || n.bits() < 512 || e.bits() < 2 {
+            return Err(der::decode_error());
+        }
+        Ok(Self { n, e })
+    }
+
+    fn len(&self) -> usize {
+        self.n.bits().div_ceil(8)
+    }
+
+    /// Checks a PKCS #1 v1.5 signature.
+    pub fn verify_pkcs1(&self, digest: DigestAlgorithm, message: &[u8], signature: &[u8]) -> bool {
+        let len = self.len();
+        if signature.len() != len {
+            return false;
+        }
+        let s = Uint::from_be_bytes(signature);
+        if !s.less_than(&self.n) {
+            return false;
+        }
+        let m = Montgomery::new(&self.n).pow(&s, &self.e);
+        encode_pkcs1(digest, message, len).is_some_and(|expected| m.to_be_bytes(len) == expected)
+    }
+}
+
+/// EMSA-PKCS1-v1_5 encoding: `00 01 FF… 00 DigestInfo`
+fn encode_pkcs1(digest: DigestAlgorithm, message: &[u8], len: usize) -> Option<Vec<u8>> {
+    let algorithm = der::encode_sequence(&[&der::encode_oid(&digest.oid()), &[0x05, 0x00]]);
+    let digest_info = der::encode_sequence(&[&algorithm, &der::encode(der::OCTET_STRING, &digest.digest(message))]);
+    let padding = len.checked_sub(digest_info.len() + 3).filter(|&p| p >= 8)?;
+    let mut em = Vec::with_capacity(len);
+    em.extend_from_slice(&[0, 1]);
+    em.resize(2 + padding, 0xff);
+    em.push(0);
+    em.extend_from_slice(&digest_info);
+    Some(em)
+}
+
+/// An RSA private key, for signing in tests.
+#[cfg(test)]
+pub(crate) struct PrivateKey {
+    public: PublicKey,
+    d: Uint,
+}
+
+#[cfg(test)]
+impl PrivateKey {
+    /// Parses a PKCS #8 `PrivateKeyInfo` holding an RSA key.
+    pub fn from_pkcs8(der: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(der);
+        let mut seq = reader.read_sequence()?;
+        reader.finish()?;
+        seq.read_small_uint()?;
+        let mut algorithm = seq.read_sequence()?;
+        if algorithm.read_oid()? != Oid::RSA_ENCRYPTION {
+            return Err(der::decode_error());
+        }
+        Self::from_pkcs1(seq.read(der::OCTET_STRING)?)
+    }
+
+    /// Parses a PKCS #1 `RSAPrivateKey`.
+    pub fn from_pkcs1(der: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(der);
+        let mut seq = reader.read_sequence()?;
+        seq.read_small_uint()?;
+        let n = Uint::from_be_bytes(seq.read_integer()?);
+        let e = Uint::from_be_bytes(seq.read_integer()?);
+        let d = Uint::from_be_bytes(seq.read_integer()?);
+        Ok(Self { public: PublicKey { n, e }, d })
+    }
+
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public
+    }
+
+    /// Creates a PKCS #1 v1.5 signature.
+    pub fn sign_pkcs1(&self, digest: DigestAlgorithm, message: &[u8]) -> Vec<u8> {
+        let len = self.public.len();
+        let em = encode_pkcs1(digest, message, len).unwrap();
+        Montgomery::new(&self.public.n).pow(&Uint::from_be_bytes(&em), &self.d).to_be_bytes(len)
+    }
+}
+
+/// Software signer for testing the certificate and request builders.
+#[cfg(test)]
+impl crate::certificate::Signer for PrivateKey {
+    fn algorithm(&self) -> Result<crate::certificate::AlgorithmIdentifier> {
+        Ok(crate::certificate::AlgorithmIdentifier::rsa_pkcs1(DigestAlgorithm::Sha256))
+    }
+
+    fn sign(&self, data: &[u8]) -> Result<Vec<u8>> {
+        Ok(self.sign_pkcs1(DigestAlgorithm::Sha256, data))
+    }
+}
+
+/// The key of `test/server.der`
+#[cfg(test)]
+pub(crate) fn server_key() -> PrivateKey {
+    let pem = std::fs::read("test/server.key").unwrap();
+    let (_, der) = crate::pem::parse_private_key(&pem).unwrap();
+    PrivateKey::from_pkcs8(&der).unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn server_certificate_signature() {
+        // server.der is signed by the CA with sha256WithRSAEncryption
+        let server = crate::certificate::Parsed::from_der(include_bytes!("../test/server.der")).unwrap();
+        let ca = crate::certificate::Parsed::from_der(include_bytes!("../test/ca.der")).unwrap();
+        let ca_key = PublicKey::from_pkcs1(ca.public_key_info().public_key()).unwrap();
+        assert!(ca_key.verify_pkcs1(DigestAlgorithm::Sha256, server.tbs_certificate(), server.signature()));
+        assert!(!ca_key.verify_pkcs1(DigestAlgorithm::Sha1, server.tbs_certificate(), server.signature()));
+        assert!(!ca_key.verify_pkcs1(DigestAlgorithm::Sha256, ca.tbs_certificate(), server.signature()));
+    }
+
+    #[test]
+    fn sign_and_verify() {
+        let key = server_key();
+        let signature = key.sign_pkcs1(DigestAlgorithm::Sha256, b"hello");
+        assert_eq!(signature.len(), 256);
+        assert!(key.public_key().verify_pkcs1(DigestAlgorithm::Sha256, b"hello", &signature));
+        assert!(!key.public_key().verify_pkcs1(DigestAlgorithm::Sha256, b"hellO", &signature));
+    }
+}
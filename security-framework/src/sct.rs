@@ -0,0 +1,435 @@
+//! Certificate Transparency signed certificate timestamps (RFC 6962).
+//!
+//! SCTs arrive embedded in the certificate, in the TLS handshake or stapled in an OCSP
+//! response, always as a `SignedCertificateTimestampList`. Pass individual SCTs to
+//! `SecTrust::set_signed_certificate_timestamps`.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use security_framework_sys::base::errSecUnimplemented;
+
+use crate::base::{Error, Result};
+#[cfg(target_vendor = "apple")]
+use crate::certificate::SecCertificate;
+use crate::certificate::{AlgorithmIdentifier, Parsed, SubjectPublicKeyInfo};
+use crate::der::{self, Oid, Reader};
+use crate::digest::DigestAlgorithm;
+use crate::signature;
+
+/// The signature algorithm of an SCT (TLS `SignatureAlgorithm`).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SignatureAlgorithm {
+    /// RSASSA-PKCS1-v1_5
+    Rsa,
+    /// ECDSA, which most logs use
+    Ecdsa,
+    /// Any other value
+    Other(u8),
+}
+
+impl SignatureAlgorithm {
+    fn from_code(code: u8) -> Self {
+        match code {
+            1 => Self::Rsa,
+            3 => Self::Ecdsa,
+            other => Self::Other(other),
+        }
+    }
+}
+
+/// One signed certificate timestamp: a log's promise to include a certificate.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SignedCertificateTimestamp {
+    log_id: [u8; 32],
+    timestamp: u64,
+    extensions: Vec<u8>,
+    hash_algorithm: u8,
+    signature_algorithm: u8,
+    signature: Vec<u8>,
+    raw: Vec<u8>,
+}
+
+impl SignedCertificateTimestamp {
+    /// Parses one serialized v1 SCT.
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let mut reader = TlsReader(data);
+        if reader.read_u8()? != 0 {
+            return Err(Error::from_code(errSecUnimplemented));
+        }
+        let log_id = reader.read_bytes(32)?.try_into().map_err(|_| der::decode_error())?;
+        let timestamp = u64::from_be_bytes(reader.read_bytes(8)?.try_into().map_err(|_| der::decode_error())?);
+        let extensions = reader.read_vec16()?.to_vec();
+        let hash_algorithm = reader.read_u8()?;
+        let signature_algorithm = reader.read_u8()?;
+        let signature = reader.read_vec16()?.to_vec();
+        reader.finish()?;
+        Ok(Self { log_id, timestamp, extensions, hash_algorithm, signature_algorithm, signature, raw: data.to_vec() })
+    }
+
+    /// Parses a `SignedCertificateTimestampList`.
+    ///
+    /// SCTs of versions other than v1 are skipped, as RFC 6962 requires.
+    pub fn parse_list(data: &[u8]) -> Result<Vec<Self>> {
+        let mut outer = TlsReader(data);
+        let mut list = TlsReader(outer.read_vec16()?);
+        outer.finish()?;
+        let mut scts = Vec::new();
+        while !list.0.is_empty() {
+            let sct = list.read_vec16()?;
+            if sct.first() == Some(&0) {
+                scts.push(Self::parse(sct)?);
+            } else if sct.is_empty() {
+                return Err(der::decode_error());
+            }
+        }
+        Ok(scts)
+    }
+
+    /// Extracts the SCTs embedded in a certificate's extension.
+    ///
+    /// Returns an empty list if there is no such extension.
+    pub fn from_parsed(certificate: &Parsed) -> Result<Vec<Self>> {
+        let Some(extension) = certificate.extension(&Oid::CT_PRECERT_SCTS) else { return Ok(Vec::new()) };
+        let mut reader = Reader::new(extension.value());
+        let list = reader.read(der::OCTET_STRING)?;
+        reader.finish()?;
+        Self::parse_list(list)
+    }
+
+    /// Extracts the SCTs embedded in a certificate's extension.
+    #[cfg(target_vendor = "apple")]
+    pub fn from_certificate(certificate: &SecCertificate) -> Result<Vec<Self>> {
+        Self::from_parsed(&certificate.parse()?)
+    }
+
+    /// SHA-256 hash of the log's public key.
+    #[inline]
+    #[must_use]
+    pub fn log_id(&self) -> &[u8; 32] {
+        &self.log_id
+    }
+
+    /// When the log saw the certificate, in milliseconds since the Unix epoch.
+    #[inline]
+    #[must_use]
+    pub fn timestamp_millis(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// When the log saw the certificate.
+    #[must_use]
+    pub fn timestamp(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(self.timestamp)
+    }
+
+    /// Opaque extensions, empty for all current logs.
+    #[inline]
+    #[must_use]
+    pub fn extensions(&self) -> &[u8] {
+        &self.extensions
+    }
+
+    /// The hash algorithm of the signature, if supported.
+    #[must_use]
+    pub fn hash_algorithm(&self) -> Option<DigestAlgorithm> {
+        match self.hash_algorithm {
+            2 => Some(DigestAlgorithm::Sha1),
+            4 => Some(DigestAlgorithm::Sha256),
+            5 => Some(DigestAlgorithm::Sha384),
+            6 => Some(DigestAlgorithm::Sha512),
+            _ => None,
+        }
+    }
+
+    /// The TLS `HashAlgorithm` code of the signature, e.g. 4 for SHA-256.
+    #[inline]
+    #[must_use]
+    pub fn hash_algorithm_code(&self) -> u8 {
+        self.hash_algorithm
+    }
+
+    /// The signature algorithm.
+    #[must_use]
+    pub fn signature_algorithm(&self) -> SignatureAlgorithm {
+        SignatureAlgorithm::from_code(self.signature_algorithm)
+    }
+
+    /// The log's signature.
+    #[inline]
+    #[must_use]
+    pub fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+
+    /// The serialized SCT.
+    #[inline]
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.raw
+    }
+
+    /// The data the log signed for `entry`.
+    #[must_use]
+    pub fn signed_data(&self, entry: &LogEntry) -> Vec<u8> {
+        let mut data = vec![0, 0]; // v1, certificate_timestamp
+        data.extend_from_slice(&self.timestamp.to_be_bytes());
+        match entry {
+            LogEntry::X509(certificate) => {
+                data.extend_from_slice(&[0, 0]);
+                push_vec24(&mut data, certificate);
+            },
+            LogEntry::Precert { issuer_key_hash, tbs_certificate } => {
+                data.extend_from_slice(&[0, 1]);
+                data.extend_from_slice(issuer_key_hash);
+                push_vec24(&mut data, tbs_certificate);
+            },
+        }
+        data.extend_from_slice(&u16::try_from(self.extensions.len()).unwrap_or(u16::MAX).to_be_bytes());
+        data.extend_from_slice(&self.extensions);
+        data
+    }
+
+    /// Checks the SCT's signature over `entry` with the log's DER-encoded `SubjectPublicKeyInfo`.
+    ///
+    /// Returns `false` if the SCT is from a different log. RSA and ECDSA P-256 and P-384 signatures
+    /// are supported; others fail with `errSecUnimplemented`.
+    pub fn verify(&self, entry: &LogEntry, log_public_key_info: &[u8]) -> Result<bool> {
+        if DigestAlgorithm::Sha256.digest(log_public_key_info) != self.log_id {
+            return Ok(false);
+        }
+        let mut reader = Reader::new(log_public_key_info);
+        let public_key_info = SubjectPublicKeyInfo::parse(&mut reader)?;
+        reader.finish()?;
+        let algorithm = match (self.signature_algorithm(), self.hash_algorithm()) {
+            (SignatureAlgorithm::Rsa, Some(digest)) => AlgorithmIdentifier::rsa_pkcs1(digest),
+            (SignatureAlgorithm::Ecdsa, Some(digest)) => AlgorithmIdentifier::ecdsa(digest)?,
+            _ => return Err(Error::from_code(errSecUnimplemented)),
+        };
+        signature::verify_signature(&public_key_info, &algorithm, &self.signed_data(entry), &self.signature)
+    }
+}
+
+/// What a log signed: a certificate, or a precertificate for SCTs embedded in the final certificate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LogEntry {
+    /// A DER-encoded certificate, for SCTs from the TLS extension or OCSP.
+    X509(Vec<u8>),
+    /// A precertificate.
+    Precert {
+        /// SHA-256 hash of the issuer's `SubjectPublicKeyInfo`.
+        issuer_key_hash: [u8; 32],
+        /// The DER-encoded `TBSCertificate` without the SCT list extension.
+        tbs_certificate: Vec<u8>,
+    },
+}
+
+impl LogEntry {
+    /// The entry for SCTs delivered alongside `certificate`.
+    #[must_use]
+    pub fn x509(certificate: &Parsed) -> Self {
+        Self::X509(certificate.der().to_vec())
+    }
+
+    /// The entry for SCTs embedded in `certificate`, which was issued by `issuer`.
+    ///
+    /// Certificates issued by a dedicated precertificate signing certificate aren't supported.
+    pub fn precert(certificate: &Parsed, issuer: &Parsed) -> Result<Self> {
+        let issuer_key_hash = DigestAlgorithm::Sha256.digest(issuer.public_key_info().der());
+        Ok(Self::Precert {
+            issuer_key_hash: issuer_key_hash.try_into().map_err(|_| der::decode_error())?,
+            tbs_certificate: remove_sct_list(certificate.tbs_certificate())?,
+        })
+    }
+}
+
+/// Re-encodes a `TBSCertificate` without the SCT list extension.
+fn remove_sct_list(tbs_certificate: &[u8]) -> Result<Vec<u8>> {
+    let mut outer = Reader::new(tbs_certificate);
+    let mut tbs = outer.read_sequence()?;
+    outer.finish()?;
+    let mut content = Vec::new();
+    while !tbs.is_empty() {
+        let field = tbs.read_tlv()?;
+        if field.tag != der::context(3) {
+            content.extend_from_slice(field.raw);
+            continue;
+        }
+        let mut explicit = Reader::new(field.content);
+        let mut list = explicit.read_sequence()?;
+        explicit.finish()?;
+        let mut extensions = Vec::new();
+        while !list.is_empty() {
+            let extension = list.read_tlv()?;
+            if Reader::new(extension.content).read_oid()? != Oid::CT_PRECERT_SCTS {
+                extensions.extend_from_slice(extension.raw);
+            }
+        }
+        if !extensions.is_empty() {
+            content.extend(der::encode(der::context(3), &der::encode(der::SEQUENCE, &extensions)));
+        }
+    }
+    Ok(der::encode(der::SEQUENCE, &content))
+}
+
+fn push_vec24(out: &mut Vec<u8>, data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes()[1..]);
+    out.extend_from_slice(data);
+}
+
+/// Reads TLS presentation language encodings.
+struct TlsReader<'a>(&'a [u8]);
+
+impl<'a> TlsReader<'a> {
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.0.len() < len {
+            return Err(der::decode_error());
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> Result<u8> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_vec16(&mut self) -> Result<&'a [u8]> {
+        let len = self.read_bytes(2)?;
+        self.read_bytes(usize::from(u16::from_be_bytes([len[0], len[1]])))
+    }
+
+    fn finish(&self) -> Result<()> {
+        if self.0.is_empty() { Ok(()) } else { Err(der::decode_error()) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::der::unix_from_time;
+
+    fn fixtures() -> (Parsed, Parsed, &'static [u8]) {
+        (
+            Parsed::from_der(include_bytes!("../test/sct/leaf.der")).unwrap(),
+            Parsed::from_der(include_bytes!("../test/sct/sct_ca.der")).unwrap(),
+            include_bytes!("../test/sct/log.der"),
+        )
+    }
+
+    #[test]
+    fn embedded() {
+        let (leaf, ca, log) = fixtures();
+        let scts = SignedCertificateTimestamp::from_parsed(&leaf).unwrap();
+        assert_eq!(scts.len(), 1);
+        let sct = &scts[0];
+        assert_eq!(hex::encode(sct.log_id()), "5192c9b2927358a7fa31ae86e62259a913e54423a8cc76f2cd78b8269fe92ca9");
+        assert_eq!(sct.log_id()[..], DigestAlgorithm::Sha256.digest(log));
+        assert_eq!(sct.timestamp_millis(), 1_704_067_200_000);
+        assert_eq!(unix_from_time(sct.timestamp()), 1_704_067_200);
+        assert!(sct.extensions().is_empty());
+        assert_eq!(sct.hash_algorithm(), Some(DigestAlgorithm::Sha256));
+        assert_eq!(sct.hash_algorithm_code(), 4);
+        assert_eq!(sct.signature_algorithm(), SignatureAlgorithm::Rsa);
+        assert_eq!(hex::encode(&sct.signature()[..8]), "664298917384d131");
+        assert_eq!(SignedCertificateTimestamp::parse(sct.as_bytes()).unwrap(), *sct);
+
+        let entry = LogEntry::precert(&leaf, &ca).unwrap();
+        assert!(sct.verify(&entry, log).unwrap());
+        // the certificate itself isn't what the log signed
+        assert!(!sct.verify(&LogEntry::x509(&leaf), log).unwrap());
+        // and a different issuer changes the entry
+        assert!(!sct.verify(&LogEntry::precert(&leaf, &leaf).unwrap(), log).unwrap());
+        // an SCT from another log
+        let other = Parsed::from_der(include_bytes!("../test/ca.der")).unwrap();
+        assert!(!sct.verify(&entry, other.public_key_info().der()).unwrap());
+
+        assert!(SignedCertificateTimestamp::from_parsed(&ca).unwrap().is_empty());
+    }
+
+    #[test]
+    fn precert_tbs() {
+        let (leaf, _, _) = fixtures();
+        let LogEntry::Precert { tbs_certificate, .. } = LogEntry::precert(&leaf, &leaf).unwrap() else { panic!() };
+        assert!(tbs_certificate.len() < leaf.tbs_certificate().len());
+        let mut certificate = tbs_certificate.clone();
+        certificate.extend(leaf.signature_algorithm().to_der());
+        certificate.extend(der::encode_bit_string(leaf.signature()));
+        let parsed = Parsed::from_der(&der::encode(der::SEQUENCE, &certificate)).unwrap();
+        assert!(parsed.extension(&Oid::CT_PRECERT_SCTS).is_none());
+        assert_eq!(parsed.extensions().len(), leaf.extensions().len() - 1);
+        assert_eq!(parsed.subject(), leaf.subject());
+
+        // without an SCT list, the TBSCertificate is unchanged
+        assert_eq!(remove_sct_list(parsed.tbs_certificate()).unwrap(), tbs_certificate);
+    }
+
+    #[test]
+    fn tls_extension() {
+        let (leaf, ca, log) = fixtures();
+        let scts = SignedCertificateTimestamp::parse_list(include_bytes!("../test/sct/tls.bin")).unwrap();
+        assert_eq!(scts.len(), 1);
+        assert_eq!(scts[0].timestamp_millis(), 1_704_153_600_000);
+        assert!(scts[0].verify(&LogEntry::x509(&leaf), log).unwrap());
+        assert!(!scts[0].verify(&LogEntry::precert(&leaf, &ca).unwrap(), log).unwrap());
+    }
+
+    #[test]
+    fn ecdsa() {
+        let (leaf, _, log) = fixtures();
+        let ecdsa_log = include_bytes!("../test/sct/ecdsa-log.der");
+        let sct = SignedCertificateTimestamp::parse(include_bytes!("../test/sct/ecdsa-sct.bin")).unwrap();
+        assert_eq!(sct.signature_algorithm(), SignatureAlgorithm::Ecdsa);
+        assert!(sct.verify(&LogEntry::x509(&leaf), ecdsa_log).unwrap());
+        assert!(!sct.verify(&LogEntry::x509(&leaf), log).unwrap());
+
+        let mut tampered = sct.as_bytes().to_vec();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        let tampered = SignedCertificateTimestamp::parse(&tampered).unwrap();
+        assert!(!tampered.verify(&LogEntry::x509(&leaf), ecdsa_log).unwrap());
+    }
+
+    #[test]
+    fn known_answer() {
+        // a hand-assembled list: one v1 ECDSA SCT with an extension, one from a future version
+        let mut sct = vec![0];
+        sct.extend([0xab; 32]);
+        sct.extend(1_600_000_000_123u64.to_be_bytes());
+        sct.extend([0, 2, 0xde, 0xad]);
+        sct.extend([4, 3, 0, 3, 0x30, 0x01, 0x00]);
+        let mut list = Vec::new();
+        list.extend(u16::try_from(sct.len()).unwrap().to_be_bytes());
+        list.extend(&sct);
+        list.extend([0, 3, 1, 0xff, 0xff]);
+        let mut data = u16::try_from(list.len()).unwrap().to_be_bytes().to_vec();
+        data.extend(&list);
+
+        let scts = SignedCertificateTimestamp::parse_list(&data).unwrap();
+        assert_eq!(scts.len(), 1);
+        let sct = &scts[0];
+        assert_eq!(sct.log_id(), &[0xab; 32]);
+        assert_eq!(sct.timestamp_millis(), 1_600_000_000_123);
+        assert_eq!(sct.timestamp(), UNIX_EPOCH + Duration::from_millis(1_600_000_000_123));
+        assert_eq!(sct.extensions(), [0xde, 0xad]);
+        assert_eq!(sct.signature_algorithm(), SignatureAlgorithm::Ecdsa);
+        assert_eq!(sct.signature(), [0x30, 0x01, 0x00]);
+
+        let entry = LogEntry::X509(vec![1, 2, 3]);
+        let mut expected = vec![0, 0];
+        expected.extend(1_600_000_000_123u64.to_be_bytes());
+        expected.extend([0, 0, 0, 0, 3, 1, 2, 3, 0, 2, 0xde, 0xad]);
+        assert_eq!(sct.signed_data(&entry), expected);
+
+        let entry = LogEntry::Precert { issuer_key_hash: [7; 32], tbs_certificate: vec![9] };
+        let signed = sct.signed_data(&entry);
+        assert_eq!(signed[10..12], [0, 1]);
+        assert_eq!(signed[12..44], [7; 32]);
+        assert_eq!(signed[44..48], [0, 0, 1, 9]);
+
+        // truncated, trailing data, empty entries
+        assert!(SignedCertificateTimestamp::parse_list(&data[..data.len() - 1]).is_err());
+        assert!(SignedCertificateTimestamp::parse(&[sct.as_bytes(), &[0]].concat()).is_err());
+        assert!(SignedCertificateTimestamp::parse_list(&[0, 2, 0, 0]).is_err());
+        assert!(SignedCertificateTimestamp::parse_list(&[0, 0]).unwrap().is_empty());
+    }
+}
@@ -98,14 +98,16 @@ use std::ptr;
 use std::result;
 use std::slice;
 
-use crate::base::{Error, Result};
-use crate::certificate::SecCertificate;
+use crate::base::{DomainError, Error, Result};
+use crate::certificate::{Oid, SecCertificate};
 use crate::cipher_suite::CipherSuite;
 use crate::cvt;
 use crate::identity::SecIdentity;
 use crate::import_export::Pkcs12ImportOptions;
 use crate::policy::SecPolicy;
 use crate::trust::SecTrust;
+use crate::trust::software::{SoftwareVerifier, Verification};
+use crate::trust::tofu::{TofuEntry, TofuStore};
 use security_framework_sys::base::errSecParam;
 
 /// Specifies a side of a TLS session.
@@ -148,9 +150,14 @@ impl<S> From<Error> for HandshakeError<S> {
 
 /// An error or intermediate state after a TLS handshake attempt.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum ClientHandshakeError<S> {
     /// The handshake failed.
     Failure(Error),
+    /// The server's certificate was rejected by trust evaluation or the software verifier. The
+    /// report includes the evaluation's error or the verifier's report, and
+    /// [`HandshakeFailure::error`] is what `Failure` would have had.
+    TrustFailure(HandshakeFailure),
     /// The handshake was interrupted midway through.
     Interrupted(MidHandshakeClientBuilder<S>),
 }
@@ -162,6 +169,295 @@ impl<S> From<Error> for ClientHandshakeError<S> {
     }
 }
 
+impl<S> HandshakeError<S> {
+    /// Returns a diagnostic report if the handshake failed.
+    #[must_use]
+    pub fn failure(&self) -> Option<HandshakeFailure> {
+        match self {
+            Self::Failure(err) => Some(HandshakeFailure::new(*err)),
+            Self::Interrupted(_) => None,
+        }
+    }
+}
+
+impl<S> ClientHandshakeError<S> {
+    /// Returns a diagnostic report if the handshake failed.
+    #[must_use]
+    pub fn failure(&self) -> Option<HandshakeFailure> {
+        match self {
+            Self::Failure(err) => Some(HandshakeFailure::new(*err)),
+            Self::TrustFailure(failure) => Some(failure.clone()),
+            Self::Interrupted(_) => None,
+        }
+    }
+}
+
+/// Why a TLS handshake failed, grouped from the `errSSL*` and trust error codes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum HandshakeFailureCause {
+    /// The peer's certificate was rejected locally.
+    Certificate(CertificateProblem),
+    /// The peer aborted the handshake with a fatal TLS alert.
+    PeerAlert(TlsAlert),
+    /// No common protocol version or cipher suite, or a malformed message.
+    Protocol,
+    /// The underlying stream failed.
+    Io,
+    /// The connection was closed before the handshake completed.
+    Closed,
+    /// Configuration, internal or cryptographic error.
+    Other,
+}
+
+/// What was wrong with the peer's certificate.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CertificateProblem {
+    /// The chain doesn't lead to a trusted root.
+    Untrusted,
+    /// A certificate in the chain has expired.
+    Expired,
+    /// A certificate in the chain is not valid yet.
+    NotYetValid,
+    /// The certificate is not valid for the requested host name.
+    HostnameMismatch,
+    /// A certificate in the chain has been revoked.
+    Revoked,
+    /// The certificate is not valid for this purpose (extended key usage).
+    Usage,
+    /// The certificate couldn't be parsed or the chain is invalid.
+    Invalid,
+    /// A trust setting explicitly denies the certificate.
+    Denied,
+}
+
+/// A TLS alert description (RFC 8446 section 6).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TlsAlert(pub u8);
+
+/// Fatal alerts reported by Secure Transport as `errSSLPeer*` codes.
+const PEER_ALERTS: [(OSStatus, u8, &str); 21] = [
+    (errSSLPeerUnexpectedMsg, 10, "unexpected_message"),
+    (errSSLPeerBadRecordMac, 20, "bad_record_mac"),
+    (errSSLPeerDecryptionFail, 21, "decryption_failed"),
+    (errSSLPeerRecordOverflow, 22, "record_overflow"),
+    (errSSLPeerDecompressFail, 30, "decompression_failure"),
+    (errSSLPeerHandshakeFail, 40, "handshake_failure"),
+    (errSSLPeerBadCert, 42, "bad_certificate"),
+    (errSSLPeerUnsupportedCert, 43, "unsupported_certificate"),
+    (errSSLPeerCertRevoked, 44, "certificate_revoked"),
+    (errSSLPeerCertExpired, 45, "certificate_expired"),
+    (errSSLPeerCertUnknown, 46, "certificate_unknown"),
+    (errSSLPeerUnknownCA, 48, "unknown_ca"),
+    (errSSLPeerAccessDenied, 49, "access_denied"),
+    (errSSLPeerDecodeError, 50, "decode_error"),
+    (errSSLPeerDecryptError, 51, "decrypt_error"),
+    (errSSLPeerExportRestriction, 60, "export_restriction"),
+    (errSSLPeerProtocolVersion, 70, "protocol_version"),
+    (errSSLPeerInsufficientSecurity, 71, "insufficient_security"),
+    (errSSLPeerInternalError, 80, "internal_error"),
+    (errSSLPeerUserCancelled, 90, "user_canceled"),
+    (errSSLPeerNoRenegotiation, 100, "no_renegotiation"),
+];
+
+impl TlsAlert {
+    /// Returns the alert's name from the TLS specification, e.g. `unknown_ca`.
+    #[must_use]
+    pub fn name(self) -> Option<&'static str> {
+        PEER_ALERTS.iter().find(|a| a.1 == self.0).map(|a| a.2)
+    }
+}
+
+impl fmt::Display for TlsAlert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "{name} ({})", self.0),
+            None => write!(f, "alert {}", self.0),
+        }
+    }
+}
+
+impl HandshakeFailureCause {
+    /// Classifies an error returned by a handshake.
+    #[must_use]
+    pub fn from_code(code: OSStatus) -> Self {
+        use security_framework_sys::base::{
+            errSecCertificateExpired, errSecCertificateNotValidYet, errSecCertificateRevoked,
+            errSecCreateChainFailed, errSecHostNameMismatch, errSecInvalidExtendedKeyUsage,
+        };
+        use CertificateProblem::*;
+
+        if let Some(&(_, alert, _)) = PEER_ALERTS.iter().find(|a| a.0 == code) {
+            return Self::PeerAlert(TlsAlert(alert));
+        }
+        match code {
+            errSSLXCertChainInvalid | errSSLBadCert => Self::Certificate(Invalid),
+            errSSLUnknownRootCert | errSSLNoRootCert | errSecNotTrusted | errSecCreateChainFailed => Self::Certificate(Untrusted),
+            errSSLCertExpired | errSecCertificateExpired => Self::Certificate(Expired),
+            errSSLCertNotYetValid | errSecCertificateNotValidYet => Self::Certificate(NotYetValid),
+            errSSLHostNameMismatch | errSecHostNameMismatch => Self::Certificate(HostnameMismatch),
+            errSecCertificateRevoked => Self::Certificate(Revoked),
+            errSecInvalidExtendedKeyUsage => Self::Certificate(Usage),
+            errSecTrustSettingDeny => Self::Certificate(Denied),
+            errSSLProtocol | errSSLNegotiation | errSSLBadCipherSuite | errSSLSessionNotFound
+            | errSSLDecryptionFail | errSSLBadRecordMac | errSSLRecordOverflow | errSSLFatalAlert => Self::Protocol,
+            errSecIO => Self::Io,
+            errSSLClosedGraceful | errSSLClosedAbort | errSSLClosedNoNotify | errSSLConnectionRefused => Self::Closed,
+            _ => Self::Other,
+        }
+    }
+}
+
+impl fmt::Display for HandshakeFailureCause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Certificate(problem) => f.write_str(match problem {
+                CertificateProblem::Untrusted => "the certificate chain does not lead to a trusted root",
+                CertificateProblem::Expired => "a certificate in the chain has expired",
+                CertificateProblem::NotYetValid => "a certificate in the chain is not valid yet",
+                CertificateProblem::HostnameMismatch => "the certificate is not valid for the requested host name",
+                CertificateProblem::Revoked => "a certificate in the chain has been revoked",
+                CertificateProblem::Usage => "the certificate is not valid for TLS (extended key usage)",
+                CertificateProblem::Invalid => "the certificate or chain is malformed",
+                CertificateProblem::Denied => "the certificate is denied by trust settings",
+            }),
+            Self::PeerAlert(alert) => write!(f, "the peer aborted the handshake with alert {alert}"),
+            Self::Protocol => f.write_str("the peers could not agree on a protocol version or cipher suite, or a message was malformed"),
+            Self::Io => f.write_str("the underlying connection failed"),
+            Self::Closed => f.write_str("the connection was closed during the handshake"),
+            Self::Other => f.write_str("the handshake failed"),
+        }
+    }
+}
+
+/// Diagnostic report for a failed TLS handshake.
+///
+/// `Display` renders a human-readable explanation.
+#[derive(Debug, Clone)]
+pub struct HandshakeFailure {
+    error: Error,
+    cause: HandshakeFailureCause,
+    trust_error: Option<DomainError>,
+    verification: Option<Verification>,
+}
+
+impl HandshakeFailure {
+    /// Classifies the error from a failed handshake.
+    #[must_use]
+    pub fn new(error: Error) -> Self {
+        Self {
+            error,
+            cause: HandshakeFailureCause::from_code(error.code()),
+            trust_error: None,
+            verification: None,
+        }
+    }
+
+    /// Adds the result of evaluating the peer's trust.
+    ///
+    /// This is only kept if the failure was certificate-related.
+    #[must_use]
+    pub fn with_trust_error(mut self, trust_error: impl Into<DomainError>) -> Self {
+        if self.is_certificate_problem() {
+            self.trust_error = Some(trust_error.into());
+        }
+        self
+    }
+
+    /// A certificate failure, which is `Untrusted` unless `error` says more.
+    fn certificate(error: Error) -> Self {
+        let cause = match HandshakeFailureCause::from_code(error.code()) {
+            cause @ HandshakeFailureCause::Certificate(_) => cause,
+            _ => HandshakeFailureCause::Certificate(CertificateProblem::Untrusted),
+        };
+        Self { error, cause, trust_error: None, verification: None }
+    }
+
+    /// A certificate failure with the error of the trust evaluation that rejected it.
+    fn from_trust_error(trust_error: DomainError) -> Self {
+        let error = trust_error.status().unwrap_or_else(|| Error::from_code(errSecNotTrusted));
+        Self { trust_error: Some(trust_error), ..Self::certificate(error) }
+    }
+
+    /// A certificate failure with the report of the software verifier that rejected it.
+    fn from_verification(verification: Verification, error: Error) -> Self {
+        Self { verification: Some(verification), ..Self::certificate(error) }
+    }
+
+    /// Evaluates the trust and adds its error, if it fails.
+    #[must_use]
+    pub fn with_trust(self, trust: &SecTrust) -> Self {
+        match trust.evaluate_with_error() {
+            Ok(()) => self,
+            Err(err) => self.with_trust_error(err),
+        }
+    }
+
+    /// The error the handshake failed with.
+    #[inline]
+    #[must_use]
+    pub fn error(&self) -> Error {
+        self.error
+    }
+
+    /// The classified cause.
+    #[inline]
+    #[must_use]
+    pub fn cause(&self) -> HandshakeFailureCause {
+        self.cause
+    }
+
+    /// The alert sent by the peer, if the peer aborted the handshake.
+    #[must_use]
+    pub fn alert(&self) -> Option<TlsAlert> {
+        match self.cause {
+            HandshakeFailureCause::PeerAlert(alert) => Some(alert),
+            _ => None,
+        }
+    }
+
+    /// The failing trust evaluation, if one was added.
+    #[inline]
+    #[must_use]
+    pub fn trust_error(&self) -> Option<&DomainError> {
+        self.trust_error.as_ref()
+    }
+
+    /// The report of the software verifier, if it rejected the certificate.
+    #[inline]
+    #[must_use]
+    pub fn verification(&self) -> Option<&Verification> {
+        self.verification.as_ref()
+    }
+
+    /// Returns `true` if the certificate was rejected locally.
+    #[must_use]
+    pub fn is_certificate_problem(&self) -> bool {
+        matches!(self.cause, HandshakeFailureCause::Certificate(_))
+    }
+}
+
+impl fmt::Display for HandshakeFailure {
+    #[cold]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "TLS handshake failed: {} ({})", self.cause, self.error)?;
+        if let Some(trust_error) = &self.trust_error {
+            write!(f, "; trust evaluation: {trust_error}")?;
+        }
+        if self.verification.is_some() {
+            f.write_str("; rejected by the software verifier")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for HandshakeFailure {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
 /// An SSL stream midway through the handshake process.
 #[derive(Debug)]
 pub struct MidHandshakeSslStream<S> {
@@ -242,6 +538,8 @@ pub struct MidHandshakeClientBuilder<S> {
     certs: Vec<SecCertificate>,
     trust_certs_only: bool,
     danger_accept_invalid_certs: bool,
+    software_verifier: Option<SoftwareVerifier>,
+    tofu_entry: Option<TofuEntry>,
 }
 
 impl<S> MidHandshakeClientBuilder<S> {
@@ -273,6 +571,8 @@ impl<S> MidHandshakeClientBuilder<S> {
             certs,
             trust_certs_only,
             danger_accept_invalid_certs,
+            software_verifier,
+            tofu_entry,
         } = self;
 
         let mut result = stream.handshake();
@@ -290,6 +590,8 @@ impl<S> MidHandshakeClientBuilder<S> {
                     certs,
                     trust_certs_only,
                     danger_accept_invalid_certs,
+                    software_verifier,
+                    tofu_entry,
                 };
                 return Err(ClientHandshakeError::Interrupted(ret));
             }
@@ -307,11 +609,37 @@ impl<S> MidHandshakeClientBuilder<S> {
                 trust.set_trust_anchor_certificates_only(self.trust_certs_only)?;
                 let policy = SecPolicy::create_ssl(SslProtocolSide::SERVER, domain.as_deref());
                 trust.set_policy(&policy)?;
-                trust.evaluate_with_error().map_err(|error| {
+                if let Some(entry) = &tofu_entry {
+                    let applied = entry.apply(&mut trust).map_err(|error| {
+                        #[cfg(feature = "log")]
+                        log::warn!("the server's key doesn't match the one approved on first use");
+                        ClientHandshakeError::TrustFailure(HandshakeFailure::from_trust_error(error.into()))
+                    })?;
+                    if !applied {
+                        #[cfg(feature = "log")]
+                        log::info!("the exceptions approved on first use were for an older certificate, and don't apply");
+                    }
+                }
+                if let Err(error) = trust.evaluate_with_error() {
                     #[cfg(feature = "log")]
                     log::warn!("SecTrustEvaluateWithError: {error}");
-                    Error::from_code(error.code() as _)
-                })?;
+                    return Err(ClientHandshakeError::TrustFailure(HandshakeFailure::from_trust_error(error.into())));
+                }
+                if let Some(verifier) = &software_verifier {
+                    let mut verifier = verifier.clone();
+                    verifier.set_hostname(domain.as_deref()).require_extended_key_usage(Oid::SERVER_AUTH);
+                    let verification = verifier.verify_trust(&trust).map_err(|error| {
+                        ClientHandshakeError::TrustFailure(HandshakeFailure::from_trust_error(error.into()))
+                    })?;
+                    if verification.is_inconclusive() {
+                        #[cfg(feature = "log")]
+                        log::info!("software verifier can't check the server's certificates:\n{verification}");
+                    } else if let Some(error) = verification.error() {
+                        #[cfg(feature = "log")]
+                        log::warn!("software verifier disagrees with SecTrust:\n{verification}");
+                        return Err(ClientHandshakeError::TrustFailure(HandshakeFailure::from_verification(verification, error)));
+                    }
+                }
                 result = stream.handshake();
                 continue;
             }
@@ -1021,7 +1349,7 @@ impl<S> SslStream<S> {
         if let Some(err) = self.connection_mut().err.take() {
             err
         } else {
-            io::Error::new(io::ErrorKind::Other, Error::from_code(ret))
+            Error::from_code(ret).into()
         }
     }
 }
@@ -1111,6 +1439,8 @@ pub struct ClientBuilder {
     blacklisted_ciphers: Vec<CipherSuite>,
     alpn: Option<Vec<Box<str>>>,
     enable_session_tickets: bool,
+    software_verifier: Option<SoftwareVerifier>,
+    tofu_store: Option<TofuStore>,
 }
 
 impl Default for ClientBuilder {
@@ -1139,6 +1469,8 @@ impl ClientBuilder {
             blacklisted_ciphers: Vec::new(),
             alpn: None,
             enable_session_tickets: false,
+            software_verifier: None,
+            tofu_store: None,
         }
     }
 
@@ -1251,6 +1583,31 @@ impl ClientBuilder {
         self
     }
 
+    /// Additionally verifies the server's certificate with `verifier` once `SecTrust` has accepted it,
+    /// failing the handshake if the two disagree.
+    ///
+    /// The verifier also checks the server authentication extended key usage, and the hostname
+    /// unless hostname verification is disabled. Ignored if invalid certificates are accepted, and
+    /// when the verification is [inconclusive](crate::trust::software::Verification::is_inconclusive) because the chain uses signatures
+    /// it can't check.
+    pub fn software_verifier(&mut self, verifier: Option<SoftwareVerifier>) -> &mut Self {
+        self.software_verifier = verifier;
+        self
+    }
+
+    /// Consults `store` for certificates approved on first use.
+    ///
+    /// If the domain is in the store, the server must present the approved public key, and the
+    /// stored exceptions are applied before evaluating trust. A different key fails the handshake
+    /// with [`ClientHandshakeError::TrustFailure`]. Other domains are unaffected.
+    ///
+    /// Like trust evaluation, this is skipped if invalid certificates are accepted, so the key isn't
+    /// checked either.
+    pub fn tofu_store(&mut self, store: Option<TofuStore>) -> &mut Self {
+        self.tofu_store = store;
+        self
+    }
+
     /// Initiates a new SSL/TLS session over a stream connected to the specified domain.
     ///
     /// If both SNI and hostname verification are disabled, the value of `domain` will be ignored.
@@ -1280,6 +1637,8 @@ impl ClientBuilder {
             certs,
             trust_certs_only: self.trust_certs_only,
             danger_accept_invalid_certs: self.danger_accept_invalid_certs,
+            software_verifier: self.software_verifier.clone(),
+            tofu_entry: self.tofu_store.as_ref().and_then(|store| store.get(domain)).cloned(),
         };
         stream.handshake()
     }
@@ -1404,6 +1763,80 @@ mod test {
 
     use super::*;
 
+    #[test]
+    fn handshake_failure_cause() {
+        use security_framework_sys::base::errSecHostNameMismatch;
+
+        assert_eq!(
+            HandshakeFailureCause::from_code(errSSLPeerUnknownCA),
+            HandshakeFailureCause::PeerAlert(TlsAlert(48))
+        );
+        assert_eq!(
+            HandshakeFailureCause::from_code(errSSLPeerProtocolVersion),
+            HandshakeFailureCause::PeerAlert(TlsAlert(70))
+        );
+        assert_eq!(
+            HandshakeFailureCause::from_code(errSSLXCertChainInvalid),
+            HandshakeFailureCause::Certificate(CertificateProblem::Invalid)
+        );
+        assert_eq!(
+            HandshakeFailureCause::from_code(errSSLCertExpired),
+            HandshakeFailureCause::Certificate(CertificateProblem::Expired)
+        );
+        assert_eq!(
+            HandshakeFailureCause::from_code(errSecHostNameMismatch),
+            HandshakeFailureCause::Certificate(CertificateProblem::HostnameMismatch)
+        );
+        assert_eq!(
+            HandshakeFailureCause::from_code(errSSLUnknownRootCert),
+            HandshakeFailureCause::Certificate(CertificateProblem::Untrusted)
+        );
+        assert_eq!(HandshakeFailureCause::from_code(errSSLNegotiation), HandshakeFailureCause::Protocol);
+        assert_eq!(HandshakeFailureCause::from_code(errSSLClosedAbort), HandshakeFailureCause::Closed);
+        assert_eq!(HandshakeFailureCause::from_code(errSecIO), HandshakeFailureCause::Io);
+        assert_eq!(HandshakeFailureCause::from_code(errSSLInternal), HandshakeFailureCause::Other);
+        // a local parameter error, not the peer's illegal_parameter alert
+        assert_eq!(HandshakeFailureCause::from_code(errSSLIllegalParam), HandshakeFailureCause::Other);
+
+        for (code, alert, name) in PEER_ALERTS {
+            assert_eq!(HandshakeFailureCause::from_code(code), HandshakeFailureCause::PeerAlert(TlsAlert(alert)));
+            assert_eq!(TlsAlert(alert).name(), Some(name));
+        }
+        assert_eq!(TlsAlert(48).to_string(), "unknown_ca (48)");
+    }
+
+    #[test]
+    fn handshake_failure_report() {
+        use security_framework_sys::base::errSecHostNameMismatch;
+
+        let report = HandshakeFailure::new(Error::from_code(errSSLPeerHandshakeFail))
+            .with_trust_error(DomainError::new("test", 1));
+        assert_eq!(report.alert(), Some(TlsAlert(40)));
+        assert!(report.trust_error().is_none());
+
+        let report = HandshakeFailure::new(Error::from_code(errSSLCertExpired))
+            .with_trust_error(DomainError::new("test", 1).with_description("expired"));
+        assert!(report.is_certificate_problem());
+        assert!(report.to_string().contains("has expired"));
+        assert!(report.to_string().ends_with("trust evaluation: expired"));
+
+        let trust_error = DomainError::from(Error::from_code(errSecHostNameMismatch)).with_description("wrong host");
+        let report = HandshakeFailure::from_trust_error(trust_error.clone());
+        assert_eq!(report.cause(), HandshakeFailureCause::Certificate(CertificateProblem::HostnameMismatch));
+        assert_eq!(report.trust_error(), Some(&trust_error));
+        let report = HandshakeFailure::from_trust_error(DomainError::new("test", 1));
+        assert_eq!(report.cause(), HandshakeFailureCause::Certificate(CertificateProblem::Untrusted));
+        assert!(report.trust_error().is_some());
+
+        let server = crate::certificate::Parsed::from_der(include_bytes!("../test/server.der")).unwrap();
+        let verification = SoftwareVerifier::new([]).verify(&server, &[]);
+        let report = HandshakeFailure::from_verification(verification.clone(), verification.error().unwrap());
+        assert!(report.is_certificate_problem());
+        assert!(report.verification().is_some_and(|v| !v.is_trusted()));
+        assert!(report.trust_error().is_none());
+        assert!(report.to_string().ends_with("rejected by the software verifier"));
+    }
+
     #[test]
     fn server_builder_from_pkcs12() {
         let pkcs12_der = include_bytes!("../test/server.p12");
@@ -0,0 +1,37 @@
+//! Software signature verification, for checking certificates without Security.framework.
+
+use security_framework_sys::base::errSecUnimplemented;
+
+use crate::base::{Error, Result};
+use crate::certificate::{AlgorithmIdentifier, SubjectPublicKeyInfo};
+use crate::der::Oid;
+use crate::digest::DigestAlgorithm;
+use crate::{ecdsa, rsa};
+
+/// Checks a signature made with a `sha*WithRSAEncryption` or `ecdsa-with-SHA*` algorithm.
+///
+/// Fails with `errSecUnimplemented` for other algorithms, and EC keys on curves other than P-256
+/// and P-384. A signature made with a different type of key than the signer's is invalid.
+pub(crate) fn verify_signature(public_key_info: &SubjectPublicKeyInfo, algorithm: &AlgorithmIdentifier, message: &[u8], signature: &[u8]) -> Result<bool> {
+    let (key_algorithm, digest) = match algorithm.oid() {
+        oid if *oid == Oid::SHA1_WITH_RSA => (Oid::RSA_ENCRYPTION, DigestAlgorithm::Sha1),
+        oid if *oid == Oid::SHA256_WITH_RSA => (Oid::RSA_ENCRYPTION, DigestAlgorithm::Sha256),
+        oid if *oid == Oid::SHA384_WITH_RSA => (Oid::RSA_ENCRYPTION, DigestAlgorithm::Sha384),
+        oid if *oid == Oid::SHA512_WITH_RSA => (Oid::RSA_ENCRYPTION, DigestAlgorithm::Sha512),
+        oid if *oid == Oid::ECDSA_WITH_SHA256 => (Oid::EC_PUBLIC_KEY, DigestAlgorithm::Sha256),
+        oid if *oid == Oid::ECDSA_WITH_SHA384 => (Oid::EC_PUBLIC_KEY, DigestAlgorithm::Sha384),
+        oid if *oid == Oid::ECDSA_WITH_SHA512 => (Oid::EC_PUBLIC_KEY, DigestAlgorithm::Sha512),
+        _ => return Err(Error::from_code(errSecUnimplemented)),
+    };
+    // e.g. an ECDSA signature by an RSA key
+    if *public_key_info.algorithm().oid() != key_algorithm {
+        return Ok(false);
+    }
+    if key_algorithm == Oid::RSA_ENCRYPTION {
+        let key = rsa::PublicKey::from_pkcs1(public_key_info.public_key())?;
+        Ok(key.verify_pkcs1(digest, message, signature))
+    } else {
+        let key = ecdsa::PublicKey::from_public_key_info(public_key_info)?;
+        Ok(key.verify(digest, message, signature))
+    }
+}
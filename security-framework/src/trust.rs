@@ -1,23 +1,51 @@
 //! Trust evaluation support.
+//!
+//! [`TrustResult`], [`TrustReport`], [`ConstraintChecker`], the [`software`] verifier and the [`tofu`] store
+//! are pure Rust and available on all platforms.
+#[cfg(target_vendor = "apple")]
 use core_foundation::array::CFArray;
 #[cfg(target_os = "macos")]
 use core_foundation::array::CFArrayRef;
-use core_foundation::base::TCFType;
+#[cfg(target_vendor = "apple")]
+use core_foundation::base::{CFType, TCFType};
+#[cfg(target_vendor = "apple")]
 use core_foundation::data::CFData;
+#[cfg(target_vendor = "apple")]
 use core_foundation::date::CFDate;
+#[cfg(target_vendor = "apple")]
 use core_foundation::{declare_TCFType, impl_TCFType};
+#[cfg(target_vendor = "apple")]
 use core_foundation_sys::base::{Boolean, CFIndex};
 
 use security_framework_sys::trust::*;
+#[cfg(target_vendor = "apple")]
 use std::ptr;
 
-use crate::base::Result;
+#[cfg(target_vendor = "apple")]
+use crate::base::{Error, Result};
+#[cfg(target_vendor = "apple")]
 use crate::certificate::SecCertificate;
+#[cfg(target_vendor = "apple")]
 use crate::cvt;
+#[cfg(target_vendor = "apple")]
 use crate::key::SecKey;
+#[cfg(target_vendor = "apple")]
+use crate::plist::Value;
+#[cfg(target_vendor = "apple")]
 use crate::policy::SecPolicy;
+#[cfg(target_vendor = "apple")]
+use security_framework_sys::base::{errSecDecode, errSecInternalComponent};
+#[cfg(target_vendor = "apple")]
 use core_foundation::error::{CFError, CFErrorRef};
 
+mod constraints;
+mod report;
+pub mod software;
+pub mod tofu;
+
+pub use constraints::{ConstraintChecker, ConstraintReport, ConstraintViolation, Subtree};
+pub use report::{CertificateStatus, TrustProblem, TrustReport};
+
 /// The result of trust evaluation.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct TrustResult(SecTrustResultType);
@@ -48,13 +76,17 @@ impl TrustResult {
     }
 }
 
+#[cfg(target_vendor = "apple")]
 declare_TCFType! {
     /// A type representing a trust evaluation for a certificate.
     SecTrust, SecTrustRef
 }
+#[cfg(target_vendor = "apple")]
 impl_TCFType!(SecTrust, SecTrustRef, SecTrustGetTypeID);
 
+#[cfg(target_vendor = "apple")]
 unsafe impl Sync for SecTrust {}
+#[cfg(target_vendor = "apple")]
 unsafe impl Send for SecTrust {}
 
 #[cfg(target_os = "macos")]
@@ -79,6 +111,7 @@ bitflags::bitflags! {
     }
 }
 
+#[cfg(target_vendor = "apple")]
 impl SecTrust {
     /// Creates a `SecTrustRef` that is configured with a certificate chain, for validating
     /// that chain against a collection of policies.
@@ -190,6 +223,9 @@ impl SecTrust {
     }
 
     /// Attaches signed certificate timestamp data to this trust object.
+    ///
+    /// Each item is one serialized SCT, e.g. from
+    /// [`SignedCertificateTimestamp::as_bytes`](crate::sct::SignedCertificateTimestamp::as_bytes).
     pub fn set_signed_certificate_timestamps<I: Iterator<Item = impl AsRef<[u8]>>>(
         &mut self,
         scts: I,
@@ -236,6 +272,46 @@ impl SecTrust {
         Ok(())
     }
 
+    /// Returns the details of the last evaluation: per-certificate problems, EV, Certificate
+    /// Transparency and revocation status, and the exceptions that would make it succeed.
+    ///
+    /// Note: evaluate must first be called on the `SecTrust`.
+    pub fn result_details(&self) -> Result<TrustReport> {
+        let result = unsafe { SecTrustCopyResult(self.0) };
+        if result.is_null() {
+            return Err(Error::from_code(errSecInternalComponent));
+        }
+        let result = unsafe { CFType::wrap_under_create_rule(result.cast()) };
+        let result = Value::from_cf(&result).ok_or_else(|| Error::from_code(errSecDecode))?;
+
+        TrustReport::from_result(&result, self.exceptions())
+    }
+
+    /// Returns an opaque blob that makes a later evaluation of the same leaf certificate succeed
+    /// despite the failures of the last one, e.g. after a user approved a self-signed certificate.
+    ///
+    /// Note: evaluate must first be called on the `SecTrust`.
+    #[must_use]
+    pub fn exceptions(&self) -> Option<Vec<u8>> {
+        let exceptions = unsafe { SecTrustCopyExceptions(self.0) };
+        (!exceptions.is_null()).then(|| unsafe { CFData::wrap_under_create_rule(exceptions) }.bytes().to_vec())
+    }
+
+    /// Applies exceptions returned by [`exceptions`](Self::exceptions) to later evaluations,
+    /// or clears them with `None`.
+    ///
+    /// Returns `false` if the exceptions were made for a different leaf certificate, in which
+    /// case they are ignored.
+    pub fn set_exceptions(&mut self, exceptions: Option<&[u8]>) -> bool {
+        match exceptions {
+            Some(exceptions) => {
+                let exceptions = CFData::from_buffer(exceptions);
+                unsafe { SecTrustSetExceptions(self.0, exceptions.as_concrete_TypeRef()) }
+            },
+            None => unsafe { SecTrustSetExceptions(self.0, ptr::null()) },
+        }
+    }
+
     /// Gets the whole evaluated certificate chain.
     ///
     /// Note: evaluate must first be called on the `SecTrust`.
@@ -280,12 +356,12 @@ impl SecTrust {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, target_vendor = "apple"))]
 mod test {
     use crate::policy::SecPolicy;
     use crate::secure_transport::SslProtocolSide;
     use crate::test::certificate;
-    use crate::trust::SecTrust;
+    use crate::trust::{SecTrust, TrustProblem};
 
     #[test]
     #[allow(deprecated)]
@@ -304,6 +380,34 @@ mod test {
         assert!(trust.evaluate_with_error().is_err());
     }
 
+    #[test]
+    fn result_details() {
+        let cert = certificate();
+        let ssl_policy = SecPolicy::create_ssl(SslProtocolSide::CLIENT, Some("certifi.io"));
+        let trust = SecTrust::create_with_certificates(&[cert], &[ssl_policy]).unwrap();
+        assert!(trust.evaluate_with_error().is_err());
+
+        let report = trust.result_details().unwrap();
+        assert!(!report.is_trusted());
+        assert!(report.evaluation_date().is_some());
+        assert!(report.certificates()[0].has(&TrustProblem::Hostname));
+        assert!(report.exceptions().is_some());
+    }
+
+    #[test]
+    fn exceptions() {
+        let cert = certificate();
+        let ssl_policy = SecPolicy::create_ssl(SslProtocolSide::CLIENT, Some("foobar.com"));
+        let mut trust = SecTrust::create_with_certificates(&[cert], &[ssl_policy]).unwrap();
+        assert!(trust.evaluate_with_error().is_err());
+
+        let exceptions = trust.exceptions().unwrap();
+        assert!(trust.set_exceptions(Some(&exceptions)));
+        trust.evaluate_with_error().unwrap();
+        assert!(trust.set_exceptions(None));
+        assert!(trust.evaluate_with_error().is_err());
+    }
+
     #[test]
     #[allow(deprecated)]
     fn certificate_count_and_at_index() {
@@ -0,0 +1,675 @@
+//! Checking basic constraints, name constraints and extended key usage along a chain (RFC 5280 6.1).
+
+use std::net::IpAddr;
+
+use security_framework_sys::base::{
+    errSecCertificateNameNotAllowed, errSecDecode, errSecInvalidExtendedKeyUsage, errSecInvalidKeyUsageForPolicy,
+    errSecNoBasicConstraintsCA, errSecPathLengthConstraintExceeded, errSecUnimplemented,
+};
+
+use crate::base::{Error, Result};
+#[cfg(target_vendor = "apple")]
+use crate::certificate::SecCertificate;
+use crate::certificate::{DistinguishedName, GeneralName, KeyUsage, Parsed};
+use crate::der::{self, Oid, Reader};
+#[cfg(all(target_vendor = "apple", any(feature = "macos-12", not(target_os = "macos"))))]
+use crate::trust::SecTrust;
+
+/// A name constraint subtree (RFC 5280 4.2.1.10).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Subtree {
+    /// DNS names equal to or under this domain: `corp.example` matches `corp.example`
+    /// and `a.corp.example`, while `.corp.example` only matches the latter.
+    DnsName(String),
+    /// Email addresses: a full mailbox, all mailboxes at a host, or with a leading `.`, at any host in a domain.
+    Rfc822Name(String),
+    /// URIs whose host is this host, or with a leading `.`, any host in this domain.
+    Uri(String),
+    /// IP addresses in a network.
+    IpAddress {
+        /// The network address.
+        network: IpAddr,
+        /// The number of leading bits that must match.
+        prefix_len: u8,
+    },
+    /// Directory names that start with these RDNs.
+    DirectoryName(DistinguishedName),
+}
+
+impl Subtree {
+    /// Fails for iPAddress subtrees with an invalid mask, and with `errSecUnimplemented` for
+    /// name forms that can't be checked, since ignoring them would allow any such name.
+    fn parse(reader: &mut Reader<'_>) -> Result<Self> {
+        let mut subtree = reader.read_sequence()?;
+        let base = subtree.read_tlv()?;
+        // minimum and maximum are unused in this profile
+        subtree.read_optional(der::context_primitive(0))?;
+        subtree.read_optional(der::context_primitive(1))?;
+        subtree.finish()?;
+
+        if base.tag == der::context_primitive(7) {
+            return ip_subtree(base.content).ok_or_else(der::decode_error);
+        }
+        Ok(match GeneralName::parse(&mut Reader::new(base.raw))? {
+            GeneralName::DnsName(name) => Self::DnsName(name),
+            GeneralName::Rfc822Name(name) => Self::Rfc822Name(name),
+            GeneralName::Uri(host) => Self::Uri(host),
+            GeneralName::DirectoryName(name) => Self::DirectoryName(name),
+            _ => return Err(Error::from_code(errSecUnimplemented)),
+        })
+    }
+
+    /// Whether `name` is within this subtree, or `None` if they are of different types.
+    #[must_use]
+    pub fn matches(&self, name: &GeneralName) -> Option<bool> {
+        match (self, name) {
+            (Self::DnsName(subtree), GeneralName::DnsName(name)) => Some(domain_within(name, subtree)),
+            (Self::Rfc822Name(subtree), GeneralName::Rfc822Name(name)) => Some(email_within(name, subtree)),
+            (Self::Uri(subtree), GeneralName::Uri(uri)) => Some(uri_host(uri).is_some_and(|host| host_within(host, subtree))),
+            (Self::IpAddress { network, prefix_len }, GeneralName::IpAddress(ip)) => Some(ip_within(*ip, *network, *prefix_len)),
+            (Self::DirectoryName(subtree), GeneralName::DirectoryName(name)) => {
+                Some(subtree.rdns().len() <= name.rdns().len() && subtree.rdns().iter().zip(name.rdns()).all(|(a, b)| a.matches(b)))
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Address and mask, as 8 or 32 bytes. The mask must be contiguous.
+fn ip_subtree(bytes: &[u8]) -> Option<Subtree> {
+    let (network, mask): (IpAddr, &[u8]) = match bytes.len() {
+        8 => (<[u8; 4]>::try_from(&bytes[..4]).ok()?.into(), &bytes[4..]),
+        32 => (<[u8; 16]>::try_from(&bytes[..16]).ok()?.into(), &bytes[16..]),
+        _ => return None,
+    };
+    let prefix_len: u32 = mask.iter().map(|b| b.leading_ones()).take_while(|&n| n > 0).sum();
+    let bits = mask.iter().flat_map(|b| (0..8).rev().map(move |i| b >> i & 1 == 1));
+    if bits.enumerate().any(|(i, bit)| bit != (i < prefix_len as usize)) {
+        return None;
+    }
+    Some(Subtree::IpAddress { network, prefix_len: u8::try_from(prefix_len).ok()? })
+}
+
+fn domain_within(name: &str, subtree: &str) -> bool {
+    let name = name.strip_suffix('.').unwrap_or(name).to_ascii_lowercase();
+    let subtree = subtree.strip_suffix('.').unwrap_or(subtree).to_ascii_lowercase();
+    if subtree.is_empty() {
+        return true;
+    }
+    if subtree.starts_with('.') {
+        return name.ends_with(&subtree);
+    }
+    name == subtree || name.strip_suffix(&subtree).is_some_and(|rest| rest.ends_with('.'))
+}
+
+fn host_within(host: &str, subtree: &str) -> bool {
+    if subtree.starts_with('.') { domain_within(host, subtree) } else { host.eq_ignore_ascii_case(subtree) }
+}
+
+fn email_within(name: &str, subtree: &str) -> bool {
+    let Some((local, host)) = name.rsplit_once('@') else { return false };
+    match subtree.rsplit_once('@') {
+        Some((subtree_local, subtree_host)) => local == subtree_local && host.eq_ignore_ascii_case(subtree_host),
+        None => host_within(host, subtree),
+    }
+}
+
+/// The host of a URI with an authority, without user info or port.
+fn uri_host(uri: &str) -> Option<&str> {
+    let (_, rest) = uri.split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+    let host = match host.strip_prefix('[') {
+        Some(bracketed) => bracketed.split_once(']')?.0,
+        None => host.split(':').next()?,
+    };
+    (!host.is_empty()).then_some(host)
+}
+
+fn ip_within(ip: IpAddr, network: IpAddr, prefix_len: u8) -> bool {
+    let (ip, network) = match (ip, network) {
+        (IpAddr::V4(ip), IpAddr::V4(network)) => (ip.octets().to_vec(), network.octets().to_vec()),
+        (IpAddr::V6(ip), IpAddr::V6(network)) => (ip.octets().to_vec(), network.octets().to_vec()),
+        _ => return false,
+    };
+    let prefix_len = usize::from(prefix_len);
+    ip.iter().zip(&network).enumerate().all(|(i, (a, b))| {
+        let bits = prefix_len.saturating_sub(8 * i).min(8);
+        let mask = if bits == 0 { 0 } else { 0xffu8 << (8 - bits) };
+        a & mask == b & mask
+    })
+}
+
+/// Decodes the name constraints extension value into permitted and excluded subtrees.
+fn parse_name_constraints(value: &[u8]) -> Result<(Vec<Subtree>, Vec<Subtree>)> {
+    let mut reader = Reader::new(value);
+    let mut seq = reader.read_sequence()?;
+    reader.finish()?;
+    let mut subtrees = |tag| -> Result<Vec<Subtree>> {
+        let mut list = Vec::new();
+        if let Some(content) = seq.read_optional(tag)? {
+            let mut content = Reader::new(content);
+            while !content.is_empty() {
+                list.push(Subtree::parse(&mut content)?);
+            }
+        }
+        Ok(list)
+    };
+    let permitted = subtrees(der::context(0))?;
+    let excluded = subtrees(der::context(1))?;
+    seq.finish()?;
+    Ok((permitted, excluded))
+}
+
+/// One way a certificate breaks the chain's constraints.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConstraintViolation {
+    /// The certificate issued another one, but its basic constraints don't make it a CA.
+    NotCa,
+    /// The certificate issued another one, but its key usage doesn't include `keyCertSign`.
+    KeyCertSignNotAllowed,
+    /// More intermediates follow the certificate than its path length constraint,
+    /// or for the anchor, the checker's maximum, allows.
+    PathLengthExceeded {
+        /// The maximum number of non-self-issued intermediates.
+        limit: u32,
+        /// The number in the chain.
+        actual: u32,
+    },
+    /// A name isn't in the permitted subtrees.
+    NameNotPermitted {
+        /// The offending name.
+        name: GeneralName,
+        /// Index of the certificate with the name constraints, or `None` for the checker's own subtrees.
+        constrained_by: Option<usize>,
+    },
+    /// A name is in an excluded subtree.
+    NameExcluded {
+        /// The offending name.
+        name: GeneralName,
+        /// Index of the certificate with the name constraints, or `None` for the checker's own subtrees.
+        constrained_by: Option<usize>,
+    },
+    /// The extended key usage extension doesn't allow a required purpose.
+    ExtendedKeyUsage {
+        /// The purpose that isn't allowed.
+        missing: Oid,
+    },
+    /// An extension couldn't be decoded, or has name constraints of a form that isn't supported.
+    Malformed {
+        /// The extension.
+        extension: Oid,
+    },
+}
+
+impl ConstraintViolation {
+    /// The closest trust error code, e.g. `errSecPathLengthConstraintExceeded`.
+    #[must_use]
+    pub fn error(&self) -> Error {
+        Error::from_code(match self {
+            Self::NotCa => errSecNoBasicConstraintsCA,
+            Self::KeyCertSignNotAllowed => errSecInvalidKeyUsageForPolicy,
+            Self::PathLengthExceeded { .. } => errSecPathLengthConstraintExceeded,
+            Self::NameNotPermitted { .. } | Self::NameExcluded { .. } => errSecCertificateNameNotAllowed,
+            Self::ExtendedKeyUsage { .. } => errSecInvalidExtendedKeyUsage,
+            Self::Malformed { .. } => errSecDecode,
+        })
+    }
+}
+
+/// The violations found for each certificate of a chain, leaf first.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ConstraintReport {
+    violations: Vec<Vec<ConstraintViolation>>,
+}
+
+impl ConstraintReport {
+    /// Whether no certificate has violations.
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.violations.iter().all(Vec::is_empty)
+    }
+
+    /// The violations of the certificate at `index` in the chain.
+    #[must_use]
+    pub fn violations(&self, index: usize) -> &[ConstraintViolation] {
+        self.violations.get(index).map_or(&[], Vec::as_slice)
+    }
+
+    /// All violations with the index of their certificate, leaf first.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &ConstraintViolation)> {
+        self.violations.iter().enumerate().flat_map(|(i, list)| list.iter().map(move |v| (i, v)))
+    }
+
+    /// Fails with the error of the first violation, if any.
+    pub fn into_result(self) -> Result<()> {
+        match self.iter().next() {
+            Some((_, violation)) => Err(violation.error()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Enforces basic constraints, path length constraints, name constraints and extended key usage along a chain.
+///
+/// Besides the constraints in the certificates themselves, the checker can impose its own,
+/// as if the trust anchor had them. This is useful for private CAs, which rarely carry name constraints:
+///
+/// ```rust,ignore
+/// let mut checker = ConstraintChecker::new();
+/// checker
+///     .add_permitted_subtree(Subtree::DnsName("corp.example".into()))
+///     .set_max_path_length(Some(1))
+///     .require_extended_key_usage(Oid::SERVER_AUTH);
+/// checker.check_trust(&trust)?.into_result()?;
+/// ```
+///
+/// Signatures, validity periods and trust in the anchor are not checked; use it along with trust evaluation.
+#[derive(Debug, Clone, Default)]
+pub struct ConstraintChecker {
+    extended_key_usages: Vec<Oid>,
+    max_path_length: Option<u32>,
+    permitted: Vec<Subtree>,
+    excluded: Vec<Subtree>,
+}
+
+impl ConstraintChecker {
+    /// A checker that only enforces the constraints in the certificates.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requires every certificate with an extended key usage extension to allow `usage`.
+    pub fn require_extended_key_usage(&mut self, usage: Oid) -> &mut Self {
+        if !self.extended_key_usages.contains(&usage) {
+            self.extended_key_usages.push(usage);
+        }
+        self
+    }
+
+    /// Limits the number of non-self-issued intermediates between the leaf and the anchor.
+    pub fn set_max_path_length(&mut self, max_path_length: Option<u32>) -> &mut Self {
+        self.max_path_length = max_path_length;
+        self
+    }
+
+    /// Only allows names within `subtree` (and any other permitted subtrees of the same type).
+    pub fn add_permitted_subtree(&mut self, subtree: Subtree) -> &mut Self {
+        self.permitted.push(subtree);
+        self
+    }
+
+    /// Forbids names within `subtree`.
+    pub fn add_excluded_subtree(&mut self, subtree: Subtree) -> &mut Self {
+        self.excluded.push(subtree);
+        self
+    }
+
+    /// Checks a chain ordered from the leaf to the anchor, as from `SecTrust::chain`.
+    #[must_use]
+    pub fn check(&self, chain: &[Parsed]) -> ConstraintReport {
+        let mut violations = vec![Vec::new(); chain.len()];
+        let Some(anchor) = chain.len().checked_sub(1) else { return ConstraintReport { violations } };
+
+        // names of each certificate that name constraints apply to; self-issued intermediates are exempt
+        let names: Vec<Option<Vec<GeneralName>>> = chain
+            .iter()
+            .enumerate()
+            .map(|(i, cert)| {
+                if i > 0 && cert.is_self_issued() {
+                    return None;
+                }
+                let names = constrained_names(cert);
+                if names.is_none() {
+                    violations[i].push(ConstraintViolation::Malformed { extension: Oid::SUBJECT_ALT_NAME });
+                }
+                names
+            })
+            .collect();
+
+        for (i, cert) in chain.iter().enumerate() {
+            if i > 0 {
+                check_issuer(chain, i, &names, &mut violations);
+            }
+            match cert.extended_key_usage() {
+                Ok(Some(usages)) if !usages.contains(&Oid::ANY_EXTENDED_KEY_USAGE) => {
+                    for usage in self.extended_key_usages.iter().filter(|&usage| !usages.contains(usage)) {
+                        violations[i].push(ConstraintViolation::ExtendedKeyUsage { missing: usage.clone() });
+                    }
+                },
+                Ok(_) => {},
+                Err(_) => violations[i].push(ConstraintViolation::Malformed { extension: Oid::EXTENDED_KEY_USAGE }),
+            }
+        }
+
+        if let Some(limit) = self.max_path_length {
+            let actual = intermediates_below(chain, anchor);
+            if actual > limit {
+                violations[anchor].push(ConstraintViolation::PathLengthExceeded { limit, actual });
+            }
+        }
+        for (j, names) in names.iter().enumerate().take(anchor.max(1)) {
+            for name in names.iter().flatten() {
+                if let Some(violation) = name_violation(name, &self.permitted, &self.excluded, None) {
+                    violations[j].push(violation);
+                }
+            }
+        }
+
+        ConstraintReport { violations }
+    }
+
+    /// Checks certificates ordered from the leaf to the anchor.
+    #[cfg(target_vendor = "apple")]
+    pub fn check_certificates(&self, chain: &[SecCertificate]) -> Result<ConstraintReport> {
+        let chain = chain.iter().map(SecCertificate::parse).collect::<Result<Vec<_>>>()?;
+        Ok(self.check(&chain))
+    }
+
+    /// Checks the chain built by evaluating `trust`.
+    #[cfg(all(target_vendor = "apple", any(feature = "macos-12", not(target_os = "macos"))))]
+    pub fn check_trust(&self, trust: &SecTrust) -> Result<ConstraintReport> {
+        self.check_certificates(&trust.chain())
+    }
+}
+
+/// Checks the certificate at `i`, which issued the one before it.
+fn check_issuer(chain: &[Parsed], i: usize, names: &[Option<Vec<GeneralName>>], violations: &mut [Vec<ConstraintViolation>]) {
+    let cert = &chain[i];
+    let is_v1_anchor = i == chain.len() - 1 && cert.version() < 3;
+    match cert.basic_constraints() {
+        Ok(Some(constraints)) if constraints.is_ca() => {
+            if let Some(limit) = constraints.path_len_constraint() {
+                let actual = intermediates_below(chain, i);
+                if actual > limit {
+                    violations[i].push(ConstraintViolation::PathLengthExceeded { limit, actual });
+                }
+            }
+        },
+        Ok(None) if is_v1_anchor => {},
+        Ok(_) => violations[i].push(ConstraintViolation::NotCa),
+        Err(_) => violations[i].push(ConstraintViolation::Malformed { extension: Oid::BASIC_CONSTRAINTS }),
+    }
+    match cert.key_usage() {
+        Ok(Some(usage)) if !usage.contains(KeyUsage::KEY_CERT_SIGN) => violations[i].push(ConstraintViolation::KeyCertSignNotAllowed),
+        Ok(_) => {},
+        Err(_) => violations[i].push(ConstraintViolation::Malformed { extension: Oid::KEY_USAGE }),
+    }
+
+    let Some(extension) = cert.extension(&Oid::NAME_CONSTRAINTS) else { return };
+    let Ok((permitted, excluded)) = parse_name_constraints(extension.value()) else {
+        violations[i].push(ConstraintViolation::Malformed { extension: Oid::NAME_CONSTRAINTS });
+        return;
+    };
+    for (j, names) in names.iter().enumerate().take(i) {
+        for name in names.iter().flatten() {
+            if let Some(violation) = name_violation(name, &permitted, &excluded, Some(i)) {
+                violations[j].push(violation);
+            }
+        }
+    }
+}
+
+/// The number of non-self-issued intermediates between the leaf and the certificate at `index`.
+fn intermediates_below(chain: &[Parsed], index: usize) -> u32 {
+    let count = chain.get(1..index).unwrap_or_default().iter().filter(|cert| !cert.is_self_issued()).count();
+    u32::try_from(count).unwrap_or(u32::MAX)
+}
+
+/// The subject, any email addresses in it, and the subject alternative names.
+fn constrained_names(cert: &Parsed) -> Option<Vec<GeneralName>> {
+    let mut names = Vec::new();
+    if !cert.subject().is_empty() {
+        names.push(GeneralName::DirectoryName(cert.subject().clone()));
+    }
+    names.extend(cert.subject().attribute_values(&Oid::EMAIL_ADDRESS).map(|email| GeneralName::Rfc822Name(email.into())));
+    names.extend(cert.subject_alt_names().ok()?);
+    Some(names)
+}
+
+fn name_violation(name: &GeneralName, permitted: &[Subtree], excluded: &[Subtree], constrained_by: Option<usize>) -> Option<ConstraintViolation> {
+    if excluded.iter().any(|subtree| subtree.matches(name) == Some(true)) {
+        return Some(ConstraintViolation::NameExcluded { name: name.clone(), constrained_by });
+    }
+    let mut same_type = permitted.iter().filter_map(|subtree| subtree.matches(name)).peekable();
+    if same_type.peek().is_some() && !same_type.any(|matched| matched) {
+        return Some(ConstraintViolation::NameNotPermitted { name: name.clone(), constrained_by });
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::certificate::fixtures::Issue;
+    use crate::certificate::{BasicConstraints, Extension};
+    use std::net::Ipv4Addr;
+
+    fn encode_subtrees(tag: u8, subtrees: &[GeneralName]) -> Vec<u8> {
+        let subtrees: Vec<_> = subtrees.iter().map(|name| der::encode_sequence(&[&name.to_der()])).collect();
+        der::encode(tag, &subtrees.concat())
+    }
+
+    fn name_constraints(permitted: &[GeneralName], excluded: &[GeneralName]) -> Extension {
+        let mut content = Vec::new();
+        if !permitted.is_empty() {
+            content.extend(encode_subtrees(der::context(0), permitted));
+        }
+        if !excluded.is_empty() {
+            content.extend(encode_subtrees(der::context(1), excluded));
+        }
+        Extension::new(Oid::NAME_CONSTRAINTS, true, der::encode(der::SEQUENCE, &content))
+    }
+
+    fn dns(name: &str) -> GeneralName {
+        GeneralName::DnsName(name.into())
+    }
+
+    #[test]
+    fn basic_constraints() {
+        let root = Issue::new("CN=Root", 1, None).build();
+        let intermediate = Issue { constraints: Some(BasicConstraints::ca(Some(0))), ..Issue::new("CN=Intermediate", 2, Some(&root)) }.build();
+        let leaf = Issue::leaf("CN=Leaf", 3, &intermediate, vec![dns("leaf.example")]).build();
+        let checker = ConstraintChecker::new();
+        assert!(checker.check(&[leaf.clone(), intermediate.clone(), root.clone()]).is_ok());
+        assert!(checker.check(&[]).is_ok());
+        assert!(checker.check(std::slice::from_ref(&leaf)).is_ok());
+
+        // pathLen 0 allows no intermediates after it
+        let sub = Issue::new("CN=Sub", 4, Some(&intermediate)).build();
+        let deep_leaf = Issue::leaf("CN=Leaf", 5, &sub, vec![]).build();
+        let report = checker.check(&[deep_leaf.clone(), sub.clone(), intermediate.clone(), root.clone()]);
+        assert_eq!(report.violations(2), [ConstraintViolation::PathLengthExceeded { limit: 0, actual: 1 }]);
+        assert_eq!(report.iter().count(), 1);
+        assert_eq!(report.into_result().unwrap_err().code(), errSecPathLengthConstraintExceeded);
+
+        // an end-entity certificate can't issue
+        let not_ca = Issue { constraints: Some(BasicConstraints::end_entity()), ..Issue::new("CN=Not CA", 6, Some(&root)) }.build();
+        let bad_leaf = Issue::leaf("CN=Leaf", 7, &not_ca, vec![]).build();
+        let report = checker.check(&[bad_leaf.clone(), not_ca.clone(), root.clone()]);
+        assert_eq!(report.violations(1), [ConstraintViolation::NotCa]);
+        assert!(report.violations(0).is_empty());
+        assert_eq!(report.into_result().unwrap_err().code(), errSecNoBasicConstraintsCA);
+        let no_constraints = Issue { constraints: None, ..Issue::new("CN=Not CA", 6, Some(&root)) }.build();
+        assert_eq!(checker.check(&[bad_leaf, no_constraints, root.clone()]).violations(1), [ConstraintViolation::NotCa]);
+
+        // the checker's own limit counts intermediates below the anchor
+        let mut limited = ConstraintChecker::new();
+        limited.set_max_path_length(Some(1));
+        assert!(limited.check(&[leaf.clone(), intermediate.clone(), root.clone()]).is_ok());
+        let report = limited.check(&[deep_leaf, sub, intermediate, root.clone()]);
+        assert_eq!(report.violations(3), [ConstraintViolation::PathLengthExceeded { limit: 1, actual: 2 }]);
+
+        // a single certificate, e.g. a leaf that is itself an anchor
+        limited.set_max_path_length(Some(0));
+        assert!(limited.check(&[root]).is_ok());
+    }
+
+    #[test]
+    fn key_usage() {
+        let root = Issue::new("CN=Root", 1, None).build();
+        let intermediate = Issue { key_usage: Some(KeyUsage::DIGITAL_SIGNATURE), ..Issue::new("CN=Intermediate", 2, Some(&root)) }.build();
+        let leaf = Issue::leaf("CN=Leaf", 3, &intermediate, vec![]).build();
+        let report = ConstraintChecker::new().check(&[leaf, intermediate, root]);
+        assert_eq!(report.violations(1), [ConstraintViolation::KeyCertSignNotAllowed]);
+    }
+
+    #[test]
+    fn extended_key_usage() {
+        let root = Issue::new("CN=Root", 1, None).build();
+        let intermediate = Issue { usages: vec![Oid::CLIENT_AUTH], ..Issue::new("CN=Intermediate", 2, Some(&root)) }.build();
+        let leaf = Issue { usages: vec![Oid::SERVER_AUTH, Oid::CLIENT_AUTH], ..Issue::leaf("CN=Leaf", 3, &intermediate, vec![]) }.build();
+        let chain = [leaf, intermediate, root];
+
+        assert!(ConstraintChecker::new().check(&chain).is_ok());
+        let mut checker = ConstraintChecker::new();
+        checker.require_extended_key_usage(Oid::CLIENT_AUTH);
+        assert!(checker.check(&chain).is_ok());
+        checker.require_extended_key_usage(Oid::SERVER_AUTH);
+        let report = checker.check(&chain);
+        // the leaf allows both, but the intermediate only allows client auth
+        assert!(report.violations(0).is_empty());
+        assert_eq!(report.violations(1), [ConstraintViolation::ExtendedKeyUsage { missing: Oid::SERVER_AUTH }]);
+        assert!(report.violations(2).is_empty());
+        assert_eq!(report.into_result().unwrap_err().code(), errSecInvalidExtendedKeyUsage);
+    }
+
+    #[test]
+    fn name_constraints_in_chain() {
+        let root = Issue::new("CN=Root", 1, None).build();
+        let mut intermediate = Issue::new("CN=Corp CA,O=Corp", 2, Some(&root));
+        intermediate.extensions.push(name_constraints(
+            &[dns("corp.example"), GeneralName::DirectoryName("O=Corp".parse().unwrap())],
+            &[dns("secret.corp.example")],
+        ));
+        let intermediate = intermediate.build();
+
+        let good = Issue::leaf("CN=www,O=Corp", 3, &intermediate, vec![dns("www.corp.example"), dns("corp.example"), dns("*.corp.example")]).build();
+        assert!(ConstraintChecker::new().check(&[good, intermediate.clone(), root.clone()]).is_ok());
+
+        let bad = Issue::leaf("CN=www,O=Corp", 3, &intermediate, vec![dns("www.corp.example"), dns("evil.example"), dns("a.secret.corp.example")]).build();
+        let report = ConstraintChecker::new().check(&[bad, intermediate.clone(), root.clone()]);
+        assert_eq!(report.violations(0), [
+            ConstraintViolation::NameNotPermitted { name: dns("evil.example"), constrained_by: Some(1) },
+            ConstraintViolation::NameExcluded { name: dns("a.secret.corp.example"), constrained_by: Some(1) },
+        ]);
+        assert_eq!(report.into_result().unwrap_err().code(), errSecCertificateNameNotAllowed);
+
+        // the directory name constraint applies to the subject
+        let other_org = Issue::leaf("CN=www,O=Other", 3, &intermediate, vec![dns("www.corp.example")]).build();
+        let report = ConstraintChecker::new().check(&[other_org, intermediate, root]);
+        assert!(matches!(report.violations(0), [ConstraintViolation::NameNotPermitted { name: GeneralName::DirectoryName(_), constrained_by: Some(1) }]));
+    }
+
+    #[test]
+    fn checker_subtrees() {
+        let root = Issue::new("CN=Root", 1, None).build();
+        let intermediate = Issue::new("CN=Intermediate", 2, Some(&root)).build();
+        let leaf = Issue::leaf("CN=Leaf", 3, &intermediate, vec![dns("app.corp.example"), GeneralName::IpAddress(Ipv4Addr::new(10, 1, 2, 3).into())]).build();
+        let chain = [leaf, intermediate, root];
+
+        let mut checker = ConstraintChecker::new();
+        checker
+            .add_permitted_subtree(Subtree::DnsName("corp.example".into()))
+            .add_permitted_subtree(Subtree::IpAddress { network: Ipv4Addr::new(10, 0, 0, 0).into(), prefix_len: 8 });
+        assert!(checker.check(&chain).is_ok());
+
+        checker.add_excluded_subtree(Subtree::IpAddress { network: Ipv4Addr::new(10, 1, 0, 0).into(), prefix_len: 16 });
+        let report = checker.check(&chain);
+        assert_eq!(report.violations(0), [ConstraintViolation::NameExcluded {
+            name: GeneralName::IpAddress(Ipv4Addr::new(10, 1, 2, 3).into()),
+            constrained_by: None,
+        }]);
+        assert!(report.violations(1).is_empty());
+    }
+
+    #[test]
+    fn subtree_matching() {
+        let dns_subtree = Subtree::DnsName("corp.example".into());
+        assert_eq!(dns_subtree.matches(&dns("corp.example")), Some(true));
+        assert_eq!(dns_subtree.matches(&dns("A.Corp.Example.")), Some(true));
+        assert_eq!(dns_subtree.matches(&dns("notcorp.example")), Some(false));
+        assert_eq!(dns_subtree.matches(&GeneralName::Uri("https://corp.example/".into())), None);
+        let subdomains = Subtree::DnsName(".corp.example".into());
+        assert_eq!(subdomains.matches(&dns("corp.example")), Some(false));
+        assert_eq!(subdomains.matches(&dns("a.corp.example")), Some(true));
+
+        let email = |s: &str| GeneralName::Rfc822Name(s.into());
+        assert_eq!(Subtree::Rfc822Name("corp.example".into()).matches(&email("alice@CORP.example")), Some(true));
+        assert_eq!(Subtree::Rfc822Name("corp.example".into()).matches(&email("alice@mail.corp.example")), Some(false));
+        assert_eq!(Subtree::Rfc822Name(".corp.example".into()).matches(&email("alice@mail.corp.example")), Some(true));
+        assert_eq!(Subtree::Rfc822Name("alice@corp.example".into()).matches(&email("alice@corp.example")), Some(true));
+        assert_eq!(Subtree::Rfc822Name("alice@corp.example".into()).matches(&email("bob@corp.example")), Some(false));
+
+        let uri = |s: &str| GeneralName::Uri(s.into());
+        let uri_subtree = Subtree::Uri(".corp.example".into());
+        assert_eq!(uri_subtree.matches(&uri("https://user@api.corp.example:8443/path")), Some(true));
+        assert_eq!(uri_subtree.matches(&uri("spiffe://corp.example/service")), Some(false));
+        assert_eq!(Subtree::Uri("corp.example".into()).matches(&uri("spiffe://corp.example/service")), Some(true));
+        assert_eq!(Subtree::Uri("::1".into()).matches(&uri("http://[::1]:80/")), Some(true));
+        assert_eq!(uri_subtree.matches(&uri("urn:uuid:1234")), Some(false));
+
+        let v6 = Subtree::IpAddress { network: "fd00::".parse().unwrap(), prefix_len: 12 };
+        assert_eq!(v6.matches(&GeneralName::IpAddress("fd0f::1".parse().unwrap())), Some(true));
+        assert_eq!(v6.matches(&GeneralName::IpAddress("fd10::1".parse().unwrap())), Some(false));
+        assert_eq!(v6.matches(&GeneralName::IpAddress(Ipv4Addr::LOCALHOST.into())), Some(false));
+
+        let directory = Subtree::DirectoryName("O=Corp,C=US".parse().unwrap());
+        assert_eq!(directory.matches(&GeneralName::DirectoryName("CN=x,O=corp,C=US".parse().unwrap())), Some(true));
+        assert_eq!(directory.matches(&GeneralName::DirectoryName("C=US".parse().unwrap())), Some(false));
+    }
+
+    #[test]
+    fn parse() {
+        let ip = [10, 0, 0, 0, 255, 255, 0, 0];
+        let value = der::encode_sequence(&[&der::encode(
+            der::context(0),
+            &[
+                der::encode_sequence(&[&dns("corp.example").to_der()]),
+                der::encode_sequence(&[&der::encode(der::context_primitive(7), &ip)]),
+            ]
+            .concat(),
+        )]);
+        let (permitted, excluded) = parse_name_constraints(&value).unwrap();
+        assert!(excluded.is_empty());
+        assert_eq!(permitted, [
+            Subtree::DnsName("corp.example".into()),
+            Subtree::IpAddress { network: Ipv4Addr::new(10, 0, 0, 0).into(), prefix_len: 16 },
+        ]);
+        assert!(parse_name_constraints(&[0x30, 0x02, 0xa0, 0x01]).is_err());
+
+        let single = |subtree: &[u8]| der::encode_sequence(&[&der::encode(der::context(1), &der::encode_sequence(&[subtree]))]);
+        // non-contiguous and short masks
+        assert_eq!(parse_name_constraints(&single(&der::encode(der::context_primitive(7), &[10, 0, 0, 0, 255, 0, 255, 0]))).unwrap_err().code(), errSecDecode);
+        assert_eq!(parse_name_constraints(&single(&der::encode(der::context_primitive(7), &[10, 0, 0, 0, 255]))).unwrap_err().code(), errSecDecode);
+        // registeredID can't be checked
+        let registered_id = GeneralName::RegisteredId(Oid::SERVER_AUTH).to_der();
+        assert_eq!(parse_name_constraints(&single(&registered_id)).unwrap_err().code(), errSecUnimplemented);
+    }
+
+    #[test]
+    fn unsupported_name_constraints() {
+        let root = Issue::new("CN=Root", 1, None).build();
+        let bad_mask = Extension::new(
+            Oid::NAME_CONSTRAINTS,
+            true,
+            der::encode_sequence(&[&der::encode(
+                der::context(1),
+                &der::encode_sequence(&[&der::encode(der::context_primitive(7), &[10, 0, 0, 0, 255, 0, 255, 0])]),
+            )]),
+        );
+        let registered_id = name_constraints(&[GeneralName::RegisteredId(Oid::SERVER_AUTH)], &[]);
+        for extension in [bad_mask, registered_id] {
+            let mut intermediate = Issue::new("CN=Intermediate", 2, Some(&root));
+            intermediate.extensions.push(extension);
+            let intermediate = intermediate.build();
+            let leaf = Issue::leaf("CN=Leaf", 3, &intermediate, vec![GeneralName::IpAddress(Ipv4Addr::new(10, 1, 2, 3).into())]).build();
+            let report = ConstraintChecker::new().check(&[leaf, intermediate, root.clone()]);
+            assert_eq!(report.violations(1), [ConstraintViolation::Malformed { extension: Oid::NAME_CONSTRAINTS }]);
+            assert_eq!(report.into_result().unwrap_err().code(), errSecDecode);
+        }
+    }
+}
@@ -0,0 +1,372 @@
+//! Decoding of the trust result dictionary (`SecTrustCopyResult`).
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::SystemTime;
+
+use security_framework_sys::base::errSecDecode;
+
+use super::TrustResult;
+use crate::base::{Error, Result};
+use crate::plist::Value;
+
+// Values of the `kSecTrust*` result keys. `TrustResultDetails` is not public API, but has been
+// stable since macOS 10.9.
+const RESULT_VALUE: &str = "TrustResultValue";
+const EVALUATION_DATE: &str = "TrustEvaluationDate";
+const EXTENDED_VALIDATION: &str = "TrustExtendedValidation";
+const ORGANIZATION_NAME: &str = "Organization";
+const REVOCATION_CHECKED: &str = "TrustRevocationChecked";
+const REVOCATION_VALID_UNTIL: &str = "TrustExpirationDate";
+const CERTIFICATE_TRANSPARENCY: &str = "TrustCertificateTransparency";
+const RESULT_DETAILS: &str = "TrustResultDetails";
+
+/// A check that failed for one certificate of the chain.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum TrustProblem {
+    /// The leaf doesn't match the SSL policy's hostname.
+    Hostname,
+    /// The leaf doesn't match the S/MIME policy's email address.
+    Email,
+    /// The certificate has expired or is not valid yet.
+    Validity,
+    /// The key usage extension doesn't allow the policy's use.
+    KeyUsage,
+    /// The extended key usage extension doesn't allow the policy's use.
+    ExtendedKeyUsage,
+    /// A CA certificate isn't marked as one, or its path length is exceeded.
+    BasicConstraints,
+    /// A name is outside a CA's name constraints.
+    NameConstraints,
+    /// The chain ends in a root that isn't trusted.
+    AnchorNotTrusted,
+    /// An intermediate certificate couldn't be found.
+    MissingIntermediate,
+    /// The certificate was revoked, or revocation status was required but unavailable.
+    Revocation,
+    /// Certificate Transparency was required but not satisfied.
+    CertificateTransparency,
+    /// The key is too small.
+    WeakKeySize,
+    /// The signature uses a weak hash, such as MD5 or SHA-1.
+    WeakSignature,
+    /// The certificate has an unrecognized critical extension.
+    CriticalExtensions,
+    /// Trust settings deny the certificate for this use.
+    TrustSettings,
+    /// The certificate or its key is on Apple's block list.
+    Blocked,
+    /// Any other check, by its key in the result dictionary.
+    Other(String),
+}
+
+/// Result dictionary keys of the checks, from Apple's `SecPolicyChecks.list`
+const PROBLEMS: [(&str, TrustProblem); 17] = [
+    ("SSLHostname", TrustProblem::Hostname),
+    ("Email", TrustProblem::Email),
+    ("TemporalValidity", TrustProblem::Validity),
+    ("KeyUsage", TrustProblem::KeyUsage),
+    ("ExtendedKeyUsage", TrustProblem::ExtendedKeyUsage),
+    ("BasicConstraints", TrustProblem::BasicConstraints),
+    ("NameConstraints", TrustProblem::NameConstraints),
+    ("AnchorTrusted", TrustProblem::AnchorNotTrusted),
+    ("MissingIntermediate", TrustProblem::MissingIntermediate),
+    ("Revocation", TrustProblem::Revocation),
+    ("CTRequired", TrustProblem::CertificateTransparency),
+    ("WeakKeySize", TrustProblem::WeakKeySize),
+    ("WeakSignature", TrustProblem::WeakSignature),
+    ("CriticalExtensions", TrustProblem::CriticalExtensions),
+    ("UsageConstraints", TrustProblem::TrustSettings),
+    ("BlackListedLeaf", TrustProblem::Blocked),
+    ("BlackListedKey", TrustProblem::Blocked),
+];
+
+impl TrustProblem {
+    /// Interprets a key of the per-certificate result dictionary.
+    #[must_use]
+    pub fn from_key(key: &str) -> Self {
+        PROBLEMS.iter().find(|p| p.0 == key).map_or_else(|| Self::Other(key.into()), |p| p.1.clone())
+    }
+
+    /// The key of the check in the result dictionary.
+    #[must_use]
+    pub fn key(&self) -> &str {
+        match self {
+            Self::Other(key) => key,
+            _ => PROBLEMS.iter().find(|p| p.1 == *self).map_or("", |p| p.0),
+        }
+    }
+}
+
+impl fmt::Display for TrustProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.key())
+    }
+}
+
+/// The failed checks of one certificate of the evaluated chain.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CertificateStatus {
+    problems: Vec<TrustProblem>,
+}
+
+impl CertificateStatus {
+    /// Whether every check passed.
+    #[inline]
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /// The checks that failed.
+    #[inline]
+    #[must_use]
+    pub fn problems(&self) -> &[TrustProblem] {
+        &self.problems
+    }
+
+    /// Whether `problem` was reported.
+    #[must_use]
+    pub fn has(&self, problem: &TrustProblem) -> bool {
+        self.problems.contains(problem)
+    }
+}
+
+/// Everything Security.framework reports about a trust evaluation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrustReport {
+    result: TrustResult,
+    evaluation_date: Option<SystemTime>,
+    extended_validation: bool,
+    organization_name: Option<String>,
+    revocation_checked: bool,
+    revocation_valid_until: Option<SystemTime>,
+    certificate_transparency: bool,
+    certificates: Vec<CertificateStatus>,
+    exceptions: Option<Vec<u8>>,
+}
+
+impl TrustReport {
+    /// Decodes the dictionary returned by `SecTrustCopyResult`, with the data returned by
+    /// `SecTrustCopyExceptions`.
+    ///
+    /// Missing keys are treated as absent or false; keys with the wrong type are an error.
+    pub fn from_result(result: &Value, exceptions: Option<Vec<u8>>) -> Result<Self> {
+        let result = result.as_dictionary().ok_or_else(decode_error)?;
+
+        let trust_result = match get(result, RESULT_VALUE, Value::as_integer)? {
+            Some(value) => TrustResult(value.try_into().map_err(|_| decode_error())?),
+            None => TrustResult::INVALID,
+        };
+        let certificates = get(result, RESULT_DETAILS, Value::as_array)?
+            .unwrap_or_default()
+            .iter()
+            .map(|element| {
+                let checks = element.as_dictionary().ok_or_else(decode_error)?;
+                Ok(CertificateStatus { problems: checks.keys().map(|key| TrustProblem::from_key(key)).collect() })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            result: trust_result,
+            evaluation_date: get(result, EVALUATION_DATE, Value::as_date)?,
+            extended_validation: get(result, EXTENDED_VALIDATION, Value::as_bool)?.unwrap_or(false),
+            organization_name: get(result, ORGANIZATION_NAME, Value::as_str)?.map(String::from),
+            revocation_checked: get(result, REVOCATION_CHECKED, Value::as_bool)?.unwrap_or(false),
+            revocation_valid_until: get(result, REVOCATION_VALID_UNTIL, Value::as_date)?,
+            certificate_transparency: get(result, CERTIFICATE_TRANSPARENCY, Value::as_bool)?.unwrap_or(false),
+            certificates,
+            exceptions,
+        })
+    }
+
+    /// The overall result.
+    #[inline]
+    #[must_use]
+    pub fn result(&self) -> TrustResult {
+        self.result
+    }
+
+    /// Whether the evaluation succeeded.
+    #[inline]
+    #[must_use]
+    pub fn is_trusted(&self) -> bool {
+        self.result.success()
+    }
+
+    /// When the evaluation happened.
+    #[inline]
+    #[must_use]
+    pub fn evaluation_date(&self) -> Option<SystemTime> {
+        self.evaluation_date
+    }
+
+    /// Whether the leaf is an Extended Validation certificate.
+    #[inline]
+    #[must_use]
+    pub fn is_extended_validation(&self) -> bool {
+        self.extended_validation
+    }
+
+    /// The organization of an Extended Validation certificate.
+    #[inline]
+    #[must_use]
+    pub fn organization_name(&self) -> Option<&str> {
+        self.organization_name.as_deref()
+    }
+
+    /// Whether revocation was checked for every certificate in the chain.
+    #[inline]
+    #[must_use]
+    pub fn revocation_checked(&self) -> bool {
+        self.revocation_checked
+    }
+
+    /// Until when the revocation information is valid.
+    #[inline]
+    #[must_use]
+    pub fn revocation_valid_until(&self) -> Option<SystemTime> {
+        self.revocation_valid_until
+    }
+
+    /// Whether the chain complies with Apple's Certificate Transparency policy.
+    #[inline]
+    #[must_use]
+    pub fn certificate_transparency(&self) -> bool {
+        self.certificate_transparency
+    }
+
+    /// Per-certificate status, from the leaf to the anchor.
+    #[inline]
+    #[must_use]
+    pub fn certificates(&self) -> &[CertificateStatus] {
+        &self.certificates
+    }
+
+    /// Every failed check, with the index of its certificate in the chain.
+    pub fn problems(&self) -> impl Iterator<Item = (usize, &TrustProblem)> {
+        self.certificates.iter().enumerate().flat_map(|(i, status)| status.problems.iter().map(move |p| (i, p)))
+    }
+
+    /// The opaque exceptions that would make this evaluation succeed, to be passed to
+    /// `SecTrust::set_exceptions` later.
+    #[inline]
+    #[must_use]
+    pub fn exceptions(&self) -> Option<&[u8]> {
+        self.exceptions.as_deref()
+    }
+}
+
+/// Looks up an optional entry of a particular type
+fn get<'a, T>(dictionary: &'a BTreeMap<String, Value>, key: &str, f: fn(&'a Value) -> Option<T>) -> Result<Option<T>> {
+    dictionary.get(key).map(|value| f(value).ok_or_else(decode_error)).transpose()
+}
+
+fn decode_error() -> Error {
+    Error::from_code(errSecDecode)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::der::time_from_unix;
+
+    fn dictionary<const N: usize>(entries: [(&str, Value); N]) -> Value {
+        Value::Dictionary(entries.into_iter().map(|(k, v)| (k.to_owned(), v)).collect())
+    }
+
+    #[test]
+    fn decode() {
+        let result = dictionary([
+            (RESULT_VALUE, Value::Integer(5)),
+            (EVALUATION_DATE, Value::Date(time_from_unix(1_798_761_600))),
+            (EXTENDED_VALIDATION, Value::Bool(true)),
+            (ORGANIZATION_NAME, Value::String("Foobar LLC".into())),
+            (REVOCATION_CHECKED, Value::Bool(false)),
+            (
+                RESULT_DETAILS,
+                Value::Array(vec![
+                    dictionary([("SSLHostname", Value::Bool(false)), ("TemporalValidity", Value::Bool(false))]),
+                    dictionary([]),
+                    dictionary([("AnchorTrusted", Value::Bool(false)), ("SomethingNew", Value::Bool(false))]),
+                ]),
+            ),
+        ]);
+        let report = TrustReport::from_result(&result, Some(vec![1, 2, 3])).unwrap();
+        assert_eq!(report.result(), TrustResult::RECOVERABLE_TRUST_FAILURE);
+        assert!(!report.is_trusted());
+        assert_eq!(report.evaluation_date(), Some(time_from_unix(1_798_761_600)));
+        assert!(report.is_extended_validation());
+        assert_eq!(report.organization_name(), Some("Foobar LLC"));
+        assert!(!report.revocation_checked());
+        assert_eq!(report.revocation_valid_until(), None);
+        assert!(!report.certificate_transparency());
+        assert_eq!(report.exceptions(), Some(&[1, 2, 3][..]));
+
+        assert_eq!(report.certificates().len(), 3);
+        assert_eq!(report.certificates()[0].problems(), [TrustProblem::Hostname, TrustProblem::Validity]);
+        assert!(report.certificates()[1].is_ok());
+        assert!(report.certificates()[2].has(&TrustProblem::AnchorNotTrusted));
+        let problems: Vec<_> = report.problems().map(|(i, p)| format!("{i}:{p}")).collect();
+        assert_eq!(problems, ["0:SSLHostname", "0:TemporalValidity", "2:AnchorTrusted", "2:SomethingNew"]);
+    }
+
+    #[test]
+    fn defaults() {
+        let report = TrustReport::from_result(&dictionary([(RESULT_VALUE, Value::Integer(4))]), None).unwrap();
+        assert!(report.is_trusted());
+        assert!(report.certificates().is_empty());
+        assert_eq!(report.exceptions(), None);
+        assert!(!report.is_extended_validation());
+
+        let report = TrustReport::from_result(&Value::Dictionary(BTreeMap::new()), None).unwrap();
+        assert_eq!(report.result(), TrustResult::INVALID);
+    }
+
+    #[test]
+    fn malformed() {
+        for result in [
+            Value::Array(Vec::new()),
+            dictionary([(RESULT_VALUE, Value::String("4".into()))]),
+            dictionary([(RESULT_VALUE, Value::Integer(-1))]),
+            dictionary([(EXTENDED_VALIDATION, Value::Integer(1))]),
+            dictionary([(EVALUATION_DATE, Value::Real(0.))]),
+            dictionary([(RESULT_DETAILS, Value::Array(vec![Value::Bool(false)]))]),
+        ] {
+            assert_eq!(TrustReport::from_result(&result, None).unwrap_err().code(), errSecDecode, "{result:?}");
+        }
+    }
+
+    #[test]
+    #[cfg(target_vendor = "apple")]
+    fn result_keys() {
+        use core_foundation::base::TCFType;
+        use core_foundation::string::CFString;
+        use security_framework_sys::trust::*;
+
+        for (key, expected) in unsafe {
+            [
+                (kSecTrustResultValue, RESULT_VALUE),
+                (kSecTrustEvaluationDate, EVALUATION_DATE),
+                (kSecTrustExtendedValidation, EXTENDED_VALIDATION),
+                (kSecTrustOrganizationName, ORGANIZATION_NAME),
+                (kSecTrustRevocationChecked, REVOCATION_CHECKED),
+                (kSecTrustRevocationValidUntilDate, REVOCATION_VALID_UNTIL),
+                (kSecTrustCertificateTransparency, CERTIFICATE_TRANSPARENCY),
+            ]
+        } {
+            assert_eq!(unsafe { CFString::wrap_under_get_rule(key) }.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn problem_keys() {
+        for (key, problem) in &PROBLEMS {
+            assert_eq!(&TrustProblem::from_key(key), problem);
+        }
+        assert_eq!(TrustProblem::Blocked.key(), "BlackListedLeaf");
+        assert_eq!(TrustProblem::from_key("Foo"), TrustProblem::Other("Foo".into()));
+        assert_eq!(TrustProblem::Other("Foo".into()).to_string(), "Foo");
+    }
+}
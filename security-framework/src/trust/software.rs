@@ -0,0 +1,583 @@
+//! A pure-Rust certificate path validator that behaves the same on every platform.
+//!
+//! [`SoftwareVerifier`] builds a path from a leaf to one of a given set of anchors and checks
+//! signatures, validity periods, [constraints](super::ConstraintChecker) and the hostname.
+//! Every check is recorded in a trace, so failures can be explained. It never consults the
+//! system's trust store, trust settings or revocation information.
+//!
+//! RSA PKCS #1 v1.5 and ECDSA P-256 and P-384 signatures can be checked; paths with other
+//! signatures fail with `errSecUnimplemented`, see [`Verification::is_inconclusive`].
+
+use std::fmt;
+use std::time::SystemTime;
+
+use security_framework_sys::base::{
+    errSecCertificateExpired, errSecCertificateNotValidYet, errSecHostNameMismatch, errSecNotSigner, errSecNotTrusted,
+    errSecUnimplemented,
+};
+#[cfg(target_vendor = "apple")]
+use security_framework_sys::base::errSecParam;
+
+use super::{ConstraintChecker, TrustResult};
+use crate::base::{Error, Result};
+#[cfg(target_vendor = "apple")]
+use crate::certificate::SecCertificate;
+use crate::certificate::expiry::ExpiryStatus;
+use crate::certificate::{Oid, Parsed};
+use crate::signature;
+#[cfg(target_vendor = "apple")]
+use crate::trust::SecTrust;
+
+/// Longest path that will be built, including the leaf and the anchor
+const MAX_PATH_LENGTH: usize = 10;
+
+/// Most complete paths that will be validated before giving up
+const MAX_CANDIDATE_PATHS: usize = 16;
+
+/// Most issuer candidates that will be followed while building paths, since sets of certificates
+/// with the same name can otherwise take exponential time
+const MAX_PATH_BUILDING_STEPS: usize = 1024;
+
+/// What a [`TraceEntry`] checked.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Check {
+    /// Building a path from the leaf to an anchor.
+    Path,
+    /// A certificate's signature, made by the next certificate in the path.
+    Signature,
+    /// A certificate's validity period.
+    Validity,
+    /// Basic constraints, name constraints and extended key usage.
+    Constraints,
+    /// The leaf's names against the expected hostname.
+    Hostname,
+}
+
+impl Check {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Path => "path",
+            Self::Signature => "signature",
+            Self::Validity => "validity",
+            Self::Constraints => "constraints",
+            Self::Hostname => "hostname",
+        }
+    }
+}
+
+/// One step of a verification.
+#[derive(Debug, Clone)]
+pub struct TraceEntry {
+    check: Check,
+    certificate: Option<usize>,
+    error: Option<Error>,
+    detail: String,
+}
+
+impl TraceEntry {
+    fn new(check: Check, certificate: Option<usize>, error: Option<Error>, detail: String) -> Self {
+        Self { check, certificate, error, detail }
+    }
+
+    /// What was checked.
+    #[inline]
+    #[must_use]
+    pub fn check(&self) -> Check {
+        self.check
+    }
+
+    /// Index in the path of the certificate that was checked, if the check was about one certificate.
+    #[inline]
+    #[must_use]
+    pub fn certificate(&self) -> Option<usize> {
+        self.certificate
+    }
+
+    /// Whether the check passed.
+    #[inline]
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// Why the check failed.
+    #[inline]
+    #[must_use]
+    pub fn error(&self) -> Option<Error> {
+        self.error
+    }
+
+    /// A human-readable description of what was checked.
+    #[inline]
+    #[must_use]
+    pub fn detail(&self) -> &str {
+        &self.detail
+    }
+}
+
+impl fmt::Display for TraceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.passed() { "[ok]   " } else { "[fail] " })?;
+        f.write_str(self.check.name())?;
+        if let Some(index) = self.certificate {
+            write!(f, " #{index}")?;
+        }
+        write!(f, ": {}", self.detail)?;
+        if let Some(error) = self.error {
+            write!(f, " ({})", error.name().unwrap_or("error"))?;
+        }
+        Ok(())
+    }
+}
+
+/// The outcome of [`SoftwareVerifier::verify`].
+#[derive(Debug, Clone)]
+pub struct Verification {
+    path: Vec<Parsed>,
+    trace: Vec<TraceEntry>,
+}
+
+impl Verification {
+    /// Whether the leaf is trusted.
+    #[must_use]
+    pub fn is_trusted(&self) -> bool {
+        !self.path.is_empty() && self.trace.iter().all(TraceEntry::passed)
+    }
+
+    /// Whether the leaf isn't trusted only because a signature algorithm or key type isn't
+    /// supported, so the outcome says nothing either way.
+    #[must_use]
+    pub fn is_inconclusive(&self) -> bool {
+        let mut failures = self.trace.iter().filter_map(TraceEntry::error).peekable();
+        failures.peek().is_some() && failures.all(|error| error.code() == errSecUnimplemented)
+    }
+
+    /// The outcome as `SecTrust` would report it: `UNSPECIFIED` when trusted, since anchors
+    /// aren't explicitly trusted by the user, and `RECOVERABLE_TRUST_FAILURE` otherwise.
+    #[must_use]
+    pub fn trust_result(&self) -> TrustResult {
+        if self.is_trusted() {
+            TrustResult::UNSPECIFIED
+        } else {
+            TrustResult::RECOVERABLE_TRUST_FAILURE
+        }
+    }
+
+    /// The first failure, with the error code `SecTrust` would use.
+    #[must_use]
+    pub fn error(&self) -> Option<Error> {
+        self.trace.iter().find_map(TraceEntry::error)
+    }
+
+    /// The validated path from the leaf to the anchor, or the closest candidate if verification failed.
+    /// Empty if no path to an anchor was found.
+    #[inline]
+    #[must_use]
+    pub fn path(&self) -> &[Parsed] {
+        &self.path
+    }
+
+    /// Every check that was made on the path, in order.
+    #[inline]
+    #[must_use]
+    pub fn trace(&self) -> &[TraceEntry] {
+        &self.trace
+    }
+
+    /// The path if trusted, or the first failure.
+    pub fn into_result(self) -> Result<Vec<Parsed>> {
+        match self.error() {
+            Some(error) => Err(error),
+            None => Ok(self.path),
+        }
+    }
+}
+
+impl fmt::Display for Verification {
+    /// One line per trace entry.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, entry) in self.trace.iter().enumerate() {
+            if i > 0 {
+                f.write_str("\n")?;
+            }
+            fmt::Display::fmt(entry, f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Verifies certificates against a fixed set of anchors, deterministically.
+///
+/// ```rust,ignore
+/// let mut verifier = SoftwareVerifier::new([Parsed::from_der(&root_der)?]);
+/// verifier
+///     .set_hostname(Some("api.corp.example"))
+///     .require_extended_key_usage(Oid::SERVER_AUTH);
+/// let verification = verifier.verify(&leaf, &intermediates);
+/// if !verification.is_trusted() {
+///     eprintln!("{verification}");
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SoftwareVerifier {
+    anchors: Vec<Parsed>,
+    time: Option<SystemTime>,
+    hostname: Option<String>,
+    checker: ConstraintChecker,
+}
+
+impl SoftwareVerifier {
+    /// Trusts `anchors`, which don't have to be self-signed.
+    #[must_use]
+    pub fn new(anchors: impl IntoIterator<Item = Parsed>) -> Self {
+        Self { anchors: anchors.into_iter().collect(), ..Self::default() }
+    }
+
+    /// Trusts `anchors`.
+    #[cfg(target_vendor = "apple")]
+    pub fn from_anchor_certificates(anchors: &[SecCertificate]) -> Result<Self> {
+        Ok(Self::new(anchors.iter().map(SecCertificate::parse).collect::<Result<Vec<_>>>()?))
+    }
+
+    /// Also trusts `anchor`.
+    pub fn add_anchor(&mut self, anchor: Parsed) -> &mut Self {
+        self.anchors.push(anchor);
+        self
+    }
+
+    /// Checks validity at `time` instead of the current time.
+    pub fn set_time(&mut self, time: Option<SystemTime>) -> &mut Self {
+        self.time = time;
+        self
+    }
+
+    /// Requires the leaf to match `hostname`, which may also be an IP address.
+    pub fn set_hostname(&mut self, hostname: Option<&str>) -> &mut Self {
+        self.hostname = hostname.map(String::from);
+        self
+    }
+
+    /// Replaces the constraints to enforce, e.g. to add name constraints for a private CA.
+    pub fn set_constraint_checker(&mut self, checker: ConstraintChecker) -> &mut Self {
+        self.checker = checker;
+        self
+    }
+
+    /// Requires every certificate with an extended key usage extension to allow `usage`,
+    /// e.g. [`Oid::SERVER_AUTH`].
+    pub fn require_extended_key_usage(&mut self, usage: Oid) -> &mut Self {
+        self.checker.require_extended_key_usage(usage);
+        self
+    }
+
+    /// Verifies `leaf`, using any of `intermediates` (in any order) to reach an anchor.
+    ///
+    /// Candidate paths are tried until one passes every check. If none does, the result
+    /// describes the candidate with the fewest failures.
+    #[must_use]
+    pub fn verify(&self, leaf: &Parsed, intermediates: &[Parsed]) -> Verification {
+        let mut paths = Vec::new();
+        let mut steps = MAX_PATH_BUILDING_STEPS;
+        self.build_paths(&mut vec![leaf], intermediates, &mut paths, &mut steps);
+
+        let mut best: Option<Verification> = None;
+        for (index, path) in paths.iter().enumerate() {
+            let verification = self.validate(path, index, paths.len());
+            if verification.is_trusted() {
+                return verification;
+            }
+            let failures = |v: &Verification| v.trace.iter().filter(|entry| !entry.passed()).count();
+            if best.as_ref().is_none_or(|best| failures(&verification) < failures(best)) {
+                best = Some(verification);
+            }
+        }
+        if steps == 0 {
+            return Verification {
+                path: Vec::new(),
+                trace: vec![TraceEntry::new(
+                    Check::Path,
+                    None,
+                    Some(Error::from_code(errSecNotTrusted)),
+                    format!("gave up building a path from {} after {MAX_PATH_BUILDING_STEPS} steps", leaf.subject()),
+                )],
+            };
+        }
+        best.unwrap_or_else(|| Verification {
+            path: Vec::new(),
+            trace: vec![TraceEntry::new(
+                Check::Path,
+                None,
+                Some(Error::from_code(errSecNotTrusted)),
+                format!("no path from {} to an anchor", leaf.subject()),
+            )],
+        })
+    }
+
+    /// Verifies certificates ordered like a TLS peer's: the leaf first, then intermediates in any order.
+    #[cfg(target_vendor = "apple")]
+    pub fn verify_certificates(&self, certificates: &[SecCertificate]) -> Result<Verification> {
+        let certificates = certificates.iter().map(SecCertificate::parse).collect::<Result<Vec<_>>>()?;
+        let (leaf, intermediates) = certificates.split_first().ok_or_else(|| Error::from_code(errSecParam))?;
+        Ok(self.verify(leaf, intermediates))
+    }
+
+    /// Verifies the certificates of `trust`, e.g. a TLS peer's trust, ignoring its anchors and policies.
+    #[cfg(target_vendor = "apple")]
+    pub fn verify_trust(&self, trust: &SecTrust) -> Result<Verification> {
+        #[cfg(any(feature = "macos-12", not(target_os = "macos")))]
+        let certificates = trust.chain();
+        #[cfg(not(any(feature = "macos-12", not(target_os = "macos"))))]
+        #[allow(deprecated)]
+        let certificates: Vec<_> = (0..trust.certificate_count()).filter_map(|i| trust.certificate_at_index(i)).collect();
+        self.verify_certificates(&certificates)
+    }
+
+    fn is_anchor(&self, certificate: &Parsed) -> bool {
+        self.anchors.iter().any(|anchor| {
+            anchor.subject().matches(certificate.subject()) && anchor.public_key_info().der() == certificate.public_key_info().der()
+        })
+    }
+
+    /// Depth-first search for paths ending at an anchor, trying anchors before intermediates.
+    ///
+    /// Stops when `steps` reaches zero.
+    fn build_paths<'a>(
+        &'a self,
+        path: &mut Vec<&'a Parsed>,
+        intermediates: &'a [Parsed],
+        paths: &mut Vec<Vec<&'a Parsed>>,
+        steps: &mut usize,
+    ) {
+        let current = path[path.len() - 1];
+        if self.is_anchor(current) {
+            paths.push(path.clone());
+            return;
+        }
+        if path.len() >= MAX_PATH_LENGTH {
+            return;
+        }
+        for candidate in self.anchors.iter().chain(intermediates) {
+            if paths.len() >= MAX_CANDIDATE_PATHS || *steps == 0 {
+                return;
+            }
+            if !current.is_issued_by(candidate) || path.iter().any(|cert| cert.der() == candidate.der()) {
+                continue;
+            }
+            *steps -= 1;
+            path.push(candidate);
+            self.build_paths(path, intermediates, paths, steps);
+            path.pop();
+        }
+    }
+
+    /// Checks the `index`th of `candidates` paths.
+    fn validate(&self, path: &[&Parsed], index: usize, candidates: usize) -> Verification {
+        let time = self.time.unwrap_or_else(SystemTime::now);
+        let path: Vec<Parsed> = path.iter().map(|&cert| cert.clone()).collect();
+        let anchor = path.len() - 1;
+        let mut trace = Vec::new();
+
+        let names: Vec<_> = path.iter().map(|cert| cert.subject().to_string()).collect();
+        trace.push(TraceEntry::new(Check::Path, None, None, format!("{} ({} of {candidates} candidates)", names.join(" <- "), index + 1)));
+
+        for (i, pair) in path.windows(2).enumerate() {
+            let (cert, issuer) = (&pair[0], &pair[1]);
+            let error = match signature::verify_signature(issuer.public_key_info(), cert.signature_algorithm(), cert.tbs_certificate(), cert.signature()) {
+                Ok(true) => None,
+                Ok(false) => Some(Error::from_code(errSecNotSigner)),
+                Err(error) => Some(error),
+            };
+            trace.push(TraceEntry::new(Check::Signature, Some(i), error, format!("{} signed by {}", names[i], names[i + 1])));
+        }
+        trace.push(TraceEntry::new(Check::Signature, Some(anchor), None, format!("{} is an anchor", names[anchor])));
+
+        for (i, cert) in path.iter().enumerate() {
+            let error = match ExpiryStatus::at(cert.not_before(), cert.not_after(), time) {
+                ExpiryStatus::Valid => None,
+                ExpiryStatus::NotYetValid => Some(Error::from_code(errSecCertificateNotValidYet)),
+                ExpiryStatus::Expired => Some(Error::from_code(errSecCertificateExpired)),
+            };
+            trace.push(TraceEntry::new(Check::Validity, Some(i), error, format!("{} valid at the verification time", names[i])));
+        }
+
+        let report = self.checker.check(&path);
+        if report.is_ok() {
+            trace.push(TraceEntry::new(Check::Constraints, None, None, "no violations".into()));
+        }
+        for (i, violation) in report.iter() {
+            trace.push(TraceEntry::new(Check::Constraints, Some(i), Some(violation.error()), format!("{violation:?}")));
+        }
+
+        if let Some(hostname) = &self.hostname {
+            let error = (!path[0].matches_host(hostname)).then(|| Error::from_code(errSecHostNameMismatch));
+            trace.push(TraceEntry::new(Check::Hostname, Some(0), error, format!("{} matches {hostname}", names[0])));
+        }
+
+        Verification { path, trace }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::certificate::fixtures::{ca, server, Issue};
+    use crate::certificate::{BasicConstraints, CertificateBuilder};
+    use crate::der::time_from_unix;
+    use security_framework_sys::base::errSecPathLengthConstraintExceeded;
+
+    // 2027-01-01, when the fixtures are valid
+    const NOW: i64 = 1_798_761_600;
+
+    fn verifier() -> SoftwareVerifier {
+        let mut verifier = SoftwareVerifier::new([ca()]);
+        verifier
+            .set_time(Some(time_from_unix(NOW)))
+            .set_hostname(Some("foobar.com"))
+            .require_extended_key_usage(Oid::SERVER_AUTH);
+        verifier
+    }
+
+    fn issue(subject: &str, issuer: Option<&Parsed>, constraints: BasicConstraints, not_after: i64) -> Parsed {
+        Issue { constraints: Some(constraints), not_after, ..Issue::signed(subject, issuer) }.build()
+    }
+
+    #[test]
+    fn fixtures() {
+        let verification = verifier().verify(&server(), &[]);
+        assert!(verification.is_trusted(), "{verification}");
+        assert_eq!(verification.trust_result(), TrustResult::UNSPECIFIED);
+        assert!(verification.trust_result().success());
+        assert!(verification.error().is_none());
+        assert_eq!(verification.path(), [server(), ca()]);
+        let checks: Vec<_> = verification.trace().iter().map(TraceEntry::check).collect();
+        assert_eq!(checks, [Check::Path, Check::Signature, Check::Signature, Check::Validity, Check::Validity, Check::Constraints, Check::Hostname]);
+        assert_eq!(verification.trace()[1].certificate(), Some(0));
+        assert!(verification.to_string().lines().all(|line| line.starts_with("[ok]")));
+        assert_eq!(verification.into_result().unwrap().len(), 2);
+
+        // the anchor itself
+        assert!(SoftwareVerifier::new([ca()]).verify(&ca(), &[]).is_trusted());
+    }
+
+    #[test]
+    fn failures() {
+        let mut verifier = verifier();
+        verifier.set_hostname(Some("example.com"));
+        let verification = verifier.verify(&server(), &[]);
+        assert_eq!(verification.trust_result(), TrustResult::RECOVERABLE_TRUST_FAILURE);
+        assert_eq!(verification.error().unwrap().code(), errSecHostNameMismatch);
+        assert_eq!(verification.path().len(), 2);
+        let last = verification.to_string().lines().last().unwrap().to_owned();
+        assert!(last.starts_with("[fail] hostname #0: "), "{last}");
+        assert!(last.ends_with("(errSecHostNameMismatch)"), "{last}");
+
+        let mut verifier = self::verifier();
+        verifier.set_time(Some(time_from_unix(1_900_000_000)));
+        assert_eq!(verifier.verify(&server(), &[]).error().unwrap().code(), errSecCertificateExpired);
+        verifier.set_time(Some(time_from_unix(1_700_000_000)));
+        assert_eq!(verifier.verify(&server(), &[]).error().unwrap().code(), errSecCertificateNotValidYet);
+
+        let verification = SoftwareVerifier::new([]).verify(&server(), &[ca()]);
+        assert!(verification.path().is_empty());
+        assert_eq!(verification.error().unwrap().code(), errSecNotTrusted);
+        assert_eq!(verification.trace()[0].check(), Check::Path);
+
+        let mut der = server().der().to_vec();
+        *der.last_mut().unwrap() ^= 1;
+        let tampered = Parsed::from_der(&der).unwrap();
+        let verification = self::verifier().verify(&tampered, &[]);
+        assert_eq!(verification.error().unwrap().code(), errSecNotSigner);
+        assert_eq!(verification.trace().iter().filter(|entry| !entry.passed()).count(), 1);
+
+        let mut verifier = self::verifier();
+        verifier.require_extended_key_usage(Oid::CLIENT_AUTH);
+        let verification = verifier.verify(&server(), &[]);
+        assert!(matches!(verification.trace()[5], TraceEntry { check: Check::Constraints, certificate: Some(0), .. }));
+    }
+
+    #[test]
+    fn path_building() {
+        let root = issue("CN=Root", None, BasicConstraints::ca(None), 2_000_000_000);
+        let expired = issue("CN=Intermediate", Some(&root), BasicConstraints::ca(None), 1_750_000_000);
+        let intermediate = issue("CN=Intermediate", Some(&root), BasicConstraints::ca(None), 2_000_000_000);
+        let leaf = issue("CN=Leaf", Some(&intermediate), BasicConstraints::end_entity(), 2_000_000_000);
+        let unrelated = issue("CN=Unrelated", None, BasicConstraints::ca(None), 2_000_000_000);
+        let mut verifier = SoftwareVerifier::new([unrelated.clone(), root.clone()]);
+        verifier.set_time(Some(time_from_unix(NOW)));
+
+        // the expired intermediate is tried first, but the other one works
+        let verification = verifier.verify(&leaf, &[unrelated, expired.clone(), intermediate.clone()]);
+        assert!(verification.is_trusted(), "{verification}");
+        assert_eq!(verification.path(), [leaf.clone(), intermediate.clone(), root.clone()]);
+        assert!(verification.trace()[0].detail().ends_with("(2 of 2 candidates)"), "{verification}");
+
+        let verification = verifier.verify(&leaf, std::slice::from_ref(&expired));
+        assert_eq!(verification.error().unwrap().code(), errSecCertificateExpired);
+        assert_eq!(verification.path()[1], expired);
+
+        // a trusted intermediate is enough
+        let mut verifier = SoftwareVerifier::new([intermediate.clone()]);
+        verifier.set_time(Some(time_from_unix(NOW)));
+        assert_eq!(verifier.verify(&leaf, &[]).path(), [leaf.clone(), intermediate]);
+
+        // pathLen 0 allows no further intermediates
+        let limited = issue("CN=Limited", Some(&root), BasicConstraints::ca(Some(0)), 2_000_000_000);
+        let sub = issue("CN=Sub", Some(&limited), BasicConstraints::ca(None), 2_000_000_000);
+        let deep = issue("CN=Deep", Some(&sub), BasicConstraints::end_entity(), 2_000_000_000);
+        let mut verifier = SoftwareVerifier::new([root]);
+        verifier.set_time(Some(time_from_unix(NOW)));
+        let verification = verifier.verify(&deep, &[sub, limited]);
+        assert_eq!(verification.path().len(), 4);
+        assert_eq!(verification.error().unwrap().code(), errSecPathLengthConstraintExceeded);
+    }
+
+    #[test]
+    fn path_building_budget() {
+        // certificates with the same name and key can follow each other in any order
+        let loops: Vec<_> = (0..12).map(|i| issue("CN=Loop", None, BasicConstraints::ca(None), 2_000_000_000 + i)).collect();
+        let leaf = issue("CN=Leaf", Some(&loops[0]), BasicConstraints::end_entity(), 2_000_000_000);
+        let mut verifier = SoftwareVerifier::new([issue("CN=Root", None, BasicConstraints::ca(None), 2_000_000_000)]);
+        verifier.set_time(Some(time_from_unix(NOW)));
+        let verification = verifier.verify(&leaf, &loops);
+        assert!(verification.path().is_empty());
+        assert_eq!(verification.error().unwrap().code(), errSecNotTrusted);
+        assert!(verification.trace()[0].detail().starts_with("gave up"), "{verification}");
+    }
+
+    #[test]
+    fn ecdsa_chain() {
+        let ca = Parsed::from_der(include_bytes!("../../test/ecdsa/ca.der")).unwrap();
+        let leaf = Parsed::from_der(include_bytes!("../../test/ecdsa/leaf.der")).unwrap();
+        let mut verifier = SoftwareVerifier::new([ca.clone()]);
+        verifier.set_time(Some(time_from_unix(NOW))).set_hostname(Some("ecdsa.example.com"));
+        let verification = verifier.verify(&leaf, &[]);
+        assert!(verification.is_trusted(), "{verification}");
+        assert_eq!(verification.path(), [leaf.clone(), ca]);
+
+        // a tampered signature
+        let mut der = leaf.der().to_vec();
+        let last = der.len() - 1;
+        der[last] ^= 1;
+        let verification = verifier.verify(&Parsed::from_der(&der).unwrap(), &[]);
+        assert_eq!(verification.error().unwrap().code(), errSecNotSigner);
+        assert!(!verification.is_inconclusive());
+    }
+
+    #[test]
+    fn unsupported_signature() {
+        // Ed25519
+        let algorithm = crate::certificate::AlgorithmIdentifier::new("1.3.101.112".parse().unwrap(), None);
+        let mut builder = CertificateBuilder::new("CN=Leaf".parse().unwrap());
+        builder.set_serial_number(&[1]).set_public_key_info(server().public_key_info().der()).unwrap().set_issuer(&ca()).unwrap();
+        let tbs = builder.tbs_certificate(&algorithm).unwrap();
+        let der = crate::der::encode_sequence(&[&tbs, &algorithm.to_der(), &crate::der::encode_bit_string(&[0; 64])]);
+        let leaf = Parsed::from_der(&der).unwrap();
+        let mut verifier = SoftwareVerifier::new([ca()]);
+        verifier.set_time(Some(time_from_unix(NOW)));
+        let verification = verifier.verify(&leaf, &[]);
+        assert_eq!(verification.error().unwrap().code(), errSecUnimplemented);
+        assert!(verification.is_inconclusive());
+        assert!(!verifier.verify(&server(), &[]).is_inconclusive());
+    }
+}
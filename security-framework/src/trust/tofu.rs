@@ -0,0 +1,322 @@
+//! Trust on first use: remembering servers whose certificates were approved once.
+//!
+//! A [`TofuStore`] maps hosts to the `pin-sha256` of the approved certificate's public key, and
+//! optionally the opaque [`SecTrust` exceptions](super::SecTrust::exceptions) that make its
+//! evaluation succeed, e.g. for a self-signed certificate. Given to
+//! `ClientBuilder::tofu_store`, it makes handshakes with a known host require the same key
+//! and apply the exceptions.
+//!
+//! The store is saved as text, one host per line:
+//!
+//! ```text
+//! # comment
+//! foobar.com bB7vC0XX1gJaYSDgYzK0ua9gRpduQgzYidZVUofVwso= AQID...
+//! ```
+//!
+//! ```rust,ignore
+//! // after the operator approved the certificate of a failed connection
+//! let mut store = TofuStore::load(&path)?;
+//! store.accept("foobar.com", &trust)?;
+//! store.save(&path)?;
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use security_framework_sys::base::{errSecDecode, errSecParam};
+
+use crate::base::{Error, Result};
+use crate::base64;
+use sha2::Digest as _;
+use crate::certificate::Parsed;
+#[cfg(target_vendor = "apple")]
+use crate::trust::SecTrust;
+
+/// What the store knows about one host.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TofuEntry {
+    pin: [u8; 32],
+    exceptions: Option<Vec<u8>>,
+}
+
+impl TofuEntry {
+    /// An entry for a certificate with the public key `pin`, in the format of [`Parsed::spki_sha256_pin`].
+    pub fn new(pin: &str, exceptions: Option<Vec<u8>>) -> Result<Self> {
+        match base64::decode(pin.as_bytes()).map(<[u8; 32]>::try_from) {
+            Some(Ok(hash)) if !pin.contains(char::is_whitespace) => Ok(Self { pin: hash, exceptions }),
+            _ => Err(Error::from_code(errSecParam)),
+        }
+    }
+
+    /// An entry for `certificate`.
+    #[must_use]
+    pub fn from_certificate(certificate: &Parsed, exceptions: Option<Vec<u8>>) -> Self {
+        Self { pin: spki_sha256(certificate), exceptions }
+    }
+
+    /// The `pin-sha256` of the approved public key.
+    #[must_use]
+    pub fn pin(&self) -> String {
+        base64::encode(&self.pin)
+    }
+
+    /// The `SecTrust` exceptions to apply, if the certificate isn't otherwise trusted.
+    #[inline]
+    #[must_use]
+    pub fn exceptions(&self) -> Option<&[u8]> {
+        self.exceptions.as_deref()
+    }
+
+    /// Whether `certificate` has the approved public key. Renewed certificates match if they keep the key.
+    #[must_use]
+    pub fn matches(&self, certificate: &Parsed) -> bool {
+        spki_sha256(certificate) == self.pin
+    }
+
+    /// Prepares `trust` for evaluation: requires its leaf to match and applies the exceptions.
+    ///
+    /// Returns `false` if there are exceptions but they were made for an older certificate with
+    /// the same key, so they don't apply and the leaf has to be trusted without them.
+    /// Fails with `errSecNotTrusted` if the leaf has a different key.
+    #[cfg(target_vendor = "apple")]
+    pub fn apply(&self, trust: &mut SecTrust) -> Result<bool> {
+        use security_framework_sys::base::errSecNotTrusted;
+
+        if !self.matches(&leaf(trust)?) {
+            return Err(Error::from_code(errSecNotTrusted));
+        }
+        Ok(self.exceptions.as_deref().is_none_or(|exceptions| trust.set_exceptions(Some(exceptions))))
+    }
+}
+
+/// Outcome of [`TofuStore::check`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PinCheck<'a> {
+    /// The host isn't in the store.
+    Unknown,
+    /// The certificate has the approved key.
+    Matched(&'a TofuEntry),
+    /// The certificate has a different key than the approved one.
+    Mismatched(&'a TofuEntry),
+}
+
+/// Approved certificates by host.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TofuStore {
+    entries: BTreeMap<String, TofuEntry>,
+}
+
+impl TofuStore {
+    /// An empty store.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses the text format.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut store = Self::new();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let (Some(host), Some(pin)) = (fields.next(), fields.next()) else {
+                return Err(Error::from_code(errSecDecode));
+            };
+            let exceptions = match fields.next() {
+                Some(exceptions) => Some(base64::decode(exceptions.as_bytes()).ok_or_else(|| Error::from_code(errSecDecode))?),
+                None => None,
+            };
+            if fields.next().is_some() {
+                return Err(Error::from_code(errSecDecode));
+            }
+            let entry = TofuEntry::new(pin, exceptions).map_err(|_| Error::from_code(errSecDecode))?;
+            store.entries.insert(normalize_host(host), entry);
+        }
+        Ok(store)
+    }
+
+    /// Reads a store saved by [`save`](Self::save). A missing file is an empty store.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Writes the store, replacing the file atomically.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let mut temp = path.as_os_str().to_owned();
+        temp.push(".tmp");
+        fs::write(&temp, self.to_string())?;
+        fs::rename(&temp, path)
+    }
+
+    /// The entry for `host`.
+    #[must_use]
+    pub fn get(&self, host: &str) -> Option<&TofuEntry> {
+        self.entries.get(&normalize_host(host))
+    }
+
+    /// Approves a certificate for `host`, returning the entry it replaced.
+    pub fn insert(&mut self, host: &str, entry: TofuEntry) -> Result<Option<TofuEntry>> {
+        if host.is_empty() || host.contains(|c: char| c.is_whitespace() || c == '#') {
+            return Err(Error::from_code(errSecParam));
+        }
+        Ok(self.entries.insert(normalize_host(host), entry))
+    }
+
+    /// Forgets `host`.
+    pub fn remove(&mut self, host: &str) -> Option<TofuEntry> {
+        self.entries.remove(&normalize_host(host))
+    }
+
+    /// The hosts in the store, normalized and sorted.
+    pub fn hosts(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+
+    /// Number of hosts.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the store is empty.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Compares `certificate` with the approved one for `host`.
+    #[must_use]
+    pub fn check(&self, host: &str, certificate: &Parsed) -> PinCheck<'_> {
+        match self.get(host) {
+            None => PinCheck::Unknown,
+            Some(entry) if entry.matches(certificate) => PinCheck::Matched(entry),
+            Some(entry) => PinCheck::Mismatched(entry),
+        }
+    }
+
+    /// Approves the leaf of an evaluated `trust` for `host`, with the exceptions that make it trusted.
+    #[cfg(target_vendor = "apple")]
+    pub fn accept(&mut self, host: &str, trust: &SecTrust) -> Result<()> {
+        let entry = TofuEntry::from_certificate(&leaf(trust)?, trust.exceptions());
+        self.insert(host, entry)?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for TofuStore {
+    /// The text format read by [`parse`](Self::parse).
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (host, entry) in &self.entries {
+            write!(f, "{host} {}", entry.pin())?;
+            if let Some(exceptions) = &entry.exceptions {
+                write!(f, " {}", base64::encode(exceptions))?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+fn spki_sha256(certificate: &Parsed) -> [u8; 32] {
+    sha2::Sha256::digest(certificate.public_key_info().der()).into()
+}
+
+/// Hostnames are case-insensitive, and may be written with a trailing dot
+fn normalize_host(host: &str) -> String {
+    host.strip_suffix('.').unwrap_or(host).to_ascii_lowercase()
+}
+
+#[cfg(target_vendor = "apple")]
+fn leaf(trust: &SecTrust) -> Result<Parsed> {
+    #[allow(deprecated)]
+    let leaf = trust.certificate_at_index(0).ok_or_else(|| Error::from_code(errSecParam))?;
+    leaf.parse()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn server() -> Parsed {
+        Parsed::from_der(include_bytes!("../../test/server.der")).unwrap()
+    }
+
+    fn ca() -> Parsed {
+        Parsed::from_der(include_bytes!("../../test/ca.der")).unwrap()
+    }
+
+    #[test]
+    fn pins() {
+        let mut store = TofuStore::new();
+        assert_eq!(store.check("foobar.com", &server()), PinCheck::Unknown);
+        store.insert("FooBar.com.", TofuEntry::from_certificate(&server(), None)).unwrap();
+        assert_eq!(store.hosts().collect::<Vec<_>>(), ["foobar.com"]);
+
+        let entry = store.get("foobar.com").unwrap();
+        assert_eq!(entry.pin(), server().spki_sha256_pin());
+        assert_eq!(store.check("FOOBAR.COM", &server()), PinCheck::Matched(entry));
+        assert_eq!(store.check("foobar.com", &ca()), PinCheck::Mismatched(entry));
+        assert_eq!(store.check("localhost", &server()), PinCheck::Unknown);
+
+        let old = store.insert("foobar.com", TofuEntry::from_certificate(&ca(), Some(vec![1]))).unwrap();
+        assert_eq!(old.unwrap().pin(), server().spki_sha256_pin());
+        assert!(matches!(store.check("foobar.com", &ca()), PinCheck::Matched(e) if e.exceptions() == Some(&[1][..])));
+        assert!(store.remove("foobar.com.").is_some());
+        assert!(store.is_empty());
+
+        assert!(store.insert("foo bar", TofuEntry::from_certificate(&ca(), None)).is_err());
+        assert!(store.insert("", TofuEntry::from_certificate(&ca(), None)).is_err());
+        assert!(TofuEntry::new("AQID", None).is_err());
+        assert_eq!(TofuEntry::new(&server().spki_sha256_pin(), None).unwrap(), TofuEntry::from_certificate(&server(), None));
+    }
+
+    #[test]
+    fn text_format() {
+        let mut store = TofuStore::new();
+        store.insert("foobar.com", TofuEntry::from_certificate(&server(), Some(vec![1, 2, 3]))).unwrap();
+        store.insert("ca.example", TofuEntry::from_certificate(&ca(), None)).unwrap();
+        let text = store.to_string();
+        assert_eq!(
+            text,
+            format!("ca.example {}\nfoobar.com {} AQID\n", ca().spki_sha256_pin(), server().spki_sha256_pin())
+        );
+        assert_eq!(TofuStore::parse(&text).unwrap(), store);
+
+        let commented = format!("# approved hosts\n\n  Foobar.com  {}\n", server().spki_sha256_pin());
+        let parsed = TofuStore::parse(&commented).unwrap();
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed.get("foobar.com").unwrap().exceptions(), None);
+
+        for bad in ["foobar.com", "foobar.com AQID", "foobar.com bB7vC0XX1gJaYSDgYzK0ua9gRpduQgzYidZVUofVwso= !!", "a b c d"] {
+            assert_eq!(TofuStore::parse(bad).unwrap_err().code(), errSecDecode, "{bad}");
+        }
+    }
+
+    #[test]
+    fn load_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("known_hosts");
+        assert!(TofuStore::load(&path).unwrap().is_empty());
+
+        let mut store = TofuStore::new();
+        store.insert("foobar.com", TofuEntry::from_certificate(&server(), Some(vec![0; 40]))).unwrap();
+        store.save(&path).unwrap();
+        assert_eq!(TofuStore::load(&path).unwrap(), store);
+
+        fs::write(&path, "garbage\n").unwrap();
+        assert_eq!(TofuStore::load(&path).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -1,33 +1,77 @@
-//! Querying trust settings.
+//! Querying and changing trust settings.
+//!
+//! [`Domain`], [`TrustSettingsForCertificate`], [`TrustSettingsEntry`], [`TrustSettingsBundle`], [`Snapshot`]
+//! and [`AnchorBundle`] are pure Rust and available on all platforms.
 
+#[cfg(target_os = "macos")]
 use core_foundation::array::{CFArray, CFArrayRef};
-use core_foundation::base::{CFIndex, TCFType};
+#[cfg(target_os = "macos")]
+use core_foundation::base::{CFIndex, CFType, TCFType};
+#[cfg(target_os = "macos")]
+use core_foundation::data::CFData;
+#[cfg(target_os = "macos")]
 use core_foundation::dictionary::CFDictionary;
+#[cfg(target_os = "macos")]
 use core_foundation::number::CFNumber;
+#[cfg(target_os = "macos")]
 use core_foundation::string::CFString;
 
+#[cfg(target_os = "macos")]
 use core_foundation_sys::base::CFTypeRef;
-use security_framework_sys::base::{errSecNoTrustSettings, errSecSuccess};
+#[cfg(target_os = "macos")]
+use core_foundation_sys::data::CFDataRef;
+#[cfg(target_os = "macos")]
+use security_framework_sys::base::{errSecNoTrustSettings, errSecSuccess, SecTrustedApplicationRef};
+#[cfg(target_os = "macos")]
 use security_framework_sys::trust_settings::*;
 
+#[cfg(target_os = "macos")]
 use std::ptr;
 
+#[cfg(target_os = "macos")]
 use crate::base::{Error, Result};
+#[cfg(target_os = "macos")]
 use crate::certificate::SecCertificate;
+#[cfg(target_os = "macos")]
 use crate::cvt;
+#[cfg(target_os = "macos")]
+use crate::plist::Value;
+#[cfg(target_os = "macos")]
+use security_framework_sys::base::errSecDecode;
+#[cfg(target_os = "macos")]
+use std::collections::BTreeMap;
+#[cfg(target_os = "macos")]
+use crate::policy::SecPolicy;
+
+mod anchors;
+mod bundle;
+mod entry;
+pub mod snapshot;
+
+pub use anchors::AnchorBundle;
+pub use bundle::{CertificateTrustSettings, TrustSettingsBundle};
+pub use entry::{TrustSettingsEntry, TrustSettingsKeyUsage};
+pub use snapshot::Snapshot;
+
+// `SecTrustSettingsResult` values, which also appear in exported trust settings
+const RESULT_TRUST_ROOT: u32 = 1;
+const RESULT_TRUST_AS_ROOT: u32 = 2;
+const RESULT_DENY: u32 = 3;
+const RESULT_UNSPECIFIED: u32 = 4;
 
 /// Which set of trust settings to query
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(u32)]
 pub enum Domain {
     /// Per-user trust settings
-    User = kSecTrustSettingsDomainUser,
+    User = 0,
     /// Locally administered, system-wide trust settings
-    Admin = kSecTrustSettingsDomainAdmin,
+    Admin = 1,
     /// System trust settings
-    System = kSecTrustSettingsDomainSystem,
+    System = 2,
 }
 
+#[cfg(target_os = "macos")]
 impl From<Domain> for SecTrustSettingsDomain {
     #[inline]
     fn from(domain: Domain) -> Self {
@@ -66,20 +110,33 @@ impl TrustSettingsForCertificate {
             return Self::Invalid;
         }
         match value as u32 {
-            kSecTrustSettingsResultTrustRoot => Self::TrustRoot,
-            kSecTrustSettingsResultTrustAsRoot => Self::TrustAsRoot,
-            kSecTrustSettingsResultDeny => Self::Deny,
-            kSecTrustSettingsResultUnspecified => Self::Unspecified,
+            RESULT_TRUST_ROOT => Self::TrustRoot,
+            RESULT_TRUST_AS_ROOT => Self::TrustAsRoot,
+            RESULT_DENY => Self::Deny,
+            RESULT_UNSPECIFIED => Self::Unspecified,
             _ => Self::Invalid,
         }
     }
+
+    /// The `kSecTrustSettingsResult*` constant, if valid
+    fn code(self) -> Option<u32> {
+        match self {
+            Self::Invalid => None,
+            Self::TrustRoot => Some(RESULT_TRUST_ROOT),
+            Self::TrustAsRoot => Some(RESULT_TRUST_AS_ROOT),
+            Self::Deny => Some(RESULT_DENY),
+            Self::Unspecified => Some(RESULT_UNSPECIFIED),
+        }
+    }
 }
 
 /// Allows access to the certificates and their trust settings in a given domain.
+#[cfg(target_os = "macos")]
 pub struct TrustSettings {
     domain: Domain,
 }
 
+#[cfg(target_os = "macos")]
 impl TrustSettings {
     /// Create a new `TrustSettings` for the given domain.
     ///
@@ -201,14 +258,132 @@ impl TrustSettings {
         // the caller to make this decision.
         Ok(None)
     }
+
+    /// Returns all usage constraints of the certificate in this domain.
+    ///
+    /// An empty list means the certificate is always trusted as a root. If the certificate has
+    /// no trust settings in this domain, the `errSecItemNotFound` error is returned.
+    pub fn trust_settings(&self, cert: &SecCertificate) -> Result<Vec<TrustSettingsEntry>> {
+        let trust_settings = unsafe {
+            let mut array_ptr: CFArrayRef = ptr::null_mut();
+            cvt(SecTrustSettingsCopyTrustSettings(cert.as_concrete_TypeRef(), self.domain.into(), &mut array_ptr))?;
+            CFArray::<CFDictionary<CFString, CFType>>::wrap_under_create_rule(array_ptr)
+        };
+
+        trust_settings
+            .iter()
+            .map(|settings| {
+                let Some(Value::Dictionary(mut dictionary)) = Value::from_cf(&settings.as_CFType()) else {
+                    return Err(Error::from_code(errSecDecode));
+                };
+                // the policy and application are objects, not property list values
+                if let Some(policy) = settings.find(CFString::from_static_string(entry::POLICY)) {
+                    let policy = policy.downcast::<SecPolicy>().ok_or_else(|| Error::from_code(errSecDecode))?;
+                    let oid = policy.properties_dictionary()?.remove("SecPolicyOid").ok_or_else(|| Error::from_code(errSecDecode))?;
+                    dictionary.insert(entry::POLICY.into(), oid);
+                }
+                if let Some(application) = settings.find(CFString::from_static_string(entry::APPLICATION)) {
+                    let data = unsafe {
+                        let mut data: CFDataRef = ptr::null();
+                        cvt(SecTrustedApplicationCopyData(application.as_CFTypeRef() as *mut _, &mut data))?;
+                        CFData::wrap_under_create_rule(data)
+                    };
+                    dictionary.insert(entry::APPLICATION.into(), Value::Data(data.bytes().to_vec()));
+                }
+                TrustSettingsEntry::from_dictionary(&dictionary)
+            })
+            .collect()
+    }
+
+    /// Replaces the trust settings of the certificate in this domain.
+    ///
+    /// An empty list means the certificate is always trusted as a root, as with
+    /// [`set_trust_settings_always`](Self::set_trust_settings_always). The same restrictions
+    /// on modifying per-user trust settings apply.
+    pub fn set_trust_settings(&self, cert: &SecCertificate, entries: &[TrustSettingsEntry]) -> Result<()> {
+        let dictionaries = entries
+            .iter()
+            .map(|entry| {
+                let mut dictionary = entry.to_dictionary()?;
+                let policy = match dictionary.remove(entry::POLICY) {
+                    Some(Value::String(oid)) => Some(SecPolicy::create_with_oid(&oid, BTreeMap::new())?),
+                    _ => None,
+                };
+                let application = match dictionary.remove(entry::APPLICATION) {
+                    Some(Value::Data(data)) => Some(trusted_application(&data)?),
+                    _ => None,
+                };
+                let mut pairs: Vec<(CFString, CFType)> =
+                    dictionary.iter().map(|(key, value)| (CFString::new(key), value.to_cf())).collect();
+                if let Some(policy) = policy {
+                    pairs.push((CFString::from_static_string(entry::POLICY), policy.into_CFType()));
+                }
+                if let Some(application) = application {
+                    pairs.push((CFString::from_static_string(entry::APPLICATION), application));
+                }
+                Ok(CFDictionary::from_CFType_pairs(&pairs))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let array = CFArray::from_CFTypes(&dictionaries);
+        cvt(unsafe { SecTrustSettingsSetTrustSettings(cert.as_concrete_TypeRef(), self.domain.into(), array.as_CFTypeRef()) })
+    }
+
+    /// Removes all trust settings of the certificate in this domain.
+    ///
+    /// If the certificate has no trust settings in this domain, the `errSecItemNotFound` error is returned.
+    pub fn remove_trust_settings(&self, cert: &SecCertificate) -> Result<()> {
+        cvt(unsafe { SecTrustSettingsRemoveTrustSettings(cert.as_concrete_TypeRef(), self.domain.into()) })
+    }
+
+    /// Exports all trust settings in this domain as a property list, in the format of
+    /// `security trust-settings-export`.
+    ///
+    /// Use [`TrustSettingsBundle::from_plist`] to inspect the result.
+    pub fn export(&self) -> Result<Vec<u8>> {
+        let data = unsafe {
+            let mut data: CFDataRef = ptr::null();
+            cvt(SecTrustSettingsCreateExternalRepresentation(self.domain.into(), &mut data))?;
+            CFData::wrap_under_create_rule(data)
+        };
+        Ok(data.bytes().to_vec())
+    }
+
+    /// Exports and decodes all trust settings in this domain.
+    pub fn export_bundle(&self) -> Result<TrustSettingsBundle> {
+        TrustSettingsBundle::from_plist(&self.export()?)
+    }
+
+    /// Replaces all trust settings in this domain with those of an exported property list,
+    /// e.g. from [`TrustSettingsBundle::to_xml`].
+    ///
+    /// The system domain can't be modified, and the same restrictions on modifying per-user
+    /// trust settings as for [`set_trust_settings`](Self::set_trust_settings) apply.
+    pub fn import(&self, data: &[u8]) -> Result<()> {
+        let data = CFData::from_buffer(data);
+        cvt(unsafe { SecTrustSettingsImportExternalRepresentation(self.domain.into(), data.as_concrete_TypeRef()) })
+    }
+}
+
+/// A `SecTrustedApplication` with the opaque data of [`TrustSettingsEntry::application`].
+#[cfg(target_os = "macos")]
+fn trusted_application(data: &[u8]) -> Result<CFType> {
+    unsafe {
+        let mut application: SecTrustedApplicationRef = ptr::null_mut();
+        cvt(SecTrustedApplicationCreateFromPath(ptr::null(), &mut application))?;
+        let application = CFType::wrap_under_create_rule(application as CFTypeRef);
+        cvt(SecTrustedApplicationSetData(application.as_CFTypeRef() as *mut _, CFData::from_buffer(data).as_concrete_TypeRef()))?;
+        Ok(application)
+    }
 }
 
 /// Iterator over certificates.
+#[cfg(target_os = "macos")]
 pub struct TrustSettingsIter {
     array: CFArray<SecCertificate>,
     index: CFIndex,
 }
 
+#[cfg(target_os = "macos")]
 impl Iterator for TrustSettingsIter {
     type Item = SecCertificate;
 
@@ -230,7 +405,7 @@ impl Iterator for TrustSettingsIter {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, target_os = "macos"))]
 mod test {
     use super::*;
     use crate::test::certificate;
@@ -247,6 +422,17 @@ mod test {
         println!("---");
     }
 
+    #[test]
+    fn constants_match_sys() {
+        assert_eq!(Domain::User as u32, kSecTrustSettingsDomainUser);
+        assert_eq!(Domain::Admin as u32, kSecTrustSettingsDomainAdmin);
+        assert_eq!(Domain::System as u32, kSecTrustSettingsDomainSystem);
+        assert_eq!(TrustSettingsForCertificate::TrustAsRoot.code(), Some(kSecTrustSettingsResultTrustAsRoot));
+        assert_eq!(TrustSettingsForCertificate::Unspecified.code(), Some(kSecTrustSettingsResultUnspecified));
+        assert_eq!(TrustSettingsKeyUsage::SIGN_REVOCATION.bits(), kSecTrustSettingsKeyUseSignRevocation);
+        assert_eq!(TrustSettingsKeyUsage::ANY.bits(), kSecTrustSettingsKeyUseAny);
+    }
+
     #[test]
     fn list_for_user() {
         list_for_domain(Domain::User);
@@ -286,6 +472,24 @@ mod test {
         //  with a resulting kSecTrustSettingsResult of kSecTrustSettingsResultTrustRoot"."
     }
 
+    #[test]
+    fn export_system() {
+        let bundle = TrustSettings::new(Domain::System).export_bundle().unwrap();
+        assert!(bundle.len() > 100);
+        let xml = bundle.to_xml().unwrap();
+        assert_eq!(TrustSettingsBundle::from_plist(xml.as_bytes()).unwrap(), bundle);
+    }
+
+    #[test]
+    fn test_typed_system_trust_settings() {
+        let ts = TrustSettings::new(Domain::System);
+        for cert in ts.iter().unwrap() {
+            let entries = ts.trust_settings(&cert).unwrap();
+            assert!(entries.iter().all(|entry| entry.result() != TrustSettingsForCertificate::Invalid));
+        }
+        assert!(ts.trust_settings(&certificate()).is_err());
+    }
+
     #[test]
     fn test_unknown_cert_is_not_trusted() {
         let ts = TrustSettings::new(Domain::System);
@@ -0,0 +1,201 @@
+//! The effectively trusted TLS anchors, for TLS stacks that don't use Security.framework.
+
+use std::collections::BTreeMap;
+
+use super::bundle::sha1_hex;
+use super::snapshot::{effective_verdict, Snapshot, PRECEDENCE};
+use super::{Domain, TrustSettingsEntry, TrustSettingsKeyUsage};
+use crate::policy::PolicyKind;
+#[cfg(target_os = "macos")]
+use super::TrustSettings;
+#[cfg(target_os = "macos")]
+use crate::base::Result;
+#[cfg(target_os = "macos")]
+use crate::trust::SecTrust;
+#[cfg(target_os = "macos")]
+use security_framework_sys::base::errSecItemNotFound;
+
+/// A certificate, whether it's a system anchor, and its settings by domain
+type Candidate = (Vec<u8>, bool, Vec<(Domain, Vec<TrustSettingsEntry>)>);
+
+/// DER-encoded certificates to use as TLS trust anchors: the system anchors that aren't denied,
+/// and certificates trusted as roots by the admin or user.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AnchorBundle {
+    /// Sorted by SHA-1 fingerprint
+    certificates: Vec<Vec<u8>>,
+}
+
+impl AnchorBundle {
+    /// Collects the anchors trusted for TLS by this machine's trust settings.
+    #[cfg(target_os = "macos")]
+    pub fn system() -> Result<Self> {
+        let anchors = SecTrust::copy_anchor_certificates()?.iter().map(|c| c.to_der()).collect::<Vec<_>>();
+        let mut settings = Vec::new();
+        for domain in PRECEDENCE {
+            let trust_settings = TrustSettings::new(domain);
+            for certificate in trust_settings.iter()? {
+                match trust_settings.trust_settings(&certificate) {
+                    Ok(entries) => settings.push((domain, certificate.to_der(), entries)),
+                    // removed since the certificates were listed
+                    Err(e) if e.code() == errSecItemNotFound => {},
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        Ok(Self::select(anchors, settings))
+    }
+
+    /// Selects the trusted anchors from system anchors and the usage constraints of certificates
+    /// by domain, as returned by [`TrustSettings::trust_settings`].
+    ///
+    /// The certificates whose [`effective_verdict`] for TLS is trusted are selected: the first
+    /// applicable constraint in [`PRECEDENCE`] order decides, and an empty list of constraints
+    /// trusts the certificate as a root. Without a decision, a certificate is included if it's a
+    /// system anchor.
+    ///
+    /// [`TrustSettings::trust_settings`]: super::TrustSettings::trust_settings
+    pub fn select(
+        anchors: impl IntoIterator<Item = Vec<u8>>,
+        settings: impl IntoIterator<Item = (Domain, Vec<u8>, Vec<TrustSettingsEntry>)>,
+    ) -> Self {
+        let mut candidates: BTreeMap<String, Candidate> =
+            anchors.into_iter().map(|der| (sha1_hex(&der), (der, true, Vec::new()))).collect();
+        for (domain, der, entries) in settings {
+            let candidate = candidates.entry(sha1_hex(&der)).or_insert_with(|| (der, false, Vec::new()));
+            candidate.2.push((domain, entries));
+        }
+        let certificates = candidates
+            .into_values()
+            .filter(|(_, is_anchor, settings)| {
+                let settings = |domain| settings.iter().find(|(d, _)| *d == domain).map(|(_, entries)| &entries[..]);
+                effective_verdict(settings, *is_anchor, PolicyKind::Ssl, None, TrustSettingsKeyUsage::empty()).is_trusted()
+            })
+            .map(|(der, ..)| der)
+            .collect();
+        Self { certificates }
+    }
+
+    /// Selects the anchors of a [`Snapshot`] whose TLS verdict is trusted.
+    #[must_use]
+    pub fn from_snapshot(snapshot: &Snapshot) -> Self {
+        let mut candidates: BTreeMap<String, &[u8]> = snapshot.anchors().map(|der| (sha1_hex(der), der)).collect();
+        for domain in PRECEDENCE {
+            candidates.extend(snapshot.certificates(domain).map(|(der, _)| (sha1_hex(der), der)));
+        }
+        let certificates = candidates
+            .into_values()
+            .filter(|der| snapshot.verdict(der, PolicyKind::Ssl, None, TrustSettingsKeyUsage::empty()).is_trusted())
+            .map(<[u8]>::to_vec)
+            .collect();
+        Self { certificates }
+    }
+
+    /// The DER-encoded certificates, sorted by SHA-1 fingerprint.
+    #[inline]
+    #[must_use]
+    pub fn certificates(&self) -> &[Vec<u8>] {
+        &self.certificates
+    }
+
+    /// The certificates as concatenated PEM `CERTIFICATE` blocks.
+    #[must_use]
+    pub fn to_pem(&self) -> String {
+        self.certificates.iter().map(|der| crate::pem::encode("CERTIFICATE", der)).collect()
+    }
+
+    /// The number of anchors.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.certificates.len()
+    }
+
+    /// Whether there are no anchors.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.certificates.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::trust_settings::TrustSettingsForCertificate;
+
+    const CA: &[u8] = include_bytes!("../../test/ca.der");
+    const SERVER: &[u8] = include_bytes!("../../test/server.der");
+
+    #[test]
+    fn select() {
+        use TrustSettingsForCertificate::{Deny, TrustAsRoot, TrustRoot};
+
+        let entry = |result| vec![TrustSettingsEntry::new(result)];
+
+        // no settings: the system anchors
+        let bundle = AnchorBundle::select([CA.to_vec()], []);
+        assert_eq!(bundle.certificates(), [CA]);
+
+        // the system denies the anchor, the user trusts another certificate
+        let bundle = AnchorBundle::select(
+            [CA.to_vec()],
+            [(Domain::System, CA.to_vec(), entry(Deny)), (Domain::User, SERVER.to_vec(), entry(TrustAsRoot))],
+        );
+        assert_eq!(bundle.certificates(), [SERVER]);
+
+        // the admin overrides the system, the user overrides the admin, regardless of order
+        let bundle = AnchorBundle::select(
+            [CA.to_vec()],
+            [
+                (Domain::User, CA.to_vec(), entry(Deny)),
+                (Domain::System, CA.to_vec(), entry(Deny)),
+                (Domain::Admin, CA.to_vec(), entry(TrustRoot)),
+                (Domain::System, SERVER.to_vec(), entry(Deny)),
+                (Domain::Admin, SERVER.to_vec(), entry(TrustRoot)),
+            ],
+        );
+        assert_eq!(bundle.certificates(), [SERVER]);
+
+        // an empty list of constraints trusts a certificate that isn't a system anchor
+        let bundle = AnchorBundle::select([], [(Domain::Admin, SERVER.to_vec(), vec![])]);
+        assert_eq!(bundle.certificates(), [SERVER]);
+
+        // settings for other uses don't decide
+        let mut smime = TrustSettingsEntry::new(Deny);
+        smime.set_policy(Some(PolicyKind::Smime));
+        let bundle = AnchorBundle::select(
+            [CA.to_vec()],
+            [(Domain::User, CA.to_vec(), vec![smime.clone()]), (Domain::User, SERVER.to_vec(), vec![smime])],
+        );
+        assert_eq!(bundle.certificates(), [CA]);
+        assert!(AnchorBundle::select([], [(Domain::User, SERVER.to_vec(), entry(Deny))]).is_empty());
+    }
+
+    #[test]
+    fn from_snapshot() {
+        let snapshot = Snapshot::from_plist(include_bytes!("../../test/trust-snapshot.plist")).unwrap();
+        // the server certificate is only denied for one hostname, and never trusted
+        assert_eq!(AnchorBundle::from_snapshot(&snapshot).certificates(), [CA]);
+
+        let mut snapshot = snapshot;
+        snapshot.set_trust_settings(Domain::User, CA, vec![TrustSettingsEntry::new(TrustSettingsForCertificate::Deny)]);
+        assert!(AnchorBundle::from_snapshot(&snapshot).is_empty());
+    }
+
+    #[test]
+    fn pem() {
+        let bundle = AnchorBundle::select([CA.to_vec(), SERVER.to_vec()], []);
+        assert_eq!(bundle.len(), 2);
+        let pem = bundle.to_pem();
+        let blocks = crate::pem::parse(pem.as_bytes()).unwrap();
+        assert_eq!(blocks.iter().map(|b| b.der()).collect::<Vec<_>>(), bundle.certificates());
+        assert!(blocks.iter().all(|b| b.label() == "CERTIFICATE"));
+    }
+
+    #[test]
+    #[cfg(target_os = "macos")]
+    fn system() {
+        assert!(AnchorBundle::system().unwrap().len() > 100);
+    }
+}
@@ -0,0 +1,411 @@
+//! The exported form of a domain's trust settings, as read and written by
+//! `security trust-settings-export` and `trust-settings-import`.
+
+use std::collections::BTreeMap;
+use std::time::SystemTime;
+
+use security_framework_sys::base::errSecDecode;
+
+use super::entry::POLICY;
+use super::TrustSettingsEntry;
+use crate::base::{Error, Result};
+use crate::certificate::{DigestAlgorithm, Fingerprint, Oid, Parsed};
+use crate::plist::Value;
+
+const TRUST_LIST: &str = "trustList";
+const TRUST_VERSION: &str = "trustVersion";
+const ISSUER_NAME: &str = "issuerName";
+const SERIAL_NUMBER: &str = "serialNumber";
+const MOD_DATE: &str = "modDate";
+const TRUST_SETTINGS: &str = "trustSettings";
+/// The only version of the format
+const VERSION: i64 = 1;
+
+/// All trust settings of a domain, keyed by certificate fingerprint.
+///
+/// This is a plain Rust model of the property list that [`TrustSettings::export`] returns and
+/// [`TrustSettings::import`] accepts, so bundles can be generated and compared on any platform.
+///
+/// [`TrustSettings::export`]: super::TrustSettings::export
+/// [`TrustSettings::import`]: super::TrustSettings::import
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrustSettingsBundle {
+    certificates: BTreeMap<String, CertificateTrustSettings>,
+}
+
+impl TrustSettingsBundle {
+    /// An empty bundle, which clears a domain's trust settings when imported.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads an exported bundle in either the XML or the binary property list format.
+    pub fn from_plist(data: &[u8]) -> Result<Self> {
+        Self::from_value(&Value::parse(data)?)
+    }
+
+    /// Decodes an exported bundle.
+    ///
+    /// Fails with `errSecDecode` on an unknown version.
+    pub fn from_value(value: &Value) -> Result<Self> {
+        if value.get(TRUST_VERSION).and_then(Value::as_integer) != Some(VERSION) {
+            return Err(decode_error());
+        }
+        let list = value.get(TRUST_LIST).and_then(Value::as_dictionary).ok_or_else(decode_error)?;
+        let certificates = list
+            .iter()
+            .map(|(fingerprint, settings)| Ok((normalize(fingerprint), CertificateTrustSettings::from_value(fingerprint, settings)?)))
+            .collect::<Result<_>>()?;
+        Ok(Self { certificates })
+    }
+
+    /// Encodes as an exported bundle.
+    ///
+    /// Fails with `errSecParam` if an entry has an `Invalid` result or a malformed policy.
+    pub fn to_value(&self) -> Result<Value> {
+        let list = self
+            .certificates
+            .iter()
+            .map(|(fingerprint, settings)| Ok((fingerprint.clone(), settings.to_value()?)))
+            .collect::<Result<_>>()?;
+        Ok(Value::Dictionary(BTreeMap::from([
+            (TRUST_LIST.into(), Value::Dictionary(list)),
+            (TRUST_VERSION.into(), Value::Integer(VERSION)),
+        ])))
+    }
+
+    /// Writes an XML property list. Certificates are sorted by fingerprint, so the output of
+    /// equal bundles is identical and suitable for diffing.
+    pub fn to_xml(&self) -> Result<String> {
+        Ok(self.to_value()?.to_xml())
+    }
+
+    /// Writes a binary property list.
+    pub fn to_binary(&self) -> Result<Vec<u8>> {
+        Ok(self.to_value()?.to_binary())
+    }
+
+    /// The settings of the certificate with this SHA-1 fingerprint, in hex.
+    #[must_use]
+    pub fn get(&self, fingerprint: &str) -> Option<&CertificateTrustSettings> {
+        self.certificates.get(&normalize(fingerprint))
+    }
+
+    /// Adds or replaces the settings of a certificate, returning the previous ones.
+    pub fn insert(&mut self, settings: CertificateTrustSettings) -> Option<CertificateTrustSettings> {
+        self.certificates.insert(settings.fingerprint.clone(), settings)
+    }
+
+    /// Removes the settings of the certificate with this SHA-1 fingerprint.
+    pub fn remove(&mut self, fingerprint: &str) -> Option<CertificateTrustSettings> {
+        self.certificates.remove(&normalize(fingerprint))
+    }
+
+    /// The certificates' settings, sorted by fingerprint.
+    pub fn iter(&self) -> impl Iterator<Item = &CertificateTrustSettings> {
+        self.certificates.values()
+    }
+
+    /// The number of certificates with settings.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.certificates.len()
+    }
+
+    /// Whether no certificate has settings.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.certificates.is_empty()
+    }
+}
+
+/// The trust settings of one certificate in a [`TrustSettingsBundle`].
+///
+/// The certificate itself isn't part of the bundle, only its fingerprint, issuer and serial number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CertificateTrustSettings {
+    fingerprint: String,
+    issuer: Vec<u8>,
+    serial_number: Vec<u8>,
+    modification_date: Option<SystemTime>,
+    entries: Vec<TrustSettingsEntry>,
+}
+
+impl CertificateTrustSettings {
+    /// Settings for a DER-encoded certificate.
+    ///
+    /// An empty list of entries means the certificate is always trusted as a root.
+    pub fn from_certificate(der: &[u8], entries: Vec<TrustSettingsEntry>) -> Result<Self> {
+        let parsed = Parsed::from_der(der)?;
+        Ok(Self {
+            fingerprint: sha1_hex(der),
+            issuer: parsed.issuer_der().to_vec(),
+            serial_number: parsed.serial_number().to_vec(),
+            modification_date: None,
+            entries,
+        })
+    }
+
+    /// Sets when the settings were last changed, which is written to the bundle if set.
+    pub fn set_modification_date(&mut self, date: Option<SystemTime>) -> &mut Self {
+        self.modification_date = date;
+        self
+    }
+
+    /// Replaces the usage constraints.
+    pub fn set_entries(&mut self, entries: Vec<TrustSettingsEntry>) -> &mut Self {
+        self.entries = entries;
+        self
+    }
+
+    /// The certificate's SHA-1 fingerprint, in uppercase hex, or
+    /// `kSecTrustSettingsDefaultRootCertSetting` for the settings of all roots.
+    #[inline]
+    #[must_use]
+    pub fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+
+    /// The DER-encoded issuer name.
+    #[inline]
+    #[must_use]
+    pub fn issuer(&self) -> &[u8] {
+        &self.issuer
+    }
+
+    /// The serial number as big-endian two's complement bytes.
+    #[inline]
+    #[must_use]
+    pub fn serial_number(&self) -> &[u8] {
+        &self.serial_number
+    }
+
+    /// When the settings were last changed.
+    #[inline]
+    #[must_use]
+    pub fn modification_date(&self) -> Option<SystemTime> {
+        self.modification_date
+    }
+
+    /// The usage constraints. An empty list means the certificate is always trusted as a root.
+    #[inline]
+    #[must_use]
+    pub fn entries(&self) -> &[TrustSettingsEntry] {
+        &self.entries
+    }
+
+    /// Whether these are the settings of this DER-encoded certificate.
+    #[must_use]
+    pub fn matches(&self, der: &[u8]) -> bool {
+        sha1_hex(der) == self.fingerprint
+    }
+
+    fn from_value(fingerprint: &str, value: &Value) -> Result<Self> {
+        let data = |key| value.get(key).map(|v| v.as_data().map(<[u8]>::to_vec).ok_or_else(decode_error)).transpose();
+        let modification_date = value.get(MOD_DATE).map(|v| v.as_date().ok_or_else(decode_error)).transpose()?;
+        let entries = match value.get(TRUST_SETTINGS) {
+            Some(settings) => settings
+                .as_array()
+                .ok_or_else(decode_error)?
+                .iter()
+                .map(|dictionary| {
+                    let mut dictionary = dictionary.as_dictionary().ok_or_else(decode_error)?.clone();
+                    // the policy is stored as the contents of its object identifier
+                    if let Some(policy) = dictionary.remove(POLICY) {
+                        let oid = Oid::from_der_content(policy.as_data().ok_or_else(decode_error)?)?;
+                        dictionary.insert(POLICY.into(), Value::String(oid.to_string()));
+                    }
+                    TrustSettingsEntry::from_dictionary(&dictionary)
+                })
+                .collect::<Result<_>>()?,
+            None => Vec::new(),
+        };
+        Ok(Self {
+            fingerprint: normalize(fingerprint),
+            // absent for the default root certificate setting
+            issuer: data(ISSUER_NAME)?.unwrap_or_default(),
+            serial_number: data(SERIAL_NUMBER)?.unwrap_or_default(),
+            modification_date,
+            entries,
+        })
+    }
+
+    fn to_value(&self) -> Result<Value> {
+        let entries = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let mut dictionary = entry.to_dictionary()?;
+                if let Some(Value::String(oid)) = dictionary.remove(POLICY) {
+                    let oid: Oid = oid.parse()?;
+                    dictionary.insert(POLICY.into(), Value::Data(oid.as_der_content().to_vec()));
+                }
+                Ok(Value::Dictionary(dictionary))
+            })
+            .collect::<Result<_>>()?;
+        let mut dictionary = BTreeMap::from([(TRUST_SETTINGS.into(), Value::Array(entries))]);
+        if !self.issuer.is_empty() {
+            dictionary.insert(ISSUER_NAME.into(), Value::Data(self.issuer.clone()));
+        }
+        if !self.serial_number.is_empty() {
+            dictionary.insert(SERIAL_NUMBER.into(), Value::Data(self.serial_number.clone()));
+        }
+        if let Some(date) = self.modification_date {
+            dictionary.insert(MOD_DATE.into(), Value::Date(date));
+        }
+        Ok(Value::Dictionary(dictionary))
+    }
+}
+
+/// Uppercases hex fingerprints, leaving other keys such as the default root setting's as they are.
+fn normalize(fingerprint: &str) -> String {
+    if fingerprint.bytes().all(|b| b.is_ascii_hexdigit()) {
+        fingerprint.to_ascii_uppercase()
+    } else {
+        fingerprint.into()
+    }
+}
+
+pub(super) fn sha1_hex(der: &[u8]) -> String {
+    Fingerprint::new(DigestAlgorithm::Sha1, der).to_hex().to_ascii_uppercase()
+}
+
+fn decode_error() -> Error {
+    Error::from_code(errSecDecode)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::policy::PolicyKind;
+    use crate::trust_settings::TrustSettingsForCertificate;
+    use security_framework_sys::base::errSecCertificateExpired;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    // trimmed from `security trust-settings-export -d`
+    const EXPORTED: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>trustList</key>
+	<dict>
+		<key>0563b8630d62d75abbc8ab1e4bdfb5a899b24d43</key>
+		<dict>
+			<key>issuerName</key>
+			<data>
+			MDAxCzAJBgNVBAYTAlVTMQ4wDAYDVQQKEwVBZG1pbjERMA8GA1UEAxMIQWRtaW4gQ0E=
+			</data>
+			<key>modDate</key>
+			<date>2024-03-01T12:00:00Z</date>
+			<key>serialNumber</key>
+			<data>
+			AQ==
+			</data>
+			<key>trustSettings</key>
+			<array>
+				<dict>
+					<key>kSecTrustSettingsAllowedError</key>
+					<integer>-2147409654</integer>
+					<key>kSecTrustSettingsPolicy</key>
+					<data>
+					KoZIhvdjZAED
+					</data>
+					<key>kSecTrustSettingsPolicyName</key>
+					<string>sslServer</string>
+					<key>kSecTrustSettingsResult</key>
+					<integer>3</integer>
+				</dict>
+				<dict>
+					<key>kSecTrustSettingsResult</key>
+					<integer>1</integer>
+				</dict>
+			</array>
+		</dict>
+	</dict>
+	<key>trustVersion</key>
+	<integer>1</integer>
+</dict>
+</plist>
+"#;
+
+    #[test]
+    fn decode_export() {
+        let bundle = TrustSettingsBundle::from_plist(EXPORTED.as_bytes()).unwrap();
+        assert_eq!(bundle.len(), 1);
+        let settings = bundle.get("0563B8630D62D75ABBC8AB1E4BDFB5A899B24D43").unwrap();
+        assert_eq!(settings.fingerprint(), "0563B8630D62D75ABBC8AB1E4BDFB5A899B24D43");
+        assert_eq!(settings.serial_number(), [1]);
+        assert_eq!(settings.issuer()[0], 0x30);
+        assert_eq!(settings.modification_date(), Some(UNIX_EPOCH + Duration::from_secs(1_709_294_400)));
+
+        let mut deny = TrustSettingsEntry::new(TrustSettingsForCertificate::Deny);
+        deny.set_policy(Some(PolicyKind::Ssl)).set_policy_name(Some("sslServer")).set_allowed_error(Some(-2_147_409_654));
+        assert_eq!(settings.entries(), [deny, TrustSettingsEntry::new(TrustSettingsForCertificate::TrustRoot)]);
+
+        // round trips through both formats
+        let xml = bundle.to_xml().unwrap();
+        assert!(xml.contains("<data>\n\t\t\t\t\tKoZIhvdjZAED\n\t\t\t\t\t</data>"));
+        assert!(xml.contains("<key>kSecTrustSettingsPolicyName</key>\n\t\t\t\t\t<string>sslServer</string>"));
+        assert_eq!(TrustSettingsBundle::from_plist(xml.as_bytes()).unwrap(), bundle);
+        assert_eq!(TrustSettingsBundle::from_plist(&bundle.to_binary().unwrap()).unwrap(), bundle);
+    }
+
+    #[test]
+    fn generate() {
+        let der = include_bytes!("../../test/server.der");
+        let mut entry = TrustSettingsEntry::new(TrustSettingsForCertificate::TrustAsRoot);
+        entry.set_policy(Some(PolicyKind::Ssl)).set_allowed_error(Some(errSecCertificateExpired));
+        let mut settings = CertificateTrustSettings::from_certificate(der, vec![entry]).unwrap();
+        settings.set_modification_date(Some(UNIX_EPOCH + Duration::from_secs(1_709_294_400)));
+        assert!(settings.matches(der));
+        assert!(!settings.matches(include_bytes!("../../test/ca.der")));
+        assert_eq!(settings.fingerprint().len(), 40);
+        assert_eq!(settings.serial_number(), Parsed::from_der(der).unwrap().serial_number());
+
+        let mut bundle = TrustSettingsBundle::new();
+        assert!(bundle.insert(settings.clone()).is_none());
+        let value = bundle.to_value().unwrap();
+        let stored = value.get(TRUST_LIST).and_then(|list| list.get(settings.fingerprint())).unwrap();
+        assert_eq!(stored.get(TRUST_SETTINGS).and_then(Value::as_array).unwrap()[0].get(POLICY), Some(&Value::Data(vec![0x2a, 0x86, 0x48, 0x86, 0xf7, 0x63, 0x64, 0x01, 0x03])));
+        assert_eq!(TrustSettingsBundle::from_value(&value).unwrap(), bundle);
+        assert_eq!(bundle.to_xml().unwrap(), bundle.clone().to_xml().unwrap());
+
+        assert_eq!(bundle.remove(&settings.fingerprint().to_ascii_lowercase()), Some(settings));
+        assert!(bundle.is_empty());
+        assert_eq!(
+            TrustSettingsBundle::from_plist(bundle.to_xml().unwrap().as_bytes()).unwrap(),
+            TrustSettingsBundle::new()
+        );
+    }
+
+    #[test]
+    fn application() {
+        let exported = EXPORTED.replace(
+            "<key>kSecTrustSettingsResult</key>\n\t\t\t\t\t<integer>1</integer>",
+            "<key>kSecTrustSettingsApplication</key>\n\t\t\t\t\t<data>AA==</data>",
+        );
+        let bundle = TrustSettingsBundle::from_plist(exported.as_bytes()).unwrap();
+        let entries = bundle.get("0563b8630d62d75abbc8ab1e4bdfb5a899b24d43").unwrap().entries();
+        assert_eq!(entries[1].application(), Some(&[0][..]));
+        assert_eq!(entries[1].result(), TrustSettingsForCertificate::TrustRoot);
+        assert_eq!(TrustSettingsBundle::from_plist(bundle.to_xml().unwrap().as_bytes()).unwrap(), bundle);
+    }
+
+    #[test]
+    fn malformed() {
+        let version = EXPORTED.replace("<integer>1</integer>\n</dict>", "<integer>2</integer>\n</dict>");
+        assert_eq!(TrustSettingsBundle::from_plist(version.as_bytes()).unwrap_err().code(), errSecDecode);
+        let policy = EXPORTED.replace("KoZIhvdjZAED", "gA==");
+        assert_eq!(TrustSettingsBundle::from_plist(policy.as_bytes()).unwrap_err().code(), errSecDecode);
+        let application = EXPORTED.replace(
+            "<key>kSecTrustSettingsResult</key>\n\t\t\t\t\t<integer>1</integer>",
+            "<key>kSecTrustSettingsApplication</key>\n\t\t\t\t\t<string>app</string>",
+        );
+        assert_eq!(TrustSettingsBundle::from_plist(application.as_bytes()).unwrap_err().code(), errSecDecode);
+        assert!(CertificateTrustSettings::from_certificate(b"not a certificate", Vec::new()).is_err());
+    }
+}
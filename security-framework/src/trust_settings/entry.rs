@@ -0,0 +1,318 @@
+//! Typed usage constraints, the elements of a certificate's trust settings array.
+
+use std::collections::BTreeMap;
+
+use core_foundation_sys::base::OSStatus;
+use security_framework_sys::base::{errSecDecode, errSecParam};
+
+use super::TrustSettingsForCertificate;
+use crate::base::{Error, Result};
+use crate::plist::Value;
+use crate::policy::PolicyKind;
+
+// Values of the `kSecTrustSettings*` usage constraint keys
+pub(crate) const POLICY: &str = "kSecTrustSettingsPolicy";
+pub(crate) const APPLICATION: &str = "kSecTrustSettingsApplication";
+const POLICY_NAME: &str = "kSecTrustSettingsPolicyName";
+const POLICY_STRING: &str = "kSecTrustSettingsPolicyString";
+const KEY_USAGE: &str = "kSecTrustSettingsKeyUsage";
+const ALLOWED_ERROR: &str = "kSecTrustSettingsAllowedError";
+const RESULT: &str = "kSecTrustSettingsResult";
+
+bitflags::bitflags! {
+    /// Key usages a usage constraint applies to, the `kSecTrustSettingsKeyUse*` values.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct TrustSettingsKeyUsage: u32 {
+        /// Signing data.
+        const SIGNATURE = 0x0000_0001;
+        /// Encrypting and decrypting data.
+        const EN_DECRYPT_DATA = 0x0000_0002;
+        /// Wrapping and unwrapping keys.
+        const EN_DECRYPT_KEY = 0x0000_0004;
+        /// Signing certificates.
+        const SIGN_CERT = 0x0000_0008;
+        /// Signing CRLs and OCSP responses.
+        const SIGN_REVOCATION = 0x0000_0010;
+        /// Key exchange.
+        const KEY_EXCHANGE = 0x0000_0020;
+        /// Any use.
+        const ANY = 0xffff_ffff;
+    }
+}
+
+/// One usage constraint: a trust result that applies when all of its conditions match.
+///
+/// An entry without conditions applies to every use. A certificate with an empty list of
+/// entries is always trusted as a root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrustSettingsEntry {
+    policy: Option<String>,
+    policy_name: Option<String>,
+    policy_string: Option<String>,
+    application: Option<Vec<u8>>,
+    key_usage: Option<TrustSettingsKeyUsage>,
+    allowed_error: Option<OSStatus>,
+    result: TrustSettingsForCertificate,
+}
+
+impl TrustSettingsEntry {
+    /// An unconditional entry with `result`.
+    #[must_use]
+    pub fn new(result: TrustSettingsForCertificate) -> Self {
+        Self { policy: None, policy_name: None, policy_string: None, application: None, key_usage: None, allowed_error: None, result }
+    }
+
+    /// Applies only to evaluations with a policy of `kind`.
+    pub fn set_policy(&mut self, kind: Option<PolicyKind>) -> &mut Self {
+        self.policy = kind.map(|kind| kind.oid().into());
+        self
+    }
+
+    /// Sets the informational name of the policy, e.g. `sslServer`. It doesn't affect which evaluations the entry applies to.
+    pub fn set_policy_name(&mut self, policy_name: Option<&str>) -> &mut Self {
+        self.policy_name = policy_name.map(String::from);
+        self
+    }
+
+    /// Applies only to evaluations whose policy matches this hostname (TLS) or email address (S/MIME).
+    pub fn set_policy_string(&mut self, policy_string: Option<&str>) -> &mut Self {
+        self.policy_string = policy_string.map(String::from);
+        self
+    }
+
+    /// Applies only to the application identified by this opaque `SecTrustedApplication` data.
+    pub fn set_application(&mut self, application: Option<&[u8]>) -> &mut Self {
+        self.application = application.map(<[u8]>::to_vec);
+        self
+    }
+
+    /// Applies only to these key usages.
+    pub fn set_key_usage(&mut self, key_usage: Option<TrustSettingsKeyUsage>) -> &mut Self {
+        self.key_usage = key_usage;
+        self
+    }
+
+    /// Ignores this error, e.g. `errSecCertificateExpired`, when the entry applies.
+    pub fn set_allowed_error(&mut self, allowed_error: Option<OSStatus>) -> &mut Self {
+        self.allowed_error = allowed_error;
+        self
+    }
+
+    /// The kind of policy the entry applies to, or `None` for any policy or one not in [`PolicyKind`].
+    #[must_use]
+    pub fn policy(&self) -> Option<PolicyKind> {
+        PolicyKind::from_oid(self.policy.as_deref()?)
+    }
+
+    /// The identifier of the policy the entry applies to.
+    #[inline]
+    #[must_use]
+    pub fn policy_oid(&self) -> Option<&str> {
+        self.policy.as_deref()
+    }
+
+    /// The informational name of the policy, e.g. `sslServer`.
+    #[inline]
+    #[must_use]
+    pub fn policy_name(&self) -> Option<&str> {
+        self.policy_name.as_deref()
+    }
+
+    /// The hostname or email address the entry applies to.
+    #[inline]
+    #[must_use]
+    pub fn policy_string(&self) -> Option<&str> {
+        self.policy_string.as_deref()
+    }
+
+    /// The opaque `SecTrustedApplication` data of the application the entry applies to.
+    #[inline]
+    #[must_use]
+    pub fn application(&self) -> Option<&[u8]> {
+        self.application.as_deref()
+    }
+
+    /// The key usages the entry applies to.
+    #[inline]
+    #[must_use]
+    pub fn key_usage(&self) -> Option<TrustSettingsKeyUsage> {
+        self.key_usage
+    }
+
+    /// The error ignored when the entry applies.
+    #[inline]
+    #[must_use]
+    pub fn allowed_error(&self) -> Option<OSStatus> {
+        self.allowed_error
+    }
+
+    /// The trust result when the entry applies.
+    #[inline]
+    #[must_use]
+    pub fn result(&self) -> TrustSettingsForCertificate {
+        self.result
+    }
+
+    /// Whether the entry applies to evaluations with `policy` for `policy_string`, using the key
+    /// for `key_usage`.
+    ///
+    /// As in the OS, an empty `key_usage` means any use of the key, and otherwise all of its
+    /// usages must be allowed by the entry. Entries restricted to an application never apply,
+    /// as the evaluating application isn't known.
+    #[must_use]
+    pub fn applies_to(&self, policy: PolicyKind, policy_string: Option<&str>, key_usage: TrustSettingsKeyUsage) -> bool {
+        self.application.is_none()
+            && self.policy.as_deref().is_none_or(|oid| oid == policy.oid())
+            && self.policy_string.as_deref().is_none_or(|s| policy_string.is_some_and(|p| p.eq_ignore_ascii_case(s)))
+            && self.key_usage.is_none_or(|allowed| allowed == TrustSettingsKeyUsage::ANY || allowed.contains(key_usage))
+    }
+
+    /// Decodes a usage constraint dictionary, with the policy given by its identifier.
+    ///
+    /// A missing result means `TrustRoot`. The application is given by its `SecTrustedApplication` data.
+    pub fn from_dictionary(dictionary: &BTreeMap<String, Value>) -> Result<Self> {
+        let string = |key| dictionary.get(key).map(|v| v.as_str().map(String::from).ok_or_else(decode_error)).transpose();
+        let integer = |key| dictionary.get(key).map(|v| v.as_integer().ok_or_else(decode_error)).transpose();
+
+        let key_usage = integer(KEY_USAGE)?
+            .map(|bits| {
+                // stored as a signed 32-bit number, so "any" is -1
+                let bits = u32::try_from(bits).or_else(|_| i32::try_from(bits).map(|bits| u32::from_ne_bytes(bits.to_ne_bytes())));
+                bits.map(TrustSettingsKeyUsage::from_bits_retain).map_err(|_| decode_error())
+            })
+            .transpose()?;
+        let allowed_error = integer(ALLOWED_ERROR)?.map(|e| OSStatus::try_from(e).map_err(|_| decode_error())).transpose()?;
+        let result = match integer(RESULT)? {
+            Some(result) => match TrustSettingsForCertificate::new(result) {
+                TrustSettingsForCertificate::Invalid => return Err(decode_error()),
+                result => result,
+            },
+            None => TrustSettingsForCertificate::TrustRoot,
+        };
+        let application = dictionary.get(APPLICATION).map(|v| v.as_data().map(<[u8]>::to_vec).ok_or_else(decode_error)).transpose()?;
+        Ok(Self { policy: string(POLICY)?, policy_name: string(POLICY_NAME)?, policy_string: string(POLICY_STRING)?, application, key_usage, allowed_error, result })
+    }
+
+    /// Encodes as a usage constraint dictionary, with the policy given by its identifier.
+    ///
+    /// Fails with `errSecParam` if the result is `Invalid`.
+    pub fn to_dictionary(&self) -> Result<BTreeMap<String, Value>> {
+        let result = self.result.code().ok_or_else(|| Error::from_code(errSecParam))?;
+        let mut dictionary = BTreeMap::from([(RESULT.into(), Value::Integer(result.into()))]);
+        if let Some(policy) = &self.policy {
+            dictionary.insert(POLICY.into(), Value::String(policy.clone()));
+        }
+        if let Some(policy_name) = &self.policy_name {
+            dictionary.insert(POLICY_NAME.into(), Value::String(policy_name.clone()));
+        }
+        if let Some(policy_string) = &self.policy_string {
+            dictionary.insert(POLICY_STRING.into(), Value::String(policy_string.clone()));
+        }
+        if let Some(application) = &self.application {
+            dictionary.insert(APPLICATION.into(), Value::Data(application.clone()));
+        }
+        if let Some(key_usage) = self.key_usage {
+            // signed, like the OS stores it
+            let bits = i32::from_ne_bytes(key_usage.bits().to_ne_bytes());
+            dictionary.insert(KEY_USAGE.into(), Value::Integer(bits.into()));
+        }
+        if let Some(allowed_error) = self.allowed_error {
+            dictionary.insert(ALLOWED_ERROR.into(), Value::Integer(allowed_error.into()));
+        }
+        Ok(dictionary)
+    }
+}
+
+fn decode_error() -> Error {
+    Error::from_code(errSecDecode)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use security_framework_sys::base::errSecCertificateExpired;
+
+    #[test]
+    fn round_trip() {
+        let mut entry = TrustSettingsEntry::new(TrustSettingsForCertificate::TrustAsRoot);
+        entry
+            .set_policy(Some(PolicyKind::Ssl))
+            .set_policy_name(Some("sslServer"))
+            .set_policy_string(Some("foobar.com"))
+            .set_application(Some(b"app"))
+            .set_key_usage(Some(TrustSettingsKeyUsage::SIGNATURE | TrustSettingsKeyUsage::KEY_EXCHANGE))
+            .set_allowed_error(Some(errSecCertificateExpired));
+        let dictionary = entry.to_dictionary().unwrap();
+        assert_eq!(
+            dictionary,
+            BTreeMap::from([
+                (RESULT.into(), Value::Integer(2)),
+                (POLICY.into(), Value::String("1.2.840.113635.100.1.3".into())),
+                (POLICY_NAME.into(), Value::String("sslServer".into())),
+                (POLICY_STRING.into(), Value::String("foobar.com".into())),
+                (APPLICATION.into(), Value::Data(b"app".to_vec())),
+                (KEY_USAGE.into(), Value::Integer(0x21)),
+                (ALLOWED_ERROR.into(), Value::Integer(errSecCertificateExpired.into())),
+            ])
+        );
+        assert_eq!(TrustSettingsEntry::from_dictionary(&dictionary).unwrap(), entry);
+        assert_eq!(entry.policy(), Some(PolicyKind::Ssl));
+
+        let deny = TrustSettingsEntry::new(TrustSettingsForCertificate::Deny);
+        assert_eq!(TrustSettingsEntry::from_dictionary(&deny.to_dictionary().unwrap()).unwrap(), deny);
+        assert!(TrustSettingsEntry::new(TrustSettingsForCertificate::Invalid).to_dictionary().is_err());
+    }
+
+    #[test]
+    fn decode() {
+        // no result means "trust as root"
+        let entry = TrustSettingsEntry::from_dictionary(&BTreeMap::new()).unwrap();
+        assert_eq!(entry, TrustSettingsEntry::new(TrustSettingsForCertificate::TrustRoot));
+
+        let dictionary = BTreeMap::from([(KEY_USAGE.into(), Value::Integer(-1)), (POLICY.into(), Value::String("1.2.3".into()))]);
+        let entry = TrustSettingsEntry::from_dictionary(&dictionary).unwrap();
+        assert_eq!(entry.key_usage(), Some(TrustSettingsKeyUsage::ANY));
+        assert_eq!(entry.policy(), None);
+        assert_eq!(entry.policy_oid(), Some("1.2.3"));
+        assert_eq!(entry.to_dictionary().unwrap()[POLICY], Value::String("1.2.3".into()));
+
+        // "any use" is encoded as -1 again
+        let dictionary = BTreeMap::from([(RESULT.into(), Value::Integer(1)), (KEY_USAGE.into(), Value::Integer(-1))]);
+        assert_eq!(TrustSettingsEntry::from_dictionary(&dictionary).unwrap().to_dictionary().unwrap(), dictionary);
+
+        for (dictionary, code) in [
+            (BTreeMap::from([(APPLICATION.into(), Value::Bool(true))]), errSecDecode),
+            (BTreeMap::from([(RESULT.into(), Value::Integer(0))]), errSecDecode),
+            (BTreeMap::from([(RESULT.into(), Value::String("1".into()))]), errSecDecode),
+            (BTreeMap::from([(KEY_USAGE.into(), Value::Integer(1 << 40))]), errSecDecode),
+            (BTreeMap::from([(POLICY_STRING.into(), Value::Bool(true))]), errSecDecode),
+        ] {
+            assert_eq!(TrustSettingsEntry::from_dictionary(&dictionary).unwrap_err().code(), code, "{dictionary:?}");
+        }
+    }
+
+    #[test]
+    fn applies_to() {
+        let any = TrustSettingsEntry::new(TrustSettingsForCertificate::Deny);
+        assert!(any.applies_to(PolicyKind::CodeSigning, None, TrustSettingsKeyUsage::empty()));
+
+        let mut ssl = TrustSettingsEntry::new(TrustSettingsForCertificate::TrustRoot);
+        ssl.set_policy(Some(PolicyKind::Ssl)).set_policy_string(Some("Foobar.com"));
+        assert!(ssl.applies_to(PolicyKind::Ssl, Some("foobar.com"), TrustSettingsKeyUsage::empty()));
+        assert!(!ssl.applies_to(PolicyKind::Ssl, Some("example.com"), TrustSettingsKeyUsage::empty()));
+        assert!(!ssl.applies_to(PolicyKind::Ssl, None, TrustSettingsKeyUsage::empty()));
+        assert!(!ssl.applies_to(PolicyKind::Smime, Some("foobar.com"), TrustSettingsKeyUsage::empty()));
+
+        let mut application = TrustSettingsEntry::new(TrustSettingsForCertificate::Deny);
+        application.set_application(Some(b"app"));
+        assert!(!application.applies_to(PolicyKind::Ssl, None, TrustSettingsKeyUsage::empty()));
+
+        let mut signing = TrustSettingsEntry::new(TrustSettingsForCertificate::TrustRoot);
+        signing.set_key_usage(Some(TrustSettingsKeyUsage::SIGNATURE | TrustSettingsKeyUsage::SIGN_CERT));
+        assert!(signing.applies_to(PolicyKind::Ssl, None, TrustSettingsKeyUsage::empty()));
+        assert!(signing.applies_to(PolicyKind::Ssl, None, TrustSettingsKeyUsage::SIGN_CERT));
+        assert!(!signing.applies_to(PolicyKind::Ssl, None, TrustSettingsKeyUsage::SIGN_CERT | TrustSettingsKeyUsage::KEY_EXCHANGE));
+        signing.set_key_usage(Some(TrustSettingsKeyUsage::ANY));
+        assert!(signing.applies_to(PolicyKind::Ssl, None, TrustSettingsKeyUsage::KEY_EXCHANGE));
+    }
+}
@@ -0,0 +1,450 @@
+//! The trust settings of all domains and the system anchors, captured together so that the
+//! effective trust of a certificate can be worked out, and explained, offline.
+
+use std::collections::BTreeMap;
+
+use core_foundation_sys::base::OSStatus;
+use security_framework_sys::base::errSecDecode;
+
+use super::bundle::sha1_hex;
+use super::{Domain, TrustSettingsEntry, TrustSettingsForCertificate, TrustSettingsKeyUsage};
+use crate::base::{Error, Result};
+use crate::plist::Value;
+use crate::policy::PolicyKind;
+#[cfg(target_os = "macos")]
+use super::TrustSettings;
+#[cfg(target_os = "macos")]
+use crate::trust::SecTrust;
+#[cfg(target_os = "macos")]
+use security_framework_sys::base::errSecItemNotFound;
+
+const VERSION_KEY: &str = "snapshotVersion";
+const ANCHORS: &str = "anchors";
+const CERTIFICATE: &str = "certificate";
+const TRUST_SETTINGS: &str = "trustSettings";
+const VERSION: i64 = 1;
+
+/// The domains in the order they're consulted: user settings override admin settings, which
+/// override system settings.
+pub const PRECEDENCE: [Domain; 3] = [Domain::User, Domain::Admin, Domain::System];
+
+/// Where a [`Verdict`] came from.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum VerdictSource {
+    /// A usage constraint in this domain.
+    Domain(Domain),
+    /// The certificate is a system anchor without applicable settings.
+    Anchor,
+    /// Nothing applies.
+    None,
+}
+
+/// The effective trust settings of a certificate for one use.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verdict {
+    result: TrustSettingsForCertificate,
+    source: VerdictSource,
+    allowed_errors: Vec<OSStatus>,
+}
+
+impl Verdict {
+    /// `TrustRoot`, `TrustAsRoot` or `Deny` if decided, otherwise `Unspecified`.
+    #[inline]
+    #[must_use]
+    pub fn result(&self) -> TrustSettingsForCertificate {
+        self.result
+    }
+
+    /// Whether the certificate is trusted as an anchor.
+    #[inline]
+    #[must_use]
+    pub fn is_trusted(&self) -> bool {
+        matches!(self.result, TrustSettingsForCertificate::TrustRoot | TrustSettingsForCertificate::TrustAsRoot)
+    }
+
+    /// What decided the result.
+    #[inline]
+    #[must_use]
+    pub fn source(&self) -> VerdictSource {
+        self.source
+    }
+
+    /// Errors ignored by the applicable usage constraints, in the order they were consulted.
+    #[inline]
+    #[must_use]
+    pub fn allowed_errors(&self) -> &[OSStatus] {
+        &self.allowed_errors
+    }
+}
+
+/// Combines the trust settings of a certificate the way the OS does.
+///
+/// `settings` returns the certificate's usage constraints in a domain, or `None` if it has no
+/// settings there. Domains are consulted in [`PRECEDENCE`] order, and the first applicable
+/// constraint with a result other than `Unspecified` decides, as per [`TrustSettingsEntry::applies_to`].
+/// An empty list of constraints means `TrustRoot` for every use. Without a decision, system
+/// anchors are trusted as roots.
+pub fn effective_verdict<'a>(
+    settings: impl Fn(Domain) -> Option<&'a [TrustSettingsEntry]>,
+    is_anchor: bool,
+    policy: PolicyKind,
+    policy_string: Option<&str>,
+    key_usage: TrustSettingsKeyUsage,
+) -> Verdict {
+    let mut allowed_errors = Vec::new();
+    for domain in PRECEDENCE {
+        let Some(entries) = settings(domain) else { continue };
+        if entries.is_empty() {
+            return Verdict { result: TrustSettingsForCertificate::TrustRoot, source: VerdictSource::Domain(domain), allowed_errors };
+        }
+        for entry in entries.iter().filter(|entry| entry.applies_to(policy, policy_string, key_usage)) {
+            allowed_errors.extend(entry.allowed_error());
+            match entry.result() {
+                TrustSettingsForCertificate::Unspecified | TrustSettingsForCertificate::Invalid => {},
+                result => return Verdict { result, source: VerdictSource::Domain(domain), allowed_errors },
+            }
+        }
+    }
+    let (result, source) = if is_anchor {
+        (TrustSettingsForCertificate::TrustRoot, VerdictSource::Anchor)
+    } else {
+        (TrustSettingsForCertificate::Unspecified, VerdictSource::None)
+    };
+    Verdict { result, source, allowed_errors }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DomainCertificate {
+    certificate: Vec<u8>,
+    entries: Vec<TrustSettingsEntry>,
+}
+
+/// The trust settings of every domain and the system anchors at one point in time.
+///
+/// A snapshot can be saved as a property list and examined on any platform.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Snapshot {
+    /// DER-encoded anchors, keyed by SHA-1 fingerprint
+    anchors: BTreeMap<String, Vec<u8>>,
+    /// Certificates with settings, in [`PRECEDENCE`] order and keyed by SHA-1 fingerprint
+    domains: [BTreeMap<String, DomainCertificate>; 3],
+}
+
+impl Snapshot {
+    /// An empty snapshot.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Captures the trust settings of all domains and the system anchors.
+    #[cfg(target_os = "macos")]
+    pub fn capture() -> Result<Self> {
+        let mut snapshot = Self::new();
+        for anchor in SecTrust::copy_anchor_certificates()? {
+            snapshot.add_anchor(&anchor.to_der());
+        }
+        for domain in PRECEDENCE {
+            let trust_settings = TrustSettings::new(domain);
+            for certificate in trust_settings.iter()? {
+                let entries = match trust_settings.trust_settings(&certificate) {
+                    Ok(entries) => entries,
+                    // removed since the certificates were listed
+                    Err(e) if e.code() == errSecItemNotFound => continue,
+                    Err(e) => return Err(e),
+                };
+                snapshot.set_trust_settings(domain, &certificate.to_der(), entries);
+            }
+        }
+        Ok(snapshot)
+    }
+
+    /// Adds a DER-encoded system anchor.
+    pub fn add_anchor(&mut self, certificate: &[u8]) -> &mut Self {
+        self.anchors.insert(sha1_hex(certificate), certificate.to_vec());
+        self
+    }
+
+    /// Sets the usage constraints of a DER-encoded certificate in `domain`.
+    pub fn set_trust_settings(&mut self, domain: Domain, certificate: &[u8], entries: Vec<TrustSettingsEntry>) -> &mut Self {
+        let certificate = DomainCertificate { certificate: certificate.to_vec(), entries };
+        self.domains[index(domain)].insert(sha1_hex(&certificate.certificate), certificate);
+        self
+    }
+
+    /// The DER-encoded system anchors, sorted by fingerprint.
+    pub fn anchors(&self) -> impl Iterator<Item = &[u8]> {
+        self.anchors.values().map(Vec::as_slice)
+    }
+
+    /// Whether the DER-encoded certificate is a system anchor.
+    #[must_use]
+    pub fn is_anchor(&self, certificate: &[u8]) -> bool {
+        self.anchors.contains_key(&sha1_hex(certificate))
+    }
+
+    /// The DER-encoded certificates with settings in `domain` and their usage constraints,
+    /// sorted by fingerprint.
+    pub fn certificates(&self, domain: Domain) -> impl Iterator<Item = (&[u8], &[TrustSettingsEntry])> {
+        self.domains[index(domain)].values().map(|c| (c.certificate.as_slice(), c.entries.as_slice()))
+    }
+
+    /// The usage constraints of the DER-encoded certificate in `domain`, if it has settings there.
+    #[must_use]
+    pub fn trust_settings(&self, domain: Domain, certificate: &[u8]) -> Option<&[TrustSettingsEntry]> {
+        self.domains[index(domain)].get(&sha1_hex(certificate)).map(|c| c.entries.as_slice())
+    }
+
+    /// The effective trust settings of the DER-encoded certificate for evaluations with `policy`
+    /// for `policy_string`, a hostname or email address, using its key for `key_usage`.
+    #[must_use]
+    pub fn verdict(&self, certificate: &[u8], policy: PolicyKind, policy_string: Option<&str>, key_usage: TrustSettingsKeyUsage) -> Verdict {
+        let fingerprint = sha1_hex(certificate);
+        let settings = |domain| self.domains[index(domain)].get(&fingerprint).map(|c| c.entries.as_slice());
+        effective_verdict(settings, self.anchors.contains_key(&fingerprint), policy, policy_string, key_usage)
+    }
+
+    /// Reads a snapshot saved in either the XML or the binary property list format.
+    pub fn from_plist(data: &[u8]) -> Result<Self> {
+        Self::from_value(&Value::parse(data)?)
+    }
+
+    /// Decodes a saved snapshot.
+    pub fn from_value(value: &Value) -> Result<Self> {
+        if value.get(VERSION_KEY).and_then(Value::as_integer) != Some(VERSION) {
+            return Err(decode_error());
+        }
+        let array = |key| value.get(key).map_or(Ok(&[][..]), |v| v.as_array().ok_or_else(decode_error));
+        let mut snapshot = Self::new();
+        for anchor in array(ANCHORS)? {
+            snapshot.add_anchor(anchor.as_data().ok_or_else(decode_error)?);
+        }
+        for domain in PRECEDENCE {
+            for certificate in array(domain_key(domain))? {
+                let der = certificate.get(CERTIFICATE).and_then(Value::as_data).ok_or_else(decode_error)?;
+                let entries = certificate
+                    .get(TRUST_SETTINGS)
+                    .and_then(Value::as_array)
+                    .ok_or_else(decode_error)?
+                    .iter()
+                    .map(|entry| TrustSettingsEntry::from_dictionary(entry.as_dictionary().ok_or_else(decode_error)?))
+                    .collect::<Result<_>>()?;
+                snapshot.set_trust_settings(domain, der, entries);
+            }
+        }
+        Ok(snapshot)
+    }
+
+    /// Encodes for saving.
+    ///
+    /// Fails with `errSecParam` if an entry has an `Invalid` result.
+    pub fn to_value(&self) -> Result<Value> {
+        let mut dictionary = BTreeMap::from([
+            (VERSION_KEY.into(), Value::Integer(VERSION)),
+            (ANCHORS.into(), Value::Array(self.anchors().map(|der| Value::Data(der.to_vec())).collect())),
+        ]);
+        for domain in PRECEDENCE {
+            let certificates = self
+                .certificates(domain)
+                .map(|(der, entries)| {
+                    let entries = entries.iter().map(|entry| entry.to_dictionary().map(Value::Dictionary)).collect::<Result<_>>()?;
+                    Ok(Value::Dictionary(BTreeMap::from([
+                        (CERTIFICATE.into(), Value::Data(der.to_vec())),
+                        (TRUST_SETTINGS.into(), Value::Array(entries)),
+                    ])))
+                })
+                .collect::<Result<_>>()?;
+            dictionary.insert(domain_key(domain).into(), Value::Array(certificates));
+        }
+        Ok(Value::Dictionary(dictionary))
+    }
+
+    /// Writes an XML property list.
+    pub fn to_xml(&self) -> Result<String> {
+        Ok(self.to_value()?.to_xml())
+    }
+
+    /// Writes a binary property list.
+    pub fn to_binary(&self) -> Result<Vec<u8>> {
+        Ok(self.to_value()?.to_binary())
+    }
+}
+
+fn index(domain: Domain) -> usize {
+    match domain {
+        Domain::User => 0,
+        Domain::Admin => 1,
+        Domain::System => 2,
+    }
+}
+
+fn domain_key(domain: Domain) -> &'static str {
+    match domain {
+        Domain::User => "user",
+        Domain::Admin => "admin",
+        Domain::System => "system",
+    }
+}
+
+fn decode_error() -> Error {
+    Error::from_code(errSecDecode)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use security_framework_sys::base::{errSecCertificateExpired, errSecHostNameMismatch};
+
+    const CA: &[u8] = include_bytes!("../../test/ca.der");
+    const SERVER: &[u8] = include_bytes!("../../test/server.der");
+
+    fn entry(result: TrustSettingsForCertificate, policy: Option<PolicyKind>, policy_string: Option<&str>) -> TrustSettingsEntry {
+        let mut entry = TrustSettingsEntry::new(result);
+        entry.set_policy(policy).set_policy_string(policy_string);
+        entry
+    }
+
+    #[test]
+    fn precedence() {
+        let deny = [entry(TrustSettingsForCertificate::Deny, None, None)];
+        let trust = [entry(TrustSettingsForCertificate::TrustAsRoot, Some(PolicyKind::Ssl), None)];
+
+        // user overrides admin overrides system
+        let verdict = effective_verdict(
+            |domain| match domain {
+                Domain::User => None,
+                Domain::Admin => Some(&trust[..]),
+                Domain::System => Some(&deny[..]),
+            },
+            true,
+            PolicyKind::Ssl,
+            None,
+            TrustSettingsKeyUsage::empty(),
+        );
+        assert_eq!(verdict.result(), TrustSettingsForCertificate::TrustAsRoot);
+        assert_eq!(verdict.source(), VerdictSource::Domain(Domain::Admin));
+        assert!(verdict.is_trusted());
+
+        let verdict = effective_verdict(|domain| (domain == Domain::User).then_some(&deny[..]), true, PolicyKind::Ssl, None, TrustSettingsKeyUsage::empty());
+        assert_eq!(verdict.result(), TrustSettingsForCertificate::Deny);
+        assert!(!verdict.is_trusted());
+
+        // the admin setting is only for TLS, so the system's applies to code signing
+        let verdict = effective_verdict(
+            |domain| match domain {
+                Domain::User => None,
+                Domain::Admin => Some(&trust[..]),
+                Domain::System => Some(&deny[..]),
+            },
+            true,
+            PolicyKind::CodeSigning,
+            None,
+            TrustSettingsKeyUsage::empty(),
+        );
+        assert_eq!(verdict.source(), VerdictSource::Domain(Domain::System));
+
+        // an empty list trusts for everything
+        let verdict = effective_verdict(|domain| (domain == Domain::System).then_some(&[][..]), false, PolicyKind::Smime, None, TrustSettingsKeyUsage::empty());
+        assert_eq!(verdict.result(), TrustSettingsForCertificate::TrustRoot);
+        assert_eq!(verdict.source(), VerdictSource::Domain(Domain::System));
+
+        // nothing applies
+        let verdict = effective_verdict(|_| None, true, PolicyKind::Ssl, None, TrustSettingsKeyUsage::empty());
+        assert_eq!((verdict.result(), verdict.source()), (TrustSettingsForCertificate::TrustRoot, VerdictSource::Anchor));
+        let verdict = effective_verdict(|_| None, false, PolicyKind::Ssl, None, TrustSettingsKeyUsage::empty());
+        assert_eq!((verdict.result(), verdict.source()), (TrustSettingsForCertificate::Unspecified, VerdictSource::None));
+    }
+
+    #[test]
+    fn key_usage() {
+        let mut key_exchange = entry(TrustSettingsForCertificate::Deny, None, None);
+        key_exchange.set_key_usage(Some(TrustSettingsKeyUsage::KEY_EXCHANGE));
+        let user = [key_exchange];
+        let settings = |domain| (domain == Domain::User).then_some(&user[..]);
+
+        let verdict = effective_verdict(settings, true, PolicyKind::Ssl, None, TrustSettingsKeyUsage::KEY_EXCHANGE);
+        assert_eq!(verdict.result(), TrustSettingsForCertificate::Deny);
+        let verdict = effective_verdict(settings, true, PolicyKind::Ssl, None, TrustSettingsKeyUsage::SIGN_CERT);
+        assert_eq!((verdict.result(), verdict.source()), (TrustSettingsForCertificate::TrustRoot, VerdictSource::Anchor));
+        // any use
+        let verdict = effective_verdict(settings, true, PolicyKind::Ssl, None, TrustSettingsKeyUsage::empty());
+        assert_eq!(verdict.result(), TrustSettingsForCertificate::Deny);
+    }
+
+    #[test]
+    fn allowed_errors() {
+        let mut expired = entry(TrustSettingsForCertificate::Unspecified, Some(PolicyKind::Ssl), None);
+        expired.set_allowed_error(Some(errSecCertificateExpired));
+        let mut mismatch = entry(TrustSettingsForCertificate::TrustRoot, Some(PolicyKind::Ssl), Some("foobar.com"));
+        mismatch.set_allowed_error(Some(errSecHostNameMismatch));
+        let user = [expired];
+        let system = [mismatch];
+        let settings = |domain| match domain {
+            Domain::User => Some(&user[..]),
+            Domain::Admin => None,
+            Domain::System => Some(&system[..]),
+        };
+
+        let verdict = effective_verdict(settings, false, PolicyKind::Ssl, Some("FOOBAR.com"), TrustSettingsKeyUsage::empty());
+        assert_eq!(verdict.source(), VerdictSource::Domain(Domain::System));
+        assert_eq!(verdict.allowed_errors(), [errSecCertificateExpired, errSecHostNameMismatch]);
+
+        let verdict = effective_verdict(settings, false, PolicyKind::Ssl, Some("example.com"), TrustSettingsKeyUsage::empty());
+        assert_eq!(verdict.result(), TrustSettingsForCertificate::Unspecified);
+        assert_eq!(verdict.allowed_errors(), [errSecCertificateExpired]);
+    }
+
+    #[test]
+    fn saved_snapshot() {
+        let snapshot = Snapshot::from_plist(include_bytes!("../../test/trust-snapshot.plist")).unwrap();
+        assert_eq!(snapshot.anchors().collect::<Vec<_>>(), [CA]);
+        assert!(snapshot.is_anchor(CA));
+        assert_eq!(snapshot.certificates(Domain::User).count(), 1);
+        assert_eq!(snapshot.trust_settings(Domain::Admin, CA), Some(&[][..]));
+        assert_eq!(snapshot.trust_settings(Domain::System, SERVER), None);
+
+        // the user distrusts the server certificate for foobar.com only
+        let verdict = snapshot.verdict(SERVER, PolicyKind::Ssl, Some("foobar.com"), TrustSettingsKeyUsage::empty());
+        assert_eq!((verdict.result(), verdict.source()), (TrustSettingsForCertificate::Deny, VerdictSource::Domain(Domain::User)));
+        let verdict = snapshot.verdict(SERVER, PolicyKind::Ssl, Some("example.com"), TrustSettingsKeyUsage::empty());
+        assert_eq!((verdict.result(), verdict.source()), (TrustSettingsForCertificate::Unspecified, VerdictSource::None));
+        assert_eq!(verdict.allowed_errors(), [errSecCertificateExpired]);
+
+        // the admin trusts the CA, which is also an anchor, for everything
+        let verdict = snapshot.verdict(CA, PolicyKind::CodeSigning, None, TrustSettingsKeyUsage::empty());
+        assert_eq!(verdict.source(), VerdictSource::Domain(Domain::Admin));
+
+        assert_eq!(Snapshot::from_plist(snapshot.to_xml().unwrap().as_bytes()).unwrap(), snapshot);
+        assert_eq!(Snapshot::from_plist(&snapshot.to_binary().unwrap()).unwrap(), snapshot);
+    }
+
+    #[test]
+    fn build() {
+        let mut unspecified = entry(TrustSettingsForCertificate::Unspecified, None, None);
+        unspecified.set_allowed_error(Some(errSecCertificateExpired));
+        let mut snapshot = Snapshot::new();
+        snapshot
+            .add_anchor(CA)
+            .set_trust_settings(Domain::Admin, CA, Vec::new())
+            .set_trust_settings(
+                Domain::User,
+                SERVER,
+                vec![entry(TrustSettingsForCertificate::Deny, Some(PolicyKind::Ssl), Some("foobar.com")), unspecified],
+            );
+        let saved = Snapshot::from_plist(include_bytes!("../../test/trust-snapshot.plist")).unwrap();
+        assert_eq!(snapshot, saved);
+
+        assert!(Snapshot::from_plist(b"<plist><dict/></plist>").is_err());
+        assert!(Snapshot::new().set_trust_settings(Domain::User, SERVER, vec![TrustSettingsEntry::new(TrustSettingsForCertificate::Invalid)]).to_xml().is_err());
+    }
+
+    #[test]
+    #[cfg(target_os = "macos")]
+    fn capture() {
+        let snapshot = Snapshot::capture().unwrap();
+        assert!(snapshot.anchors().count() > 100);
+        let anchor = snapshot.anchors().next().unwrap().to_vec();
+        assert_ne!(snapshot.verdict(&anchor, PolicyKind::Ssl, None, TrustSettingsKeyUsage::empty()).source(), VerdictSource::None);
+    }
+}
@@ -181,3 +181,239 @@ openssl pkcs12 -export -out keystore.p12 -inkey cms.key -in cms.crt \
 
 rm -f cms_ca.key cms_ca.crt cms_ca.srl cms.key cms.crt cms.csr \
       cms_ca.cnf cms_req.cnf cms_ext.cnf plaintext.txt signed_inner.der
+
+######################
+
+mkdir -p "$TEST_DIR/crl"
+cd "$TEST_DIR/crl"
+
+openssl genrsa -out crl_ca.key 2048
+
+cat > crl_ca.cnf << 'EOF'
+[ca]
+default_ca = crl_ca
+
+[crl_ca]
+database = index.txt
+crlnumber = crlnumber
+default_md = sha256
+default_crl_days = 3650
+crl_extensions = crl_ext
+
+[crl_ext]
+authorityKeyIdentifier = keyid:always
+
+[req]
+distinguished_name = req_distinguished_name
+x509_extensions = v3_ca
+prompt = no
+
+[req_distinguished_name]
+O = Foobar LLC
+CN = CRL Test CA
+
+[v3_ca]
+basicConstraints = critical, CA:TRUE
+subjectKeyIdentifier = hash
+authorityKeyIdentifier = keyid:always
+keyUsage = critical, keyCertSign, cRLSign
+
+[v3_leaf]
+basicConstraints = CA:FALSE
+subjectKeyIdentifier = hash
+authorityKeyIdentifier = keyid:always
+crlDistributionPoints = URI:http://crl.example.com/crl_ca.crl
+EOF
+
+openssl req -new -x509 -key crl_ca.key -out crl_ca.crt -days 3650 \
+    -sha256 -config crl_ca.cnf
+
+# good.der is not revoked, revoked.der is revoked for keyCompromise
+for name in good revoked; do
+    openssl req -new -newkey rsa:2048 -nodes -keyout "$name.key" \
+        -subj "/CN=$name.example.com" -out "$name.csr"
+    openssl x509 -req -in "$name.csr" -CA crl_ca.crt -CAkey crl_ca.key -CAcreateserial \
+        -out "$name.crt" -days 3650 -sha256 \
+        -extfile crl_ca.cnf -extensions v3_leaf
+    openssl x509 -in "$name.crt" -out "$name.der" -outform DER
+done
+
+touch index.txt
+echo 1000 > crlnumber
+openssl ca -config crl_ca.cnf -cert crl_ca.crt -keyfile crl_ca.key \
+    -revoke revoked.crt -crl_reason keyCompromise
+# entries for certificates that don't exist: one without a reason, one superseded
+printf 'R\t351231235959Z\t240101000000Z\t0A0B0C\tunknown\t/CN=other.example.com\n' >> index.txt
+printf 'R\t351231235959Z\t240202000000Z,superseded\t0D0E\tunknown\t/CN=old.example.com\n' >> index.txt
+openssl ca -gencrl -config crl_ca.cnf -cert crl_ca.crt -keyfile crl_ca.key -out crl.pem
+openssl crl -in crl.pem -out crl.der -outform DER
+openssl x509 -in crl_ca.crt -out crl_ca.der -outform DER
+
+rm -f crl_ca.key crl_ca.crt crl_ca.srl crl_ca.cnf good.key good.csr good.crt \
+      revoked.key revoked.csr revoked.crt index.txt* crlnumber*
+
+######################
+
+mkdir -p "$TEST_DIR/ocsp"
+cd "$TEST_DIR/ocsp"
+
+openssl genrsa -out ocsp_ca.key 2048
+
+cat > ocsp_ca.cnf << 'EOF'
+[req]
+distinguished_name = req_distinguished_name
+x509_extensions = v3_ca
+prompt = no
+
+[req_distinguished_name]
+O = Foobar LLC
+CN = OCSP Test CA
+
+[v3_ca]
+basicConstraints = critical, CA:TRUE
+subjectKeyIdentifier = hash
+authorityKeyIdentifier = keyid:always
+keyUsage = critical, keyCertSign, cRLSign, digitalSignature
+
+[v3_leaf]
+basicConstraints = CA:FALSE
+subjectKeyIdentifier = hash
+authorityKeyIdentifier = keyid:always
+authorityInfoAccess = OCSP;URI:http://ocsp.example.com
+
+[v3_responder]
+basicConstraints = CA:FALSE
+extendedKeyUsage = OCSPSigning
+EOF
+
+openssl req -new -x509 -key ocsp_ca.key -out ocsp_ca.crt -days 3650 \
+    -sha256 -config ocsp_ca.cnf
+
+# good is valid, revoked is revoked for keyCompromise, unknown isn't in the responder's database,
+# and responder is a delegated OCSP signer
+for name in good revoked unknown responder; do
+    openssl req -new -newkey rsa:2048 -nodes -keyout "$name.key" \
+        -subj "/CN=$name.example.com" -out "$name.csr"
+    if [ "$name" = responder ]; then ext=v3_responder; else ext=v3_leaf; fi
+    openssl x509 -req -in "$name.csr" -CA ocsp_ca.crt -CAkey ocsp_ca.key -CAcreateserial \
+        -out "$name.crt" -days 3650 -sha256 \
+        -extfile ocsp_ca.cnf -extensions "$ext"
+    openssl x509 -in "$name.crt" -out "$name.der" -outform DER
+done
+
+serial() { openssl x509 -in "$1" -noout -serial | cut -d= -f2; }
+printf 'V\t351231235959Z\t\t%s\tunknown\t/CN=good.example.com\n' "$(serial good.crt)" > index.txt
+printf 'R\t351231235959Z\t240101000000Z,keyCompromise\t%s\tunknown\t/CN=revoked.example.com\n' "$(serial revoked.crt)" >> index.txt
+
+# request.der asks about good.der only; request-all.der about all three with a nonce
+openssl ocsp -issuer ocsp_ca.crt -cert good.crt -no_nonce -reqout request.der
+openssl ocsp -issuer ocsp_ca.crt -cert good.crt -cert revoked.crt -cert unknown.crt \
+    -nonce -reqout request-all.der
+
+# response.der is signed by the CA, response-delegated.der by the responder (identified by key)
+openssl ocsp -index index.txt -CA ocsp_ca.crt -rsigner ocsp_ca.crt -rkey ocsp_ca.key \
+    -reqin request-all.der -respout response.der -ndays 7
+openssl ocsp -index index.txt -CA ocsp_ca.crt -rsigner responder.crt -rkey responder.key \
+    -resp_key_id -reqin request.der -respout response-delegated.der -ndays 7
+
+openssl x509 -in ocsp_ca.crt -out ocsp_ca.der -outform DER
+
+rm -f ocsp_ca.key ocsp_ca.crt ocsp_ca.srl ocsp_ca.cnf index.txt* \
+      good.key good.csr good.crt revoked.key revoked.csr revoked.crt \
+      unknown.key unknown.csr unknown.crt responder.key responder.csr responder.crt
+
+######################
+
+mkdir -p "$TEST_DIR/sct"
+cd "$TEST_DIR/sct"
+
+# a test CT log with an RSA key; log.der is its SubjectPublicKeyInfo
+openssl genrsa -out log.key 2048
+openssl pkey -in log.key -pubout -outform DER -out log.der
+
+openssl req -new -x509 -newkey rsa:2048 -nodes -keyout sct_ca.key -out sct_ca.crt -days 3650 \
+    -sha256 -subj "/O=Foobar LLC/CN=CT Test CA" \
+    -addext "basicConstraints=critical,CA:TRUE" -addext "keyUsage=critical,keyCertSign,cRLSign"
+openssl req -new -newkey rsa:2048 -nodes -keyout leaf.key -subj "/CN=ct.example.com" -out leaf.csr
+
+cat > leaf.cnf << 'EOF'
+[leaf]
+basicConstraints = CA:FALSE
+subjectAltName = DNS:ct.example.com
+EOF
+
+# the same certificate without and with the SCT list, so tbs.der is what the log signed
+issue() {
+    openssl x509 -req -in leaf.csr -CA sct_ca.crt -CAkey sct_ca.key -set_serial 0x5c7 \
+        -not_before 20240101000000Z -not_after 20340101000000Z -sha256 \
+        -extfile leaf.cnf -extensions leaf -outform DER -out "$1"
+}
+issue precert.der
+openssl asn1parse -inform DER -in precert.der -strparse 4 -noout -out tbs.der
+
+u16() { printf "\\x$(printf %02x $(($1 >> 8)))\\x$(printf %02x $(($1 & 255)))"; }
+u24() { printf "\\x$(printf %02x $(($1 >> 16)))"; u16 $(($1 & 65535)); }
+size() { wc -c < "$1" | tr -d ' '; }
+
+# sct <timestamp> <entry file> <out>: a v1 SCT with SHA-256, see RFC 6962 section 3.2,
+# signed by $LOG.key with TLS signature algorithm $SIG (RSA unless set)
+sct() {
+    { printf '\x00\x00'; printf "$(printf %016x "$1" | sed 's/../\\x&/g')"; cat "$2"; u16 0; } > signed.bin
+    openssl dgst -sha256 -sign "${LOG:-log}.key" -out signature.bin signed.bin
+    { printf '\x00'; openssl dgst -sha256 -binary "${LOG:-log}.der"
+      printf "$(printf %016x "$1" | sed 's/../\\x&/g')"; u16 0
+      printf "\\x04\\x${SIG:-01}"; u16 "$(size signature.bin)"; cat signature.bin; } > "$3"
+}
+sct_list() {
+    { u16 $(($(size "$1") + 2)); u16 "$(size "$1")"; cat "$1"; } > "$2"
+}
+
+# embedded: a precert entry over the issuer key hash and the TBSCertificate without the SCT list
+{ printf '\x00\x01'
+  openssl x509 -in sct_ca.crt -pubkey -noout | openssl pkey -pubin -outform DER | openssl dgst -sha256 -binary
+  u24 "$(size tbs.der)"; cat tbs.der; } > entry.bin
+sct 1704067200000 entry.bin embedded.bin
+sct_list embedded.bin embedded-list.bin
+list=$(xxd -p embedded-list.bin | tr -d '\n')
+printf 'ct_precert_scts = DER:04%s%s\n' "$(printf %04x "$(size embedded-list.bin)" | sed 's/^/82/')" "$list" >> leaf.cnf
+issue leaf.der
+
+# tls.bin is a list as sent in the TLS extension: an X.509 entry for the final certificate
+{ printf '\x00\x00'; u24 "$(size leaf.der)"; cat leaf.der; } > entry.bin
+sct 1704153600000 entry.bin tls-sct.bin
+sct_list tls-sct.bin tls.bin
+
+# ecdsa-log.der is a P-256 log, and ecdsa-sct.bin its SCT for the same entry
+openssl genpkey -algorithm EC -pkeyopt ec_paramgen_curve:P-256 -out ecdsa-log.key
+openssl pkey -in ecdsa-log.key -pubout -outform DER -out ecdsa-log.der
+LOG=ecdsa-log SIG=03 sct 1704153600000 entry.bin ecdsa-sct.bin
+
+openssl x509 -in sct_ca.crt -out sct_ca.der -outform DER
+
+rm -f log.key ecdsa-log.key sct_ca.key sct_ca.crt leaf.key leaf.csr leaf.cnf precert.der tbs.der \
+      signed.bin signature.bin entry.bin embedded.bin embedded-list.bin tls-sct.bin
+
+######################
+
+mkdir -p "$TEST_DIR/ecdsa"
+cd "$TEST_DIR/ecdsa"
+
+# a P-384 CA signing with ecdsa-with-SHA384, and a P-256 leaf it signed
+openssl req -new -x509 -newkey ec -pkeyopt ec_paramgen_curve:P-384 -nodes -keyout ca.key -out ca.crt \
+    -sha384 -not_before 20240101000000Z -not_after 20440101000000Z -subj "/O=Foobar LLC/CN=ECDSA Test CA" \
+    -addext "basicConstraints=critical,CA:TRUE" -addext "keyUsage=critical,keyCertSign,cRLSign"
+openssl req -new -newkey ec -pkeyopt ec_paramgen_curve:P-256 -nodes -keyout leaf.key \
+    -subj "/CN=ecdsa.example.com" -out leaf.csr
+
+cat > leaf.cnf << 'EOF2'
+[leaf]
+basicConstraints = CA:FALSE
+subjectAltName = DNS:ecdsa.example.com
+EOF2
+
+openssl x509 -req -in leaf.csr -CA ca.crt -CAkey ca.key -set_serial 0xec \
+    -not_before 20240101000000Z -not_after 20340101000000Z -sha384 \
+    -extfile leaf.cnf -extensions leaf -outform DER -out leaf.der
+openssl x509 -in ca.crt -out ca.der -outform DER
+
+rm -f ca.key ca.crt leaf.key leaf.csr leaf.cnf